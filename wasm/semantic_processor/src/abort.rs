@@ -0,0 +1,195 @@
+//! Cancellation of long-running operations
+//!
+//! Setting the abort flag makes the next check inside a load or graph
+//! generation fail with an `Aborted` error. Calls into WASM are synchronous,
+//! so the flag takes effect between the calls of a chunked load, at the start
+//! of the next operation, or while a load runs if it is set from the load's
+//! progress callback through an [`AbortHandle`]. Every loader reads its input
+//! through a reader that stops once the flag is set, and SPARQL evaluation
+//! checks it as it joins solutions:
+//!
+//! ```javascript
+//! const abort = processor.abort_handle();
+//! window.addEventListener('pagehide', () => abort.abort());
+//! processor.load_turtle(ttl, () => { if (navigatingAway) abort.abort(); });
+//! ```
+//!
+//! The flag is cleared once an operation has been aborted by it. An aborted
+//! load leaves the processor as it was; an aborted chunked load keeps the
+//! statements of the chunks parsed before.
+
+use crate::error::ProcessorError;
+use crate::progress::ProgressReader;
+use crate::SemanticProcessor;
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Handle that sets a processor's abort flag
+///
+/// Unlike the processor itself, the handle can be used while the processor
+/// is busy, e.g. from a progress callback.
+#[wasm_bindgen]
+pub struct AbortHandle {
+    flag: Rc<Cell<bool>>,
+}
+
+#[wasm_bindgen]
+impl AbortHandle {
+    /// Abort the operation in progress, or the next one to start
+    pub fn abort(&self) {
+        self.flag.set(true);
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Abort the operation in progress, or the next one to start
+    pub fn set_abort_flag(&self) {
+        self.abort.set(true);
+    }
+
+    /// Withdraw an abort request that no operation has seen yet
+    pub fn clear_abort_flag(&self) {
+        self.abort.set(false);
+    }
+
+    /// Get a handle that can set the abort flag while the processor is busy
+    pub fn abort_handle(&self) -> AbortHandle {
+        AbortHandle {
+            flag: Rc::clone(&self.abort),
+        }
+    }
+}
+
+impl SemanticProcessor {
+    /// Fail with an `Aborted` error if the abort flag is set, clearing it
+    pub(crate) fn check_abort(&self) -> Result<(), ProcessorError> {
+        check_flag(&self.abort)
+    }
+
+    /// Reader over a document that stops once the abort flag is set
+    pub(crate) fn abortable<'a>(&self, document: &'a str) -> ProgressReader<'a, fn(u32, usize)> {
+        let ignore: fn(u32, usize) = |_, _| {};
+        ProgressReader::new(document.as_bytes(), ignore).with_abort_flag(&self.abort)
+    }
+}
+
+/// Fail with an `Aborted` error if `flag` is set, clearing it
+pub(crate) fn check_flag(flag: &Cell<bool>) -> Result<(), ProcessorError> {
+    if flag.replace(false) {
+        Err(ProcessorError::aborted())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abort_flag_is_cleared_once_seen() {
+        let processor = SemanticProcessor::new();
+        assert!(processor.check_abort().is_ok());
+
+        processor.abort_handle().abort();
+        let error = processor.check_abort().unwrap_err();
+        assert_eq!(error.code, crate::ErrorCode::Aborted);
+        assert!(processor.check_abort().is_ok());
+    }
+
+    const NT: &str = r#"
+        <https://sinople.org/ontology#Construct> <http://www.w3.org/2000/01/rdf-schema#subClassOf> <https://sinople.org/ontology#Concept> .
+        <https://sinople.org/ontology#time> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://sinople.org/ontology#Construct> .
+    "#;
+
+    fn inferred(processor: &SemanticProcessor) -> bool {
+        processor
+            .explanation("sn:time", "rdf:type", "sn:Concept")
+            .unwrap()
+            .is_some_and(|explanation| !explanation.asserted)
+    }
+
+    #[test]
+    fn test_failed_or_aborted_load_leaves_the_processor_as_it_was() {
+        let mut processor = SemanticProcessor::new();
+        processor.set_reasoning_level("rdfs").unwrap();
+        processor.read_ntriples(NT).unwrap();
+        let edit = processor
+            .triple_terms("sn:time", "rdfs:label", "\"Time\"", false)
+            .unwrap();
+        processor.add_terms(&edit);
+        processor.finish_edit();
+        let count = processor.triple_count();
+
+        processor.set_abort_flag();
+        let error = processor
+            .read_ntriples("<https://example.org/b> <https://example.org/p> \"y\" .")
+            .unwrap_err();
+        assert_eq!(error.code, crate::ErrorCode::Aborted);
+        let broken = "<https://example.org/b> <https://example.org/p> \"y\" .\n<oops> .";
+        assert!(processor.read_ntriples(broken).is_err());
+
+        assert_eq!(processor.triple_count(), count);
+        assert!(inferred(&processor));
+        assert!(processor.undo_edit().unwrap());
+        assert_eq!(processor.triple_count(), count - 1);
+    }
+
+    #[test]
+    fn test_aborted_chunked_load_keeps_inferences() {
+        let mut processor = SemanticProcessor::new();
+        processor.set_reasoning_level("rdfs").unwrap();
+        processor.read_ntriples(NT).unwrap();
+
+        processor.begin_load();
+        processor
+            .stream_chunk(
+                "<https://example.org/b> <https://example.org/p> \"y\" .\n<https://example.org/c> ",
+            )
+            .unwrap();
+        processor.set_abort_flag();
+        let error = processor
+            .stream_chunk("<https://example.org/p> \"z\" .")
+            .unwrap_err();
+        assert_eq!(error.code, crate::ErrorCode::Aborted);
+
+        assert!(processor.streaming.is_none());
+        assert!(inferred(&processor));
+        let added = processor
+            .match_pattern(Some("<https://example.org/b>"), None, None)
+            .unwrap();
+        assert_eq!(added.len(), 1);
+    }
+
+    #[test]
+    fn test_abort_stops_loads_and_queries() {
+        use crate::sparql::{evaluate_select, parse_select};
+        use std::io::Read;
+
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_ntriples("<https://example.org/a> <https://example.org/p> \"x\" .")
+            .unwrap();
+
+        processor.set_abort_flag();
+        let mut sink = Vec::new();
+        assert!(processor
+            .abortable("<a> <b> <c> .")
+            .read_to_end(&mut sink)
+            .is_err());
+        assert!(sink.is_empty());
+
+        let query = parse_select(
+            "SELECT ?s WHERE { ?s ?p ?o }",
+            &processor.namespaces,
+            &processor.aliases,
+        )
+        .unwrap();
+        let error = evaluate_select(&processor.graph, &query, &processor.abort).unwrap_err();
+        assert_eq!(error.code, crate::ErrorCode::Aborted);
+        let results = evaluate_select(&processor.graph, &query, &processor.abort).unwrap();
+        assert_eq!(results.results.bindings.len(), 1);
+    }
+}
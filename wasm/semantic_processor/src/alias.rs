@@ -0,0 +1,275 @@
+//! Namespace aliases
+//!
+//! The same vocabulary is often written under more than one IRI: W3C
+//! vocabularies are published under `http://` IRIs, but documents also use
+//! `https://` forms of them, which would never match `rdf:type`. IRIs in an
+//! alias of a registered namespace are rewritten to that namespace as
+//! documents are loaded, and so are IRIs passed to query methods, so either
+//! form finds the same triples.
+//!
+//! Every registered namespace has its other scheme (`http`/`https`) as an
+//! alias, unless `set_scheme_aliases(false)` is called. Other aliases are
+//! added explicitly:
+//!
+//! ```javascript
+//! processor.add_namespace_alias('http://purl.org/dc/elements/1.1/', 'http://purl.org/dc/terms/');
+//! ```
+//!
+//! The namespace IRI itself is also matched without its trailing `/` or `#`,
+//! so `<https://www.w3.org/2002/07/owl>` becomes `<http://www.w3.org/2002/07/owl>`.
+//! Aliases apply to documents loaded after they are set; triples already in
+//! the graph are not rewritten.
+//!
+//! **Breaking:** the rewrite changes the stored data, not just how it is
+//! matched. A document written with `https://www.w3.org/...` IRIs is
+//! exported by `to_turtle`, snapshots and the other serializers with the
+//! registered `http://` IRIs instead, so it does not round-trip as written.
+//! Call `set_scheme_aliases(false)` before loading to keep IRIs exactly as
+//! they are, at the cost of the two forms no longer matching.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_api::quad::Quad;
+use sophia_api::term::{IriRef, SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_api::MownStr;
+use sophia_iri::Iri;
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// Namespace aliases and the rewrites they imply
+#[derive(Debug, Clone)]
+pub(crate) struct Aliases {
+    /// Aliases added with `add_namespace_alias`, mapped to their namespace
+    explicit: HashMap<String, String>,
+    /// Whether registered namespaces have their other scheme as an alias
+    schemes: bool,
+    /// (alias, namespace), longest alias first
+    rewrites: Vec<(String, String)>,
+}
+
+impl Aliases {
+    pub fn new(namespaces: &HashMap<String, String>) -> Self {
+        let mut aliases = Aliases {
+            explicit: HashMap::new(),
+            schemes: true,
+            rewrites: Vec::new(),
+        };
+        aliases.rebuild(namespaces);
+        aliases
+    }
+
+    /// Recompute the rewrites after namespaces or aliases change
+    pub fn rebuild(&mut self, namespaces: &HashMap<String, String>) {
+        let mut rewrites: Vec<(String, String)> = self
+            .explicit
+            .iter()
+            .map(|(alias, namespace)| (alias.clone(), namespace.clone()))
+            .collect();
+        if self.schemes {
+            rewrites.extend(namespaces.values().filter_map(|namespace| {
+                let alias = other_scheme(namespace)?;
+                Some((alias, namespace.clone()))
+            }));
+        }
+        // An explicit alias wins over a scheme variant of the same IRI
+        let mut seen = HashSet::new();
+        rewrites.retain(|(alias, _)| seen.insert(alias.clone()));
+        rewrites.sort_by(|a, b| Ord::cmp(&b.0.len(), &a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        self.rewrites = rewrites;
+    }
+
+    /// The IRI an alias stands for, if `iri` is in an alias
+    pub fn iri(&self, iri: &str) -> Option<String> {
+        self.rewrites.iter().find_map(|(alias, namespace)| {
+            if let Some(local) = iri.strip_prefix(alias.as_str()) {
+                return Some(format!("{}{}", namespace, local));
+            }
+            let bare = alias.strip_suffix(['/', '#'])?;
+            (iri == bare).then(|| {
+                namespace
+                    .strip_suffix(['/', '#'])
+                    .unwrap_or(namespace)
+                    .to_string()
+            })
+        })
+    }
+
+    /// Rewrite the IRIs of a term, including the datatype of a literal and
+    /// the terms of a quoted triple
+    pub fn term<T: Term>(&self, term: T) -> SimpleTerm<'static> {
+        let rewritten = match term.as_simple() {
+            SimpleTerm::Iri(iri) => self.iri(iri.as_str()).map(iri_term),
+            SimpleTerm::LiteralDatatype(lex, datatype) => {
+                self.iri(datatype.as_str()).map(|canonical| {
+                    SimpleTerm::LiteralDatatype(
+                        MownStr::from(lex.to_string()),
+                        IriRef::new_unchecked(MownStr::from(canonical)),
+                    )
+                })
+            }
+            SimpleTerm::Triple(triple) => {
+                let [s, p, o] = &*triple;
+                Some(SimpleTerm::Triple(Box::new([
+                    self.term(s),
+                    self.term(p),
+                    self.term(o),
+                ])))
+            }
+            _ => None,
+        };
+        rewritten.unwrap_or_else(|| term.into_term())
+    }
+
+    pub fn triple<T: Triple>(&self, triple: T) -> [SimpleTerm<'static>; 3] {
+        let [s, p, o] = triple.to_spo();
+        [self.term(s), self.term(p), self.term(o)]
+    }
+
+    pub fn quad<Q: Quad>(
+        &self,
+        quad: Q,
+    ) -> ([SimpleTerm<'static>; 3], Option<SimpleTerm<'static>>) {
+        let (spo, name) = quad.to_spog();
+        (self.triple(spo), name.map(|name| self.term(name)))
+    }
+}
+
+fn iri_term(iri: String) -> SimpleTerm<'static> {
+    SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(iri)))
+}
+
+/// The same IRI with `http` and `https` swapped
+fn other_scheme(iri: &str) -> Option<String> {
+    if let Some(rest) = iri.strip_prefix("https://") {
+        Some(format!("http://{}", rest))
+    } else {
+        iri.strip_prefix("http://")
+            .map(|rest| format!("https://{}", rest))
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Treat the IRIs of one namespace as those of another
+    ///
+    /// # Arguments
+    /// * `alias` - Namespace IRI to rewrite, e.g. `"http://xmlns.com/foaf/0.1/"`
+    /// * `namespace` - Namespace IRI to rewrite it to
+    pub fn add_namespace_alias(&mut self, alias: &str, namespace: &str) -> Result<(), JsValue> {
+        Ok(self.add_alias(alias, namespace)?)
+    }
+
+    /// Remove an alias added with `add_namespace_alias`
+    ///
+    /// # Returns
+    /// `true` if the alias was registered
+    pub fn remove_namespace_alias(&mut self, alias: &str) -> bool {
+        let removed = self.aliases.explicit.remove(alias).is_some();
+        if removed {
+            self.rebuild_aliases();
+        }
+        removed
+    }
+
+    /// Choose whether registered namespaces match their other scheme
+    ///
+    /// On by default: `http://` and `https://` forms of a registered
+    /// namespace are treated as the same namespace.
+    pub fn set_scheme_aliases(&mut self, enabled: bool) {
+        self.aliases.schemes = enabled;
+        self.rebuild_aliases();
+    }
+}
+
+impl SemanticProcessor {
+    /// Validate and register a namespace alias
+    pub(crate) fn add_alias(&mut self, alias: &str, namespace: &str) -> Result<(), ProcessorError> {
+        for iri in [alias, namespace] {
+            if Iri::new(iri).is_err() {
+                return Err(ProcessorError::invalid_argument(format!(
+                    "Invalid namespace IRI '{}'; expected an absolute IRI",
+                    iri
+                ))
+                .with_context("iri", iri));
+            }
+        }
+
+        self.aliases
+            .explicit
+            .insert(alias.to_string(), namespace.to_string());
+        self.rebuild_aliases();
+        Ok(())
+    }
+
+    /// Recompute aliases after namespaces or aliases change
+    pub(crate) fn rebuild_aliases(&mut self) {
+        self.aliases.rebuild(&self.namespaces);
+        // Cached results depend on how IRIs given to queries are rewritten
+        self.invalidate_cache();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheme_variants_match_registered_namespaces() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdf: <https://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+                @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+                sn:time rdf:type sn:Construct ; rdfs:label "Time" .
+                sn:space a sn:Construct .
+                <https://www.w3.org/2002/07/owl> rdfs:comment "OWL" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let constructs = processor.collect_constructs(None).unwrap();
+        assert_eq!(constructs.len(), 2);
+        assert_eq!(constructs[1].label, "Time");
+        assert_eq!(
+            processor
+                .match_pattern(Some("https://www.w3.org/2002/07/owl"), None, None)
+                .unwrap()[0]
+                .subject
+                .value,
+            "http://www.w3.org/2002/07/owl"
+        );
+
+        processor.set_scheme_aliases(false);
+        assert_eq!(
+            processor
+                .aliases
+                .iri("http://www.w3.org/2000/01/rdf-schema#label"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_explicit_alias() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .add_alias("http://example.org/old/", "https://sinople.org/ontology#")
+            .unwrap();
+        assert_eq!(
+            processor
+                .aliases
+                .iri("http://example.org/old/time")
+                .as_deref(),
+            Some("https://sinople.org/ontology#time")
+        );
+        assert!(processor
+            .add_alias("old", "https://sinople.org/ontology#")
+            .is_err());
+
+        assert!(processor.remove_namespace_alias("http://example.org/old/"));
+        assert_eq!(processor.aliases.iri("http://example.org/old/time"), None);
+    }
+}
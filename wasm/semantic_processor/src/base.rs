@@ -0,0 +1,148 @@
+//! Base IRI for relative references
+//!
+//! Documents written inside WordPress often use relative IRIs such as
+//! `<#entropy>` or `<../glossary/time>`. They are resolved against the
+//! document's own `@base`/`BASE` directive if it has one, and otherwise
+//! against the base IRI set on the processor:
+//!
+//! ```javascript
+//! processor.set_base_iri('https://example.org/posts/42');
+//! processor.load_turtle('<#entropy> a sn:Construct .');
+//! // subject: https://example.org/posts/42#entropy
+//! ```
+//!
+//! A directive only applies to the document it appears in, so documents
+//! loaded one after the other cannot change each other's base.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_iri::Iri;
+use sophia_jsonld::loader::NoLoader;
+use sophia_jsonld::loader_factory::DefaultLoaderFactory;
+use sophia_jsonld::{JsonLdOptions, JsonLdParser};
+use sophia_turtle::parser::trig::TriGParser;
+use sophia_turtle::parser::turtle::TurtleParser;
+use sophia_xml::parser::RdfXmlParser;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Set the IRI that relative references are resolved against
+    ///
+    /// Applies to Turtle, TriG, JSON-LD and RDF/XML; N-Triples and N-Quads
+    /// only allow absolute IRIs.
+    ///
+    /// # Arguments
+    /// * `iri` - Absolute IRI, or `None` to resolve against nothing, so that
+    ///   documents with relative IRIs and no `@base` fail to load
+    pub fn set_base_iri(&mut self, iri: Option<String>) -> Result<(), JsValue> {
+        self.base_iri = iri.map(parse_base).transpose()?;
+        Ok(())
+    }
+
+    /// Get the base IRI set with `set_base_iri`
+    pub fn base_iri(&self) -> Option<String> {
+        self.base_iri.as_ref().map(|iri| iri.as_str().to_string())
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn turtle_parser(&self) -> TurtleParser {
+        TurtleParser {
+            base: self.base_iri.clone(),
+        }
+    }
+
+    pub(crate) fn trig_parser(&self) -> TriGParser {
+        TriGParser {
+            base: self.base_iri.clone(),
+        }
+    }
+
+    pub(crate) fn rdfxml_parser(&self) -> RdfXmlParser {
+        RdfXmlParser {
+            base: self.base_iri.clone(),
+        }
+    }
+
+    pub(crate) fn jsonld_parser(&self) -> JsonLdParser {
+        let options = JsonLdOptions::<DefaultLoaderFactory<NoLoader>>::default();
+        match &self.base_iri {
+            Some(iri) => JsonLdParser::new_with_options(
+                options.with_base(Iri::new_unchecked(Arc::from(iri.as_str()))),
+            ),
+            None => JsonLdParser::new_with_options(options),
+        }
+    }
+}
+
+/// Check that a base IRI is absolute
+fn parse_base(iri: String) -> Result<Iri<String>, ProcessorError> {
+    Iri::new(iri).map_err(|e| {
+        let iri = e.0.clone();
+        ProcessorError::invalid_argument(format!(
+            "Invalid base IRI '{}'; expected an absolute IRI",
+            iri
+        ))
+        .with_context("iri", &iri)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_iris_resolve_against_base() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .set_base_iri(Some("https://example.org/posts/42".to_string()))
+            .unwrap();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                <#entropy> sn:near <../glossary/time> .
+                "#,
+                None,
+            )
+            .unwrap();
+        processor
+            .load_turtle(
+                r#"
+                @base <https://other.org/> .
+                <space> <https://sinople.org/ontology#near> <#entropy> .
+                "#,
+                None,
+            )
+            .unwrap();
+        processor
+            .load_turtle(
+                "<#time> <https://sinople.org/ontology#near> <#entropy> .",
+                None,
+            )
+            .unwrap();
+
+        let subjects = |predicate| {
+            let mut found: Vec<String> = processor
+                .match_pattern(None, Some(predicate), None)
+                .unwrap()
+                .into_iter()
+                .map(|m| format!("{} {}", m.subject.value, m.object.value))
+                .collect();
+            found.sort();
+            found
+        };
+        assert_eq!(
+            subjects("sn:near"),
+            vec![
+                "https://example.org/posts/42#entropy https://example.org/glossary/time",
+                "https://example.org/posts/42#time https://example.org/posts/42#entropy",
+                "https://other.org/space https://other.org/#entropy",
+            ]
+        );
+
+        assert!(parse_base("relative".to_string()).is_err());
+    }
+}
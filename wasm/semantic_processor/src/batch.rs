@@ -0,0 +1,196 @@
+//! Running several queries in one call
+//!
+//! Every call into the module pays for crossing the WASM boundary and for
+//! building JavaScript objects. Pages that issue many queries can send them
+//! together and parse a single JSON result:
+//!
+//! ```javascript
+//! const results = JSON.parse(processor.run_batch(JSON.stringify({
+//!   constructs: { type: 'constructs', options: { sort: 'label' } },
+//!   cast: { type: 'characters', graph: 'https://example.org/book1' },
+//!   network: { type: 'graph' },
+//!   glosses: { type: 'match', predicate: 'sn:hasGloss' },
+//!   counts: { type: 'sparql', query: 'SELECT ?c WHERE { ?c a sn:Construct }' },
+//! })));
+//! results.cast; // array of Character objects
+//! ```
+
+use crate::error::ProcessorError;
+use crate::options::QueryOptions;
+use crate::sparql::{evaluate_select, parse_select};
+use crate::SemanticProcessor;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// One query of a batch, tagged by its `type`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum BatchQuery {
+    Constructs {
+        graph: Option<String>,
+        #[serde(default)]
+        options: QueryOptions,
+    },
+    Entanglements {
+        graph: Option<String>,
+        #[serde(default)]
+        options: QueryOptions,
+    },
+    Characters {
+        graph: Option<String>,
+        #[serde(default)]
+        options: QueryOptions,
+    },
+    /// The network graph of `generate_network_graph`
+    Graph,
+    /// A triple pattern, as for `match_triples`
+    Match {
+        subject: Option<String>,
+        predicate: Option<String>,
+        object: Option<String>,
+    },
+    /// A SPARQL SELECT query, as for `sparql_select`
+    Sparql { query: String },
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Run several named queries at once
+    ///
+    /// # Arguments
+    /// * `queries_json` - JSON object mapping names to queries. Each query has
+    ///   a `type` of `constructs`, `entanglements` or `characters` (with
+    ///   optional `graph` and `options`), `graph`, `match` (with optional
+    ///   `subject`, `predicate` and `object`) or `sparql` (with `query`)
+    ///
+    /// # Returns
+    /// * `Ok(String)` with a JSON object mapping each name to its result
+    /// * `Err(JsValue)` if the batch is malformed or any query fails
+    pub fn run_batch(&self, queries_json: &str) -> Result<String, JsValue> {
+        Ok(self.batch(queries_json)?)
+    }
+}
+
+impl SemanticProcessor {
+    /// Run a batch and serialize its results
+    pub(crate) fn batch(&self, queries_json: &str) -> Result<String, ProcessorError> {
+        let queries: BTreeMap<String, BatchQuery> = serde_json::from_str(queries_json)
+            .map_err(|e| ProcessorError::invalid_argument(format!("Invalid batch: {}", e)))?;
+
+        let mut results = BTreeMap::new();
+        for (name, query) in queries {
+            self.check_abort()?;
+            let result = self
+                .run_batch_query(query)
+                .map_err(|e| e.with_context("query", &name))?;
+            results.insert(name, result);
+        }
+
+        serde_json::to_string(&results).map_err(ProcessorError::serialization)
+    }
+
+    fn run_batch_query(&self, query: BatchQuery) -> Result<Value, ProcessorError> {
+        match query {
+            BatchQuery::Constructs { graph, options } => {
+                let constructs = self.cached(
+                    |cache| &mut cache.constructs,
+                    graph.clone(),
+                    || self.collect_constructs(graph.as_deref()),
+                )?;
+                self.to_value(&options.apply(&constructs))
+            }
+            BatchQuery::Entanglements { graph, options } => {
+                let entanglements = self.cached(
+                    |cache| &mut cache.entanglements,
+                    graph.clone(),
+                    || self.collect_entanglements(graph.as_deref()),
+                )?;
+                self.to_value(&options.apply(&entanglements))
+            }
+            BatchQuery::Characters { graph, options } => {
+                let characters = self.cached(
+                    |cache| &mut cache.characters,
+                    graph.clone(),
+                    || self.collect_characters(graph.as_deref()),
+                )?;
+                self.to_value(&options.apply(&characters))
+            }
+            BatchQuery::Graph => {
+                let graph = self.cached(
+                    |cache| &mut cache.network_graph,
+                    (),
+                    || self.collect_network_graph(),
+                )?;
+                self.to_value(&*graph)
+            }
+            BatchQuery::Match {
+                subject,
+                predicate,
+                object,
+            } => self.to_value(&self.match_pattern(
+                subject.as_deref(),
+                predicate.as_deref(),
+                object.as_deref(),
+            )?),
+            BatchQuery::Sparql { query } => {
+                let query = parse_select(&query, &self.namespaces, &self.aliases)
+                    .map_err(|e| ProcessorError::parse("SPARQL query", e))?;
+                self.to_value(&evaluate_select(&self.graph, &query, &self.abort)?)
+            }
+        }
+    }
+
+    /// Convert a result to JSON in the selected key case and IRI form
+    fn to_value<T: Serialize + ?Sized>(&self, value: &T) -> Result<Value, ProcessorError> {
+        let curies = self.curies();
+        serde_json::to_value(self.shaped(value, curies.as_ref()))
+            .map_err(ProcessorError::serialization)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_runs_named_queries() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdf: <https://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+                @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+                sn:time rdf:type sn:Construct ; rdfs:label "Time" .
+                sn:mira rdf:type sn:Character ; rdfs:label "Mira" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let results = processor
+            .batch(
+                r#"{
+                    "constructs": {"type": "constructs", "options": {"limit": 5}},
+                    "cast": {"type": "characters"},
+                    "network": {"type": "graph"},
+                    "labels": {"type": "match", "predicate": "rdfs:label"}
+                }"#,
+            )
+            .unwrap();
+        let results: Value = serde_json::from_str(&results).unwrap();
+
+        assert_eq!(results["constructs"].as_array().unwrap().len(), 1);
+        assert_eq!(results["cast"].as_array().unwrap().len(), 1);
+        assert_eq!(results["network"]["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(results["labels"].as_array().unwrap().len(), 2);
+
+        let error = processor
+            .batch(r#"{"bad": {"type": "sparql", "query": "SELECT"}}"#)
+            .unwrap_err();
+        assert_eq!(error.context.get("query").map(String::as_str), Some("bad"));
+        assert!(processor.batch(r#"{"x": {"type": "unknown"}}"#).is_err());
+    }
+}
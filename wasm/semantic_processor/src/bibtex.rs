@@ -0,0 +1,190 @@
+//! BibTeX export
+//!
+//! Readers can import the works the site cites into their reference
+//! manager. `to_bibtex` writes every `sn:Work` as a BibTeX entry:
+//!
+//! ```javascript
+//! const blob = new Blob([processor.to_bibtex()], { type: 'application/x-bibtex' });
+//! link.href = URL.createObjectURL(blob);
+//! ```
+//!
+//! A work with an ISBN is a `@book`, one with a DOI an `@article`, and any
+//! other `@misc`. The key of each entry is the last segment of the work's
+//! IRI, made unique with a letter where two works share one. Characters
+//! special to LaTeX are escaped, except in the `url` of the work.
+
+use crate::error::ProcessorError;
+use crate::works::Work;
+use crate::SemanticProcessor;
+use std::collections::HashSet;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Export all works as BibTeX entries
+    ///
+    /// # Returns
+    /// * `Ok(String)` with one entry per work, sorted by IRI
+    /// * `Err(JsValue)` with error message if the graph cannot be read
+    pub fn to_bibtex(&self) -> Result<String, JsValue> {
+        self.check_abort()?;
+        Ok(self.bibtex()?)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn bibtex(&self) -> Result<String, ProcessorError> {
+        let works = self.cached(|cache| &mut cache.works, None, || self.collect_works(None))?;
+
+        let mut keys = HashSet::new();
+        let mut out = String::new();
+        for work in works.iter() {
+            let name = work.id.rsplit(['#', '/']).next().unwrap_or_default();
+            let key = unique_key(&mut keys, name);
+            let _ = writeln!(out, "@{}{{{},", entry_type(work), key);
+            let mut field = |name: &str, value: &str| {
+                let _ = writeln!(out, "  {} = {{{}}},", name, escape_latex(value));
+            };
+            if !work.authors.is_empty() {
+                field("author", &work.authors.join(" and "));
+            }
+            field("title", &work.title);
+            if let Some(year) = work.year {
+                field("year", &year.to_string());
+            }
+            if let Some(publisher) = &work.publisher {
+                field("publisher", publisher);
+            }
+            if let Some(isbn) = &work.isbn {
+                field("isbn", isbn);
+            }
+            // BibTeX styles read DOIs and URLs verbatim, so escaping would
+            // end up in the link; IRIs hold no braces
+            if let Some(doi) = &work.doi {
+                let _ = writeln!(out, "  doi = {{{}}},", doi);
+            }
+            let _ = writeln!(out, "  url = {{{}}},", work.id);
+            out.push_str("}\n\n");
+        }
+        out.truncate(out.trim_end().len());
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+/// The kind of BibTeX entry a work is written as
+fn entry_type(work: &Work) -> &'static str {
+    if work.isbn.is_some() {
+        "book"
+    } else if work.doi.is_some() {
+        "article"
+    } else {
+        "misc"
+    }
+}
+
+/// A citation key from a name, keeping the characters BibTeX allows, with
+/// `a`, `b`, ... appended if it is taken
+fn unique_key(taken: &mut HashSet<String>, name: &str) -> String {
+    let base: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        .collect();
+    let base = if base.is_empty() {
+        "work".to_string()
+    } else {
+        base
+    };
+    let mut key = base.clone();
+    let mut count = 0;
+    while !taken.insert(key.clone()) {
+        count += 1;
+        key = format!("{}{}", base, suffix(count));
+    }
+    key
+}
+
+/// The letters that tell apart the `n`th repeat of a key: a to z, then aa, ab…
+fn suffix(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    letters.iter().rev().map(|&c| c as char).collect()
+}
+
+/// Escape the characters LaTeX gives a meaning to
+fn escape_latex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bibtex_entries() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix dcterms: <http://purl.org/dc/terms/> .
+                sn:solaris a sn:Work ; dcterms:title "Solaris" ; dcterms:creator "Lem, S." ;
+                    dcterms:issued "1961" ; sn:isbn "978-0-15-602760-1" .
+                <https://example.org/solaris> a sn:Work ; dcterms:title "Heat & Time: 100%" ;
+                    dcterms:creator ( "Ng, A." "Abel, B." ) ; sn:doi "doi:10.1002/(SICI)1097_4571" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(
+            processor.bibtex().unwrap(),
+            "@article{solaris,\n  \
+               author = {Ng, A. and Abel, B.},\n  \
+               title = {Heat \\& Time: 100\\%},\n  \
+               doi = {10.1002/(SICI)1097_4571},\n  \
+               url = {https://example.org/solaris},\n\
+             }\n\n\
+             @book{solarisa,\n  \
+               author = {Lem, S.},\n  \
+               title = {Solaris},\n  \
+               year = {1961},\n  \
+               isbn = {978-0-15-602760-1},\n  \
+               url = {https://sinople.org/ontology#solaris},\n\
+             }\n"
+        );
+        assert_eq!(SemanticProcessor::new().bibtex().unwrap(), "");
+    }
+
+    #[test]
+    fn test_repeated_keys_get_letter_suffixes() {
+        let mut taken = HashSet::new();
+        let keys: Vec<String> = (0..30).map(|_| unique_key(&mut taken, "lem")).collect();
+        assert_eq!(keys[0], "lem");
+        assert_eq!(keys[1], "lema");
+        assert_eq!(keys[26], "lemz");
+        assert_eq!(keys[27], "lemaa");
+        assert_eq!(keys[29], "lemac");
+        assert_eq!(suffix(26 * 27 + 1), "aaa");
+    }
+}
@@ -0,0 +1,418 @@
+//! Binary snapshots of the graph
+//!
+//! A compact encoding of the graph and its named graphs, meant to be cached
+//! (e.g. in IndexedDB) so repeat visits skip parsing Turtle:
+//!
+//! ```javascript
+//! const cached = await db.get('ontology');
+//! if (cached) processor.from_snapshot(cached);
+//! else { processor.load_turtle(ttl); await db.put('ontology', processor.to_snapshot()); }
+//! ```
+//!
+//! Layout, with all numbers as unsigned LEB128 varints:
+//!
+//! ```text
+//! "SNPS" version
+//! string count, then each string as byte length and UTF-8 bytes
+//! term count, then each term as a kind byte and string or term indexes
+//! triple count, then each triple as three term indexes
+//! named graph count, then each as name string index, triple count and triples
+//! ```
+//!
+//! Terms only refer to strings and to terms listed before them.
+//!
+//! Only asserted triples are stored. Restoring a snapshot materializes the
+//! inferences again at the restoring processor's reasoning level, so they
+//! stay explained and go away with their premises.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::term::{BnodeId, IriRef, LanguageTag, SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_api::MownStr;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+const MAGIC: &[u8; 4] = b"SNPS";
+const VERSION: u64 = 1;
+
+const IRI: u8 = 0;
+const BLANK_NODE: u8 = 1;
+const TYPED_LITERAL: u8 = 2;
+const LANGUAGE_LITERAL: u8 = 3;
+const QUOTED_TRIPLE: u8 = 4;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Serialize the graph and its named graphs to a binary snapshot
+    ///
+    /// # Returns
+    /// Uint8Array to pass to `from_snapshot` later
+    pub fn to_snapshot(&self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.encode_snapshot()?)
+    }
+
+    /// Replace the graph and its named graphs with a binary snapshot
+    ///
+    /// Source tracking and the undo history are cleared. Nothing changes if
+    /// the snapshot is invalid.
+    pub fn from_snapshot(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        Ok(self.decode_snapshot(bytes)?)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn encode_snapshot(&self) -> Result<Vec<u8>, ProcessorError> {
+        let mut encoder = Encoder::default();
+        let mut named: Vec<(&String, &FastGraph)> = self.named_graphs.iter().collect();
+        named.sort_by_key(|(name, _)| *name);
+
+        // Intern everything first, so the tables can be written up front
+        let main = encoder.triples(&self.graph, |triple| !self.inferred.contains_key(triple))?;
+        let named: Vec<(usize, Vec<[usize; 3]>)> = named
+            .into_iter()
+            .map(|(name, graph)| Ok((encoder.string(name), encoder.triples(graph, |_| true)?)))
+            .collect::<Result<_, ProcessorError>>()?;
+
+        let mut out = MAGIC.to_vec();
+        write_varint(&mut out, VERSION);
+        write_varint(&mut out, encoder.strings.len() as u64);
+        for string in &encoder.strings {
+            write_varint(&mut out, string.len() as u64);
+            out.extend_from_slice(string.as_bytes());
+        }
+        write_varint(&mut out, encoder.terms.len() as u64);
+        for (kind, refs) in &encoder.terms {
+            out.push(*kind);
+            for index in refs {
+                write_varint(&mut out, *index as u64);
+            }
+        }
+        write_triples(&mut out, &main);
+        write_varint(&mut out, named.len() as u64);
+        for (name, triples) in &named {
+            write_varint(&mut out, *name as u64);
+            write_triples(&mut out, triples);
+        }
+        Ok(out)
+    }
+
+    pub(crate) fn decode_snapshot(&mut self, bytes: &[u8]) -> Result<(), ProcessorError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(invalid("not a snapshot"));
+        }
+        let version = reader.varint()?;
+        if version != VERSION {
+            return Err(invalid(format!("unsupported version {}", version)));
+        }
+
+        let mut strings = Vec::new();
+        for _ in 0..reader.varint()? {
+            let length = reader.varint()? as usize;
+            let string =
+                std::str::from_utf8(reader.take(length)?).map_err(|e| invalid(e.to_string()))?;
+            strings.push(string.to_string());
+        }
+
+        let mut terms: Vec<SimpleTerm<'static>> = Vec::new();
+        for _ in 0..reader.varint()? {
+            let kind = reader.take(1)?[0];
+            let term = match kind {
+                IRI => SimpleTerm::Iri(iri(reader.string(&strings)?)?),
+                BLANK_NODE => SimpleTerm::BlankNode(
+                    BnodeId::new(MownStr::from(reader.string(&strings)?.to_string()))
+                        .map_err(|e| invalid(e.to_string()))?,
+                ),
+                TYPED_LITERAL => {
+                    let lexical = reader.string(&strings)?.to_string();
+                    SimpleTerm::LiteralDatatype(
+                        MownStr::from(lexical),
+                        iri(reader.string(&strings)?)?,
+                    )
+                }
+                LANGUAGE_LITERAL => {
+                    let lexical = reader.string(&strings)?.to_string();
+                    let tag = LanguageTag::new(MownStr::from(reader.string(&strings)?.to_string()))
+                        .map_err(|e| invalid(e.to_string()))?;
+                    SimpleTerm::LiteralLanguage(MownStr::from(lexical), tag)
+                }
+                QUOTED_TRIPLE => {
+                    let [s, p, o] = reader.triple(&terms)?;
+                    SimpleTerm::Triple(Box::new([s, p, o]))
+                }
+                other => return Err(invalid(format!("unknown term kind {}", other))),
+            };
+            terms.push(term);
+        }
+
+        let graph = reader.graph(&terms)?;
+        let mut named_graphs = HashMap::new();
+        for _ in 0..reader.varint()? {
+            let name = reader.string(&strings)?.to_string();
+            named_graphs.insert(name, reader.graph(&terms)?);
+        }
+        if reader.position != bytes.len() {
+            return Err(invalid("trailing bytes"));
+        }
+
+        self.graph = graph;
+        self.named_graphs = named_graphs;
+        self.invalidate_cache();
+        self.sources.clear();
+        self.inferred.clear();
+        self.materialize();
+        self.reset_history();
+        Ok(())
+    }
+}
+
+/// String and term tables built while encoding
+#[derive(Default)]
+struct Encoder {
+    strings: Vec<String>,
+    string_index: HashMap<String, usize>,
+    terms: Vec<(u8, Vec<usize>)>,
+    term_index: HashMap<SimpleTerm<'static>, usize>,
+}
+
+impl Encoder {
+    fn string(&mut self, string: &str) -> usize {
+        if let Some(&index) = self.string_index.get(string) {
+            return index;
+        }
+        self.strings.push(string.to_string());
+        self.string_index
+            .insert(string.to_string(), self.strings.len() - 1);
+        self.strings.len() - 1
+    }
+
+    fn term(&mut self, term: SimpleTerm<'static>) -> Result<usize, ProcessorError> {
+        if let Some(&index) = self.term_index.get(&term) {
+            return Ok(index);
+        }
+        let entry = match &term {
+            SimpleTerm::Iri(iri) => (IRI, vec![self.string(iri.as_str())]),
+            SimpleTerm::BlankNode(id) => (BLANK_NODE, vec![self.string(id.as_str())]),
+            SimpleTerm::LiteralDatatype(lexical, datatype) => (
+                TYPED_LITERAL,
+                vec![self.string(lexical), self.string(datatype.as_str())],
+            ),
+            SimpleTerm::LiteralLanguage(lexical, tag) => (
+                LANGUAGE_LITERAL,
+                vec![self.string(lexical), self.string(tag.as_str())],
+            ),
+            SimpleTerm::Triple(triple) => {
+                let [s, p, o] = triple.as_ref().clone();
+                (
+                    QUOTED_TRIPLE,
+                    vec![self.term(s)?, self.term(p)?, self.term(o)?],
+                )
+            }
+            SimpleTerm::Variable(_) => {
+                return Err(ProcessorError::serialization(
+                    "variables cannot be stored in a snapshot",
+                ))
+            }
+        };
+        self.terms.push(entry);
+        self.term_index.insert(term, self.terms.len() - 1);
+        Ok(self.terms.len() - 1)
+    }
+
+    /// Intern the triples of a graph that `keep` accepts
+    fn triples(
+        &mut self,
+        graph: &FastGraph,
+        keep: impl Fn(&[SimpleTerm<'static>; 3]) -> bool,
+    ) -> Result<Vec<[usize; 3]>, ProcessorError> {
+        graph
+            .triples()
+            .flatten()
+            .map(|t| [t.s().into_term(), t.p().into_term(), t.o().into_term()])
+            .filter(|triple| keep(triple))
+            .map(|[s, p, o]| Ok([self.term(s)?, self.term(p)?, self.term(o)?]))
+            .collect()
+    }
+}
+
+/// Cursor over snapshot bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ProcessorError> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn varint(&mut self) -> Result<u64, ProcessorError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("number too large"))
+    }
+
+    fn index(&mut self, length: usize) -> Result<usize, ProcessorError> {
+        let index = self.varint()? as usize;
+        if index < length {
+            Ok(index)
+        } else {
+            Err(invalid(format!("index {} out of range", index)))
+        }
+    }
+
+    fn string<'s>(&mut self, strings: &'s [String]) -> Result<&'s str, ProcessorError> {
+        Ok(&strings[self.index(strings.len())?])
+    }
+
+    fn triple(
+        &mut self,
+        terms: &[SimpleTerm<'static>],
+    ) -> Result<[SimpleTerm<'static>; 3], ProcessorError> {
+        Ok([
+            terms[self.index(terms.len())?].clone(),
+            terms[self.index(terms.len())?].clone(),
+            terms[self.index(terms.len())?].clone(),
+        ])
+    }
+
+    fn graph(&mut self, terms: &[SimpleTerm<'static>]) -> Result<FastGraph, ProcessorError> {
+        let mut graph = FastGraph::new();
+        for _ in 0..self.varint()? {
+            let [s, p, o] = self.triple(terms)?;
+            graph
+                .insert(&s, &p, &o)
+                .map_err(|e| invalid(e.to_string()))?;
+        }
+        Ok(graph)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn write_triples(out: &mut Vec<u8>, triples: &[[usize; 3]]) {
+    write_varint(out, triples.len() as u64);
+    for triple in triples {
+        for index in triple {
+            write_varint(out, *index as u64);
+        }
+    }
+}
+
+fn iri(iri: &str) -> Result<IriRef<MownStr<'static>>, ProcessorError> {
+    IriRef::new(MownStr::from(iri.to_string())).map_err(|e| invalid(e.to_string()))
+}
+
+fn invalid(reason: impl std::fmt::Display) -> ProcessorError {
+    ProcessorError::parse("snapshot", reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let trig = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            sn:a sn:label "A"@en ; sn:weight 2 ; sn:related [ sn:label "anon" ] .
+            sn:g { sn:b sn:label "B" . }
+        "#;
+        let mut processor = SemanticProcessor::new();
+        processor.load_trig(trig).unwrap();
+        let bytes = processor.encode_snapshot().unwrap();
+
+        let mut restored = SemanticProcessor::new();
+        restored.decode_snapshot(&bytes).unwrap();
+
+        assert_eq!(restored.triple_count(), processor.triple_count());
+        assert!(restored
+            .named_graphs
+            .contains_key("https://sinople.org/ontology#g"));
+        let triples = |graph: &FastGraph| -> std::collections::HashSet<[SimpleTerm<'static>; 3]> {
+            graph
+                .triples()
+                .flatten()
+                .map(|t| [t.s().into_term(), t.p().into_term(), t.o().into_term()])
+                .collect()
+        };
+        assert_eq!(triples(&restored.graph), triples(&processor.graph));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_inferences_inferred() {
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            sn:Construct rdfs:subClassOf sn:Concept .
+            sn:time rdf:type sn:Construct .
+        "#;
+        let mut processor = SemanticProcessor::new();
+        processor.set_reasoning_level("rdfs").unwrap();
+        processor.load_turtle(ttl, None).unwrap();
+        let bytes = processor.encode_snapshot().unwrap();
+
+        let mut restored = SemanticProcessor::new();
+        restored.set_reasoning_level("rdfs").unwrap();
+        restored.decode_snapshot(&bytes).unwrap();
+        assert_eq!(restored.triple_count(), processor.triple_count());
+        let explanation = restored
+            .explanation("sn:time", "rdf:type", "sn:Concept")
+            .unwrap()
+            .unwrap();
+        assert!(!explanation.asserted);
+
+        let premise = restored
+            .triple_terms("sn:time", "rdf:type", "sn:Construct", false)
+            .unwrap();
+        restored.remove_terms(&premise);
+        restored.finish_edit();
+        assert!(restored
+            .explanation("sn:time", "rdf:type", "sn:Concept")
+            .unwrap()
+            .is_none());
+
+        // Without reasoning, the snapshot holds the asserted triples only
+        let mut plain = SemanticProcessor::new();
+        plain.decode_snapshot(&bytes).unwrap();
+        assert_eq!(plain.triple_count(), 2);
+    }
+
+    #[test]
+    fn test_invalid_snapshot_is_rejected() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_ntriples("<https://example.org/a> <https://example.org/p> \"x\" .")
+            .unwrap();
+        let bytes = processor.encode_snapshot().unwrap();
+
+        assert!(processor.decode_snapshot(b"TTL!").is_err());
+        assert!(processor
+            .decode_snapshot(&bytes[..bytes.len() - 1])
+            .is_err());
+        assert_eq!(processor.triple_count(), 1);
+    }
+}
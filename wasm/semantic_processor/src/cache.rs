@@ -0,0 +1,111 @@
+//! Memoized query results
+//!
+//! `query_constructs`, `query_entanglements`, `query_characters`,
+//! `query_events`, `query_places`, `query_works`, `generate_network_graph`
+//! and `export_all` remember their results until the graph changes, and the
+//! latest layout is kept for exports until then too.
+//! Every path that modifies the graph or its named graphs calls
+//! [`SemanticProcessor::invalidate_cache`].
+
+use crate::error::ProcessorError;
+use crate::events::Event;
+use crate::export::SemanticExport;
+use crate::identity::Identities;
+use crate::inference::Hierarchy;
+use crate::places::Place;
+use crate::works::Work;
+use crate::{Character, Construct, Entanglement, NetworkGraph, SemanticProcessor};
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+/// Results computed since the graph last changed, keyed by query arguments
+#[derive(Default)]
+pub(crate) struct QueryCache {
+    pub constructs: HashMap<Option<String>, Rc<Vec<Construct>>>,
+    pub entanglements: HashMap<Option<String>, Rc<Vec<Entanglement>>>,
+    pub characters: HashMap<Option<String>, Rc<Vec<Character>>>,
+    pub events: HashMap<Option<String>, Rc<Vec<Event>>>,
+    pub places: HashMap<Option<String>, Rc<Vec<Place>>>,
+    pub works: HashMap<Option<String>, Rc<Vec<Work>>>,
+    pub network_graph: HashMap<(), Rc<NetworkGraph>>,
+    pub export: HashMap<(), Rc<SemanticExport>>,
+    /// Closure of `rdfs:subClassOf`, shared by all type queries
+    pub class_hierarchy: Option<Rc<Hierarchy>>,
+    /// Closure of `rdfs:subPropertyOf`, shared by all relationship queries
+    pub property_hierarchy: Option<Rc<Hierarchy>>,
+    /// Resources linked by `owl:sameAs`, and the graphs with them merged
+    pub identities: Option<Rc<Identities>>,
+    pub merged_graphs: HashMap<Option<String>, Rc<FastGraph>>,
+    /// Position of each node in the latest layout of the network graph
+    pub layout: Option<Rc<HashMap<String, (f64, f64)>>>,
+}
+
+impl SemanticProcessor {
+    /// Look up a result in the cache, computing and storing it if missing
+    pub(crate) fn cached<K, T>(
+        &self,
+        slot: fn(&mut QueryCache) -> &mut HashMap<K, Rc<T>>,
+        key: K,
+        compute: impl FnOnce() -> Result<T, ProcessorError>,
+    ) -> Result<Rc<T>, ProcessorError>
+    where
+        K: Eq + Hash,
+    {
+        if let Some(hit) = slot(&mut self.cache.borrow_mut()).get(&key) {
+            return Ok(Rc::clone(hit));
+        }
+        let value = Rc::new(compute()?);
+        slot(&mut self.cache.borrow_mut()).insert(key, Rc::clone(&value));
+        Ok(value)
+    }
+
+    /// Forget all cached results and interned strings; called whenever the
+    /// graph changes
+    pub(crate) fn invalidate_cache(&mut self) {
+        *self.cache.get_mut() = QueryCache::default();
+        self.strings.get_mut().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_are_cached_until_the_graph_changes() {
+        let mut processor = SemanticProcessor::new();
+        let triple = processor
+            .triple_terms("sn:a", "rdf:type", "sn:Construct", false)
+            .unwrap();
+        processor.add_terms(&triple);
+
+        let first = processor
+            .cached(
+                |c| &mut c.constructs,
+                None,
+                || processor.collect_constructs(None),
+            )
+            .unwrap();
+        let again = processor
+            .cached(
+                |c| &mut c.constructs,
+                None,
+                || processor.collect_constructs(None),
+            )
+            .unwrap();
+        assert!(Rc::ptr_eq(&first, &again));
+
+        processor.remove_terms(&triple);
+        let after = processor
+            .cached(
+                |c| &mut c.constructs,
+                None,
+                || processor.collect_constructs(None),
+            )
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert!(after.is_empty());
+    }
+}
@@ -0,0 +1,471 @@
+//! Key casing of serialized results
+//!
+//! Result fields are snake_case (`node_type`, `relationship_type`). Front
+//! ends following JavaScript conventions can ask for camelCase instead:
+//!
+//! ```javascript
+//! processor.set_key_case('camel');
+//! processor.generate_network_graph().nodes[0].nodeType;
+//! ```
+//!
+//! Only the names of struct fields are converted. Keys that are data, such
+//! as the predicate IRIs of `describe` or the variables of `sparql_select`,
+//! are kept as they are.
+//!
+//! The serializer doing the renaming also compacts IRIs to CURIEs when
+//! `set_compact_iris` is on; see `curie`.
+
+use crate::curie::Curies;
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::ser::{
+    SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// Casing of the field names in results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum KeyCase {
+    #[default]
+    Snake,
+    Camel,
+}
+
+impl KeyCase {
+    fn parse(name: &str) -> Result<Self, ProcessorError> {
+        match name.to_ascii_lowercase().as_str() {
+            "snake" | "snake_case" => Ok(Self::Snake),
+            "camel" | "camelcase" => Ok(Self::Camel),
+            _ => Err(ProcessorError::invalid_argument(format!(
+                "Unknown key case '{}'; expected 'snake' or 'camel'",
+                name
+            ))
+            .with_context("case", name)),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose the casing of field names in results
+    ///
+    /// # Arguments
+    /// * `case` - `"snake"` (the default) or `"camel"`
+    pub fn set_key_case(&mut self, case: &str) -> Result<(), JsValue> {
+        self.key_case = KeyCase::parse(case)?;
+        Ok(())
+    }
+}
+
+/// A value serialized with its field names in the given case
+///
+/// With `curies`, strings that start with a registered namespace are
+/// compacted to CURIEs as well.
+pub(crate) struct Shaped<'a, T: ?Sized> {
+    pub value: &'a T,
+    pub case: KeyCase,
+    pub curies: Option<&'a Curies>,
+}
+
+impl<T: Serialize + ?Sized> Serialize for Shaped<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self.case, self.curies) {
+            (KeyCase::Snake, None) => self.value.serialize(serializer),
+            (case, curies) => self.value.serialize(ShapeSerializer {
+                inner: serializer,
+                shape: Shape { case, curies },
+            }),
+        }
+    }
+}
+
+thread_local! {
+    /// camelCase forms of field names; field names are `&'static str`, and
+    /// there are only as many as there are struct fields
+    static CAMEL_NAMES: RefCell<HashMap<&'static str, &'static str>> = RefCell::default();
+}
+
+/// Convert a snake_case field name to camelCase
+fn camel_case(name: &'static str) -> &'static str {
+    if !name.contains('_') {
+        return name;
+    }
+    CAMEL_NAMES.with(|names| {
+        *names.borrow_mut().entry(name).or_insert_with(|| {
+            let mut camel = String::with_capacity(name.len());
+            let mut upper = false;
+            for c in name.chars() {
+                if c == '_' && !camel.is_empty() {
+                    upper = true;
+                } else if upper {
+                    camel.extend(c.to_uppercase());
+                    upper = false;
+                } else {
+                    camel.push(c);
+                }
+            }
+            Box::leak(camel.into_boxed_str())
+        })
+    })
+}
+
+/// How nested values are serialized
+#[derive(Clone, Copy)]
+struct Shape<'a> {
+    case: KeyCase,
+    curies: Option<&'a Curies>,
+}
+
+impl<'a> Shape<'a> {
+    fn nest<'v, T: ?Sized>(self, value: &'v T) -> Shaped<'v, T>
+    where
+        'a: 'v,
+    {
+        Shaped {
+            value,
+            case: self.case,
+            curies: self.curies,
+        }
+    }
+
+    fn compound<C>(self, inner: C) -> ShapeCompound<'a, C> {
+        ShapeCompound { inner, shape: self }
+    }
+
+    fn field(self, name: &'static str) -> &'static str {
+        match self.case {
+            KeyCase::Snake => name,
+            KeyCase::Camel => camel_case(name),
+        }
+    }
+}
+
+/// Serializer that renames struct fields, compacts strings and passes
+/// everything else through
+struct ShapeSerializer<'a, S> {
+    inner: S,
+    shape: Shape<'a>,
+}
+
+/// Compound serializer whose elements are serialized with [`Shaped`]
+struct ShapeCompound<'a, C> {
+    inner: C,
+    shape: Shape<'a>,
+}
+
+macro_rules! forward {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+            self.inner.$method(v)
+        })*
+    };
+}
+
+impl<'a, S: Serializer> Serializer for ShapeSerializer<'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = ShapeCompound<'a, S::SerializeSeq>;
+    type SerializeTuple = ShapeCompound<'a, S::SerializeTuple>;
+    type SerializeTupleStruct = ShapeCompound<'a, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = ShapeCompound<'a, S::SerializeTupleVariant>;
+    type SerializeMap = ShapeCompound<'a, S::SerializeMap>;
+    type SerializeStruct = ShapeCompound<'a, S::SerializeStruct>;
+    type SerializeStructVariant = ShapeCompound<'a, S::SerializeStructVariant>;
+
+    forward!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        match self.shape.curies.and_then(|curies| curies.compact(v)) {
+            Some(curie) => self.inner.serialize_str(&curie),
+            None => self.inner.serialize_str(v),
+        }
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_some(&self.shape.nest(value))
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_unit_variant(name, index, variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner
+            .serialize_newtype_struct(name, &self.shape.nest(value))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        self.inner
+            .serialize_newtype_variant(name, index, variant, &self.shape.nest(value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        let inner = self.inner.serialize_seq(len)?;
+        Ok(self.shape.compound(inner))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        let inner = self.inner.serialize_tuple(len)?;
+        Ok(self.shape.compound(inner))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(self.shape.compound(inner))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, index, variant, len)?;
+        Ok(self.shape.compound(inner))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let inner = self.inner.serialize_map(len)?;
+        Ok(self.shape.compound(inner))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let inner = self.inner.serialize_struct(name, len)?;
+        Ok(self.shape.compound(inner))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, index, variant, len)?;
+        Ok(self.shape.compound(inner))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<C: SerializeSeq> SerializeSeq for ShapeCompound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_element(&self.shape.nest(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTuple> SerializeTuple for ShapeCompound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_element(&self.shape.nest(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleStruct> SerializeTupleStruct for ShapeCompound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_field(&self.shape.nest(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleVariant> SerializeTupleVariant for ShapeCompound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_field(&self.shape.nest(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeMap> SerializeMap for ShapeCompound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        self.inner.serialize_key(&self.shape.nest(key))
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.inner.serialize_value(&self.shape.nest(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStruct> SerializeStruct for ShapeCompound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.inner
+            .serialize_field(self.shape.field(key), &self.shape.nest(value))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(self.shape.field(key))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStructVariant> SerializeStructVariant for ShapeCompound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.inner
+            .serialize_field(self.shape.field(key), &self.shape.nest(value))
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(self.shape.field(key))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GraphNode, NetworkGraph};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_camel_case_renames_struct_fields_only() {
+        let graph = NetworkGraph {
+            nodes: vec![GraphNode {
+                id: "sn:a".to_string(),
+                label: "A".to_string(),
+                node_type: "construct".to_string(),
+                types: Vec::new(),
+                in_degree: 0,
+                out_degree: 0,
+            }],
+            edges: Vec::new(),
+        };
+        let camel = serde_json::to_value(Shaped {
+            value: &graph,
+            case: KeyCase::Camel,
+            curies: None,
+        })
+        .unwrap();
+        assert_eq!(camel["nodes"][0]["nodeType"], "construct");
+        assert!(camel["nodes"][0].get("node_type").is_none());
+
+        let snake = serde_json::to_value(Shaped {
+            value: &graph,
+            case: KeyCase::Snake,
+            curies: None,
+        })
+        .unwrap();
+        assert_eq!(snake["nodes"][0]["node_type"], "construct");
+
+        let data = BTreeMap::from([("has_source", 1)]);
+        let camel = serde_json::to_value(Shaped {
+            value: &data,
+            case: KeyCase::Camel,
+            curies: None,
+        })
+        .unwrap();
+        assert_eq!(camel["has_source"], 1);
+
+        assert_eq!(camel_case("distinct_subjects"), "distinctSubjects");
+        assert_eq!(camel_case("id"), "id");
+    }
+}
@@ -0,0 +1,315 @@
+//! Centrality of network graph nodes
+//!
+//! The landing page highlights the resources that the rest of the network
+//! points to most. `compute_pagerank` scores the nodes of the network graph
+//! by PageRank, where each edge passes on a share of its source's score in
+//! proportion to its weight:
+//!
+//! ```javascript
+//! const [top] = processor.compute_pagerank(0.85, 50);
+//! console.log(`${top.label} is the most central (${top.score.toFixed(3)})`);
+//! ```
+//!
+//! Symmetric edges pass score both ways. The scores add up to 1.
+//!
+//! `betweenness_centrality` instead finds the bridges: the nodes that lie on
+//! the most shortest paths between other nodes, which are the ones joining
+//! otherwise separate clusters.
+
+use crate::error::ProcessorError;
+use crate::{NetworkGraph, SemanticProcessor};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use wasm_bindgen::prelude::*;
+
+/// Damping factor used when none is given
+const DEFAULT_DAMPING: f64 = 0.85;
+
+/// Iterations used when no number is given
+const DEFAULT_ITERATIONS: u32 = 50;
+
+/// The centrality of one node
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeScore {
+    pub id: String,
+    pub label: String,
+    pub score: f64,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Score the nodes of the network graph by PageRank
+    ///
+    /// # Arguments
+    /// * `damping` - Probability of following an edge rather than jumping to
+    ///   any node, between 0 and 1; 0.85 if omitted
+    /// * `iterations` - Number of rounds of passing scores along edges; 50 if
+    ///   omitted
+    ///
+    /// # Returns
+    /// JsValue containing array of {id, label, score}, highest score first
+    #[wasm_bindgen(unchecked_return_type = "NodeScore[]")]
+    pub fn compute_pagerank(
+        &self,
+        damping: Option<f64>,
+        iterations: Option<u32>,
+    ) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let scores = self.pagerank(
+            damping.unwrap_or(DEFAULT_DAMPING),
+            iterations.unwrap_or(DEFAULT_ITERATIONS),
+        )?;
+
+        self.output(&scores)
+    }
+
+    /// Score the nodes of the network graph by betweenness centrality
+    ///
+    /// Edges are followed in either direction. Scores are normalized to
+    /// between 0 and 1, where 1 is the centre of a star.
+    ///
+    /// # Returns
+    /// JsValue containing array of {id, label, score}, highest score first
+    #[wasm_bindgen(unchecked_return_type = "NodeScore[]")]
+    pub fn betweenness_centrality(&self) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let scores = self.betweenness()?;
+
+        self.output(&scores)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn pagerank(
+        &self,
+        damping: f64,
+        iterations: u32,
+    ) -> Result<Vec<NodeScore>, ProcessorError> {
+        if !(0.0..=1.0).contains(&damping) {
+            return Err(ProcessorError::invalid_argument(format!(
+                "Damping factor {} is not between 0 and 1",
+                damping
+            ))
+            .with_context("damping", damping));
+        }
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+
+        let (ids, index) = node_index(&network);
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut links: Vec<Vec<(usize, f64)>> = vec![Vec::new(); ids.len()];
+        for edge in &network.edges {
+            let (source, target) = (index[edge.source.as_str()], index[edge.target.as_str()]);
+            links[source].push((target, edge.weight));
+            if edge.symmetric {
+                links[target].push((source, edge.weight));
+            }
+        }
+
+        let n = ids.len() as f64;
+        let mut scores = vec![1.0 / n; ids.len()];
+        for _ in 0..iterations {
+            self.check_abort()?;
+            // Nodes without outgoing edges share their score with every node
+            let dangling: f64 = links
+                .iter()
+                .zip(&scores)
+                .filter(|(targets, _)| targets.is_empty())
+                .map(|(_, score)| score)
+                .sum();
+            let mut next = vec![(1.0 - damping + damping * dangling) / n; ids.len()];
+            for (source, targets) in links.iter().enumerate() {
+                let total: f64 = targets.iter().map(|(_, weight)| weight).sum();
+                for &(target, weight) in targets {
+                    next[target] += damping * scores[source] * weight / total;
+                }
+            }
+            scores = next;
+        }
+
+        Ok(self.ranked(&network, &ids, scores))
+    }
+
+    /// Betweenness centrality by Brandes' algorithm, on undirected edges
+    pub(crate) fn betweenness(&self) -> Result<Vec<NodeScore>, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let (ids, index) = node_index(&network);
+        let links = undirected_links(&network, &index);
+
+        let mut scores = vec![0.0; ids.len()];
+        for start in 0..ids.len() {
+            self.check_abort()?;
+            // Breadth-first from the start, counting shortest paths to each node
+            let mut order = Vec::new();
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+            let mut paths = vec![0.0; ids.len()];
+            let mut distance = vec![usize::MAX; ids.len()];
+            paths[start] = 1.0;
+            distance[start] = 0;
+            let mut queue = VecDeque::from([start]);
+            while let Some(node) = queue.pop_front() {
+                order.push(node);
+                for &next in &links[node] {
+                    if distance[next] == usize::MAX {
+                        distance[next] = distance[node] + 1;
+                        queue.push_back(next);
+                    }
+                    if distance[next] == distance[node] + 1 {
+                        paths[next] += paths[node];
+                        predecessors[next].push(node);
+                    }
+                }
+            }
+
+            // Then back again, crediting each node with the paths through it
+            let mut dependency = vec![0.0; ids.len()];
+            for &node in order.iter().rev() {
+                for &previous in &predecessors[node] {
+                    dependency[previous] +=
+                        paths[previous] / paths[node] * (1.0 + dependency[node]);
+                }
+                if node != start {
+                    scores[node] += dependency[node];
+                }
+            }
+        }
+
+        // Each pair was counted from both ends
+        let n = ids.len() as f64;
+        let pairs = (n - 1.0) * (n - 2.0);
+        for score in &mut scores {
+            *score = if pairs > 0.0 { *score / pairs } else { 0.0 };
+        }
+        Ok(self.ranked(&network, &ids, scores))
+    }
+
+    /// Pair scores with their nodes, highest first
+    fn ranked(&self, network: &NetworkGraph, ids: &[&str], scores: Vec<f64>) -> Vec<NodeScore> {
+        let mut labels: BTreeMap<&str, &str> = BTreeMap::new();
+        for node in &network.nodes {
+            labels.entry(&node.id).or_insert(&node.label);
+        }
+        let mut ranked: Vec<NodeScore> = ids
+            .iter()
+            .zip(scores)
+            .map(|(id, score)| NodeScore {
+                id: id.to_string(),
+                label: labels
+                    .get(id)
+                    .map(|label| label.to_string())
+                    .unwrap_or_else(|| self.extract_local_name(id)),
+                score,
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        ranked
+    }
+}
+
+/// The nodes of a network graph and the edges' ends, sorted, with the
+/// position of each
+pub(crate) fn node_index(network: &NetworkGraph) -> (Vec<&str>, BTreeMap<&str, usize>) {
+    let mut ids: Vec<&str> = network
+        .nodes
+        .iter()
+        .map(|node| node.id.as_str())
+        .chain(
+            network
+                .edges
+                .iter()
+                .flat_map(|edge| [edge.source.as_str(), edge.target.as_str()]),
+        )
+        .collect();
+    ids.sort();
+    ids.dedup();
+    let index = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    (ids, index)
+}
+
+/// The neighbours of each node, ignoring direction, loops and repeated edges
+pub(crate) fn undirected_links(
+    network: &NetworkGraph,
+    index: &BTreeMap<&str, usize>,
+) -> Vec<Vec<usize>> {
+    let mut links: Vec<Vec<usize>> = vec![Vec::new(); index.len()];
+    for edge in &network.edges {
+        let (source, target) = (index[edge.source.as_str()], index[edge.target.as_str()]);
+        if source != target && !links[source].contains(&target) {
+            links[source].push(target);
+            links[target].push(source);
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pagerank_favours_pointed_to_nodes() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                sn:hub a sn:Construct ; rdfs:label "Hub" .
+                sn:a sn:relatesTo sn:hub .
+                sn:b sn:relatesTo sn:hub .
+                sn:c sn:relatesTo sn:hub .
+                sn:hub sn:relatesTo sn:a .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let scores = processor.pagerank(0.85, 50).unwrap();
+        assert_eq!(scores.len(), 4);
+        assert_eq!(scores[0].label, "Hub");
+        assert_eq!(scores[1].label, "a");
+        let total: f64 = scores.iter().map(|s| s.score).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // Without following edges every node is equally likely
+        let uniform = processor.pagerank(0.0, 10).unwrap();
+        assert!(uniform.iter().all(|s| (s.score - 0.25).abs() < 1e-9));
+
+        let error = processor.pagerank(1.5, 10).unwrap_err();
+        assert_eq!(error.context["damping"], "1.5");
+    }
+
+    #[test]
+    fn test_betweenness_finds_bridges() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:a1 sn:relatesTo sn:a2 . sn:a2 sn:relatesTo sn:a3 . sn:a3 sn:relatesTo sn:a1 .
+                sn:b1 sn:relatesTo sn:b2 . sn:b2 sn:relatesTo sn:b3 . sn:b3 sn:relatesTo sn:b1 .
+                sn:a1 sn:relatesTo sn:bridge .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:b1 ; sn:hasTarget sn:bridge .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let scores = processor.betweenness().unwrap();
+        let top: Vec<&str> = scores[..3].iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(top, ["bridge", "a1", "b1"]);
+        // 9 of the 21 pairs of other nodes are on either side of the bridge
+        assert!((scores[0].score - 9.0 / 21.0).abs() < 1e-9);
+        assert_eq!(scores[6].score, 0.0);
+    }
+}
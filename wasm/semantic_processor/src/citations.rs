@@ -0,0 +1,169 @@
+//! Citations
+//!
+//! Construct pages footnote the works a construct or gloss draws on.
+//! `citations_for` gathers them from the graph:
+//!
+//! ```javascript
+//! processor.citations_for('sn:entropy').forEach((c, i) => {
+//!   footnotes.append(`${i + 1}. ${c.authors.join(', ')}, ${c.title} (${c.year}), ${c.locator ?? ''}`);
+//! });
+//! ```
+//!
+//! A resource cites a work it points at with `dcterms:references` or
+//! `dcterms:source`, or a work that is `sn:citedBy` it. A resource that is
+//! `sn:citedBy` it and has a `dcterms:source` is a citation of its own,
+//! usually a blank node, which gives the `sn:locator` (page, chapter or
+//! section) of the passage cited:
+//!
+//! ```turtle
+//! [] sn:citedBy sn:entropy ; dcterms:source sn:solaris ; sn:locator "p. 23" .
+//! ```
+
+use crate::error::ProcessorError;
+use crate::works::DCTERMS;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+/// A reference to a work
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Citation {
+    pub authors: Vec<String>,
+    pub year: Option<i32>,
+    pub title: String,
+    /// Where in the work the passage is, e.g. `p. 23`
+    pub locator: Option<String>,
+    /// IRI of the work
+    pub work: String,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Find the works a construct or gloss cites
+    ///
+    /// # Arguments
+    /// * `iri` - Citing resource as `prefix:local` or full IRI
+    ///
+    /// # Returns
+    /// JsValue containing array of {authors, year, title, locator, work},
+    /// sorted by authors, year and title
+    #[wasm_bindgen(unchecked_return_type = "Citation[]")]
+    pub fn citations_for(&self, iri: &str) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let citations = self.citations(iri)?;
+
+        self.output(&citations)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn citations(&self, iri: &str) -> Result<Vec<Citation>, ProcessorError> {
+        let graph = self.query_graph(None)?;
+        let graph: &FastGraph = &graph;
+        let citing = self.make_term(iri);
+        let source = self.make_term(&format!("{}source", DCTERMS));
+        let locator = self.make_term("sn:locator");
+        let object = |subject: &SimpleTerm, predicate: &SimpleTerm| {
+            graph
+                .triples_matching([subject], [predicate], Any)
+                .flatten()
+                .next()
+                .map(|t| t.o().into_term::<SimpleTerm<'static>>())
+        };
+
+        // Each work cited, with the locator of the passage if given
+        let mut cited: BTreeSet<(String, Option<String>)> = BTreeSet::new();
+        for property in [
+            source.clone(),
+            self.make_term(&format!("{}references", DCTERMS)),
+        ] {
+            for t in graph
+                .triples_matching([&citing], [&property], Any)
+                .flatten()
+            {
+                if t.o().is_iri() {
+                    cited.insert((self.term_to_string(t.o()), None));
+                }
+            }
+        }
+        for t in graph
+            .triples_matching(Any, [self.make_term("sn:citedBy")], [&citing])
+            .flatten()
+        {
+            let citation = t.s().into_term::<SimpleTerm<'static>>();
+            let work = object(&citation, &source).unwrap_or_else(|| citation.clone());
+            if work.is_iri() {
+                cited.insert((
+                    self.term_to_string(&work),
+                    object(&citation, &locator).map(|l| self.term_to_string(&l)),
+                ));
+            }
+        }
+
+        let mut citations: Vec<Citation> = cited
+            .into_iter()
+            .map(|(work, locator)| {
+                let work = self.describe_work(graph, &work);
+                Citation {
+                    authors: work.authors,
+                    year: work.year,
+                    title: work.title,
+                    locator,
+                    work: work.id,
+                }
+            })
+            .collect();
+        citations.sort();
+        Ok(citations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_citations_with_locators() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix dcterms: <http://purl.org/dc/terms/> .
+                sn:solaris a sn:Work ; dcterms:title "Solaris" ;
+                    dcterms:creator "Lem, S." ; dcterms:issued "1961" .
+                sn:dune a sn:Work ; dcterms:title "Dune" ; dcterms:creator "Herbert, F." .
+                sn:essay dcterms:title "On Entropy" ; sn:citedBy sn:entropy .
+                [] sn:citedBy sn:entropy ; dcterms:source sn:solaris ; sn:locator "p. 23" .
+                sn:entropy a sn:Construct ; dcterms:references sn:dune, sn:solaris ;
+                    dcterms:source "folklore" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let citations = processor.citations("sn:entropy").unwrap();
+        let cited: Vec<(&str, Option<&str>)> = citations
+            .iter()
+            .map(|c| (c.title.as_str(), c.locator.as_deref()))
+            .collect();
+        assert_eq!(
+            cited,
+            [
+                ("On Entropy", None),
+                ("Dune", None),
+                ("Solaris", None),
+                ("Solaris", Some("p. 23"))
+            ]
+        );
+        assert_eq!(citations[2].authors, ["Lem, S."]);
+        assert_eq!(citations[2].year, Some(1961));
+        assert!(processor.citations("sn:nothing").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+//! Query results as class instances
+//!
+//! The `query_*` methods return plain objects. The methods here return the
+//! same results as instances of the exported classes, with getters for their
+//! fields and a few helpers:
+//!
+//! ```javascript
+//! for (const construct of processor.constructs()) {
+//!   const english = construct.glossesIn('en');
+//!   construct.free();
+//! }
+//! ```
+//!
+//! Instances are copies: changing one does not change the graph. Output
+//! encoding, key case and CURIE compaction do not apply to them.
+
+use crate::error::to_js;
+use crate::options::QueryOptions;
+use crate::{
+    Character, Construct, Entanglement, Gloss, GraphNode, NetworkGraph, SemanticProcessor,
+};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl Construct {
+    /// Glosses written in a language
+    ///
+    /// # Arguments
+    /// * `language` - Language tag, compared ignoring case
+    #[wasm_bindgen(js_name = glossesIn)]
+    pub fn glosses_in(&self, language: &str) -> Vec<Gloss> {
+        self.glosses
+            .iter()
+            .filter(|gloss| gloss.language.eq_ignore_ascii_case(language))
+            .cloned()
+            .collect()
+    }
+}
+
+#[wasm_bindgen]
+impl Entanglement {
+    /// Whether the entanglement has a construct as its source or target
+    pub fn involves(&self, construct_id: &str) -> bool {
+        self.source == construct_id || self.target == construct_id
+    }
+
+    /// Metadata from reified statements about the entanglement
+    #[wasm_bindgen(getter, unchecked_return_type = "Annotation[]")]
+    pub fn annotations(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.annotations)
+    }
+}
+
+#[wasm_bindgen]
+impl Character {
+    /// Whether the character is linked to a construct
+    #[wasm_bindgen(js_name = hasConstruct)]
+    pub fn has_construct(&self, construct_id: &str) -> bool {
+        self.constructs.iter().any(|id| id == construct_id)
+    }
+}
+
+#[wasm_bindgen]
+impl NetworkGraph {
+    /// Find a node by its IRI
+    pub fn node(&self, id: &str) -> Option<GraphNode> {
+        self.nodes.iter().find(|node| node.id == id).cloned()
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Query all constructs as `Construct` instances
+    ///
+    /// Arguments are as for `query_constructs`.
+    pub fn constructs(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<Vec<Construct>, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let constructs = self.cached(
+            |cache| &mut cache.constructs,
+            graph.clone(),
+            || self.collect_constructs(graph.as_deref()),
+        )?;
+
+        Ok(options.apply(&constructs))
+    }
+
+    /// Query all entanglements as `Entanglement` instances
+    ///
+    /// Arguments are as for `query_entanglements`.
+    pub fn entanglements(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<Vec<Entanglement>, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let entanglements = self.cached(
+            |cache| &mut cache.entanglements,
+            graph.clone(),
+            || self.collect_entanglements(graph.as_deref()),
+        )?;
+
+        Ok(options.apply(&entanglements))
+    }
+
+    /// Query all characters as `Character` instances
+    ///
+    /// Arguments are as for `query_characters`.
+    pub fn characters(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<Vec<Character>, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let characters = self.cached(
+            |cache| &mut cache.characters,
+            graph.clone(),
+            || self.collect_characters(graph.as_deref()),
+        )?;
+
+        Ok(options.apply(&characters))
+    }
+
+    /// Generate the network graph as a `NetworkGraph` instance
+    pub fn network_graph(&self) -> Result<NetworkGraph, JsValue> {
+        self.check_abort()?;
+        let graph = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+
+        Ok((*graph).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_helpers() {
+        let gloss = |language: &str| Gloss {
+            id: "sn:time#gloss".to_string(),
+            text: "Duration".to_string(),
+            language: language.to_string(),
+            position: None,
+        };
+        let construct = Construct {
+            id: "sn:time".to_string(),
+            label: "Time".to_string(),
+            description: None,
+            glosses: vec![gloss("en"), gloss("fr"), gloss("EN")],
+            relationships: Vec::new(),
+        };
+        assert_eq!(construct.glosses_in("en").len(), 2);
+        assert!(construct.glosses_in("de").is_empty());
+
+        let character = Character {
+            id: "sn:mira".to_string(),
+            name: "Mira".to_string(),
+            description: None,
+            constructs: vec!["sn:time".to_string()],
+        };
+        assert!(character.has_construct("sn:time"));
+        assert!(!character.has_construct("sn:space"));
+    }
+}
@@ -0,0 +1,143 @@
+//! RDF collections
+//!
+//! Ordered sequences are written as RDF lists, `( "first" "second" )` in
+//! Turtle, which the graph stores as a chain of `rdf:first`/`rdf:rest` nodes
+//! ending in `rdf:nil`. Glosses attached with `sn:hasGlossList` keep their
+//! order this way, and any other list can be read back in order:
+//!
+//! ```javascript
+//! const steps = processor.get_list('sn:ritual', 'sn:steps');
+//! steps.map((step) => step.value);
+//! ```
+
+use crate::error::ProcessorError;
+use crate::{SemanticProcessor, TermValue};
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Read the RDF list that is the value of a property, in order
+    ///
+    /// # Arguments
+    /// * `subject` - Resource as `prefix:local` or full IRI
+    /// * `predicate` - Property as `prefix:local` or full IRI
+    /// * `graph` - Optional named graph to read from
+    ///
+    /// # Returns
+    /// JsValue containing the items as {kind, value, datatype?, language?}
+    /// terms; empty if the property has no value
+    #[wasm_bindgen(unchecked_return_type = "TermValue[]")]
+    pub fn get_list(
+        &self,
+        subject: &str,
+        predicate: &str,
+        graph: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let items = self.list_values(subject, predicate, graph.as_deref())?;
+
+        self.output_json(&items)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn list_values(
+        &self,
+        subject: &str,
+        predicate: &str,
+        graph: Option<&str>,
+    ) -> Result<Vec<TermValue>, ProcessorError> {
+        let graph = self.select_graph(graph)?;
+        let subject = self.make_term(subject);
+        let predicate = self.make_term(predicate);
+
+        let head = graph
+            .triples_matching([&subject], [&predicate], Any)
+            .flatten()
+            .next()
+            .map(|t| t.o().into_term::<SimpleTerm<'static>>());
+        Ok(head
+            .map(|head| self.list_items(graph, head))
+            .unwrap_or_default()
+            .iter()
+            .map(TermValue::from_term)
+            .collect())
+    }
+
+    /// Follow an RDF list from its head node
+    ///
+    /// Stops at `rdf:nil`, at a node without `rdf:first`, or where the list
+    /// loops back on itself.
+    pub(crate) fn list_items(
+        &self,
+        graph: &FastGraph,
+        head: SimpleTerm<'static>,
+    ) -> Vec<SimpleTerm<'static>> {
+        let first = self.make_term("rdf:first");
+        let rest = self.make_term("rdf:rest");
+        let nil = self.make_term("rdf:nil");
+
+        let mut items = Vec::new();
+        let mut visited = HashSet::new();
+        let mut node = head;
+        while !Term::eq(&node, &nil) && visited.insert(node.clone()) {
+            let value = |property: &SimpleTerm| {
+                graph
+                    .triples_matching([&node], [property], Any)
+                    .flatten()
+                    .next()
+                    .map(|t| t.o().into_term::<SimpleTerm<'static>>())
+            };
+            let Some(item) = value(&first) else {
+                break;
+            };
+            items.push(item);
+            match value(&rest) {
+                Some(next) => node = next,
+                None => break,
+            }
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_items_in_order() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:ritual sn:steps ( "kindle" sn:wait 3 ) ;
+                    sn:empty () .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let steps = processor
+            .list_values("sn:ritual", "sn:steps", None)
+            .unwrap();
+        let values: Vec<&str> = steps.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, ["kindle", "https://sinople.org/ontology#wait", "3"]);
+        assert_eq!(steps[1].kind, "iri");
+
+        assert!(processor
+            .list_values("sn:ritual", "sn:empty", None)
+            .unwrap()
+            .is_empty());
+        assert!(processor
+            .list_values("sn:ritual", "sn:missing", None)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+//! Loading compressed documents
+//!
+//! Ontologies compress well, so they can be shipped gzip- or
+//! Brotli-compressed and decompressed here, without a JavaScript
+//! decompression library or an extra copy of the text in JavaScript:
+//!
+//! ```javascript
+//! const bytes = new Uint8Array(await (await fetch('/ontology.ttl.gz')).arrayBuffer());
+//! processor.load_turtle_gz(bytes);
+//! ```
+
+use crate::encoding::decode_text;
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Load RDF data from gzip-compressed Turtle
+    ///
+    /// The decompressed bytes are decoded as in `load_turtle_bytes`.
+    pub fn load_turtle_gz(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let data = gunzip(bytes)?;
+        self.load_turtle(&decode_text(&data)?, None)
+    }
+
+    /// Load RDF data from Brotli-compressed Turtle
+    ///
+    /// The decompressed bytes are decoded as in `load_turtle_bytes`.
+    pub fn load_turtle_br(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let data = unbrotli(bytes)?;
+        self.load_turtle(&decode_text(&data)?, None)
+    }
+}
+
+/// Decompress gzip data, including concatenated gzip members
+pub(crate) fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, ProcessorError> {
+    let mut data = Vec::new();
+    MultiGzDecoder::new(bytes)
+        .read_to_end(&mut data)
+        .map_err(|e| ProcessorError::parse("gzip", e))?;
+    Ok(data)
+}
+
+/// Decompress Brotli data
+pub(crate) fn unbrotli(bytes: &[u8]) -> Result<Vec<u8>, ProcessorError> {
+    let mut data = Vec::new();
+    brotli_decompressor::Decompressor::new(bytes, 4096)
+        .read_to_end(&mut data)
+        .map_err(|e| ProcessorError::parse("Brotli", e))?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TTL: &str = "<https://example.org/a> <https://example.org/p> \"x\" .";
+
+    #[test]
+    fn test_gunzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(TTL.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(gunzip(&compressed).unwrap(), TTL.as_bytes());
+        assert!(gunzip(TTL.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_unbrotli() {
+        let mut compressed = Vec::new();
+        {
+            let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+            encoder.write_all(TTL.as_bytes()).unwrap();
+        }
+
+        assert_eq!(unbrotli(&compressed).unwrap(), TTL.as_bytes());
+    }
+}
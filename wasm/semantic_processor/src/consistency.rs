@@ -0,0 +1,302 @@
+//! Domain and range checking
+//!
+//! The ontology declares what its properties connect:
+//!
+//! ```turtle
+//! sn:hasSource rdfs:domain sn:Entanglement ; rdfs:range sn:Construct .
+//! sn:published rdfs:range xsd:date .
+//! ```
+//!
+//! `check_consistency` reports every asserted triple that uses a property
+//! on a resource whose types do not include the declared class, such as a
+//! gloss given as the source of an entanglement, and every value of the
+//! wrong kind, such as a plain string where a date is expected:
+//!
+//! ```javascript
+//! for (const v of processor.check_consistency()) console.warn(v.message);
+//! ```
+//!
+//! A resource satisfies a class if one of its asserted types is the class or
+//! one of its subclasses. Declarations on a property apply to its
+//! sub-properties too. Resources without any type are not reported, since
+//! their types may be stated in a document that has not been loaded. Types
+//! added by reasoning are ignored, as `rdfs:domain` and `rdfs:range` would
+//! otherwise make every use consistent.
+
+use crate::{SemanticProcessor, TermValue};
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Classes every resource belongs to
+const UNIVERSAL: [&str; 2] = ["rdfs:Resource", "owl:Thing"];
+
+/// Datatypes of the RDF vocabulary, besides `rdfs:Literal`
+const RDF_DATATYPES: [&str; 4] = ["rdf:langString", "rdf:HTML", "rdf:XMLLiteral", "rdf:JSON"];
+
+/// A triple that does not respect a domain or range declaration
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    /// `"domain"` or `"range"`
+    pub kind: String,
+    pub subject: String,
+    pub predicate: String,
+    pub object: TermValue,
+    /// Class or datatype the declaration requires
+    pub expected: String,
+    /// Types of the offending resource, or the datatype of the offending literal
+    pub found: Vec<String>,
+    pub message: String,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Check asserted triples against `rdfs:domain` and `rdfs:range`
+    ///
+    /// # Returns
+    /// JsValue containing array of violations
+    /// {kind, subject, predicate, object, expected, found, message}, sorted
+    /// by subject and predicate; empty if the graph is consistent
+    #[wasm_bindgen(unchecked_return_type = "Violation[]")]
+    pub fn check_consistency(&self) -> Result<JsValue, JsValue> {
+        let violations = self.consistency_violations();
+
+        self.output(&violations)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn consistency_violations(&self) -> Vec<Violation> {
+        let rdf_type = self.make_term("rdf:type");
+        let datatype = self.make_term("rdfs:Datatype");
+        let literal = self.make_term("rdfs:Literal");
+        let universal: Vec<SimpleTerm> = UNIVERSAL.iter().map(|c| self.make_term(c)).collect();
+        let rdf_datatypes: Vec<SimpleTerm> =
+            RDF_DATATYPES.iter().map(|d| self.make_term(d)).collect();
+        let hierarchy = self.class_hierarchy();
+
+        let asserted =
+            |s: &SimpleTerm<'static>, p: &SimpleTerm<'static>, o: &SimpleTerm<'static>| {
+                !self
+                    .inferred
+                    .contains_key(&[s.clone(), p.clone(), o.clone()])
+            };
+        let types_of = |resource: &SimpleTerm<'static>| -> Vec<SimpleTerm<'static>> {
+            self.graph
+                .triples_matching([resource], [&rdf_type], Any)
+                .flatten()
+                .map(|t| t.o().into_term::<SimpleTerm<'static>>())
+                .filter(|class| asserted(resource, &rdf_type, class))
+                .collect()
+        };
+        let is_datatype = |class: &SimpleTerm| {
+            Term::eq(class, &literal)
+                || rdf_datatypes.iter().any(|d| Term::eq(class, d))
+                || class.iri().is_some_and(|iri| iri.as_str().starts_with(XSD))
+                || self
+                    .graph
+                    .contains(class, &rdf_type, &datatype)
+                    .unwrap_or(false)
+        };
+
+        let mut violations = Vec::new();
+        for kind in ["domain", "range"] {
+            let declaration = self.make_term(&format!("rdfs:{}", kind));
+            let declarations: Vec<[SimpleTerm<'static>; 3]> = self
+                .graph
+                .triples_matching(Any, [&declaration], Any)
+                .flatten()
+                .map(|t| t.to_spo().map(|term| term.into_term()))
+                .collect();
+
+            for [property, _, expected] in declarations {
+                if universal.iter().any(|class| Term::eq(class, &expected)) {
+                    continue;
+                }
+                let properties = self.subproperties_of(&self.term_to_string(&property));
+                let accepted: HashSet<SimpleTerm<'static>> = hierarchy
+                    .with_descendants(expected.clone())
+                    .into_iter()
+                    .collect();
+                let expects_literal = kind == "range" && is_datatype(&expected);
+
+                for t in self
+                    .graph
+                    .triples_matching(Any, &properties[..], Any)
+                    .flatten()
+                {
+                    let [s, p, o]: [SimpleTerm<'static>; 3] =
+                        t.to_spo().map(|term| term.into_term());
+                    if !asserted(&s, &p, &o) {
+                        continue;
+                    }
+                    let value = if kind == "domain" { &s } else { &o };
+
+                    let found = if expects_literal {
+                        let matches = value.is_literal()
+                            && (Term::eq(&expected, &literal)
+                                || value.datatype().is_some_and(|dt| Term::eq(&dt, &expected)));
+                        if matches {
+                            continue;
+                        }
+                        match value.datatype() {
+                            Some(dt) => vec![dt.as_str().to_string()],
+                            None => types_of(value)
+                                .iter()
+                                .map(|class| self.term_to_string(class))
+                                .collect(),
+                        }
+                    } else if value.is_literal() {
+                        vec![value
+                            .datatype()
+                            .map(|dt| dt.as_str().to_string())
+                            .unwrap_or_default()]
+                    } else {
+                        let types = types_of(value);
+                        if types.is_empty() || types.iter().any(|class| accepted.contains(class)) {
+                            continue;
+                        }
+                        let mut types: Vec<String> = types
+                            .iter()
+                            .map(|class| self.term_to_string(class))
+                            .collect();
+                        types.sort();
+                        types
+                    };
+
+                    let expected_name = self.term_to_string(&expected);
+                    let message = format!(
+                        "{} is the {} of {}, whose {} is {}, but {} {}",
+                        self.term_to_string(value),
+                        if kind == "domain" {
+                            "subject"
+                        } else {
+                            "object"
+                        },
+                        self.term_to_string(&p),
+                        kind,
+                        expected_name,
+                        if value.is_literal() {
+                            "it is typed"
+                        } else if found.is_empty() {
+                            "it has no type"
+                        } else {
+                            "it is a"
+                        },
+                        found.join(", "),
+                    );
+                    violations.push(Violation {
+                        kind: kind.to_string(),
+                        subject: self.term_to_string(&s),
+                        predicate: self.term_to_string(&p),
+                        object: TermValue::from_term(&o),
+                        expected: expected_name,
+                        found,
+                        message: message.trim_end().to_string(),
+                    });
+                }
+            }
+        }
+
+        violations.sort_by(|a, b| {
+            (
+                &a.subject,
+                &a.predicate,
+                &a.kind,
+                &a.expected,
+                &a.object.value,
+            )
+                .cmp(&(
+                    &b.subject,
+                    &b.predicate,
+                    &b.kind,
+                    &b.expected,
+                    &b.object.value,
+                ))
+        });
+        violations.dedup();
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+        sn:hasSource rdfs:domain sn:Entanglement ; rdfs:range sn:Construct .
+        sn:hasOrigin rdfs:subPropertyOf sn:hasSource .
+        sn:published rdfs:range xsd:date .
+        sn:CoreConstruct rdfs:subClassOf sn:Construct .
+        sn:time a sn:CoreConstruct .
+        sn:g1 a sn:Gloss .
+        sn:e1 a sn:Entanglement ; sn:hasSource sn:time ;
+            sn:published "2024-03-01"^^xsd:date .
+        sn:e2 a sn:Entanglement ; sn:hasOrigin sn:g1 ; sn:published "March" .
+        sn:e3 sn:hasSource sn:unknown .
+        sn:g1 sn:hasSource "time" .
+    "#;
+
+    #[test]
+    fn test_domain_and_range_violations() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        let violations = processor.consistency_violations();
+        let summary: Vec<(&str, &str, &str, &str)> = violations
+            .iter()
+            .map(|v| {
+                (
+                    v.kind.as_str(),
+                    v.subject
+                        .trim_start_matches("https://sinople.org/ontology#"),
+                    v.predicate
+                        .trim_start_matches("https://sinople.org/ontology#"),
+                    v.expected
+                        .trim_start_matches("https://sinople.org/ontology#"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("range", "e2", "hasOrigin", "Construct"),
+                (
+                    "range",
+                    "e2",
+                    "published",
+                    "http://www.w3.org/2001/XMLSchema#date"
+                ),
+                ("domain", "g1", "hasSource", "Entanglement"),
+                ("range", "g1", "hasSource", "Construct"),
+            ]
+        );
+        assert_eq!(violations[0].found, ["https://sinople.org/ontology#Gloss"]);
+        assert_eq!(
+            violations[1].found,
+            ["http://www.w3.org/2001/XMLSchema#string"]
+        );
+        assert!(violations[2]
+            .message
+            .ends_with("but it is a https://sinople.org/ontology#Gloss"));
+    }
+
+    #[test]
+    fn test_inferred_types_do_not_hide_violations() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        let before = processor.consistency_violations();
+
+        processor.set_reasoning_level("rdfs").unwrap();
+        assert_eq!(processor.consistency_violations(), before);
+    }
+}
@@ -0,0 +1,156 @@
+//! Compact IRIs (CURIEs)
+//!
+//! Full IRIs make results large and awkward to match against in front-end
+//! code. With compaction enabled, results carry `sn:Entropy` instead of
+//! `https://sinople.org/ontology#Entropy`:
+//!
+//! ```javascript
+//! processor.set_compact_iris(true);
+//! processor.query_constructs()[0].id;      // "sn:Entropy"
+//! processor.expand('sn:Entropy');          // "https://sinople.org/ontology#Entropy"
+//! ```
+//!
+//! Every string in a result that starts with a registered namespace is
+//! compacted, including map keys and literal values that happen to be IRIs.
+
+use crate::SemanticProcessor;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// The registered namespaces, ready for compacting IRIs
+#[derive(Debug, Clone)]
+pub(crate) struct Curies {
+    /// (namespace IRI, prefix), longest namespace first
+    namespaces: Vec<(String, String)>,
+}
+
+impl Curies {
+    pub fn new(namespaces: &HashMap<String, String>) -> Self {
+        let mut namespaces: Vec<(String, String)> = namespaces
+            .iter()
+            .map(|(prefix, iri)| (iri.clone(), prefix.clone()))
+            .collect();
+        // Prefer the most specific namespace; break ties by prefix so the
+        // result does not depend on hash order
+        namespaces.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.1.cmp(&b.1)));
+        Curies { namespaces }
+    }
+
+    /// Compact an IRI, if it is in a registered namespace and the rest of it
+    /// is a valid local name
+    pub fn compact(&self, iri: &str) -> Option<String> {
+        self.namespaces.iter().find_map(|(namespace, prefix)| {
+            let local = iri.strip_prefix(namespace.as_str())?;
+            is_local_name(local).then(|| format!("{}:{}", prefix, local))
+        })
+    }
+}
+
+/// Whether a string can follow the colon of a CURIE without escaping
+fn is_local_name(local: &str) -> bool {
+    !local.ends_with('.')
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose whether results carry CURIEs instead of full IRIs
+    ///
+    /// Applies to the same methods as `set_output_encoding`.
+    pub fn set_compact_iris(&mut self, enabled: bool) {
+        self.compact_iris = enabled;
+    }
+
+    /// Expand a CURIE to a full IRI
+    ///
+    /// # Returns
+    /// The full IRI, or `curie` unchanged if its prefix is not registered
+    pub fn expand(&self, curie: &str) -> String {
+        self.term_to_string(&self.make_term(curie))
+    }
+
+    /// Compact a full IRI to a CURIE
+    ///
+    /// # Returns
+    /// The CURIE, or `iri` unchanged if it is in no registered namespace
+    pub fn compact(&self, iri: &str) -> String {
+        Curies::new(&self.namespaces)
+            .compact(iri)
+            .unwrap_or_else(|| iri.to_string())
+    }
+}
+
+impl SemanticProcessor {
+    /// Namespaces to compact results with, if compaction is enabled
+    pub(crate) fn curies(&self) -> Option<Curies> {
+        self.compact_iris.then(|| Curies::new(&self.namespaces))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::casing::{KeyCase, Shaped};
+    use crate::Entanglement;
+
+    #[test]
+    fn test_compact_and_expand() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .add_namespace("ex", "https://sinople.org/ontology#extra/")
+            .unwrap();
+
+        assert_eq!(
+            processor.compact("https://sinople.org/ontology#Entropy"),
+            "sn:Entropy"
+        );
+        assert_eq!(
+            processor.compact("https://sinople.org/ontology#extra/a"),
+            "ex:a"
+        );
+        assert_eq!(
+            processor.compact("https://sinople.org/ontology#a/b"),
+            "https://sinople.org/ontology#a/b"
+        );
+        assert_eq!(
+            processor.compact("https://example.org/x"),
+            "https://example.org/x"
+        );
+        assert_eq!(
+            processor.expand("sn:Entropy"),
+            "https://sinople.org/ontology#Entropy"
+        );
+        assert_eq!(
+            processor.expand("https://example.org/x"),
+            "https://example.org/x"
+        );
+    }
+
+    #[test]
+    fn test_results_are_compacted() {
+        let processor = SemanticProcessor::new();
+        let entanglement = Entanglement {
+            id: "https://sinople.org/ontology#e1".to_string(),
+            label: "Entropy drives time".to_string(),
+            source: "https://sinople.org/ontology#entropy".to_string(),
+            target: "https://example.org/time".to_string(),
+            relationship_type: "related".to_string(),
+            description: None,
+            annotations: Vec::new(),
+        };
+        let curies = Curies::new(&processor.namespaces);
+        let value = serde_json::to_value(Shaped {
+            value: &entanglement,
+            case: KeyCase::Snake,
+            curies: Some(&curies),
+        })
+        .unwrap();
+
+        assert_eq!(value["id"], "sn:e1");
+        assert_eq!(value["source"], "sn:entropy");
+        assert_eq!(value["target"], "https://example.org/time");
+        assert_eq!(value["label"], "Entropy drives time");
+    }
+}
@@ -0,0 +1,259 @@
+//! Cycle detection
+//!
+//! Hierarchies drawn from directed relationships, such as chains of
+//! `sn:derivesFrom`, must not loop back on themselves. `find_cycles` finds
+//! every group of resources that can reach each other, and one loop through
+//! each group:
+//!
+//! ```javascript
+//! for (const cycle of processor.find_cycles('sn:derivesFrom')) {
+//!   console.warn(cycle.path.join(' → '));
+//! }
+//! ```
+//!
+//! Without a property, the directed edges of the network graph are checked:
+//! entanglements from source to target, and relationships not declared
+//! `owl:SymmetricProperty`.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::SimpleTerm;
+use sophia_api::triple::Triple;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use wasm_bindgen::prelude::*;
+
+/// Resources that can all reach each other
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Cycle {
+    /// Every resource of the group, sorted
+    pub nodes: Vec<String>,
+    /// One shortest loop, from the first node back to just before it
+    pub path: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Find cycles among directed relationships
+    ///
+    /// # Arguments
+    /// * `property_filter` - Only follow this property, with its
+    ///   sub-properties and inverses, e.g. `sn:derivesFrom`
+    ///
+    /// # Returns
+    /// JsValue containing array of {nodes, path}, one for each group of
+    /// resources in a cycle, sorted by first node; empty for a DAG
+    #[wasm_bindgen(unchecked_return_type = "Cycle[]")]
+    pub fn find_cycles(&self, property_filter: Option<String>) -> Result<JsValue, JsValue> {
+        let cycles = self.cycles(property_filter.as_deref())?;
+
+        self.output(&cycles)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn cycles(&self, property: Option<&str>) -> Result<Vec<Cycle>, ProcessorError> {
+        let mut successors: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut link = |from: String, to: String| {
+            successors.entry(to.clone()).or_default();
+            successors.entry(from).or_default().insert(to);
+        };
+
+        match property {
+            Some(property) => {
+                let graph = self.query_graph(None)?;
+                let forward = self.subproperties_of(property);
+                let inverses: Vec<SimpleTerm> = self
+                    .inverses_of(property)
+                    .into_iter()
+                    .map(|(inverse, _)| inverse)
+                    .collect();
+                for t in graph.triples_matching(Any, &forward[..], Any).flatten() {
+                    link(self.term_to_string(t.s()), self.term_to_string(t.o()));
+                }
+                for t in graph.triples_matching(Any, &inverses[..], Any).flatten() {
+                    link(self.term_to_string(t.o()), self.term_to_string(t.s()));
+                }
+            }
+            None => {
+                let network = self.cached(
+                    |cache| &mut cache.network_graph,
+                    (),
+                    || self.collect_network_graph(),
+                )?;
+                for edge in network.edges.iter().filter(|edge| !edge.symmetric) {
+                    link(edge.source.clone(), edge.target.clone());
+                }
+            }
+        }
+
+        let mut cycles: Vec<Cycle> = strongly_connected(&successors)
+            .into_iter()
+            .filter(|group| group.len() > 1 || successors[&group[0]].contains(&group[0]))
+            .map(|mut nodes| {
+                nodes.sort();
+                let path = shortest_loop(&successors, &nodes);
+                Cycle { nodes, path }
+            })
+            .collect();
+        cycles.sort_by(|a, b| a.nodes.cmp(&b.nodes));
+        Ok(cycles)
+    }
+}
+
+/// The strongly connected components of a graph, by Tarjan's algorithm
+///
+/// Iterative, so that long chains cannot overflow the stack.
+pub(crate) fn strongly_connected(
+    successors: &BTreeMap<String, BTreeSet<String>>,
+) -> Vec<Vec<String>> {
+    let nodes: Vec<&String> = successors.keys().collect();
+    let index_of: HashMap<&String, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect();
+    let edges: Vec<Vec<usize>> = nodes
+        .iter()
+        .map(|node| {
+            successors[*node]
+                .iter()
+                .map(|next| index_of[next])
+                .collect()
+        })
+        .collect();
+
+    let mut index = vec![usize::MAX; nodes.len()];
+    let mut low = vec![0; nodes.len()];
+    let mut on_stack = vec![false; nodes.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut counter = 0;
+
+    for root in 0..nodes.len() {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        // Each frame is a node and the position of the next edge to visit
+        let mut frames = vec![(root, 0)];
+        index[root] = counter;
+        low[root] = counter;
+        counter += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&mut (node, ref mut edge)) = frames.last_mut() {
+            if let Some(&next) = edges[node].get(*edge) {
+                *edge += 1;
+                if index[next] == usize::MAX {
+                    index[next] = counter;
+                    low[next] = counter;
+                    counter += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    frames.push((next, 0));
+                } else if on_stack[next] {
+                    low[node] = low[node].min(index[next]);
+                }
+                continue;
+            }
+
+            frames.pop();
+            if let Some(&(parent, _)) = frames.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if low[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(nodes[member].clone());
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+/// The shortest loop from the first node of a component back to itself
+pub(crate) fn shortest_loop(
+    successors: &BTreeMap<String, BTreeSet<String>>,
+    nodes: &[String],
+) -> Vec<String> {
+    let start = &nodes[0];
+    let mut parent: HashMap<&String, &String> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for next in &successors[node] {
+            if next == start {
+                let mut path = vec![node.clone()];
+                let mut current = node;
+                while let Some(previous) = parent.get(current) {
+                    path.push((*previous).clone());
+                    current = previous;
+                }
+                path.reverse();
+                return path;
+            }
+            if nodes.binary_search(next).is_ok() && !parent.contains_key(next) {
+                parent.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    vec![start.clone()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix owl: <http://www.w3.org/2002/07/owl#> .
+        sn:derivesFrom owl:inverseOf sn:gaveRiseTo .
+        sn:a sn:derivesFrom sn:b .
+        sn:b sn:derivesFrom sn:c .
+        sn:d sn:gaveRiseTo sn:c .
+        sn:c sn:derivesFrom sn:a .
+        sn:x sn:derivesFrom sn:y .
+        sn:z sn:derivesFrom sn:z .
+        sn:e1 a sn:Entanglement ; sn:hasSource sn:x ; sn:hasTarget sn:y .
+        sn:e2 a sn:Entanglement ; sn:hasSource sn:y ; sn:hasTarget sn:x .
+    "#;
+
+    fn local(iris: &[String]) -> Vec<&str> {
+        iris.iter()
+            .map(|iri| iri.trim_start_matches("https://sinople.org/ontology#"))
+            .collect()
+    }
+
+    #[test]
+    fn test_cycles_of_a_property() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        let cycles = processor.cycles(Some("sn:derivesFrom")).unwrap();
+        assert_eq!(cycles.len(), 2);
+        // sn:c derives from sn:d through the inverse, which closes no loop
+        assert_eq!(local(&cycles[0].nodes), ["a", "b", "c"]);
+        assert_eq!(local(&cycles[0].path), ["a", "b", "c"]);
+        assert_eq!(local(&cycles[1].path), ["z"]);
+    }
+
+    #[test]
+    fn test_cycles_of_the_network_graph() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        let cycles = processor.cycles(None).unwrap();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(local(&cycles[0].nodes), ["x", "y"]);
+        assert!(processor.cycles(Some("sn:relatesTo")).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! Cytoscape.js export
+//!
+//! `to_cytoscape` returns the network graph as the elements Cytoscape.js
+//! takes, so it can be handed over without mapping fields in JavaScript:
+//!
+//! ```javascript
+//! const cy = cytoscape({ container, elements: processor.to_cytoscape() });
+//! cy.style().selector('.construct').style('background-color', '#2a9d8f');
+//! ```
+//!
+//! Each node's `data` holds the fields of a network graph node, and each
+//! edge's those of a network graph edge plus an `id`. Nodes have their
+//! `node_type` as class and edges `directed` or `undirected`. If a layout
+//! has been computed since the graph last changed, nodes carry its
+//! `position`, for Cytoscape's `preset` layout.
+
+use crate::error::ProcessorError;
+use crate::{GraphEdge, GraphNode, SemanticProcessor};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Network graph in the Cytoscape.js elements format
+#[derive(Debug, Clone, Serialize)]
+pub struct CytoscapeElements {
+    pub nodes: Vec<CytoscapeNode>,
+    pub edges: Vec<CytoscapeEdge>,
+}
+
+/// A Cytoscape.js node
+#[derive(Debug, Clone, Serialize)]
+pub struct CytoscapeNode {
+    pub data: GraphNode,
+    pub classes: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<CytoscapePosition>,
+}
+
+/// Where a Cytoscape.js node is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CytoscapePosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A Cytoscape.js edge
+#[derive(Debug, Clone, Serialize)]
+pub struct CytoscapeEdge {
+    pub data: CytoscapeEdgeData,
+    pub classes: String,
+}
+
+/// The fields of a network graph edge, with the id Cytoscape.js requires
+#[derive(Debug, Clone, Serialize)]
+pub struct CytoscapeEdgeData {
+    pub id: String,
+    #[serde(flatten)]
+    pub edge: GraphEdge,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Export the network graph as Cytoscape.js elements
+    ///
+    /// # Returns
+    /// JsValue containing {nodes, edges}, each element with `data` and
+    /// `classes`, and nodes with `position` if a layout has been computed
+    #[wasm_bindgen(unchecked_return_type = "CytoscapeElements")]
+    pub fn to_cytoscape(&self) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let elements = self.cytoscape()?;
+
+        self.output_json(&elements)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn cytoscape(&self) -> Result<CytoscapeElements, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let layout = self.cache.borrow().layout.clone();
+
+        let nodes = self
+            .every_node(&network)
+            .into_iter()
+            .map(|node| CytoscapeNode {
+                classes: node.node_type.clone(),
+                position: layout
+                    .as_ref()
+                    .and_then(|layout| layout.get(&node.id))
+                    .map(|&(x, y)| CytoscapePosition { x, y }),
+                data: node,
+            })
+            .collect();
+        let edges = network
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, edge)| CytoscapeEdge {
+                data: CytoscapeEdgeData {
+                    id: format!("e{}", i),
+                    edge: edge.clone(),
+                },
+                classes: if edge.directed {
+                    "directed"
+                } else {
+                    "undirected"
+                }
+                .to_string(),
+            })
+            .collect();
+        Ok(CytoscapeElements { nodes, edges })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cytoscape_elements() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:entropy a sn:Construct ; sn:relatesTo sn:time .
+                "#,
+                None,
+            )
+            .unwrap();
+        processor.radial("sn:entropy", None).unwrap();
+
+        let elements = processor.cytoscape().unwrap();
+        let json = serde_json::to_value(&elements).unwrap();
+        assert_eq!(
+            json["nodes"][0]["data"]["id"],
+            "https://sinople.org/ontology#entropy"
+        );
+        assert_eq!(json["nodes"][0]["classes"], "construct");
+        assert_eq!(json["nodes"][0]["position"]["x"], 0.0);
+        assert_eq!(json["nodes"][1]["classes"], "other");
+        assert_eq!(json["edges"][0]["data"]["id"], "e0");
+        assert_eq!(
+            json["edges"][0]["data"]["target"],
+            "https://sinople.org/ontology#time"
+        );
+        assert_eq!(json["edges"][0]["classes"], "directed");
+    }
+}
@@ -0,0 +1,179 @@
+//! Duplicate label detection
+//!
+//! The same concept is easily created twice, in two posts, as `sn:entropy`
+//! and `sn:entropie`, both labelled "Entropy". `find_duplicate_labels`
+//! groups the resources whose labels are the same once case, accents and
+//! spacing are ignored, so "Éntropy " and "entropy" match:
+//!
+//! ```javascript
+//! for (const group of processor.find_duplicate_labels()) {
+//!   console.warn(`"${group.label}" is used by ${group.entities.map(e => e.id).join(', ')}`);
+//! }
+//! ```
+//!
+//! Resources merged through `owl:sameAs` count as one.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::triple::Triple;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// Resources that share a label
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateLabel {
+    /// The label as the first resource writes it
+    pub label: String,
+    pub entities: Vec<LabelledEntity>,
+}
+
+/// A resource and the label it was grouped by
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LabelledEntity {
+    pub id: String,
+    pub label: String,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Group resources whose `rdfs:label`s match, ignoring case, accents and
+    /// spacing
+    ///
+    /// # Returns
+    /// JsValue containing array of {label, entities: [{id, label}]}, one for
+    /// each label shared by two resources or more, sorted by label
+    #[wasm_bindgen(unchecked_return_type = "DuplicateLabel[]")]
+    pub fn find_duplicate_labels(&self) -> Result<JsValue, JsValue> {
+        let duplicates = self.duplicate_labels()?;
+
+        self.output(&duplicates)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn duplicate_labels(&self) -> Result<Vec<DuplicateLabel>, ProcessorError> {
+        let graph = self.query_graph(None)?;
+        let label = self.make_term("rdfs:label");
+
+        let mut groups: BTreeMap<String, Vec<LabelledEntity>> = BTreeMap::new();
+        for t in graph.triples_matching(Any, [&label], Any).flatten() {
+            let text = self.term_to_string(t.o());
+            let key = fold_label(&text);
+            if key.is_empty() {
+                continue;
+            }
+            groups.entry(key).or_default().push(LabelledEntity {
+                id: self.term_to_string(t.s()),
+                label: text,
+            });
+        }
+
+        Ok(groups
+            .into_values()
+            .filter_map(|mut entities| {
+                entities.sort_by(|a, b| (&a.id, &a.label).cmp(&(&b.id, &b.label)));
+                // A resource labelled "Time"@en and "time"@fr is not a duplicate
+                entities.dedup_by(|a, b| a.id == b.id);
+                (entities.len() > 1).then(|| DuplicateLabel {
+                    label: entities[0].label.clone(),
+                    entities,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Lowercase a label, strip the accents of Latin letters and collapse spacing
+pub(crate) fn fold_label(label: &str) -> String {
+    let mut folded = String::with_capacity(label.len());
+    for c in label.to_lowercase().chars() {
+        match c {
+            // Combining diacritical marks, from decomposed input
+            '\u{300}'..='\u{36f}' => {}
+            'ß' => folded.push_str("ss"),
+            'æ' => folded.push_str("ae"),
+            'œ' => folded.push_str("oe"),
+            'þ' => folded.push_str("th"),
+            c => folded.push(base_letter(c)),
+        }
+    }
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The unaccented letter of a lowercase Latin-1 or Latin Extended-A letter
+fn base_letter(c: char) -> char {
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ð' | 'ď' | 'đ' => 'd',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_label() {
+        assert_eq!(fold_label("  Éntropy\tof  Time "), "entropy of time");
+        assert_eq!(fold_label("Cafe\u{301}"), fold_label("Café"));
+        assert_eq!(fold_label("Straße"), "strasse");
+        assert_eq!(fold_label("Łódź"), "lodz");
+    }
+
+    #[test]
+    fn test_duplicate_labels_are_grouped() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                @prefix owl: <http://www.w3.org/2002/07/owl#> .
+                sn:entropy a sn:Construct ; rdfs:label "Entropy"@en, "entropy"@fr .
+                sn:entropie a sn:Construct ; rdfs:label "Éntropy " .
+                sn:time a sn:Construct ; rdfs:label "Time" .
+                sn:zeit a sn:Construct ; rdfs:label "time" ; owl:sameAs sn:time .
+                sn:space a sn:Construct ; rdfs:label "Space" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let duplicates = processor.duplicate_labels().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].label, "Éntropy ");
+        let ids: Vec<&str> = duplicates[0]
+            .entities
+            .iter()
+            .map(|entity| entity.id.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "https://sinople.org/ontology#entropie",
+                "https://sinople.org/ontology#entropy"
+            ]
+        );
+    }
+}
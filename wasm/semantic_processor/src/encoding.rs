@@ -0,0 +1,82 @@
+//! Loading documents from raw bytes
+//!
+//! Lets `fetch` results be passed as they arrive, without decoding them in
+//! JavaScript first:
+//!
+//! ```javascript
+//! const bytes = new Uint8Array(await response.arrayBuffer());
+//! processor.load_turtle_bytes(bytes);
+//! ```
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use std::borrow::Cow;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Load RDF data from Turtle bytes
+    ///
+    /// The encoding is detected from the byte order mark: UTF-8, UTF-16LE or
+    /// UTF-16BE. Without one, the bytes are read as UTF-8, or as ISO-8859-1
+    /// if they are not valid UTF-8.
+    ///
+    /// # Arguments
+    /// * `bytes` - Turtle document, e.g. a `Uint8Array` over a fetched `ArrayBuffer`
+    pub fn load_turtle_bytes(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
+        let ttl = decode_text(bytes)?;
+        self.load_turtle(&ttl, None)
+    }
+}
+
+/// Decode a document, detecting its encoding
+pub(crate) fn decode_text(bytes: &[u8]) -> Result<Cow<'_, str>, ProcessorError> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return std::str::from_utf8(rest)
+            .map(Cow::Borrowed)
+            .map_err(|e| ProcessorError::parse("UTF-8", e));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+
+    Ok(match std::str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(bytes.iter().map(|&b| char::from(b)).collect()),
+    })
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Result<Cow<'_, str>, ProcessorError> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(ProcessorError::parse("UTF-16", "odd number of bytes"));
+    }
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .map(Cow::Owned)
+        .map_err(|e| ProcessorError::parse("UTF-16", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_text_detects_encoding() {
+        assert_eq!(decode_text(b"\xEF\xBB\xBFcaf\xC3\xA9").unwrap(), "café");
+        assert_eq!(decode_text(b"caf\xC3\xA9").unwrap(), "café");
+        assert_eq!(decode_text(b"caf\xE9").unwrap(), "café");
+        assert_eq!(
+            decode_text(b"\xFF\xFEc\x00a\x00f\x00\xE9\x00").unwrap(),
+            "café"
+        );
+        assert_eq!(
+            decode_text(b"\xFE\xFF\x00c\x00a\x00f\x00\xE9").unwrap(),
+            "café"
+        );
+        assert!(decode_text(b"\xFF\xFEc").is_err());
+    }
+}
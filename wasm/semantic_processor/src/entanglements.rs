@@ -0,0 +1,197 @@
+//! Referential integrity of entanglements
+//!
+//! Every entanglement must connect two resources that the graph describes
+//! with a type, and its `sn:relationshipType`, if any, should be one the
+//! theme knows how to draw. The allowed types are configured once:
+//!
+//! ```javascript
+//! processor.set_relationship_types(['causes', 'opposes', 'sn:mirrors']);
+//! for (const { entanglement, errors } of processor.validate_entanglements()) {
+//!   errors.forEach(e => console.warn(entanglement, e.message));
+//! }
+//! ```
+//!
+//! Without a configured vocabulary every relationship type is accepted.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// The problems of one entanglement
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntanglementErrors {
+    pub entanglement: String,
+    pub errors: Vec<EntanglementError>,
+}
+
+/// One problem of an entanglement
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntanglementError {
+    /// `missing-source`, `missing-target`, `untyped-source`,
+    /// `untyped-target` or `unknown-relationship-type`
+    pub code: String,
+    /// The offending source, target or relationship type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub message: String,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Set the relationship types that entanglements may use
+    ///
+    /// # Arguments
+    /// * `types` - Plain values such as `causes`, or IRIs as `prefix:local`
+    ///   or full IRI; an empty list accepts every type
+    pub fn set_relationship_types(&mut self, types: Vec<String>) {
+        self.relationship_types = types;
+    }
+
+    /// The relationship types that entanglements may use; empty if any
+    pub fn relationship_types(&self) -> Vec<String> {
+        self.relationship_types.clone()
+    }
+
+    /// Check the source, target and relationship type of every entanglement
+    ///
+    /// # Returns
+    /// JsValue containing array of {entanglement, errors: [{code, value?,
+    /// message}]}, one for each entanglement with problems, sorted by IRI
+    #[wasm_bindgen(unchecked_return_type = "EntanglementErrors[]")]
+    pub fn validate_entanglements(&self) -> Result<JsValue, JsValue> {
+        let errors = self.entanglement_errors()?;
+
+        self.output(&errors)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn entanglement_errors(&self) -> Result<Vec<EntanglementErrors>, ProcessorError> {
+        let graph = self.query_graph(None)?;
+        let graph: &FastGraph = &graph;
+        let rdf_type = self.make_term("rdf:type");
+        // Configured IRIs are compared in full, whichever way they were written
+        let allowed: HashSet<String> = self
+            .relationship_types
+            .iter()
+            .flat_map(|t| [t.clone(), self.term_to_string(&self.make_term(t))])
+            .collect();
+
+        let mut results = Vec::new();
+        for entanglement in self.instances_of(graph, "sn:Entanglement") {
+            let mut errors = Vec::new();
+            for (end, property) in [("source", "sn:hasSource"), ("target", "sn:hasTarget")] {
+                match self.inferred_value(graph, &entanglement, property) {
+                    None => errors.push(EntanglementError {
+                        code: format!("missing-{}", end),
+                        value: None,
+                        message: format!("Entanglement {} has no {}", entanglement, end),
+                    }),
+                    Some(value) => {
+                        let typed = graph
+                            .triples_matching([self.iri_term(&value)], [&rdf_type], Any)
+                            .next()
+                            .is_some();
+                        if !typed {
+                            errors.push(EntanglementError {
+                                code: format!("untyped-{}", end),
+                                message: format!(
+                                    "The {} of entanglement {}, {}, has no type in the graph",
+                                    end, entanglement, value
+                                ),
+                                value: Some(value),
+                            });
+                        }
+                    }
+                }
+            }
+            let relationship_type =
+                self.get_object_value(graph, &entanglement, "sn:relationshipType");
+            if let Some(value) = relationship_type {
+                if !allowed.is_empty() && !allowed.contains(&value) {
+                    errors.push(EntanglementError {
+                        code: "unknown-relationship-type".to_string(),
+                        message: format!(
+                            "Entanglement {} has relationship type {}, which is not in the vocabulary",
+                            entanglement, value
+                        ),
+                        value: Some(value),
+                    });
+                }
+            }
+
+            if !errors.is_empty() {
+                results.push(EntanglementErrors {
+                    entanglement: entanglement.to_string(),
+                    errors,
+                });
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entanglement_errors() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:time a sn:Construct .
+                sn:space a sn:Construct .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:time ; sn:hasTarget sn:space ;
+                    sn:relationshipType "causes" .
+                sn:e2 a sn:Entanglement ; sn:hasSource sn:nowhere ;
+                    sn:relationshipType sn:mirrors .
+                sn:e3 a sn:Entanglement ; sn:hasSource sn:time ; sn:hasTarget sn:space ;
+                    sn:relationshipType "loves" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let codes = |processor: &SemanticProcessor| -> Vec<(String, Vec<String>)> {
+            processor
+                .entanglement_errors()
+                .unwrap()
+                .into_iter()
+                .map(|e| {
+                    let id = e
+                        .entanglement
+                        .trim_start_matches("https://sinople.org/ontology#")
+                        .to_string();
+                    (id, e.errors.into_iter().map(|error| error.code).collect())
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            codes(&processor),
+            [(
+                "e2".to_string(),
+                vec!["untyped-source".to_string(), "missing-target".to_string()]
+            )]
+        );
+
+        processor.set_relationship_types(vec!["causes".to_string(), "sn:mirrors".to_string()]);
+        let codes = codes(&processor);
+        assert_eq!(codes.len(), 2);
+        assert_eq!(
+            codes[1],
+            (
+                "e3".to_string(),
+                vec!["unknown-relationship-type".to_string()]
+            )
+        );
+    }
+}
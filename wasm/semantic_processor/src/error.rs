@@ -0,0 +1,251 @@
+//! Structured errors for the Semantic Processor
+//!
+//! Errors reach JavaScript as `Error` objects carrying a `code` (also used as
+//! the error's `name`), a human-readable `message` and a `context` object with
+//! details such as the input format or the offending term:
+//!
+//! ```javascript
+//! try {
+//!   processor.load_turtle(ttl);
+//! } catch (e) {
+//!   if (e.code === 'ParseError') highlight(e.context.line, e.context.column);
+//! }
+//! ```
+
+use rio_api::parser::ParseError;
+use serde::Serialize;
+use sophia_api::source::StreamError;
+use std::collections::BTreeMap;
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+/// Category of a [`ProcessorError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ErrorCode {
+    /// An input document, query or patch could not be parsed
+    ParseError,
+    /// A query or lookup refers to something invalid or unknown
+    QueryError,
+    /// Results could not be serialized
+    SerializationError,
+    /// A transaction, history or streaming load operation is not allowed in
+    /// the current state
+    TransactionError,
+    /// An argument has an unsupported value
+    InvalidArgument,
+    /// The operation was cancelled through the abort flag
+    Aborted,
+    /// A literal does not have the requested type, or is not a valid value of it
+    TypeMismatch,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::ParseError => "ParseError",
+            ErrorCode::QueryError => "QueryError",
+            ErrorCode::SerializationError => "SerializationError",
+            ErrorCode::TransactionError => "TransactionError",
+            ErrorCode::InvalidArgument => "InvalidArgument",
+            ErrorCode::Aborted => "Aborted",
+            ErrorCode::TypeMismatch => "TypeMismatch",
+        }
+    }
+}
+
+/// Error returned by the processor
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessorError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub context: BTreeMap<String, String>,
+}
+
+impl ProcessorError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ProcessorError {
+            code,
+            message: message.into(),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// A document in `format` failed to parse
+    pub fn parse(format: &str, error: impl fmt::Display) -> Self {
+        Self::new(
+            ErrorCode::ParseError,
+            format!("Failed to parse {}: {}", format, error),
+        )
+        .with_context("format", format)
+    }
+
+    /// A document in `format` failed to parse, located within `input`
+    ///
+    /// Syntax errors get `line`, `column` (both 1-based, the column counted in
+    /// characters) and the offending `token` in their context.
+    pub(crate) fn parse_at<E, M>(format: &str, input: &str, error: StreamError<E, M>) -> Self
+    where
+        E: ParseError,
+        M: std::error::Error,
+    {
+        let StreamError::SourceError(source) = &error else {
+            return Self::parse(format, error);
+        };
+        match SyntaxError::locate(input, source) {
+            Some(syntax) => Self::syntax(format, syntax),
+            None => Self::parse(format, source),
+        }
+    }
+
+    /// A document in `format` has a syntax error
+    pub(crate) fn syntax(format: &str, syntax: SyntaxError) -> Self {
+        let mut message = format!(
+            "Failed to parse {} at line {}, column {}: {}",
+            format, syntax.line, syntax.column, syntax.detail
+        );
+        if !syntax.token.is_empty() {
+            message.push_str(&format!(" (near '{}')", syntax.token));
+        }
+
+        Self::new(ErrorCode::ParseError, message)
+            .with_context("format", format)
+            .with_context("line", syntax.line)
+            .with_context("column", syntax.column)
+            .with_context("token", syntax.token)
+    }
+
+    pub fn query(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::QueryError, message)
+    }
+
+    pub fn serialization(error: impl fmt::Display) -> Self {
+        Self::new(
+            ErrorCode::SerializationError,
+            format!("Serialization error: {}", error),
+        )
+    }
+
+    pub fn transaction(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::TransactionError, message)
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidArgument, message)
+    }
+
+    pub fn aborted() -> Self {
+        Self::new(ErrorCode::Aborted, "Operation aborted")
+    }
+
+    pub fn type_mismatch(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::TypeMismatch, message)
+    }
+
+    /// Attach a detail to the error's context
+    pub fn with_context(mut self, key: &str, value: impl fmt::Display) -> Self {
+        self.context.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ProcessorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code.as_str(), self.message)
+    }
+}
+
+impl std::error::Error for ProcessorError {}
+
+impl From<ProcessorError> for JsValue {
+    fn from(error: ProcessorError) -> Self {
+        let js_error = js_sys::Error::new(&error.message);
+        js_error.set_name(error.code.as_str());
+
+        let context = js_sys::Object::new();
+        for (key, value) in &error.context {
+            let _ = js_sys::Reflect::set(&context, &key.into(), &value.into());
+        }
+        let _ = js_sys::Reflect::set(&js_error, &"code".into(), &error.code.as_str().into());
+        let _ = js_sys::Reflect::set(&js_error, &"context".into(), &context);
+
+        js_error.into()
+    }
+}
+
+/// A syntax error located within the parsed text
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SyntaxError {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, counted in characters
+    pub column: usize,
+    /// Text from the error position up to the next whitespace
+    pub token: String,
+    /// The parser's description of the error
+    pub detail: String,
+}
+
+impl SyntaxError {
+    /// Locate a parser error in `input`, if the parser reported a position
+    pub(crate) fn locate<E: ParseError>(input: &str, error: &E) -> Option<Self> {
+        let position = error.textual_position()?;
+
+        // Both numbers count from 1
+        let line = (position.line_number() as usize).max(1);
+        let text = input.lines().nth(line - 1).unwrap_or("");
+        let mut offset = (position.byte_number() as usize)
+            .saturating_sub(1)
+            .min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        // The parser's own message ends with the raw position; drop it
+        let detail = error.to_string();
+        let detail = detail
+            .rsplit_once(" on line ")
+            .map_or(detail.as_str(), |(detail, _)| detail);
+
+        Some(SyntaxError {
+            line,
+            column: text[..offset].chars().count() + 1,
+            token: text[offset..]
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .to_string(),
+            detail: detail.to_string(),
+        })
+    }
+}
+
+/// Serialize a value for JavaScript
+pub(crate) fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(value).map_err(|e| ProcessorError::serialization(e).into())
+}
+
+/// Serialize a value for JavaScript as plain JSON-compatible objects
+///
+/// Maps become plain objects instead of `Map`s.
+pub(crate) fn to_js_json<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsValue> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| ProcessorError::serialization(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_carries_format() {
+        let error = ProcessorError::parse("Turtle", "unexpected token");
+        assert_eq!(error.code, ErrorCode::ParseError);
+        assert_eq!(error.message, "Failed to parse Turtle: unexpected token");
+        assert_eq!(error.context["format"], "Turtle");
+        assert_eq!(
+            error.to_string(),
+            "ParseError: Failed to parse Turtle: unexpected token"
+        );
+    }
+}
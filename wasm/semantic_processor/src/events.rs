@@ -0,0 +1,377 @@
+//! Events and scenes in time
+//!
+//! Instances of `sn:Event` and `sn:Scene` are placed in time with
+//! `sn:occursOn`, an `xsd:date` or `xsd:dateTime`, and may last until
+//! `sn:endsOn`. They can be listed in chronological order, or looked up by
+//! date range for timelines:
+//!
+//! ```javascript
+//! const timeline = processor.query_events(null, { sort: 'date' });
+//! const spring = processor.events_between('2024-03-01', '2024-05-31');
+//! const ongoing = processor.events_in_range('2024-03-01', '2024-05-31');
+//! ```
+//!
+//! `events_between` finds the events that start within the range, and
+//! `events_in_range` those whose span overlaps it. Each event lists the
+//! characters taking part and the places it happens at: those linked to it
+//! in either direction, as `query_places` does the other way round, so a
+//! timeline and a map built from the two agree.
+
+use crate::error::ProcessorError;
+use crate::literals::parse_date;
+use crate::options::{Labelled, QueryOptions};
+use crate::SemanticProcessor;
+use serde::{Deserialize, Serialize};
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+const DAY_MILLIS: f64 = 86_400_000.0;
+
+/// An event or scene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: String,
+    pub label: String,
+    /// Class IRI: `sn:Event` or `sn:Scene`
+    pub kind: String,
+    /// Lexical form of `sn:occursOn`
+    pub occurs_on: Option<String>,
+    /// Lexical form of `sn:endsOn`
+    pub ends_on: Option<String>,
+    pub description: Option<String>,
+    /// IRIs of the characters linked to the event, sorted
+    pub participants: Vec<String>,
+    /// IRIs of the places linked to the event, sorted
+    pub locations: Vec<String>,
+}
+
+impl Event {
+    /// When the event occurs, in milliseconds since the Unix epoch
+    pub(crate) fn timestamp(&self) -> Option<f64> {
+        self.occurs_on.as_deref().and_then(parse_date)
+    }
+
+    /// When the event is over, in milliseconds since the Unix epoch; an
+    /// event without a valid `sn:endsOn` is over when it occurs
+    pub(crate) fn end_timestamp(&self) -> Option<f64> {
+        let start = self.timestamp()?;
+        let end = self
+            .ends_on
+            .as_deref()
+            .and_then(|end| Some(end_of(end, parse_date(end)?)));
+        Some(end.map_or(start, |end| end.max(start)))
+    }
+}
+
+impl Labelled for Event {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn date(&self) -> Option<f64> {
+        self.timestamp()
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Query all events and scenes from the graph
+    ///
+    /// # Arguments
+    /// * `graph` - Optional named graph to query; `None` queries the union of all graphs
+    /// * `options` - Optional `QueryOptions` object; `sort: 'date'` orders
+    ///   them chronologically, with undated events last
+    ///
+    /// # Returns
+    /// JsValue containing array of Event objects
+    #[wasm_bindgen(unchecked_return_type = "EventData[]")]
+    pub fn query_events(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let events = self.cached(
+            |cache| &mut cache.events,
+            graph.clone(),
+            || self.collect_events(graph.as_deref()),
+        )?;
+
+        if options == QueryOptions::default() {
+            return self.output(&*events);
+        }
+        self.output(&options.apply(&events))
+    }
+
+    /// Find the events and scenes that occur within a date range
+    ///
+    /// Both ends are inclusive; an end given as a date without a time
+    /// includes the whole of that day. Undated events are left out.
+    ///
+    /// # Arguments
+    /// * `start` - `xsd:date` or `xsd:dateTime`, or `None` for no lower bound
+    /// * `end` - `xsd:date` or `xsd:dateTime`, or `None` for no upper bound
+    /// * `graph` - Optional named graph to query
+    ///
+    /// # Returns
+    /// JsValue containing array of Event objects in chronological order
+    #[wasm_bindgen(unchecked_return_type = "EventData[]")]
+    pub fn events_between(
+        &self,
+        start: Option<String>,
+        end: Option<String>,
+        graph: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let events = self.find_events_between(start.as_deref(), end.as_deref(), graph)?;
+
+        self.output(&events)
+    }
+
+    /// Find the events and scenes whose span overlaps a date range
+    ///
+    /// An event spans from `sn:occursOn` to `sn:endsOn`, or just the moment
+    /// it occurs if it has no end. Ends are inclusive as in `events_between`.
+    ///
+    /// # Arguments
+    /// * `start` - `xsd:date` or `xsd:dateTime`, or `None` for no lower bound
+    /// * `end` - `xsd:date` or `xsd:dateTime`, or `None` for no upper bound
+    /// * `graph` - Optional named graph to query
+    ///
+    /// # Returns
+    /// JsValue containing array of Event objects in chronological order
+    #[wasm_bindgen(unchecked_return_type = "EventData[]")]
+    pub fn events_in_range(
+        &self,
+        start: Option<String>,
+        end: Option<String>,
+        graph: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let events = self.find_events_in_range(start.as_deref(), end.as_deref(), graph)?;
+
+        self.output(&events)
+    }
+}
+
+impl SemanticProcessor {
+    /// Build the result of `query_events`
+    pub(crate) fn collect_events(&self, graph: Option<&str>) -> Result<Vec<Event>, ProcessorError> {
+        let graph = self.query_graph(graph)?;
+        let graph: &FastGraph = &graph;
+        let instances = |class: &str| -> HashSet<String> {
+            self.instances_of(graph, class)
+                .iter()
+                .map(|iri| iri.to_string())
+                .collect()
+        };
+        let characters = instances("sn:Character");
+        let places = instances("sn:Place");
+        let mut seen = HashSet::new();
+        let mut events = Vec::new();
+
+        for class in ["sn:Event", "sn:Scene"] {
+            let kind = self.expand(class);
+            for subject_iri in self.instances_of(graph, class) {
+                if !seen.insert(subject_iri.clone()) {
+                    continue;
+                }
+                let linked = self.linked(graph, &subject_iri);
+                let among = |kind: &HashSet<String>| -> Vec<String> {
+                    linked
+                        .iter()
+                        .filter(|iri| kind.contains(*iri))
+                        .cloned()
+                        .collect()
+                };
+                events.push(Event {
+                    id: subject_iri.to_string(),
+                    label: self
+                        .get_object_value(graph, &subject_iri, "rdfs:label")
+                        .unwrap_or_default(),
+                    kind: kind.clone(),
+                    occurs_on: self.get_object_value(graph, &subject_iri, "sn:occursOn"),
+                    ends_on: self.get_object_value(graph, &subject_iri, "sn:endsOn"),
+                    description: self.get_object_value(graph, &subject_iri, "rdfs:comment"),
+                    participants: among(&characters),
+                    locations: among(&places),
+                });
+            }
+        }
+
+        events.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(events)
+    }
+
+    pub(crate) fn find_events_between(
+        &self,
+        start: Option<&str>,
+        end: Option<&str>,
+        graph: Option<String>,
+    ) -> Result<Vec<Event>, ProcessorError> {
+        self.find_events(start, end, graph, Event::timestamp)
+    }
+
+    pub(crate) fn find_events_in_range(
+        &self,
+        start: Option<&str>,
+        end: Option<&str>,
+        graph: Option<String>,
+    ) -> Result<Vec<Event>, ProcessorError> {
+        self.find_events(start, end, graph, Event::end_timestamp)
+    }
+
+    /// The dated events from their start to `until`, overlapping a range,
+    /// in chronological order
+    fn find_events(
+        &self,
+        start: Option<&str>,
+        end: Option<&str>,
+        graph: Option<String>,
+        until: fn(&Event) -> Option<f64>,
+    ) -> Result<Vec<Event>, ProcessorError> {
+        let start = start.map(bound).transpose()?;
+        let end = end
+            .map(|end| Ok::<_, ProcessorError>(end_of(end, bound(end)?)))
+            .transpose()?;
+
+        let events = self.cached(
+            |cache| &mut cache.events,
+            graph.clone(),
+            || self.collect_events(graph.as_deref()),
+        )?;
+        let mut found: Vec<(f64, &Event)> = events
+            .iter()
+            .filter(|event| {
+                let (Some(from), Some(to)) = (event.timestamp(), until(event)) else {
+                    return false;
+                };
+                start.is_none_or(|start| to >= start) && end.is_none_or(|end| from <= end)
+            })
+            .map(|event| (event.timestamp().unwrap_or_default(), event))
+            .collect();
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(found.into_iter().map(|(_, event)| event.clone()).collect())
+    }
+}
+
+/// The last moment of a date: a bare date ends at the end of the day
+fn end_of(date: &str, millis: f64) -> f64 {
+    if date.contains('T') {
+        millis
+    } else {
+        millis + DAY_MILLIS - 1.0
+    }
+}
+
+/// Parse one end of a date range
+fn bound(date: &str) -> Result<f64, ProcessorError> {
+    parse_date(date).ok_or_else(|| {
+        ProcessorError::invalid_argument(format!(
+            "Invalid date '{}'; expected an xsd:date or xsd:dateTime",
+            date
+        ))
+        .with_context("date", date)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{SortKey, SortOrder};
+
+    fn processor() -> SemanticProcessor {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+                sn:thaw a sn:Event ; rdfs:label "Thaw" ; sn:occursOn "2024-03-20"^^xsd:date .
+                sn:flood a sn:Scene ; rdfs:label "Flood" ;
+                    sn:occursOn "2024-05-31T18:00:00Z"^^xsd:dateTime .
+                sn:frost a sn:Event ; rdfs:label "Frost" ; sn:occursOn "2023-12-01"^^xsd:date ;
+                    sn:endsOn "2024-03-01"^^xsd:date ; sn:locatedIn sn:valley .
+                sn:valley a sn:Place .
+                sn:mira a sn:Character ; sn:participatesIn sn:frost .
+                sn:rumour a sn:Event ; rdfs:label "Rumour" .
+                "#,
+                None,
+            )
+            .unwrap();
+        processor
+    }
+
+    fn labels(events: &[Event]) -> Vec<&str> {
+        events.iter().map(|e| e.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_events_sort_chronologically() {
+        let processor = processor();
+        let events = processor.collect_events(None).unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].kind, "https://sinople.org/ontology#Scene");
+
+        let options = QueryOptions {
+            sort: SortKey::Date,
+            ..QueryOptions::default()
+        };
+        assert_eq!(
+            labels(&options.apply(&events)),
+            ["Frost", "Thaw", "Flood", "Rumour"]
+        );
+        let options = QueryOptions {
+            order: SortOrder::Desc,
+            ..options
+        };
+        assert_eq!(
+            labels(&options.apply(&events)),
+            ["Rumour", "Flood", "Thaw", "Frost"]
+        );
+    }
+
+    #[test]
+    fn test_events_between() {
+        let processor = processor();
+        let between = |start, end| {
+            processor
+                .find_events_between(start, end, None)
+                .map(|events| labels(&events).join(","))
+        };
+
+        assert_eq!(
+            between(Some("2024-01-01"), Some("2024-05-31")).unwrap(),
+            "Thaw,Flood"
+        );
+        assert_eq!(
+            between(None, Some("2024-05-31T12:00:00Z")).unwrap(),
+            "Frost,Thaw"
+        );
+        assert_eq!(between(Some("2024-03-21"), None).unwrap(), "Flood");
+        assert!(between(Some("March"), None).is_err());
+    }
+
+    #[test]
+    fn test_events_in_range_overlap() {
+        let processor = processor();
+        let in_range = |start, end| {
+            processor
+                .find_events_in_range(start, end, None)
+                .map(|events| labels(&events).join(","))
+        };
+
+        assert_eq!(
+            in_range(Some("2024-03-01"), Some("2024-03-20")).unwrap(),
+            "Frost,Thaw"
+        );
+        assert_eq!(in_range(Some("2024-03-02"), None).unwrap(), "Thaw,Flood");
+        assert!(in_range(None, Some("2024-13-01")).is_err());
+
+        let events = processor.collect_events(None).unwrap();
+        let frost = events.iter().find(|e| e.label == "Frost").unwrap();
+        assert_eq!(frost.participants, ["https://sinople.org/ontology#mira"]);
+        assert_eq!(frost.locations, ["https://sinople.org/ontology#valley"]);
+    }
+}
@@ -0,0 +1,348 @@
+//! Exporting all semantic data at once
+//!
+//! `query_constructs`, `query_entanglements`, `query_characters` and
+//! `generate_network_graph` each look up the properties of every instance
+//! they return. For a first render that needs all four, `export_all` reads
+//! every triple once instead and builds the four results together:
+//!
+//! ```javascript
+//! const { constructs, entanglements, characters, graph } = processor.export_all();
+//! ```
+
+use crate::error::ProcessorError;
+use crate::literals::parse_weight;
+use crate::{
+    Character, Construct, Entanglement, Gloss, GraphEdge, NetworkGraph, SemanticProcessor,
+};
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Results of all semantic queries over the whole graph
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticExport {
+    pub constructs: Vec<Construct>,
+    pub entanglements: Vec<Entanglement>,
+    pub characters: Vec<Character>,
+    pub graph: NetworkGraph,
+}
+
+/// What the queries need to know about one subject
+#[derive(Default)]
+struct Resource {
+    types: Vec<Rc<str>>,
+    label: Option<Rc<str>>,
+    comment: Option<Rc<str>>,
+    source: Option<Rc<str>>,
+    target: Option<Rc<str>>,
+    /// Source and target stated with an inverse property, used when no
+    /// `sn:hasSource` or `sn:hasTarget` is
+    inverse_source: Option<Rc<str>>,
+    inverse_target: Option<Rc<str>>,
+    relationship_type: Option<Rc<str>>,
+    weight: Option<Rc<str>>,
+    glosses: Vec<Gloss>,
+    constructs: Vec<Rc<str>>,
+    /// Subjects linking to this resource through `sn:hasSource` or
+    /// `sn:hasTarget`, and resources related to it through `sn:relatesTo`,
+    /// in either direction where the properties have inverses
+    relationships: Vec<Rc<str>>,
+}
+
+impl Resource {
+    /// The entanglement's source, preferring one stated with `sn:hasSource`
+    fn source(&self) -> Option<String> {
+        string(&self.source.clone().or_else(|| self.inverse_source.clone()))
+    }
+
+    /// The entanglement's target, preferring one stated with `sn:hasTarget`
+    fn target(&self) -> Option<String> {
+        string(&self.target.clone().or_else(|| self.inverse_target.clone()))
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Export constructs, entanglements, characters and the network graph
+    ///
+    /// Equivalent to calling the four query methods on the union of all
+    /// graphs, but reads the graph only once.
+    ///
+    /// # Returns
+    /// JsValue containing {constructs, entanglements, characters, graph}
+    #[wasm_bindgen(unchecked_return_type = "SemanticExport")]
+    pub fn export_all(&self) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let export = self.cached(|cache| &mut cache.export, (), || self.collect_export())?;
+
+        self.output(&*export)
+    }
+}
+
+impl SemanticProcessor {
+    /// Build the result of `export_all` in a single pass over the graph
+    pub(crate) fn collect_export(&self) -> Result<SemanticExport, ProcessorError> {
+        let rdf_type = self.make_term("rdf:type");
+        let label = self.make_term("rdfs:label");
+        let comment = self.make_term("rdfs:comment");
+        let has_source = self.subproperties_of("sn:hasSource");
+        let has_target = self.subproperties_of("sn:hasTarget");
+        let inverse_terms = |property| -> Vec<SimpleTerm> {
+            self.inverses_of(property)
+                .into_iter()
+                .map(|(inverse, _)| inverse)
+                .collect()
+        };
+        let source_inverses = inverse_terms("sn:hasSource");
+        let target_inverses = inverse_terms("sn:hasTarget");
+        let relates_inverses = self.inverses_of("sn:relatesTo");
+        let mut relates_to = self.subproperties_of("sn:relatesTo");
+        relates_to.extend(relates_inverses.iter().map(|(inverse, _)| inverse.clone()));
+        let relationship_type = self.make_term("sn:relationshipType");
+        let weight = self.make_term("sn:weight");
+        let has_gloss = self.make_term("sn:hasGloss");
+        let has_gloss_list = self.make_term("sn:hasGlossList");
+        let has_construct = self.make_term("sn:hasConstruct");
+
+        let mut resources: HashMap<Rc<str>, Resource> = HashMap::new();
+        // (subject, type) pairs in graph order, one network node each
+        let mut typed: Vec<(Rc<str>, Rc<str>)> = Vec::new();
+        let mut related = Vec::new();
+
+        let graph = self.query_graph(None)?;
+        let graph: &FastGraph = &graph;
+        for triple in graph.triples() {
+            let triple =
+                triple.map_err(|e| ProcessorError::query(format!("Graph error: {}", e)))?;
+            let (p, subject, object) = (
+                triple.p(),
+                self.term_to_rc(triple.s()),
+                self.term_to_rc(triple.o()),
+            );
+
+            let is = |properties: &[SimpleTerm]| properties.iter().any(|q| Term::eq(p, q));
+            if is(&has_source) || is(&has_target) || is(&relates_to) {
+                resources
+                    .entry(object.clone())
+                    .or_default()
+                    .relationships
+                    .push(subject.clone());
+            }
+            if is(&relates_to) || is(&source_inverses) || is(&target_inverses) {
+                resources
+                    .entry(subject.clone())
+                    .or_default()
+                    .relationships
+                    .push(object.clone());
+            }
+            if is(&source_inverses) {
+                let entanglement = resources.entry(object.clone()).or_default();
+                entanglement.inverse_source.get_or_insert(subject.clone());
+            } else if is(&target_inverses) {
+                let entanglement = resources.entry(object.clone()).or_default();
+                entanglement.inverse_target.get_or_insert(subject.clone());
+            }
+
+            let resource = resources.entry(subject.clone()).or_default();
+            if Term::eq(p, &rdf_type) {
+                resource.types.push(object.clone());
+                typed.push((subject, object));
+            } else if Term::eq(p, &label) {
+                resource.label.get_or_insert(object);
+            } else if Term::eq(p, &comment) {
+                resource.comment.get_or_insert(object);
+            } else if is(&has_source) {
+                resource.source.get_or_insert(object);
+            } else if is(&has_target) {
+                resource.target.get_or_insert(object);
+            } else if Term::eq(p, &relationship_type) {
+                resource.relationship_type.get_or_insert(object);
+            } else if Term::eq(p, &weight) {
+                resource.weight.get_or_insert(object);
+            } else if Term::eq(p, &has_gloss) {
+                resource.glosses.push(self.gloss(&subject, triple.o()));
+            } else if Term::eq(p, &has_gloss_list) {
+                let list = self.gloss_list(graph, &subject, triple.o());
+                resource.glosses.extend(list);
+            } else if Term::eq(p, &has_construct) {
+                resource.constructs.push(object);
+            } else if is(&relates_to) {
+                related.push(triple);
+            }
+        }
+        self.check_abort()?;
+
+        let types = |class| -> Vec<Rc<str>> {
+            self.subclasses_of(class)
+                .iter()
+                .map(|class| self.term_to_rc(class))
+                .collect()
+        };
+        let construct_types = types("sn:Construct");
+        let entanglement_types = types("sn:Entanglement");
+        let character_types = types("sn:Character");
+
+        let mut subjects: Vec<(&Rc<str>, &Resource)> = resources
+            .iter()
+            .filter(|(_, resource)| !resource.types.is_empty())
+            .collect();
+        subjects.sort_by(|a, b| a.0.cmp(b.0));
+        let of_type = |class| of_type(&subjects, class);
+
+        let constructs = of_type(&construct_types)
+            .map(|(iri, resource)| Construct {
+                id: iri.to_string(),
+                label: string(&resource.label).unwrap_or_default(),
+                description: string(&resource.comment),
+                glosses: {
+                    // Listed glosses first, as `query_constructs` returns them
+                    let mut glosses = resource.glosses.clone();
+                    glosses.sort_by_key(|gloss| gloss.position.is_none());
+                    glosses
+                },
+                relationships: {
+                    let mut relationships = strings(&resource.relationships);
+                    relationships.extend(self.transitive_relations(graph, self.iri_term(iri)));
+                    relationships.sort();
+                    relationships.dedup();
+                    relationships
+                },
+            })
+            .collect();
+
+        let entanglements: Vec<Entanglement> = of_type(&entanglement_types)
+            .map(|(iri, resource)| {
+                let source = resource.source().unwrap_or_default();
+                let target = resource.target().unwrap_or_default();
+                Entanglement {
+                    id: iri.to_string(),
+                    label: string(&resource.label).unwrap_or_default(),
+                    annotations: self.entanglement_annotations(graph, iri, &source, &target),
+                    source,
+                    target,
+                    relationship_type: string(&resource.relationship_type)
+                        .unwrap_or_else(|| "related".to_string()),
+                    description: string(&resource.comment),
+                }
+            })
+            .collect();
+
+        let characters = of_type(&character_types)
+            .map(|(iri, resource)| Character {
+                id: iri.to_string(),
+                name: string(&resource.label).unwrap_or_default(),
+                description: string(&resource.comment),
+                constructs: strings(&resource.constructs),
+            })
+            .collect();
+
+        let nodes = self.network_nodes(&typed, |iri| {
+            string(&resources[iri].label).unwrap_or_else(|| self.extract_local_name(iri))
+        });
+        let symmetric_properties = self.symmetric_properties();
+        let mut edges: Vec<GraphEdge> = of_type(&entanglement_types)
+            .filter_map(|(_, resource)| {
+                let label =
+                    string(&resource.relationship_type).unwrap_or_else(|| "related".to_string());
+                let symmetric = symmetric_properties.contains(&label);
+                Some(GraphEdge {
+                    source: resource.source()?,
+                    target: resource.target()?,
+                    label,
+                    symmetric,
+                    directed: self.edge_directed(symmetric),
+                    weight: parse_weight(resource.weight.as_deref()),
+                    count: 1,
+                    parallel_index: 0,
+                    parallel_count: 1,
+                })
+            })
+            .collect();
+        edges.extend(self.relation_edges(related, &relates_inverses));
+
+        Ok(SemanticExport {
+            constructs,
+            entanglements,
+            characters,
+            graph: NetworkGraph::new(nodes, self.merge_edges(edges)),
+        })
+    }
+}
+
+/// Select the subjects with any of the types, keeping their order
+fn of_type<'a>(
+    subjects: &'a [(&'a Rc<str>, &'a Resource)],
+    classes: &'a [Rc<str>],
+) -> impl Iterator<Item = (&'a Rc<str>, &'a Resource)> + 'a {
+    subjects
+        .iter()
+        .filter(move |(_, resource)| resource.types.iter().any(|t| classes.contains(t)))
+        .copied()
+}
+
+fn string(value: &Option<Rc<str>>) -> Option<String> {
+    value.as_deref().map(str::to_string)
+}
+
+fn strings(values: &[Rc<str>]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GraphNode;
+    use serde_json::to_value;
+
+    #[test]
+    fn test_export_matches_individual_queries() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdf: <https://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+                @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+                sn:time rdf:type sn:Construct ; rdfs:label "Time" ; sn:hasGloss "Duration", "Durée"@fr ;
+                    sn:hasGlossList ( "Span" "Interval" ) .
+                sn:space rdf:type sn:Construct ; rdfs:comment "Extent" .
+                sn:e1 rdf:type sn:Entanglement ; sn:hasSource sn:time ; sn:hasTarget sn:space .
+                sn:mira rdf:type sn:Character ; rdfs:label "Mira" ; sn:hasConstruct sn:time .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let export = processor.collect_export().unwrap();
+
+        assert_eq!(
+            to_value(&export.constructs).unwrap(),
+            to_value(processor.collect_constructs(None).unwrap()).unwrap()
+        );
+        assert_eq!(
+            to_value(&export.entanglements).unwrap(),
+            to_value(processor.collect_entanglements(None).unwrap()).unwrap()
+        );
+        assert_eq!(
+            to_value(&export.characters).unwrap(),
+            to_value(processor.collect_characters(None).unwrap()).unwrap()
+        );
+
+        let graph = processor.collect_network_graph().unwrap();
+        let ids = |nodes: &[GraphNode]| {
+            let mut ids: Vec<String> = nodes.iter().map(|n| n.id.clone()).collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&export.graph.nodes), ids(&graph.nodes));
+        assert_eq!(
+            to_value(&export.graph.edges).unwrap(),
+            to_value(&graph.edges).unwrap()
+        );
+    }
+}
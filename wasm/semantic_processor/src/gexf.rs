@@ -0,0 +1,178 @@
+//! GEXF export
+//!
+//! Gephi's native format. `to_gexf` writes the network graph with each
+//! node's label and type, each edge's label and weight, and, if a layout has
+//! been computed since the graph last changed, where it placed each node:
+//!
+//! ```javascript
+//! processor.layout_force_directed(200);
+//! download('sinople.gexf', processor.to_gexf());
+//! ```
+//!
+//! Gephi's y axis points up, so positions are written with y negated to
+//! keep the layout the right way round.
+
+use crate::error::ProcessorError;
+use crate::graphml::escape_xml;
+use crate::SemanticProcessor;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Node attributes: id, title and type
+const NODE_ATTRIBUTES: [(&str, &str, &str); 4] = [
+    ("node_type", "node_type", "string"),
+    ("types", "types", "string"),
+    ("in_degree", "in_degree", "integer"),
+    ("out_degree", "out_degree", "integer"),
+];
+
+/// Edge attributes: id, title and type
+const EDGE_ATTRIBUTES: [(&str, &str, &str); 2] = [
+    ("count", "count", "integer"),
+    ("symmetric", "symmetric", "boolean"),
+];
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Serialize the network graph to GEXF
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the GEXF 1.3 document
+    /// * `Err(JsValue)` with error message if the graph cannot be built
+    pub fn to_gexf(&self) -> Result<String, JsValue> {
+        self.check_abort()?;
+        Ok(self.gexf()?)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn gexf(&self) -> Result<String, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let layout = self.cache.borrow().layout.clone();
+
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" ",
+            "version=\"1.3\">\n",
+            "  <meta>\n    <creator>Sinople</creator>\n  </meta>\n",
+            "  <graph mode=\"static\" defaultedgetype=\"directed\">\n",
+        ));
+        for (class, attributes) in [("node", &NODE_ATTRIBUTES[..]), ("edge", &EDGE_ATTRIBUTES)] {
+            let _ = writeln!(out, "    <attributes class=\"{}\">", class);
+            for (id, title, kind) in attributes {
+                let _ = writeln!(
+                    out,
+                    "      <attribute id=\"{}\" title=\"{}\" type=\"{}\"/>",
+                    id, title, kind
+                );
+            }
+            out.push_str("    </attributes>\n");
+        }
+
+        out.push_str("    <nodes>\n");
+        for node in self.every_node(&network) {
+            let _ = writeln!(
+                out,
+                "      <node id=\"{}\" label=\"{}\">",
+                escape_xml(&node.id),
+                escape_xml(&node.label)
+            );
+            out.push_str("        <attvalues>\n");
+            attvalue(&mut out, "node_type", &node.node_type);
+            attvalue(&mut out, "types", &node.types.join(" "));
+            attvalue(&mut out, "in_degree", &node.in_degree.to_string());
+            attvalue(&mut out, "out_degree", &node.out_degree.to_string());
+            out.push_str("        </attvalues>\n");
+            if let Some(&(x, y)) = layout.as_ref().and_then(|layout| layout.get(&node.id)) {
+                // Adding 0 turns -0 into 0
+                let _ = writeln!(
+                    out,
+                    "        <viz:position x=\"{}\" y=\"{}\" z=\"0\"/>",
+                    x + 0.0,
+                    -y + 0.0
+                );
+            }
+            out.push_str("      </node>\n");
+        }
+        out.push_str("    </nodes>\n    <edges>\n");
+
+        for (i, edge) in network.edges.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\" label=\"{}\" weight=\"{}\"{}>",
+                i,
+                escape_xml(&edge.source),
+                escape_xml(&edge.target),
+                escape_xml(&edge.label),
+                edge.weight,
+                if edge.directed {
+                    ""
+                } else {
+                    " type=\"undirected\""
+                }
+            );
+            out.push_str("        <attvalues>\n");
+            attvalue(&mut out, "count", &edge.count.to_string());
+            attvalue(&mut out, "symmetric", &edge.symmetric.to_string());
+            out.push_str("        </attvalues>\n      </edge>\n");
+        }
+
+        out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        Ok(out)
+    }
+}
+
+/// Write one attribute value of a node or edge
+fn attvalue(out: &mut String, id: &str, value: &str) {
+    let _ = writeln!(
+        out,
+        "          <attvalue for=\"{}\" value=\"{}\"/>",
+        id,
+        escape_xml(value)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gexf_includes_the_latest_layout() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:entropy a sn:Construct .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:entropy ; sn:hasTarget sn:time ;
+                    sn:relationshipType "<opposes>" ; sn:weight 2 .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let gexf = processor.gexf().unwrap();
+        assert!(
+            gexf.contains("<node id=\"https://sinople.org/ontology#entropy\" label=\"entropy\">")
+        );
+        assert!(gexf.contains("<attvalue for=\"node_type\" value=\"construct\"/>"));
+        assert!(gexf.contains("label=\"&lt;opposes&gt;\" weight=\"2\">"));
+        assert!(!gexf.contains("viz:position x"));
+
+        processor.hierarchical("sn:time", "sn:derivesFrom").unwrap();
+        processor.radial("sn:time", Some(0)).unwrap();
+        let gexf = processor.gexf().unwrap();
+        assert_eq!(
+            gexf.matches("<viz:position x=\"0\" y=\"0\" z=\"0\"/>")
+                .count(),
+            1
+        );
+
+        processor.load_turtle("", None).unwrap();
+        assert!(!processor.gexf().unwrap().contains("viz:position x"));
+    }
+}
@@ -0,0 +1,197 @@
+//! GraphML export
+//!
+//! Researchers open the network graph in yEd or Gephi, which both read
+//! GraphML. `to_graphml` writes the same nodes and edges as
+//! `generate_network_graph`, with their fields as GraphML attributes:
+//!
+//! ```javascript
+//! const blob = new Blob([processor.to_graphml()], { type: 'application/graphml+xml' });
+//! ```
+//!
+//! Edges are directed by default; undirected edges say so with
+//! `directed="false"`. Every IRI an edge points at is a node, even if it has
+//! no `rdf:type`.
+
+use crate::centrality::node_index;
+use crate::error::ProcessorError;
+use crate::{GraphNode, NetworkGraph, SemanticProcessor};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// GraphML attributes: id, element, name and type
+const KEYS: [(&str, &str, &str, &str); 9] = [
+    ("label", "node", "label", "string"),
+    ("node_type", "node", "node_type", "string"),
+    ("types", "node", "types", "string"),
+    ("in_degree", "node", "in_degree", "int"),
+    ("out_degree", "node", "out_degree", "int"),
+    ("edge_label", "edge", "label", "string"),
+    ("weight", "edge", "weight", "double"),
+    ("count", "edge", "count", "int"),
+    ("symmetric", "edge", "symmetric", "boolean"),
+];
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Serialize the network graph to GraphML
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the GraphML document
+    /// * `Err(JsValue)` with error message if the graph cannot be built
+    pub fn to_graphml(&self) -> Result<String, JsValue> {
+        self.check_abort()?;
+        Ok(self.graphml()?)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn graphml(&self) -> Result<String, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" ",
+            "xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" ",
+            "xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns ",
+            "http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n",
+        ));
+        for (id, element, name, kind) in KEYS {
+            let _ = writeln!(
+                out,
+                "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+                id, element, name, kind
+            );
+        }
+        out.push_str("  <graph id=\"sinople\" edgedefault=\"directed\">\n");
+
+        for node in self.every_node(&network) {
+            let _ = writeln!(out, "    <node id=\"{}\">", escape_xml(&node.id));
+            data(&mut out, "label", &node.label);
+            data(&mut out, "node_type", &node.node_type);
+            data(&mut out, "types", &node.types.join(" "));
+            data(&mut out, "in_degree", &node.in_degree.to_string());
+            data(&mut out, "out_degree", &node.out_degree.to_string());
+            out.push_str("    </node>\n");
+        }
+
+        for (i, edge) in network.edges.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"{}>",
+                i,
+                escape_xml(&edge.source),
+                escape_xml(&edge.target),
+                if edge.directed {
+                    ""
+                } else {
+                    " directed=\"false\""
+                }
+            );
+            data(&mut out, "edge_label", &edge.label);
+            data(&mut out, "weight", &edge.weight.to_string());
+            data(&mut out, "count", &edge.count.to_string());
+            data(&mut out, "symmetric", &edge.symmetric.to_string());
+            out.push_str("    </edge>\n");
+        }
+
+        out.push_str("  </graph>\n</graphml>\n");
+        Ok(out)
+    }
+
+    /// The nodes of a network graph, followed by a node of type `other` for
+    /// each edge end without one
+    pub(crate) fn every_node(&self, network: &NetworkGraph) -> Vec<GraphNode> {
+        let mut every = network.nodes.clone();
+        let known: HashSet<&str> = network.nodes.iter().map(|node| node.id.as_str()).collect();
+        let (ids, index) = node_index(network);
+        let mut degrees = vec![(0, 0); ids.len()];
+        for edge in &network.edges {
+            degrees[index[edge.target.as_str()]].0 += 1;
+            degrees[index[edge.source.as_str()]].1 += 1;
+        }
+        for (id, (in_degree, out_degree)) in ids.into_iter().zip(degrees) {
+            if !known.contains(id) {
+                every.push(GraphNode {
+                    id: id.to_string(),
+                    label: self.extract_local_name(id),
+                    node_type: "other".to_string(),
+                    types: Vec::new(),
+                    in_degree,
+                    out_degree,
+                });
+            }
+        }
+        every
+    }
+}
+
+/// Write one attribute value of a node or edge
+fn data(out: &mut String, key: &str, value: &str) {
+    let _ = writeln!(
+        out,
+        "      <data key=\"{}\">{}</data>",
+        key,
+        escape_xml(value)
+    );
+}
+
+/// Escape text for XML content or a quoted attribute
+pub(crate) fn escape_xml(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphml_lists_nodes_and_edges() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                sn:entropy a sn:Construct ; rdfs:label "Entropy & decay" .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:entropy ; sn:hasTarget sn:time ;
+                    sn:relationshipType "opposes" ; sn:weight 2.5 .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let graphml = processor.graphml().unwrap();
+        assert!(graphml.starts_with("<?xml"));
+        assert!(graphml.contains("<data key=\"label\">Entropy &amp; decay</data>"));
+        assert!(graphml.contains(
+            "<edge id=\"e0\" source=\"https://sinople.org/ontology#entropy\" \
+             target=\"https://sinople.org/ontology#time\">"
+        ));
+        assert!(graphml.contains("<data key=\"weight\">2.5</data>"));
+        // time has no type but is still a node, with the edge counted
+        assert!(graphml.contains("<node id=\"https://sinople.org/ontology#time\">"));
+        assert!(graphml.contains("<data key=\"in_degree\">1</data>"));
+        assert_eq!(graphml.matches("<node ").count(), 3);
+        assert!(graphml.ends_with("</graphml>\n"));
+    }
+}
@@ -0,0 +1,278 @@
+//! Undo/redo history for graph edits
+//!
+//! Every edit API records the elementary changes it makes (triples inserted
+//! into or removed from a graph, triples claimed or released by a source).
+//! Undoing an edit applies the inverse changes in reverse order.
+//!
+//! Bulk loads and `clear` start a new history: undoing an edit across them
+//! would not restore a meaningful state.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_api::graph::MutableGraph;
+use sophia_api::term::SimpleTerm;
+use wasm_bindgen::prelude::*;
+
+/// Maximum number of edits kept for undo
+const MAX_HISTORY: usize = 100;
+
+type Triple = [SimpleTerm<'static>; 3];
+
+/// An elementary change; `None` designates the default (union) graph
+#[derive(Debug, Clone)]
+pub(crate) enum Change {
+    Inserted(Option<String>, Triple),
+    Removed(Option<String>, Triple),
+    Claimed(String, Triple),
+    Released(String, Triple),
+}
+
+impl Change {
+    fn inverse(&self) -> Change {
+        match self {
+            Change::Inserted(graph, t) => Change::Removed(graph.clone(), t.clone()),
+            Change::Removed(graph, t) => Change::Inserted(graph.clone(), t.clone()),
+            Change::Claimed(source, t) => Change::Released(source.clone(), t.clone()),
+            Change::Released(source, t) => Change::Claimed(source.clone(), t.clone()),
+        }
+    }
+}
+
+/// Undo and redo stacks; each entry holds the changes of one edit
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    undo: Vec<Vec<Change>>,
+    redo: Vec<Vec<Change>>,
+    /// Changes of the edit in progress
+    pending: Vec<Change>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Undo the last edit
+    ///
+    /// A committed transaction is undone as a whole.
+    ///
+    /// # Returns
+    /// * `Ok(true)` if an edit was undone, `Ok(false)` if there was none
+    /// * `Err(JsValue)` if a transaction is open
+    pub fn undo(&mut self) -> Result<bool, JsValue> {
+        self.undo_edit().map_err(JsValue::from)
+    }
+
+    /// Redo the last undone edit
+    ///
+    /// # Returns
+    /// * `Ok(true)` if an edit was redone, `Ok(false)` if there was none
+    /// * `Err(JsValue)` if a transaction is open
+    pub fn redo(&mut self) -> Result<bool, JsValue> {
+        self.redo_edit().map_err(JsValue::from)
+    }
+
+    /// Number of edits that can be undone
+    pub fn history_length(&self) -> usize {
+        self.history.undo.len()
+    }
+}
+
+impl SemanticProcessor {
+    /// Insert a triple into a graph, recording the change; true if it was new
+    pub(crate) fn insert_in(&mut self, graph: Option<&str>, triple: &Triple) -> bool {
+        let change = Change::Inserted(graph.map(str::to_string), triple.clone());
+        self.record(change)
+    }
+
+    /// Remove a triple from a graph, recording the change; true if it was present
+    pub(crate) fn remove_in(&mut self, graph: Option<&str>, triple: &Triple) -> bool {
+        let change = Change::Removed(graph.map(str::to_string), triple.clone());
+        self.record(change)
+    }
+
+    /// Record that a source contributed a triple
+    pub(crate) fn claim(&mut self, source: &str, triple: &Triple) -> bool {
+        self.record(Change::Claimed(source.to_string(), triple.clone()))
+    }
+
+    /// Record that a source no longer contributes a triple
+    pub(crate) fn release(&mut self, source: &str, triple: &Triple) -> bool {
+        self.record(Change::Released(source.to_string(), triple.clone()))
+    }
+
+    /// Close the edit in progress, making it one undo step
+    ///
+    /// Inside a transaction the edit stays open until commit.
+    pub(crate) fn finish_edit(&mut self) {
+        if self.in_transaction() || self.history.pending.is_empty() {
+            return;
+        }
+        let changes = std::mem::take(&mut self.history.pending);
+        self.history.undo.push(changes);
+        if self.history.undo.len() > MAX_HISTORY {
+            self.history.undo.remove(0);
+        }
+        self.history.redo.clear();
+        self.materialize();
+    }
+
+    /// Drop the changes of the edit in progress without undoing them
+    pub(crate) fn discard_edit(&mut self) {
+        self.history.pending.clear();
+    }
+
+    /// Forget all history
+    pub(crate) fn reset_history(&mut self) {
+        self.history = History::default();
+    }
+
+    pub(crate) fn undo_edit(&mut self) -> Result<bool, ProcessorError> {
+        if self.in_transaction() {
+            return Err(ProcessorError::transaction(
+                "Cannot undo while a transaction is in progress",
+            ));
+        }
+        let Some(changes) = self.history.undo.pop() else {
+            return Ok(false);
+        };
+        for change in changes.iter().rev() {
+            self.apply_change(&change.inverse());
+        }
+        self.history.redo.push(changes);
+        self.materialize();
+        Ok(true)
+    }
+
+    pub(crate) fn redo_edit(&mut self) -> Result<bool, ProcessorError> {
+        if self.in_transaction() {
+            return Err(ProcessorError::transaction(
+                "Cannot redo while a transaction is in progress",
+            ));
+        }
+        let Some(changes) = self.history.redo.pop() else {
+            return Ok(false);
+        };
+        for change in &changes {
+            self.apply_change(change);
+        }
+        self.history.undo.push(changes);
+        self.materialize();
+        Ok(true)
+    }
+
+    /// Apply a change and add it to the edit in progress if it had an effect
+    fn record(&mut self, change: Change) -> bool {
+        let applied = self.apply_change(&change);
+        if applied {
+            self.history.pending.push(change);
+        }
+        applied
+    }
+
+    /// Apply a change without recording it; true if it had an effect
+    fn apply_change(&mut self, change: &Change) -> bool {
+        if matches!(change, Change::Inserted(..) | Change::Removed(..)) {
+            self.invalidate_cache();
+        }
+        match change {
+            // Asserting an inferred triple is a change even though it is present
+            Change::Inserted(None, triple @ [s, p, o]) => {
+                let inferred = self.inferred.remove(triple).is_some();
+                self.graph.insert(s, p, o).unwrap_or(false) || inferred
+            }
+            Change::Inserted(Some(name), [s, p, o]) => self
+                .named_graphs
+                .entry(name.clone())
+                .or_default()
+                .insert(s, p, o)
+                .unwrap_or(false),
+            Change::Removed(None, triple @ [s, p, o]) => {
+                self.inferred.remove(triple);
+                self.graph.remove(s, p, o).unwrap_or(false)
+            }
+            Change::Removed(Some(name), [s, p, o]) => self
+                .named_graphs
+                .get_mut(name)
+                .is_some_and(|named| named.remove(s, p, o).unwrap_or(false)),
+            Change::Claimed(source, triple) => self
+                .sources
+                .entry(source.clone())
+                .or_default()
+                .insert(triple.clone()),
+            Change::Released(source, triple) => {
+                let Some(contributed) = self.sources.get_mut(source) else {
+                    return false;
+                };
+                let released = contributed.remove(triple);
+                if contributed.is_empty() {
+                    self.sources.remove(source);
+                }
+                released
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sophia_api::graph::Graph;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut processor = SemanticProcessor::new();
+        let a = processor
+            .triple_terms("sn:a", "rdfs:label", "A", true)
+            .unwrap();
+        let b = processor
+            .triple_terms("sn:b", "rdfs:label", "B", true)
+            .unwrap();
+
+        processor.add_terms(&a);
+        processor.finish_edit();
+        processor.add_terms(&b);
+        processor.remove_terms(&a);
+        processor.finish_edit();
+        assert_eq!(processor.history_length(), 2);
+
+        assert!(processor.undo_edit().unwrap());
+        assert_eq!(processor.triple_count(), 1);
+        assert!(processor.graph.contains(&a[0], &a[1], &a[2]).unwrap());
+
+        assert!(processor.undo_edit().unwrap());
+        assert_eq!(processor.triple_count(), 0);
+        assert!(!processor.undo_edit().unwrap());
+
+        assert!(processor.redo_edit().unwrap());
+        assert!(processor.redo_edit().unwrap());
+        assert_eq!(processor.triple_count(), 1);
+        assert!(processor.graph.contains(&b[0], &b[1], &b[2]).unwrap());
+
+        // A new edit drops what could be redone
+        processor.undo_edit().unwrap();
+        processor.remove_terms(&a);
+        processor.finish_edit();
+        assert!(!processor.redo_edit().unwrap());
+    }
+
+    #[test]
+    fn test_committed_transaction_is_one_undo_step() {
+        let mut processor = SemanticProcessor::new();
+        let a = processor
+            .triple_terms("sn:a", "rdfs:label", "A", true)
+            .unwrap();
+        let b = processor
+            .triple_terms("sn:b", "rdfs:label", "B", true)
+            .unwrap();
+
+        processor.begin_transaction().unwrap();
+        processor.add_terms(&a);
+        processor.finish_edit();
+        processor.add_terms(&b);
+        processor.finish_edit();
+        assert!(processor.undo_edit().is_err());
+        processor.commit_transaction().unwrap();
+
+        assert_eq!(processor.history_length(), 1);
+        processor.undo_edit().unwrap();
+        assert_eq!(processor.triple_count(), 0);
+    }
+}
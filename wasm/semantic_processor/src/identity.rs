@@ -0,0 +1,262 @@
+//! Merging `owl:sameAs` identities
+//!
+//! Constructs are linked to Wikidata and to the IRIs they had before with
+//! `owl:sameAs`:
+//!
+//! ```turtle
+//! sn:time owl:sameAs wd:Q11471, <http://sinople.org/old/time> .
+//! ```
+//!
+//! Query results, relationships, the network graph and `export_all` treat
+//! every resource linked this way as one, named by its canonical IRI: one in
+//! a registered namespace if there is one, otherwise the first in sort order.
+//! `canonical_iri` tells which IRI that is, and
+//! `set_identity_merging(false)` keeps all of them apart.
+//!
+//! Queries read a view of the graph in which every IRI is replaced by its
+//! canonical IRI. Like the query results, it is kept until the graph changes.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// The canonical IRI of each resource that has other names
+#[derive(Debug, Default)]
+pub(crate) struct Identities {
+    canonical: HashMap<SimpleTerm<'static>, SimpleTerm<'static>>,
+}
+
+impl Identities {
+    /// Group the IRIs linked by `owl:sameAs`, directly or through others
+    fn new(graph: &FastGraph, same_as: &SimpleTerm, namespaces: &[&str]) -> Self {
+        let mut index: HashMap<SimpleTerm<'static>, usize> = HashMap::new();
+        let mut members: Vec<SimpleTerm<'static>> = Vec::new();
+        let mut parent: Vec<usize> = Vec::new();
+        let mut id = |term: SimpleTerm<'static>, parent: &mut Vec<usize>| {
+            *index.entry(term.clone()).or_insert_with(|| {
+                members.push(term);
+                parent.push(parent.len());
+                parent.len() - 1
+            })
+        };
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        for t in graph.triples_matching(Any, [same_as], Any).flatten() {
+            if !t.s().is_iri() || !t.o().is_iri() {
+                continue;
+            }
+            let s = id(t.s().into_term(), &mut parent);
+            let o = id(t.o().into_term(), &mut parent);
+            let (s, o) = (root(&mut parent, s), root(&mut parent, o));
+            parent[s] = o;
+        }
+
+        let mut groups: HashMap<usize, Vec<SimpleTerm<'static>>> = HashMap::new();
+        for (i, member) in members.into_iter().enumerate() {
+            groups.entry(root(&mut parent, i)).or_default().push(member);
+        }
+
+        let mut canonical = HashMap::new();
+        for group in groups.into_values() {
+            let iri = |term: &SimpleTerm| term.iri().map(|iri| iri.as_str().to_string());
+            let chosen = group
+                .iter()
+                .min_by_key(|term| {
+                    let iri = iri(term).unwrap_or_default();
+                    let registered = namespaces.iter().any(|ns| iri.starts_with(ns));
+                    (!registered, iri)
+                })
+                .cloned()
+                .expect("identity groups are never empty");
+            for member in group {
+                if !Term::eq(&member, &chosen) {
+                    canonical.insert(member, chosen.clone());
+                }
+            }
+        }
+
+        Identities { canonical }
+    }
+
+    /// The canonical form of a term, or the term itself
+    fn get<'a>(&'a self, term: &'a SimpleTerm<'static>) -> &'a SimpleTerm<'static> {
+        self.canonical.get(term).unwrap_or(term)
+    }
+}
+
+/// A graph to query: the stored one, or its view with identities merged
+pub(crate) enum Merged<'a> {
+    Stored(&'a FastGraph),
+    View(Rc<FastGraph>),
+}
+
+impl Deref for Merged<'_> {
+    type Target = FastGraph;
+
+    fn deref(&self) -> &FastGraph {
+        match self {
+            Merged::Stored(graph) => graph,
+            Merged::View(graph) => graph,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose whether resources linked by `owl:sameAs` are merged in query
+    /// results
+    ///
+    /// On by default.
+    pub fn set_identity_merging(&mut self, enabled: bool) {
+        if self.identity_merging != enabled {
+            self.identity_merging = enabled;
+            self.invalidate_cache();
+        }
+    }
+
+    /// Whether resources linked by `owl:sameAs` are merged in query results
+    pub fn identity_merging(&self) -> bool {
+        self.identity_merging
+    }
+
+    /// The IRI that query results use for a resource
+    ///
+    /// # Arguments
+    /// * `iri` - Resource as `prefix:local` or full IRI
+    ///
+    /// # Returns
+    /// The canonical IRI of the resources `owl:sameAs` it, or its own IRI
+    pub fn canonical_iri(&self, iri: &str) -> String {
+        self.term_to_string(&self.canonical(self.make_term(iri)))
+    }
+}
+
+impl SemanticProcessor {
+    /// The canonical form of a term, if identity merging is on
+    pub(crate) fn canonical(&self, term: SimpleTerm<'static>) -> SimpleTerm<'static> {
+        if !self.identity_merging {
+            return term;
+        }
+        self.identities().get(&term).clone()
+    }
+
+    /// The graph that queries of a graph, or of the union when `None`, read
+    pub(crate) fn query_graph(&self, name: Option<&str>) -> Result<Merged<'_>, ProcessorError> {
+        let graph = self.select_graph(name)?;
+        if !self.identity_merging {
+            return Ok(Merged::Stored(graph));
+        }
+        let identities = self.identities();
+        if identities.canonical.is_empty() {
+            return Ok(Merged::Stored(graph));
+        }
+
+        let key = name.map(str::to_string);
+        if let Some(view) = self.cache.borrow().merged_graphs.get(&key) {
+            return Ok(Merged::View(Rc::clone(view)));
+        }
+        let same_as = self.make_term("owl:sameAs");
+        let mut view = FastGraph::new();
+        for t in graph.triples().flatten() {
+            let [s, p, o] = t
+                .to_spo()
+                .map(|term| identities.get(&term.into_term()).clone());
+            // Links within one identity would only say it is itself
+            if Term::eq(&p, &same_as) && Term::eq(&s, &o) {
+                continue;
+            }
+            view.insert(&s, &p, &o)
+                .map_err(|e| ProcessorError::query(format!("Graph error: {}", e)))?;
+        }
+        let view = Rc::new(view);
+        self.cache
+            .borrow_mut()
+            .merged_graphs
+            .insert(key, Rc::clone(&view));
+        Ok(Merged::View(view))
+    }
+
+    /// The identities of the union graph, computed once after each change
+    fn identities(&self) -> Rc<Identities> {
+        if let Some(hit) = &self.cache.borrow().identities {
+            return Rc::clone(hit);
+        }
+        let namespaces: Vec<&str> = self.namespaces.values().map(String::as_str).collect();
+        let identities = Rc::new(Identities::new(
+            &self.graph,
+            &self.make_term("owl:sameAs"),
+            &namespaces,
+        ));
+        self.cache.borrow_mut().identities = Some(Rc::clone(&identities));
+        identities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix owl: <http://www.w3.org/2002/07/owl#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        @prefix wd: <http://www.wikidata.org/entity/> .
+        sn:time a sn:Construct ; rdfs:label "Time" ; owl:sameAs wd:Q11471 .
+        <http://example.org/old/time> owl:sameAs wd:Q11471 ;
+            a sn:Construct ; sn:hasGloss "Duration" .
+        sn:space a sn:Construct ; rdfs:label "Space" .
+        sn:e1 a sn:Entanglement ; sn:hasSource <http://example.org/old/time> ;
+            sn:hasTarget sn:space .
+    "#;
+
+    #[test]
+    fn test_same_as_resources_are_merged() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        assert_eq!(
+            processor.canonical_iri("http://www.wikidata.org/entity/Q11471"),
+            "https://sinople.org/ontology#time"
+        );
+        assert_eq!(
+            processor.canonical_iri("http://example.org/old/time"),
+            "https://sinople.org/ontology#time"
+        );
+
+        let constructs = processor.collect_constructs(None).unwrap();
+        assert_eq!(constructs.len(), 2);
+        let time = &constructs[1];
+        assert_eq!(time.id, "https://sinople.org/ontology#time");
+        assert_eq!(time.label, "Time");
+        assert_eq!(time.glosses[0].text, "Duration");
+        assert_eq!(time.relationships, ["https://sinople.org/ontology#e1"]);
+
+        let entanglements = processor.collect_entanglements(None).unwrap();
+        assert_eq!(entanglements[0].source, "https://sinople.org/ontology#time");
+
+        let export = processor.collect_export().unwrap();
+        assert_eq!(export.constructs.len(), 2);
+        assert_eq!(
+            export.graph.edges[0].source,
+            "https://sinople.org/ontology#time"
+        );
+        let graph = processor.collect_network_graph().unwrap();
+        assert_eq!(graph.nodes.len(), export.graph.nodes.len());
+
+        processor.set_identity_merging(false);
+        assert_eq!(processor.collect_constructs(None).unwrap().len(), 3);
+    }
+}
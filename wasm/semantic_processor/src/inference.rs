@@ -0,0 +1,618 @@
+//! RDFS class and property hierarchies
+//!
+//! Ontologies refine the Sinople classes with `rdfs:subClassOf`, and its
+//! properties with `rdfs:subPropertyOf`:
+//!
+//! ```turtle
+//! sn:CoreConstruct rdfs:subClassOf sn:Construct .
+//! sn:entangles rdfs:subPropertyOf sn:relatesTo .
+//! sn:time a sn:CoreConstruct ; sn:entangles sn:space .
+//! ```
+//!
+//! Type queries such as `query_constructs` include the instances of every
+//! subclass, direct or not, so `sn:time` above is listed as a construct.
+//! Classes declared `owl:equivalentClass` are each other's subclasses, so
+//! instances of a legacy class such as `old:Concept` in
+//! `old:Concept owl:equivalentClass sn:Construct` are constructs too.
+//! `set_subclass_inference(false)` restricts them to the classes named.
+//!
+//! Relationships follow sub-properties, and properties declared
+//! `owl:equivalentProperty`, in the same way: `sn:relatesTo` and
+//! its sub-properties relate two resources directly, and sub-properties of
+//! `sn:hasSource` and `sn:hasTarget` attach entanglements to their ends. So
+//! `find_relationships` relates `sn:time` and `sn:space` above, and the
+//! network graph has an `entangles` edge between them.
+//!
+//! Edges of properties declared `owl:SymmetricProperty` are undirected: they
+//! are marked `symmetric`, and a statement made both ways is drawn once.
+//!
+//! Properties declared inverse with `owl:inverseOf` are answered in either
+//! direction without adding triples: given
+//! `sn:hasTarget owl:inverseOf sn:isTargetOf`, the statement
+//! `sn:space sn:isTargetOf sn:e1` makes `sn:space` the target of `sn:e1`.
+//!
+//! The closure of each hierarchy is computed once after the graph changes,
+//! from the union of all graphs, and shared by every query until the next
+//! change.
+
+use crate::cache::QueryCache;
+use crate::{GraphEdge, SemanticProcessor};
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// The transitive closure of a hierarchy such as `rdfs:subClassOf`
+#[derive(Debug)]
+pub(crate) struct Hierarchy {
+    /// Each term with descendants, mapped to itself and all of them
+    descendants: HashMap<SimpleTerm<'static>, Vec<SimpleTerm<'static>>>,
+}
+
+impl Hierarchy {
+    /// Close the hierarchy that `relation` triples describe, child to parent
+    ///
+    /// Terms linked by `equivalence` are each other's children.
+    pub(crate) fn new(graph: &FastGraph, relation: &SimpleTerm, equivalence: &SimpleTerm) -> Self {
+        let mut children: HashMap<SimpleTerm<'static>, Vec<SimpleTerm<'static>>> = HashMap::new();
+        for t in graph.triples_matching(Any, [relation], Any).flatten() {
+            children
+                .entry(t.o().into_term())
+                .or_default()
+                .push(t.s().into_term());
+        }
+        for t in graph.triples_matching(Any, [equivalence], Any).flatten() {
+            for (parent, child) in [(t.o(), t.s()), (t.s(), t.o())] {
+                children
+                    .entry(parent.into_term())
+                    .or_default()
+                    .push(child.into_term());
+            }
+        }
+
+        let descendants = children
+            .keys()
+            .map(|parent| {
+                // Depth-first, skipping terms already reached so cycles end
+                let mut reached = HashSet::from([parent.clone()]);
+                let mut all = vec![parent.clone()];
+                let mut stack = vec![parent];
+                while let Some(term) = stack.pop() {
+                    for child in children.get(term).into_iter().flatten() {
+                        if reached.insert(child.clone()) {
+                            all.push(child.clone());
+                            stack.push(child);
+                        }
+                    }
+                }
+                (parent.clone(), all)
+            })
+            .collect();
+
+        Hierarchy { descendants }
+    }
+
+    /// A term followed by all of its descendants
+    pub(crate) fn with_descendants(&self, term: SimpleTerm<'static>) -> Vec<SimpleTerm<'static>> {
+        match self.descendants.get(&term) {
+            Some(all) => all.clone(),
+            None => vec![term],
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose whether type queries include instances of subclasses
+    ///
+    /// On by default.
+    pub fn set_subclass_inference(&mut self, enabled: bool) {
+        if self.subclass_inference != enabled {
+            self.subclass_inference = enabled;
+            self.invalidate_cache();
+        }
+    }
+
+    /// Whether type queries include instances of subclasses
+    pub fn subclass_inference(&self) -> bool {
+        self.subclass_inference
+    }
+}
+
+impl SemanticProcessor {
+    /// A class followed by its subclasses, or only the class if subclass
+    /// inference is off
+    pub(crate) fn subclasses_of(&self, class: &str) -> Vec<SimpleTerm<'static>> {
+        let class = self.make_term(class);
+        if !self.subclass_inference {
+            return vec![class];
+        }
+
+        self.class_hierarchy().with_descendants(class)
+    }
+
+    /// The closure of `rdfs:subClassOf` and `owl:equivalentClass`
+    pub(crate) fn class_hierarchy(&self) -> Rc<Hierarchy> {
+        self.hierarchy(
+            |cache| &mut cache.class_hierarchy,
+            "rdfs:subClassOf",
+            "owl:equivalentClass",
+        )
+    }
+
+    /// A property followed by its sub-properties
+    pub(crate) fn subproperties_of(&self, property: &str) -> Vec<SimpleTerm<'static>> {
+        self.hierarchy(
+            |cache| &mut cache.property_hierarchy,
+            "rdfs:subPropertyOf",
+            "owl:equivalentProperty",
+        )
+        .with_descendants(self.make_term(property))
+    }
+
+    /// The properties that state a property, or one of its sub-properties,
+    /// in the other direction, each paired with the property it inverts
+    ///
+    /// `owl:inverseOf` is read both ways, and sub-properties of an inverse
+    /// are inverses too.
+    pub(crate) fn inverses_of(
+        &self,
+        property: &str,
+    ) -> Vec<(SimpleTerm<'static>, SimpleTerm<'static>)> {
+        let inverse_of = self.make_term("owl:inverseOf");
+        let hierarchy = self.hierarchy(
+            |cache| &mut cache.property_hierarchy,
+            "rdfs:subPropertyOf",
+            "owl:equivalentProperty",
+        );
+
+        let mut inverses: Vec<(SimpleTerm<'static>, SimpleTerm<'static>)> = Vec::new();
+        for forward in self.subproperties_of(property) {
+            let declared: Vec<SimpleTerm<'static>> = self
+                .graph
+                .triples_matching([&forward], [&inverse_of], Any)
+                .flatten()
+                .map(|t| t.o().into_term())
+                .chain(
+                    self.graph
+                        .triples_matching(Any, [&inverse_of], [&forward])
+                        .flatten()
+                        .map(|t| t.s().into_term()),
+                )
+                .collect();
+            for inverse in declared
+                .into_iter()
+                .flat_map(|i| hierarchy.with_descendants(i))
+            {
+                if !inverses.iter().any(|(known, _)| Term::eq(known, &inverse)) {
+                    inverses.push((inverse, forward.clone()));
+                }
+            }
+        }
+        inverses
+    }
+
+    /// The value of a property or of any of its sub-properties, or else the
+    /// subject of an inverse statement about `subject`
+    pub(crate) fn inferred_value(
+        &self,
+        graph: &FastGraph,
+        subject: &str,
+        property: &str,
+    ) -> Option<String> {
+        let subject = self.iri_term(subject);
+        let properties = self.subproperties_of(property);
+        let inverses: Vec<SimpleTerm> = self
+            .inverses_of(property)
+            .into_iter()
+            .map(|(inverse, _)| inverse)
+            .collect();
+
+        let value = graph
+            .triples_matching([&subject], &properties[..], Any)
+            .flatten()
+            .map(|t| self.term_to_string(t.o()))
+            .chain(
+                graph
+                    .triples_matching(Any, &inverses[..], [&subject])
+                    .flatten()
+                    .map(|t| self.term_to_string(t.s())),
+            )
+            .next();
+        value
+    }
+
+    /// Network graph edges for direct relationships, labelled with the local
+    /// name of their property and sorted
+    ///
+    /// Triples using one of `inverses` are turned around and labelled with
+    /// the property they invert. Edges of properties declared
+    /// `owl:SymmetricProperty` are marked symmetric, and only the first of an
+    /// edge and its mirror image is kept.
+    pub(crate) fn relation_edges<T: Triple>(
+        &self,
+        triples: impl IntoIterator<Item = T>,
+        inverses: &[(SimpleTerm, SimpleTerm)],
+    ) -> Vec<GraphEdge> {
+        let symmetric = self.symmetric_properties();
+
+        let mut edges: Vec<GraphEdge> = triples
+            .into_iter()
+            .map(|t| {
+                let inverted = inverses
+                    .iter()
+                    .find(|(inverse, _)| Term::eq(&t.p(), inverse));
+                let (source, target, property) = match inverted {
+                    Some((_, forward)) => (
+                        self.term_to_string(&t.o()),
+                        self.term_to_string(&t.s()),
+                        self.term_to_string(forward),
+                    ),
+                    None => (
+                        self.term_to_string(&t.s()),
+                        self.term_to_string(&t.o()),
+                        self.term_to_string(&t.p()),
+                    ),
+                };
+                let is_symmetric = symmetric.contains(&property);
+                GraphEdge {
+                    source,
+                    target,
+                    label: self.extract_local_name(&property),
+                    symmetric: is_symmetric,
+                    directed: self.edge_directed(is_symmetric),
+                    weight: 1.0,
+                    count: 1,
+                    parallel_index: 0,
+                    parallel_count: 1,
+                }
+            })
+            .collect();
+        edges.sort_by(|a, b| {
+            (&a.source, &a.target, &a.label).cmp(&(&b.source, &b.target, &b.label))
+        });
+        let mut drawn = HashSet::new();
+        edges.retain(|edge| {
+            let (low, high) = if edge.source <= edge.target {
+                (&edge.source, &edge.target)
+            } else {
+                (&edge.target, &edge.source)
+            };
+            !edge.symmetric || drawn.insert((low.clone(), high.clone(), edge.label.clone()))
+        });
+        edges
+    }
+
+    /// The IRIs of the properties declared `owl:SymmetricProperty`
+    pub(crate) fn symmetric_properties(&self) -> HashSet<String> {
+        let rdf_type = self.make_term("rdf:type");
+        let symmetric_property = self.make_term("owl:SymmetricProperty");
+        self.graph
+            .triples_matching(Any, [&rdf_type], [&symmetric_property])
+            .flatten()
+            .map(|t| self.term_to_string(t.s()))
+            .collect()
+    }
+
+    /// Look up the closure of a hierarchy in the cache, computing it if missing
+    fn hierarchy(
+        &self,
+        slot: fn(&mut QueryCache) -> &mut Option<Rc<Hierarchy>>,
+        relation: &str,
+        equivalence: &str,
+    ) -> Rc<Hierarchy> {
+        if let Some(hit) = slot(&mut self.cache.borrow_mut()) {
+            return Rc::clone(hit);
+        }
+        let hierarchy = Rc::new(Hierarchy::new(
+            &self.graph,
+            &self.make_term(relation),
+            &self.make_term(equivalence),
+        ));
+        *slot(&mut self.cache.borrow_mut()) = Some(Rc::clone(&hierarchy));
+        hierarchy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        sn:CoreConstruct rdfs:subClassOf sn:Construct .
+        sn:Axiom rdfs:subClassOf sn:CoreConstruct .
+        sn:Construct rdfs:subClassOf sn:Axiom .
+        sn:time a sn:Construct ; rdfs:label "Time" .
+        sn:space a sn:CoreConstruct ; rdfs:label "Space" .
+        sn:void a sn:Axiom, sn:Construct ; rdfs:label "Void" .
+        sn:mira a sn:Character .
+    "#;
+
+    fn ids(processor: &SemanticProcessor) -> Vec<String> {
+        processor
+            .collect_constructs(None)
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_type_queries_include_subclasses() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        assert_eq!(
+            ids(&processor),
+            [
+                "https://sinople.org/ontology#space",
+                "https://sinople.org/ontology#time",
+                "https://sinople.org/ontology#void",
+            ]
+        );
+        assert_eq!(processor.collect_export().unwrap().constructs.len(), 3);
+        assert_eq!(processor.collect_characters(None).unwrap().len(), 1);
+
+        processor.set_subclass_inference(false);
+        assert_eq!(
+            ids(&processor),
+            [
+                "https://sinople.org/ontology#time",
+                "https://sinople.org/ontology#void",
+            ]
+        );
+        assert_eq!(processor.collect_export().unwrap().constructs.len(), 2);
+    }
+
+    #[test]
+    fn test_equivalent_classes_and_properties() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix owl: <http://www.w3.org/2002/07/owl#> .
+                @prefix old: <https://example.org/legacy#> .
+                old:Concept owl:equivalentClass sn:Construct .
+                old:linkedTo owl:equivalentProperty sn:relatesTo .
+                sn:time a sn:Construct .
+                old:space a old:Concept ; old:linkedTo sn:time .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(
+            ids(&processor),
+            [
+                "https://example.org/legacy#space",
+                "https://sinople.org/ontology#time",
+            ]
+        );
+        assert_eq!(
+            processor.get_relationships(&processor.graph, "https://sinople.org/ontology#time"),
+            ["https://example.org/legacy#space"]
+        );
+    }
+
+    #[test]
+    fn test_hierarchy_follows_graph_changes() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                "@prefix sn: <https://sinople.org/ontology#> . sn:space a sn:CoreConstruct .",
+                None,
+            )
+            .unwrap();
+        assert!(ids(&processor).is_empty());
+
+        let triple = processor
+            .triple_terms("sn:CoreConstruct", "rdfs:subClassOf", "sn:Construct", false)
+            .unwrap();
+        processor.add_terms(&triple);
+        assert_eq!(ids(&processor), ["https://sinople.org/ontology#space"]);
+    }
+
+    #[test]
+    fn test_inverse_properties_answer_both_ways() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix owl: <http://www.w3.org/2002/07/owl#> .
+                sn:hasTarget owl:inverseOf sn:isTargetOf .
+                sn:isSourceOf owl:inverseOf sn:hasSource .
+                sn:relatesTo owl:inverseOf sn:isRelatedFrom .
+                sn:time a sn:Construct ; sn:isSourceOf sn:e1 .
+                sn:space a sn:Construct ; sn:isTargetOf sn:e1 ; sn:isRelatedFrom sn:void .
+                sn:void a sn:Construct .
+                sn:e1 a sn:Entanglement ; sn:relationshipType "bends" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let entanglements = processor.collect_entanglements(None).unwrap();
+        assert_eq!(entanglements[0].source, "https://sinople.org/ontology#time");
+        assert_eq!(
+            entanglements[0].target,
+            "https://sinople.org/ontology#space"
+        );
+        assert_eq!(
+            processor.get_relationships(&processor.graph, "https://sinople.org/ontology#space"),
+            [
+                "https://sinople.org/ontology#e1",
+                "https://sinople.org/ontology#void",
+            ]
+        );
+
+        let graph = processor.collect_network_graph().unwrap();
+        let edges: Vec<(&str, &str, &str)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source.as_str(), e.target.as_str(), e.label.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                (
+                    "https://sinople.org/ontology#time",
+                    "https://sinople.org/ontology#space",
+                    "bends"
+                ),
+                (
+                    "https://sinople.org/ontology#void",
+                    "https://sinople.org/ontology#space",
+                    "relatesTo"
+                ),
+            ]
+        );
+
+        let export = processor.collect_export().unwrap();
+        assert_eq!(export.entanglements[0].source, entanglements[0].source);
+        assert_eq!(export.entanglements[0].target, entanglements[0].target);
+        assert_eq!(export.graph.edges.len(), 2);
+        assert_eq!(
+            export.constructs[0].relationships,
+            processor.get_relationships(&processor.graph, &export.constructs[0].id)
+        );
+    }
+
+    #[test]
+    fn test_symmetric_properties_draw_undirected_edges() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix owl: <http://www.w3.org/2002/07/owl#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                sn:mirrors a owl:SymmetricProperty ; rdfs:subPropertyOf sn:relatesTo .
+                sn:feeds rdfs:subPropertyOf sn:relatesTo .
+                sn:time sn:mirrors sn:space ; sn:feeds sn:space .
+                sn:space sn:mirrors sn:time ; sn:feeds sn:time .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let graph = processor.collect_network_graph().unwrap();
+        let edges: Vec<(&str, bool)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.label.as_str(), e.symmetric))
+            .collect();
+        assert_eq!(
+            edges,
+            [("feeds", false), ("mirrors", true), ("feeds", false)]
+        );
+        assert_eq!(
+            processor.collect_export().unwrap().graph.edges.len(),
+            graph.edges.len()
+        );
+    }
+
+    #[test]
+    fn test_edges_are_directed_unless_symmetric_or_forced() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix owl: <http://www.w3.org/2002/07/owl#> .
+                sn:mirrors a owl:SymmetricProperty .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:a ; sn:hasTarget sn:b ;
+                    sn:relationshipType sn:mirrors .
+                sn:e2 a sn:Entanglement ; sn:hasSource sn:b ; sn:hasTarget sn:c ;
+                    sn:relationshipType "feeds" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let directed = |processor: &SemanticProcessor| -> Vec<(bool, bool)> {
+            let graph = processor.collect_network_graph().unwrap();
+            let export = processor.collect_export().unwrap();
+            assert_eq!(
+                export
+                    .graph
+                    .edges
+                    .iter()
+                    .map(|e| e.directed)
+                    .collect::<Vec<_>>(),
+                graph.edges.iter().map(|e| e.directed).collect::<Vec<_>>()
+            );
+            graph
+                .edges
+                .iter()
+                .map(|e| (e.symmetric, e.directed))
+                .collect()
+        };
+        assert_eq!(directed(&processor), [(true, false), (false, true)]);
+
+        processor.set_undirected_edges(true);
+        assert_eq!(directed(&processor), [(true, false), (false, false)]);
+    }
+
+    #[test]
+    fn test_relationships_follow_subproperties() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                sn:entangles rdfs:subPropertyOf sn:relatesTo .
+                sn:mirrors rdfs:subPropertyOf sn:entangles .
+                sn:emergesFrom rdfs:subPropertyOf sn:hasSource .
+                sn:time a sn:Construct ; sn:entangles sn:space .
+                sn:space a sn:Construct ; sn:mirrors sn:void .
+                sn:void a sn:Construct .
+                sn:e1 a sn:Entanglement ; sn:emergesFrom sn:void ; sn:hasTarget sn:time .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(
+            processor.get_relationships(&processor.graph, "https://sinople.org/ontology#space"),
+            [
+                "https://sinople.org/ontology#time",
+                "https://sinople.org/ontology#void",
+            ]
+        );
+        assert_eq!(
+            processor.get_relationships(&processor.graph, "https://sinople.org/ontology#void"),
+            [
+                "https://sinople.org/ontology#e1",
+                "https://sinople.org/ontology#space",
+            ]
+        );
+        let entanglements = processor.collect_entanglements(None).unwrap();
+        assert_eq!(entanglements[0].source, "https://sinople.org/ontology#void");
+
+        let graph = processor.collect_network_graph().unwrap();
+        let labels: Vec<&str> = graph.edges.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, ["related", "mirrors", "entangles"]);
+
+        let export = processor.collect_export().unwrap();
+        let edges = |edges: &[GraphEdge]| -> Vec<(String, String, String)> {
+            edges
+                .iter()
+                .map(|e| (e.source.clone(), e.target.clone(), e.label.clone()))
+                .collect()
+        };
+        assert_eq!(edges(&export.graph.edges), edges(&graph.edges));
+        assert_eq!(
+            export.constructs[1].relationships,
+            processor.get_relationships(&processor.graph, &export.constructs[1].id)
+        );
+    }
+}
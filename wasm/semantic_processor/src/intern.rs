@@ -0,0 +1,69 @@
+//! Shared strings for IRIs
+//!
+//! Queries convert the same IRIs to strings over and over: every instance
+//! IRI is looked up once per property, and type and predicate IRIs appear in
+//! every result. Interning hands out one reference-counted copy of each IRI
+//! instead of a fresh allocation per conversion. The interner is emptied with
+//! the query cache, so IRIs that leave the graph are not kept alive forever.
+
+use crate::SemanticProcessor;
+use sophia_api::term::{SimpleTerm, Term};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Set of interned strings
+#[derive(Debug, Default)]
+pub(crate) struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    /// Get the shared copy of a string, adding it if it is new
+    pub fn intern(&mut self, s: &str) -> Rc<str> {
+        if let Some(shared) = self.strings.get(s) {
+            return Rc::clone(shared);
+        }
+        let shared: Rc<str> = Rc::from(s);
+        self.strings.insert(Rc::clone(&shared));
+        shared
+    }
+
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+}
+
+impl SemanticProcessor {
+    /// Convert a term to a string as `term_to_string` does, sharing the
+    /// strings of IRIs
+    ///
+    /// Literals are not interned: their values are rarely repeated.
+    pub(crate) fn term_to_rc<T: Term>(&self, term: &T) -> Rc<str> {
+        match term.as_simple() {
+            SimpleTerm::Iri(iri) => self.strings.borrow_mut().intern(iri.as_str()),
+            _ => Rc::from(self.term_to_string(term)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interned_strings_are_shared() {
+        let mut interner = Interner::default();
+        let a = interner.intern("https://sinople.org/ontology#a");
+        let again = interner.intern("https://sinople.org/ontology#a");
+        let b = interner.intern("https://sinople.org/ontology#b");
+
+        assert!(Rc::ptr_eq(&a, &again));
+        assert!(!Rc::ptr_eq(&a, &b));
+
+        interner.clear();
+        assert!(!Rc::ptr_eq(
+            &a,
+            &interner.intern("https://sinople.org/ontology#a")
+        ));
+    }
+}
@@ -0,0 +1,624 @@
+//! Force-directed layout
+//!
+//! Simulating a layout in JavaScript drops frames on phones once the
+//! network passes a few thousand nodes. `layout_force_directed` runs the
+//! Fruchterman–Reingold simulation here instead, and returns where to draw
+//! each node:
+//!
+//! ```javascript
+//! for (const { id, x, y } of processor.layout_force_directed(100)) network.moveNode(id, x, y);
+//! ```
+//!
+//! Every pair of nodes repels and every edge pulls its ends together, more
+//! strongly the heavier its `weight`. As in the grid variant of the
+//! algorithm, nodes only repel those within twice the ideal edge length, so
+//! each round takes time in proportion to the number of nodes rather than
+//! its square. Nodes start on a spiral, so the same graph always gets the
+//! same layout.
+//!
+//! `layout_hierarchical` draws a hierarchy such as `sn:derivesFrom` in
+//! layers instead, the root on top and everything derived from it below:
+//!
+//! ```javascript
+//! for (const { id, x, y, layer } of processor.layout_hierarchical('sn:origin')) { ... }
+//! ```
+//!
+//! Each resource sits one layer below the lowest resource it derives from,
+//! and each layer is ordered to cross as few edges as it can, in the manner
+//! of Sugiyama. Edges closing a cycle are left out of the layering.
+//!
+//! `layout_radial` puts a focus resource in the middle of concentric rings,
+//! one for each hop away from it along network graph edges, for the
+//! "you are here" map of a post:
+//!
+//! ```javascript
+//! const map = processor.layout_radial('sn:entropy', 2);
+//! ```
+//!
+//! Each resource shares out its slice of the circle among the resources
+//! first reached through it, in proportion to how many they lead on to, so
+//! related resources stay together.
+//!
+//! Exports such as `to_gexf` include the positions of the latest layout,
+//! until the graph changes.
+
+use crate::centrality::node_index;
+use crate::error::ProcessorError;
+use crate::paths::neighbours;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Rounds of the simulation run when no number is given
+const DEFAULT_ITERATIONS: u32 = 100;
+
+/// Ideal distance between the ends of an edge
+const EDGE_LENGTH: f64 = 50.0;
+
+/// Property followed by a hierarchical layout when none is given
+const DEFAULT_HIERARCHY: &str = "sn:derivesFrom";
+
+/// Distance between neighbouring nodes of a layer
+const NODE_SPACING: f64 = 80.0;
+
+/// Distance between layers, or between rings around a centre
+const LAYER_SPACING: f64 = 100.0;
+
+/// Rounds of reordering layers, each one down and back up
+const ORDERING_SWEEPS: usize = 4;
+
+/// Where to draw one node
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodePosition {
+    pub id: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Where to draw one node of a hierarchy or of rings around a centre
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LayeredPosition {
+    pub id: String,
+    pub x: f64,
+    pub y: f64,
+    /// Steps below the root, or hops from the centre, which are layer 0
+    pub layer: u32,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Lay out the network graph by simulating forces between its nodes
+    ///
+    /// # Arguments
+    /// * `iterations` - Rounds of the simulation; 100 if omitted
+    ///
+    /// # Returns
+    /// JsValue containing array of {id, x, y}, sorted by IRI, centred on the
+    /// origin with edges about 50 units long
+    #[wasm_bindgen(unchecked_return_type = "NodePosition[]")]
+    pub fn layout_force_directed(&self, iterations: Option<u32>) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let positions = self.force_directed(iterations.unwrap_or(DEFAULT_ITERATIONS))?;
+
+        self.output(&positions)
+    }
+
+    /// Lay out a hierarchy in layers below its root
+    ///
+    /// # Arguments
+    /// * `root_iri` - Root resource as `prefix:local` or full IRI
+    /// * `property` - Property from a resource to the one it derives from,
+    ///   followed with its sub-properties and inverses; `sn:derivesFrom` if
+    ///   omitted
+    ///
+    /// # Returns
+    /// JsValue containing array of {id, x, y, layer} for the root and every
+    /// resource deriving from it, by layer and then from left to right; the
+    /// root is at the origin and y grows downwards
+    #[wasm_bindgen(unchecked_return_type = "LayeredPosition[]")]
+    pub fn layout_hierarchical(
+        &self,
+        root_iri: &str,
+        property: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let positions =
+            self.hierarchical(root_iri, property.as_deref().unwrap_or(DEFAULT_HIERARCHY))?;
+
+        self.output(&positions)
+    }
+
+    /// Lay out the network graph in rings around one resource
+    ///
+    /// # Arguments
+    /// * `center_iri` - Focus resource as `prefix:local` or full IRI
+    /// * `max_depth` - Most hops from the focus resource; every resource
+    ///   connected to it if omitted
+    ///
+    /// # Returns
+    /// JsValue containing array of {id, x, y, layer} for the focus resource
+    /// and those reached from it, by layer and then clockwise from the
+    /// right; the focus resource is at the origin and `layer` is the hops
+    /// from it
+    #[wasm_bindgen(unchecked_return_type = "LayeredPosition[]")]
+    pub fn layout_radial(
+        &self,
+        center_iri: &str,
+        max_depth: Option<u32>,
+    ) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let positions = self.radial(center_iri, max_depth.map(|depth| depth as usize))?;
+
+        self.output(&positions)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn force_directed(
+        &self,
+        iterations: u32,
+    ) -> Result<Vec<NodePosition>, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let (ids, index) = node_index(&network);
+        let springs: Vec<(usize, usize, f64)> = network
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    index[edge.source.as_str()],
+                    index[edge.target.as_str()],
+                    edge.weight,
+                )
+            })
+            .filter(|(source, target, _)| source != target)
+            .collect();
+
+        // A sunflower spiral spreads the nodes evenly around the origin
+        let golden_angle = PI * (3.0 - 5f64.sqrt());
+        let mut positions: Vec<(f64, f64)> = (0..ids.len())
+            .map(|i| {
+                let radius = EDGE_LENGTH * (i as f64 + 0.5).sqrt();
+                let angle = i as f64 * golden_angle;
+                (radius * angle.cos(), radius * angle.sin())
+            })
+            .collect();
+
+        let k = EDGE_LENGTH;
+        let start_temperature = k * (ids.len() as f64).sqrt() / 10.0;
+        for round in 0..iterations {
+            self.check_abort()?;
+            let mut shift = vec![(0.0, 0.0); ids.len()];
+
+            let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+            for (i, (x, y)) in positions.iter().enumerate() {
+                cells.entry(cell(*x, *y, k)).or_default().push(i);
+            }
+            for (i, &(x, y)) in positions.iter().enumerate() {
+                let (cx, cy) = cell(x, y, k);
+                for nearby in
+                    (cx - 1..=cx + 1).flat_map(|nx| (cy - 1..=cy + 1).map(move |ny| (nx, ny)))
+                {
+                    for &j in cells.get(&nearby).into_iter().flatten() {
+                        if i == j {
+                            continue;
+                        }
+                        let (dx, dy) = separation(positions[i], positions[j], i, j);
+                        let distance = dx.hypot(dy);
+                        if distance < 2.0 * k {
+                            let force = k * k / distance;
+                            shift[i].0 += dx / distance * force;
+                            shift[i].1 += dy / distance * force;
+                        }
+                    }
+                }
+            }
+
+            for &(source, target, weight) in &springs {
+                let (dx, dy) = separation(positions[source], positions[target], source, target);
+                let distance = dx.hypot(dy);
+                let force = distance * distance / k * weight;
+                let (fx, fy) = (dx / distance * force, dy / distance * force);
+                shift[source].0 -= fx;
+                shift[source].1 -= fy;
+                shift[target].0 += fx;
+                shift[target].1 += fy;
+            }
+
+            // Cool down linearly, so the last rounds only settle the layout
+            let temperature = start_temperature * (1.0 - round as f64 / iterations as f64);
+            for (position, (dx, dy)) in positions.iter_mut().zip(shift) {
+                let length = dx.hypot(dy);
+                if length > 0.0 {
+                    let step = length.min(temperature);
+                    position.0 += dx / length * step;
+                    position.1 += dy / length * step;
+                }
+            }
+        }
+
+        let count = positions.len().max(1) as f64;
+        let centre_x = positions.iter().map(|(x, _)| x).sum::<f64>() / count;
+        let centre_y = positions.iter().map(|(_, y)| y).sum::<f64>() / count;
+        let positions: Vec<NodePosition> = ids
+            .iter()
+            .zip(positions)
+            .map(|(id, (x, y))| NodePosition {
+                id: id.to_string(),
+                x: x - centre_x,
+                y: y - centre_y,
+            })
+            .collect();
+        self.remember_layout(positions.iter().map(|p| (&p.id, p.x, p.y)));
+        Ok(positions)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn hierarchical(
+        &self,
+        root: &str,
+        property: &str,
+    ) -> Result<Vec<LayeredPosition>, ProcessorError> {
+        let children = self.derived_children(property)?;
+
+        // Depth-first from the root, leaving out edges back to a resource
+        // still being visited
+        let root = self.canonical_iri(root);
+        let mut ids = vec![root.clone()];
+        let mut index: HashMap<String, usize> = HashMap::from([(root, 0)]);
+        let mut below: Vec<Vec<usize>> = vec![Vec::new()];
+        let mut visiting = vec![true];
+        let mut finished = Vec::new();
+        let mut frames = vec![(0, 0)];
+        while let Some(frame) = frames.last_mut() {
+            let (node, next) = *frame;
+            frame.1 += 1;
+            let child = children
+                .get(&ids[node])
+                .and_then(|set| set.iter().nth(next))
+                .cloned();
+            let Some(child) = child else {
+                visiting[node] = false;
+                finished.push(node);
+                frames.pop();
+                continue;
+            };
+            match index.get(&child) {
+                Some(&seen) if visiting[seen] => {}
+                Some(&seen) => below[node].push(seen),
+                None => {
+                    self.check_abort()?;
+                    let seen = ids.len();
+                    index.insert(child.clone(), seen);
+                    ids.push(child);
+                    below.push(Vec::new());
+                    visiting.push(true);
+                    below[node].push(seen);
+                    frames.push((seen, 0));
+                }
+            }
+        }
+
+        // Longest path from the root, taking resources in topological order
+        let mut layer = vec![0; ids.len()];
+        for &node in finished.iter().rev() {
+            for &child in &below[node] {
+                layer[child] = layer[child].max(layer[node] + 1);
+            }
+        }
+        let mut above: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+        for (node, children) in below.iter().enumerate() {
+            for &child in children {
+                above[child].push(node);
+            }
+        }
+        let depth = layer.iter().max().map_or(0, |deepest| deepest + 1);
+        let mut layers: Vec<Vec<usize>> = vec![Vec::new(); depth];
+        for (node, &l) in layer.iter().enumerate() {
+            layers[l].push(node);
+        }
+
+        // Barycentre heuristic: order each layer by the average position of
+        // its neighbours, sweeping down by parents and back up by children
+        let mut position = vec![0.0; ids.len()];
+        let place = |layers: &[Vec<usize>], position: &mut [f64]| {
+            for nodes in layers {
+                for (i, &node) in nodes.iter().enumerate() {
+                    position[node] = i as f64;
+                }
+            }
+        };
+        place(&layers, &mut position);
+        for _ in 0..ORDERING_SWEEPS {
+            self.check_abort()?;
+            for l in 1..depth {
+                order_by(&mut layers[l], &above, &position);
+                place(&layers[l..=l], &mut position);
+            }
+            for l in (0..depth.saturating_sub(1)).rev() {
+                order_by(&mut layers[l], &below, &position);
+                place(&layers[l..=l], &mut position);
+            }
+        }
+
+        let positions: Vec<LayeredPosition> = layers
+            .iter()
+            .enumerate()
+            .flat_map(|(l, nodes)| {
+                let middle = (nodes.len() as f64 - 1.0) / 2.0;
+                let ids = &ids;
+                nodes
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, &node)| LayeredPosition {
+                        id: ids[node].clone(),
+                        x: (i as f64 - middle) * NODE_SPACING,
+                        y: l as f64 * LAYER_SPACING,
+                        layer: l as u32,
+                    })
+            })
+            .collect();
+        self.remember_layout(positions.iter().map(|p| (&p.id, p.x, p.y)));
+        Ok(positions)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn radial(
+        &self,
+        center: &str,
+        max_depth: Option<usize>,
+    ) -> Result<Vec<LayeredPosition>, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let center = self.canonical_iri(center);
+        let neighbours = neighbours(&network.edges);
+
+        // Breadth-first, so each resource hangs off the first one to reach it
+        let mut ids = vec![center.as_str()];
+        let mut ring = vec![0];
+        let mut parent = vec![usize::MAX];
+        let mut index: HashMap<&str, usize> = HashMap::from([(center.as_str(), 0)]);
+        let mut next = 0;
+        while next < ids.len() {
+            if max_depth.is_some_and(|depth| ring[next] >= depth) {
+                next += 1;
+                continue;
+            }
+            self.check_abort()?;
+            for &(neighbour, _) in neighbours.get(ids[next]).into_iter().flatten() {
+                if !index.contains_key(neighbour) {
+                    index.insert(neighbour, ids.len());
+                    ids.push(neighbour);
+                    ring.push(ring[next] + 1);
+                    parent.push(next);
+                }
+            }
+            next += 1;
+        }
+
+        // Slices are shared out in proportion to the outermost resources
+        // reached through each resource
+        let mut leaves = vec![0.0; ids.len()];
+        for node in (0..ids.len()).rev() {
+            if leaves[node] == 0.0 {
+                leaves[node] = 1.0;
+            }
+            if node > 0 {
+                leaves[parent[node]] += leaves[node];
+            }
+        }
+        let mut slice = vec![(0.0, 2.0 * PI); ids.len()];
+        let mut taken = vec![0.0; ids.len()];
+        for node in 1..ids.len() {
+            let (start, width) = slice[parent[node]];
+            let share = width * leaves[node] / leaves[parent[node]];
+            slice[node] = (start + taken[parent[node]], share);
+            taken[parent[node]] += share;
+        }
+
+        let mut positions: Vec<LayeredPosition> = ids
+            .iter()
+            .enumerate()
+            .map(|(node, id)| {
+                let (start, width) = slice[node];
+                let angle = start + width / 2.0;
+                let radius = ring[node] as f64 * LAYER_SPACING;
+                LayeredPosition {
+                    id: id.to_string(),
+                    x: radius * angle.cos(),
+                    y: radius * angle.sin(),
+                    layer: ring[node] as u32,
+                }
+            })
+            .collect();
+        positions.sort_by(|a, b| {
+            let angle = |p: &LayeredPosition| p.y.atan2(p.x).rem_euclid(2.0 * PI);
+            a.layer
+                .cmp(&b.layer)
+                .then_with(|| angle(a).total_cmp(&angle(b)))
+        });
+        self.remember_layout(positions.iter().map(|p| (&p.id, p.x, p.y)));
+        Ok(positions)
+    }
+
+    /// Keep the positions of the latest layout for exports, until the graph
+    /// changes
+    fn remember_layout<'a>(&self, positions: impl Iterator<Item = (&'a String, f64, f64)>) {
+        let positions = positions.map(|(id, x, y)| (id.clone(), (x, y))).collect();
+        self.cache.borrow_mut().layout = Some(Rc::new(positions));
+    }
+}
+
+/// Order a layer by the average position of each node's neighbours within
+/// their own layers, keeping nodes without any where they are
+fn order_by(nodes: &mut [usize], neighbours: &[Vec<usize>], position: &[f64]) {
+    let key = |node: usize| {
+        let linked = &neighbours[node];
+        if linked.is_empty() {
+            position[node]
+        } else {
+            linked.iter().map(|&n| position[n]).sum::<f64>() / linked.len() as f64
+        }
+    };
+    let mut keyed: Vec<(f64, usize)> = nodes.iter().map(|&node| (key(node), node)).collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for (slot, (_, node)) in nodes.iter_mut().zip(keyed) {
+        *slot = node;
+    }
+}
+
+/// The grid cell of a position, with cells twice the ideal edge length wide
+fn cell(x: f64, y: f64, k: f64) -> (i64, i64) {
+    (
+        (x / (2.0 * k)).floor() as i64,
+        (y / (2.0 * k)).floor() as i64,
+    )
+}
+
+/// The vector from `b` to `a`, nudged apart by their indexes if they coincide
+fn separation(a: (f64, f64), b: (f64, f64), i: usize, j: usize) -> (f64, f64) {
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+    if dx == 0.0 && dy == 0.0 {
+        let nudge = if i < j { 0.01 } else { -0.01 };
+        (nudge, nudge)
+    } else {
+        (dx, dy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_pulls_connected_nodes_together() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:a sn:relatesTo sn:b . sn:b sn:relatesTo sn:c . sn:c sn:relatesTo sn:a .
+                sn:x sn:relatesTo sn:y . sn:y sn:relatesTo sn:z . sn:z sn:relatesTo sn:x .
+                sn:c sn:relatesTo sn:x .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let positions = processor.force_directed(200).unwrap();
+        assert_eq!(positions, processor.force_directed(200).unwrap());
+        let at = |name: &str| {
+            let id = format!("https://sinople.org/ontology#{}", name);
+            let p = positions.iter().find(|p| p.id == id).unwrap();
+            (p.x, p.y)
+        };
+        let distance = |a: &str, b: &str| {
+            let ((ax, ay), (bx, by)) = (at(a), at(b));
+            (ax - bx).hypot(ay - by)
+        };
+        // Nodes of a triangle end up closer to each other than to the other one
+        assert!(distance("a", "b") < distance("a", "y"));
+        assert!(distance("y", "z") < distance("z", "b"));
+        let centre: f64 = positions.iter().map(|p| p.x).sum();
+        assert!(centre.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hierarchy_is_layered_below_its_root() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix owl: <http://www.w3.org/2002/07/owl#> .
+                sn:derivesFrom owl:inverseOf sn:gaveRiseTo .
+                sn:time sn:derivesFrom sn:origin .
+                sn:origin sn:gaveRiseTo sn:void .
+                sn:entropy sn:derivesFrom sn:time, sn:void .
+                sn:echo sn:derivesFrom sn:entropy, sn:origin .
+                sn:origin sn:derivesFrom sn:echo .
+                sn:elsewhere sn:derivesFrom sn:nowhere .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let positions = processor
+            .hierarchical("sn:origin", "sn:derivesFrom")
+            .unwrap();
+        let layers: Vec<(&str, u32, f64)> = positions
+            .iter()
+            .map(|p| (&p.id["https://sinople.org/ontology#".len()..], p.layer, p.y))
+            .collect();
+        // echo sits below entropy, and the loop back to origin is ignored
+        assert_eq!(
+            layers,
+            [
+                ("origin", 0, 0.0),
+                ("time", 1, 100.0),
+                ("void", 1, 100.0),
+                ("entropy", 2, 200.0),
+                ("echo", 3, 300.0),
+            ]
+        );
+        assert_eq!(
+            (positions[0].x, positions[1].x, positions[2].x),
+            (0.0, -40.0, 40.0)
+        );
+
+        let alone = processor
+            .hierarchical("sn:nowhere", "sn:gaveRiseTo")
+            .unwrap();
+        assert_eq!(alone.len(), 1);
+    }
+
+    #[test]
+    fn test_radial_layout_rings_by_hops() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:hub sn:relatesTo sn:a, sn:b .
+                sn:a sn:relatesTo sn:a1, sn:a2, sn:a3 .
+                sn:b1 sn:relatesTo sn:b .
+                sn:a3 sn:relatesTo sn:far .
+                sn:x sn:relatesTo sn:y .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let positions = processor.radial("sn:hub", None).unwrap();
+        let at = |name: &str| {
+            let id = format!("https://sinople.org/ontology#{}", name);
+            positions.iter().find(|p| p.id == id).unwrap()
+        };
+        assert_eq!(positions.len(), 8);
+        assert_eq!((at("hub").x, at("hub").y), (0.0, 0.0));
+        for (name, ring) in [("a", 1), ("b1", 2), ("a3", 2), ("far", 3)] {
+            let p = at(name);
+            assert_eq!(p.layer, ring);
+            assert!((p.x.hypot(p.y) - ring as f64 * 100.0).abs() < 1e-9);
+        }
+        // a leads on to three of the four outermost resources, so takes
+        // three quarters of the circle, and b1 stays in line with b
+        let angle = |name: &str| at(name).y.atan2(at(name).x);
+        assert!((angle("a") - 0.75 * PI).abs() < 1e-9);
+        assert!((angle("b1") - angle("b")).abs() < 1e-9);
+
+        let near = processor.radial("sn:hub", Some(1)).unwrap();
+        assert_eq!(near.len(), 3);
+    }
+}
@@ -0,0 +1,316 @@
+//! Lenient Turtle loading
+//!
+//! A document is split into its statements, each of which is checked on its
+//! own. Malformed statements are skipped and reported as warnings, so one bad
+//! triple does not keep the rest of the ontology from loading:
+//!
+//! ```javascript
+//! const warnings = processor.load_turtle_lenient(ttl);
+//! for (const w of warnings) console.warn(`line ${w.line}:${w.column} ${w.message}`);
+//! ```
+
+use crate::error::{ProcessorError, SyntaxError};
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::parser::TripleParser;
+use sophia_api::source::{StreamError, TripleSource};
+use sophia_inmem::graph::FastGraph;
+use sophia_turtle::parser::turtle::TurtleParser;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+/// A statement skipped by a lenient load
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadWarning {
+    /// 1-based line of the error
+    pub line: usize,
+    /// 1-based column of the error, counted in characters
+    pub column: usize,
+    /// Text at the error position
+    pub token: String,
+    pub message: String,
+}
+
+/// One statement or directive of a Turtle document
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Statement<'a> {
+    pub text: &'a str,
+    /// 1-based line and column where the statement starts
+    pub line: usize,
+    pub column: usize,
+    /// Byte offset just past the statement
+    pub end: usize,
+    /// Whether the statement ends with its terminator, rather than the input
+    pub terminated: bool,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Load RDF data from Turtle format, skipping malformed statements
+    ///
+    /// # Arguments
+    /// * `ttl` - Turtle-formatted RDF string
+    ///
+    /// # Returns
+    /// JsValue containing array of warnings {line, column, token, message},
+    /// one for each statement that was skipped
+    #[wasm_bindgen(unchecked_return_type = "LoadWarning[]")]
+    pub fn load_turtle_lenient(&mut self, ttl: &str) -> Result<JsValue, JsValue> {
+        let warnings = self.load_lenient(ttl)?;
+
+        self.output(&warnings)
+    }
+}
+
+impl SemanticProcessor {
+    /// Load the well-formed statements of a Turtle document
+    pub(crate) fn load_lenient(&mut self, ttl: &str) -> Result<Vec<LoadWarning>, ProcessorError> {
+        let parser = self.turtle_parser();
+        let (accepted, warnings) = check_statements(ttl, &parser);
+
+        let mut incoming = FastGraph::new();
+        parser
+            .parse_str(&accepted)
+            .map_triples(|t| self.aliases.triple(t))
+            .add_to_graph(&mut incoming)
+            .map_err(|e| ProcessorError::parse_at("Turtle", &accepted, e))?;
+
+        self.merge_loaded(incoming, HashMap::new());
+        Ok(warnings)
+    }
+}
+
+/// Check each statement of a document separately
+///
+/// # Returns
+/// The well-formed statements, joined into one document, and a warning for
+/// each malformed one
+fn check_statements(ttl: &str, parser: &TurtleParser) -> (String, Vec<LoadWarning>) {
+    let mut directives = String::new();
+    let mut accepted = String::new();
+    let mut warnings = Vec::new();
+
+    for statement in split_statements(ttl) {
+        // Earlier directives are needed to resolve prefixed names
+        let document = format!("{}{}\n", directives, statement.text);
+        let result = parser
+            .parse_str(&document)
+            .add_to_graph(&mut FastGraph::new());
+
+        match result {
+            Ok(_) => {
+                if is_directive(statement.text) {
+                    directives.push_str(statement.text);
+                    directives.push('\n');
+                }
+                accepted.push_str(statement.text);
+                accepted.push('\n');
+            }
+            Err(e) => {
+                let offset = directives.lines().count();
+                let located = match &e {
+                    StreamError::SourceError(source) => SyntaxError::locate(&document, source),
+                    StreamError::SinkError(_) => None,
+                };
+                warnings.push(match located {
+                    Some(syntax) => {
+                        let line = syntax.line.saturating_sub(offset).max(1);
+                        LoadWarning {
+                            line: statement.line + line - 1,
+                            column: if line == 1 {
+                                statement.column + syntax.column - 1
+                            } else {
+                                syntax.column
+                            },
+                            token: syntax.token,
+                            message: syntax.detail,
+                        }
+                    }
+                    None => LoadWarning {
+                        line: statement.line,
+                        column: statement.column,
+                        token: String::new(),
+                        message: e.to_string(),
+                    },
+                });
+            }
+        }
+    }
+
+    (accepted, warnings)
+}
+
+/// Whether a statement is a `@prefix`/`@base` or `PREFIX`/`BASE` directive
+pub(crate) fn is_directive(statement: &str) -> bool {
+    let keyword = statement
+        .split(|c: char| c.is_whitespace() || c == '<')
+        .next()
+        .unwrap_or("");
+    keyword == "@prefix"
+        || keyword == "@base"
+        || keyword.eq_ignore_ascii_case("prefix")
+        || keyword.eq_ignore_ascii_case("base")
+}
+
+/// Split a Turtle document into statements and directives
+///
+/// A statement ends at a `.` followed by whitespace, a comment or the end of
+/// the document; SPARQL-style `PREFIX`/`BASE` directives end with their IRI.
+/// Dots inside IRIs, strings and comments are ignored. Trailing text without
+/// a terminator is returned as a final statement.
+pub(crate) fn split_statements(input: &str) -> Vec<Statement<'_>> {
+    let mut statements = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    let mut start: Option<(usize, usize, usize)> = None;
+    let (mut line, mut column) = (1, 1);
+    let mut in_iri = false;
+    let mut in_comment = false;
+    let mut quote: Option<(char, bool)> = None;
+
+    while let Some((index, c)) = chars.next() {
+        let (at_line, at_column) = (line, column);
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
+        if let Some((delimiter, long)) = quote {
+            if c == '\\' {
+                if let Some((_, escaped)) = chars.next() {
+                    column += 1;
+                    if escaped == '\n' {
+                        line += 1;
+                        column = 1;
+                    }
+                }
+            } else if c == delimiter {
+                if !long {
+                    quote = None;
+                } else if input[index..].starts_with(&delimiter.to_string().repeat(3)) {
+                    chars.next();
+                    chars.next();
+                    column += 2;
+                    quote = None;
+                }
+            }
+            continue;
+        }
+        if in_iri {
+            if c == '>' {
+                in_iri = false;
+                if let Some((begin, begin_line, begin_column)) = start {
+                    let text = &input[begin..=index];
+                    if is_directive(text) && !text.starts_with('@') {
+                        statements.push(Statement {
+                            text,
+                            line: begin_line,
+                            column: begin_column,
+                            end: index + 1,
+                            terminated: true,
+                        });
+                        start = None;
+                    }
+                }
+            }
+            continue;
+        }
+
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '#' {
+            in_comment = true;
+            continue;
+        }
+        let (begin, ..) = *start.get_or_insert((index, at_line, at_column));
+
+        match c {
+            // Quoted triples are not IRIs
+            '<' if input[index..].starts_with("<<") => {
+                chars.next();
+                column += 1;
+            }
+            '<' => in_iri = true,
+            '"' | '\'' => {
+                let long = input[index..].starts_with(&c.to_string().repeat(3));
+                if long {
+                    chars.next();
+                    chars.next();
+                    column += 2;
+                }
+                quote = Some((c, long));
+            }
+            '.' => {
+                let ends = chars
+                    .peek()
+                    .is_none_or(|&(_, next)| next.is_whitespace() || next == '#');
+                if ends {
+                    if let Some((_, begin_line, begin_column)) = start.take() {
+                        statements.push(Statement {
+                            text: &input[begin..=index],
+                            line: begin_line,
+                            column: begin_column,
+                            end: index + 1,
+                            terminated: true,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((begin, begin_line, begin_column)) = start {
+        statements.push(Statement {
+            text: input[begin..].trim_end(),
+            line: begin_line,
+            column: begin_column,
+            end: input.len(),
+            terminated: false,
+        });
+    }
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        let ttl = "@prefix sn: <https://sinople.org/ontology#> .\n\
+                   PREFIX ex: <https://example.org/>\n\
+                   sn:a sn:label \"A. B.\" ; # comment .\n  sn:ratio 1.5 .\n\
+                   ex:b ex:note \"\"\"long \" text.\n\"\"\" .";
+        let statements = split_statements(ttl);
+
+        let texts: Vec<&str> = statements.iter().map(|s| s.text).collect();
+        assert_eq!(texts.len(), 4);
+        assert_eq!(texts[1], "PREFIX ex: <https://example.org/>");
+        assert!(texts[2].ends_with("sn:ratio 1.5 ."));
+        assert_eq!((statements[3].line, statements[3].column), (5, 1));
+    }
+
+    #[test]
+    fn test_lenient_load_skips_malformed_statements() {
+        let ttl = "@prefix sn: <https://sinople.org/ontology#> .\n\
+                   sn:a sn:label \"A\" .\n\
+                   sn:b sn:label sn:c sn:d .\n\
+                   sn:e sn:label \"E\" .\n\
+                   sn:f sn:label \"unterminated .\n";
+        let mut processor = SemanticProcessor::new();
+        let warnings = processor.load_lenient(ttl).unwrap();
+
+        assert_eq!(processor.triple_count(), 2);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!((warnings[0].line, warnings[0].column), (3, 20));
+        assert_eq!(warnings[0].token, "sn:d");
+        assert_eq!(warnings[1].line, 5);
+    }
+}
@@ -4,10 +4,26 @@
 //! It uses Sophia 0.8 for in-memory RDF graph management and SPARQL-like querying.
 //!
 //! # Features
-//! - Load and parse Turtle (TTL) format ontologies
-//! - Query constructs, entanglements, and character relationships
-//! - Find glosses and annotations
-//! - Export semantic data for visualization
+//! - Load Turtle, N-Triples, N-Quads, TriG, JSON-LD and RDF/XML, from text, raw or
+//!   compressed bytes, or in chunks, with progress, abort and lenient parsing
+//!   (`base`, `encoding`, `compression`, `streaming`, `progress`, `abort`, `lenient`)
+//! - Keep named graphs and the documents each triple came from, and edit the graph
+//!   in transactions with undo (`history`, `patch`)
+//! - Query constructs, entanglements, characters, events, places, works and
+//!   citations, filtered, sorted, paged or in batches (`options`, `pagination`,
+//!   `batch`, `events`, `narrative`, `places`, `works`, `citations`)
+//! - Run SPARQL SELECT, ASK and updates, and custom rules (`sparql`, `rules`)
+//! - Infer with RDFS or OWL-lite, subclasses, inverse, symmetric and transitive
+//!   properties and `owl:sameAs` (`reasoning`, `inference`, `classes`, `transitive`, `identity`)
+//! - Check the graph with SHACL, domain and range declarations and lint rules
+//!   (`shacl`, `consistency`, `lint`, `orphans`, `duplicates`, `cycles`)
+//! - Build, analyse and lay out the network graph (`subgraphs`, `paths`,
+//!   `centrality`, `metrics`, `layout`, `hierarchy`)
+//! - Export as Turtle, JSON-LD, GraphML, GEXF, Cytoscape.js, SVG, PlantUML,
+//!   TimelineJS, GeoJSON or BibTeX (`export`, `graphml`, `gexf`, `cytoscape`,
+//!   `svg`, `plantuml`, `timeline`, `places`, `bibtex`)
+//! - Return results as JSON or CBOR, with either key case and CURIEs, cached until
+//!   the graph changes (`output`, `casing`, `curie`, `cache`, `typescript`)
 //!
 //! # Usage
 //! ```javascript
@@ -18,14 +34,130 @@
 //! const constructs = await processor.query_constructs();
 //! ```
 
-use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
-use sophia_api::graph::Graph;
-use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::prefix::{Prefix, PrefixMapPair};
+use sophia_api::serializer::{QuadSerializer, Stringifier, TripleSerializer};
+use sophia_api::source::{QuadSource, StreamResult, TripleSource};
+use sophia_api::term::matcher::{Any, TermMatcher};
+use sophia_api::term::{BnodeId, IriRef, SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_api::MownStr;
 use sophia_inmem::graph::FastGraph;
-use sophia_turtle::parser::turtle::TurtleParser;
-use sophia_api::parser::TripleParser;
-use std::collections::HashMap;
+use sophia_iri::Iri;
+use sophia_jsonld::JsonLdSerializer;
+use sophia_turtle::parser::nq::NQuadsParser;
+use sophia_turtle::parser::nt::NTriplesParser;
+use sophia_turtle::serializer::turtle::{TurtleConfig, TurtleSerializer};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+mod abort;
+mod alias;
+mod base;
+mod batch;
+mod bibtex;
+mod binary;
+mod cache;
+mod casing;
+mod centrality;
+mod citations;
+mod classes;
+mod collections;
+mod compression;
+mod consistency;
+mod curie;
+mod cycles;
+mod cytoscape;
+mod duplicates;
+mod encoding;
+mod entanglements;
+mod error;
+mod events;
+mod export;
+mod gexf;
+mod graphml;
+mod hierarchy;
+mod history;
+mod identity;
+mod inference;
+mod intern;
+mod layout;
+mod lenient;
+mod lint;
+mod literals;
+mod metrics;
+mod namespaces;
+mod narrative;
+mod options;
+mod orphans;
+mod output;
+mod pagination;
+mod parallel;
+mod patch;
+mod paths;
+mod places;
+mod plantuml;
+mod progress;
+mod reasoning;
+mod reification;
+mod rules;
+mod shacl;
+mod skolem;
+mod sparql;
+mod star;
+mod stats;
+mod streaming;
+mod subgraphs;
+mod svg;
+mod timeline;
+mod transitive;
+mod typescript;
+mod works;
+
+use alias::Aliases;
+use cache::QueryCache;
+use casing::KeyCase;
+use history::History;
+use intern::Interner;
+use literals::parse_weight;
+use output::OutputEncoding;
+use parallel::index_parallel_edges;
+use progress::{js_progress, ProgressReader};
+use reasoning::{Derivation, Reasoning};
+use rules::Rule;
+use streaming::StreamingLoad;
+
+pub use abort::AbortHandle;
+pub use centrality::NodeScore;
+pub use citations::Citation;
+pub use consistency::Violation;
+pub use cycles::Cycle;
+pub use cytoscape::{
+    CytoscapeEdge, CytoscapeEdgeData, CytoscapeElements, CytoscapeNode, CytoscapePosition,
+};
+pub use duplicates::{DuplicateLabel, LabelledEntity};
+pub use entanglements::{EntanglementError, EntanglementErrors};
+pub use error::{ErrorCode, ProcessorError};
+pub use events::Event;
+pub use hierarchy::HierarchyNode;
+pub use layout::{LayeredPosition, NodePosition};
+pub use lenient::LoadWarning;
+pub use lint::{LintIssue, LintReport};
+pub use metrics::GraphMetrics;
+pub use narrative::NarrativeOrder;
+pub use options::{QueryOptions, SortKey, SortOrder};
+pub use paths::GraphPath;
+pub use places::{Feature, FeatureCollection, Place, PlaceProperties, Point};
+pub use reification::{Annotation, ReifiedStatement};
+pub use rules::RuleReport;
+pub use shacl::{ValidationReport, ValidationResult};
+pub use svg::{SvgLayout, SvgOptions};
+pub use timeline::{Timeline, TimelineDate, TimelineEvent, TimelineText};
+pub use works::Work;
 
 /// Initialize panic hook for better error messages in console
 #[wasm_bindgen(start)]
@@ -35,6 +167,7 @@ pub fn init() {
 
 /// Represents a Construct in the Sinople ontology
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen(getter_with_clone)]
 pub struct Construct {
     pub id: String,
     pub label: String,
@@ -45,6 +178,7 @@ pub struct Construct {
 
 /// Represents an Entanglement (relationship between constructs)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen(getter_with_clone)]
 pub struct Entanglement {
     pub id: String,
     pub label: String,
@@ -52,19 +186,25 @@ pub struct Entanglement {
     pub target: String,
     pub relationship_type: String,
     pub description: Option<String>,
+    /// Metadata from reified statements about the entanglement
+    #[wasm_bindgen(skip)]
+    pub annotations: Vec<Annotation>,
 }
 
 /// Represents a Gloss (annotation or explanation)
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen(getter_with_clone)]
 pub struct Gloss {
     pub id: String,
     pub text: String,
+    /// Language tag of the text, e.g. `"fr"`, or empty if it has none
     pub language: String,
     pub position: Option<usize>,
 }
 
 /// Represents a Character in the semantic universe
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen(getter_with_clone)]
 pub struct Character {
     pub id: String,
     pub name: String,
@@ -72,36 +212,262 @@ pub struct Character {
     pub constructs: Vec<String>,
 }
 
+/// An RDF term together with its kind, and the datatype and language of a literal
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TermValue {
+    /// `"iri"`, `"blank"`, `"literal"` or `"triple"`
+    pub kind: String,
+    /// IRI, `_:`-prefixed blank node label, or lexical form of a literal;
+    /// empty for a quoted triple
+    pub value: String,
+    /// Datatype IRI of a literal; `rdf:langString` if it has a language tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datatype: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Terms of a quoted (RDF-star) triple
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub triple: Option<Box<TripleMatch>>,
+}
+
+impl TermValue {
+    fn from_term<T: Term>(term: T) -> Self {
+        let (kind, value) = match term.as_simple() {
+            SimpleTerm::Iri(iri) => ("iri", iri.as_str().to_string()),
+            SimpleTerm::BlankNode(bn) => ("blank", format!("_:{}", bn.as_str())),
+            SimpleTerm::LiteralDatatype(lex, _) | SimpleTerm::LiteralLanguage(lex, _) => {
+                ("literal", lex.to_string())
+            }
+            SimpleTerm::Triple(_) => ("triple", String::new()),
+            SimpleTerm::Variable(v) => ("variable", v.as_str().to_string()),
+        };
+        TermValue {
+            kind: kind.to_string(),
+            value,
+            datatype: term.datatype().map(|dt| dt.as_str().to_string()),
+            language: term.language_tag().map(|tag| tag.as_str().to_string()),
+            triple: match term.as_simple() {
+                SimpleTerm::Triple(triple) => Some(Box::new(TripleMatch::from_triple(&*triple))),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A triple returned by pattern matching, with fully typed terms
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TripleMatch {
+    pub subject: TermValue,
+    pub predicate: TermValue,
+    pub object: TermValue,
+}
+
+impl TripleMatch {
+    fn from_triple<T: Triple>(t: &T) -> Self {
+        TripleMatch {
+            subject: TermValue::from_term(t.s()),
+            predicate: TermValue::from_term(t.p()),
+            object: TermValue::from_term(t.o()),
+        }
+    }
+}
+
+/// A link pointing at a resource
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct IncomingLink {
+    /// The resource the link comes from
+    pub source: String,
+    /// IRI of the linking predicate
+    pub predicate: String,
+}
+
+/// Every property of a single resource, keyed by predicate IRI
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceDescription {
+    pub id: String,
+    pub properties: BTreeMap<String, Vec<TermValue>>,
+    /// Subjects linking to this resource, keyed by predicate IRI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incoming: Option<BTreeMap<String, Vec<TermValue>>>,
+}
+
+/// Outcome of merging a document into the graph
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct MergeReport {
+    /// Triples that were not in the graph yet
+    pub added: usize,
+    /// Triples that were already present
+    pub duplicates: usize,
+}
+
+/// Triples that differ between the graph and another document
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphDiff {
+    /// In the other document but not in the graph
+    pub added: Vec<TripleMatch>,
+    /// In the graph but not in the other document
+    pub removed: Vec<TripleMatch>,
+}
+
 /// Network graph node for visualization
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen(getter_with_clone)]
 pub struct GraphNode {
     pub id: String,
     pub label: String,
     pub node_type: String,
+    /// IRIs of every `rdf:type` of the resource, sorted
+    #[serde(default)]
+    pub types: Vec<String>,
+    /// Number of edges of the graph that end at the node
+    #[serde(default)]
+    pub in_degree: u32,
+    /// Number of edges of the graph that start at the node
+    #[serde(default)]
+    pub out_degree: u32,
 }
 
 /// Network graph edge for visualization
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen(getter_with_clone)]
 pub struct GraphEdge {
     pub source: String,
     pub target: String,
     pub label: String,
+    /// True if the relationship is declared `owl:SymmetricProperty`, so the
+    /// edge has no direction
+    #[serde(default)]
+    pub symmetric: bool,
+    /// True if the edge should be drawn with an arrow: its relationship is
+    /// not symmetric and edges are not all exported undirected
+    #[serde(default = "directed_by_default")]
+    pub directed: bool,
+    /// Strength of the relationship, from the entanglement's `sn:weight`;
+    /// 1.0 if it has none
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// Number of relationships the edge stands for; more than 1 if parallel
+    /// edges are merged
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Position of the edge among those joining the same two nodes, in
+    /// either direction, for offsetting curves
+    #[serde(default)]
+    pub parallel_index: u32,
+    /// Number of edges joining the same two nodes, in either direction
+    #[serde(default = "default_count")]
+    pub parallel_count: u32,
+}
+
+fn directed_by_default() -> bool {
+    true
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_count() -> u32 {
+    1
 }
 
 /// Network graph structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[wasm_bindgen(getter_with_clone)]
 pub struct NetworkGraph {
     pub nodes: Vec<GraphNode>,
     pub edges: Vec<GraphEdge>,
 }
 
+impl NetworkGraph {
+    /// Create a network graph, counting the degree of each node from the edges
+    /// and numbering parallel edges
+    ///
+    /// A symmetric edge counts as stored, leaving its source and entering its
+    /// target.
+    fn new(mut nodes: Vec<GraphNode>, mut edges: Vec<GraphEdge>) -> Self {
+        index_parallel_edges(&mut edges);
+        let mut degrees: HashMap<&str, (u32, u32)> = HashMap::new();
+        for edge in &edges {
+            degrees.entry(&edge.source).or_default().1 += 1;
+            degrees.entry(&edge.target).or_default().0 += 1;
+        }
+        for node in &mut nodes {
+            (node.in_degree, node.out_degree) =
+                degrees.get(node.id.as_str()).copied().unwrap_or_default();
+        }
+        NetworkGraph { nodes, edges }
+    }
+}
+
 /// Main Semantic Processor struct
 ///
 /// Manages an in-memory RDF graph and provides query methods
 #[wasm_bindgen]
 pub struct SemanticProcessor {
+    /// Union of everything loaded
     graph: FastGraph,
+    /// Triples that arrived in a named graph (TriG, N-Quads), also kept apart
+    /// so they can be queried individually
+    named_graphs: HashMap<String, FastGraph>,
+    /// Registered namespace prefixes
     namespaces: HashMap<String, String>,
+    /// Triples contributed by each document loaded with `load_turtle_named`
+    sources: HashMap<String, HashSet<[SimpleTerm<'static>; 3]>>,
+    /// State to return to on rollback, while a transaction is open
+    transaction: Option<Snapshot>,
+    /// Edits that can be undone
+    history: History,
+    /// Unparsed tail of a chunked load
+    streaming: Option<StreamingLoad>,
+    /// Flag that cancels long-running operations
+    abort: Rc<Cell<bool>>,
+    /// Query results, remembered until the graph changes
+    cache: RefCell<QueryCache>,
+    /// Strings of IRIs shared between cached results
+    strings: RefCell<Interner>,
+    /// Encoding results are returned in
+    output: OutputEncoding,
+    /// Case of field names in results
+    key_case: KeyCase,
+    /// Whether IRIs in results are compacted to CURIEs
+    compact_iris: bool,
+    /// IRI that relative IRIs in loaded documents resolve against
+    base_iri: Option<Iri<String>>,
+    /// Rewrites IRIs in alternative forms of the registered namespaces
+    aliases: Aliases,
+    /// Whether blank nodes are replaced with stable IRIs at the end of every load
+    skolemize: bool,
+    /// Whether type queries include instances of subclasses
+    subclass_inference: bool,
+    /// Whether resources linked by `owl:sameAs` are merged in results
+    identity_merging: bool,
+    /// Inferences added to the union graph
+    reasoning: Reasoning,
+    /// How each inferred triple was derived
+    inferred: HashMap<[SimpleTerm<'static>; 3], Derivation>,
+    /// SHACL shapes that `validate` checks the graph against
+    shapes: FastGraph,
+    /// Rules that `apply_rules` runs
+    rules: Vec<Rule>,
+    /// Vocabulary that `validate_entanglements` checks relationship types against
+    relationship_types: Vec<String>,
+    /// Whether every network graph edge is undirected
+    undirected_edges: bool,
+    /// Whether edges joining the same nodes are merged into one
+    merge_parallel_edges: bool,
+}
+
+/// A quad document parsed into its union graph and its named graphs
+type ParsedQuads = (FastGraph, HashMap<String, FastGraph>);
+
+/// Copy of the mutable state, taken by `begin` and restored by `rollback`
+#[derive(Clone)]
+struct Snapshot {
+    graph: FastGraph,
+    named_graphs: HashMap<String, FastGraph>,
+    sources: HashMap<String, HashSet<[SimpleTerm<'static>; 3]>>,
+    inferred: HashMap<[SimpleTerm<'static>; 3], Derivation>,
 }
 
 #[wasm_bindgen]
@@ -111,16 +477,58 @@ impl SemanticProcessor {
     pub fn new() -> Self {
         let mut namespaces = HashMap::new();
 
-        // Register common namespaces
-        namespaces.insert("sn".to_string(), "https://sinople.org/ontology#".to_string());
-        namespaces.insert("rdf".to_string(), "https://www.w3.org/1999/02/22-rdf-syntax-ns#".to_string());
-        namespaces.insert("rdfs".to_string(), "https://www.w3.org/2000/01/rdf-schema#".to_string());
-        namespaces.insert("owl".to_string(), "https://www.w3.org/2002/07/owl#".to_string());
-        namespaces.insert("xsd".to_string(), "https://www.w3.org/2001/XMLSchema#".to_string());
+        // Register common namespaces. The W3C vocabularies use their published
+        // http:// IRIs; before namespace aliases they were registered under
+        // https://, which is now an alias rewritten to http:// on load.
+        namespaces.insert(
+            "sn".to_string(),
+            "https://sinople.org/ontology#".to_string(),
+        );
+        namespaces.insert(
+            "rdf".to_string(),
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#".to_string(),
+        );
+        namespaces.insert(
+            "rdfs".to_string(),
+            "http://www.w3.org/2000/01/rdf-schema#".to_string(),
+        );
+        namespaces.insert(
+            "owl".to_string(),
+            "http://www.w3.org/2002/07/owl#".to_string(),
+        );
+        namespaces.insert(
+            "xsd".to_string(),
+            "http://www.w3.org/2001/XMLSchema#".to_string(),
+        );
+
+        let aliases = Aliases::new(&namespaces);
 
         SemanticProcessor {
             graph: FastGraph::new(),
+            named_graphs: HashMap::new(),
             namespaces,
+            sources: HashMap::new(),
+            transaction: None,
+            history: History::default(),
+            streaming: None,
+            abort: Rc::default(),
+            cache: RefCell::default(),
+            strings: RefCell::default(),
+            output: OutputEncoding::default(),
+            key_case: KeyCase::default(),
+            compact_iris: false,
+            base_iri: None,
+            aliases,
+            skolemize: false,
+            subclass_inference: true,
+            identity_merging: true,
+            reasoning: Reasoning::default(),
+            inferred: HashMap::new(),
+            shapes: FastGraph::new(),
+            rules: Vec::new(),
+            relationship_types: Vec::new(),
+            undirected_edges: false,
+            merge_parallel_edges: false,
         }
     }
 
@@ -128,90 +536,309 @@ pub fn new() -> Self {
     ///
     /// # Arguments
     /// * `ttl` - Turtle-formatted RDF string
+    /// * `on_progress` - Optional callback `(percent, bytes)`, called as parsing advances
+    ///
+    /// If the abort flag is set, the load stops with an `Aborted` error and
+    /// leaves the processor as it was.
+    ///
+    /// # Returns
+    /// * `Ok(())` if successful
+    /// * `Err(JsValue)` with error message if parsing fails
+    pub fn load_turtle(
+        &mut self,
+        ttl: &str,
+        on_progress: Option<js_sys::Function>,
+    ) -> Result<(), JsValue> {
+        let parser = self.turtle_parser();
+
+        self.check_abort()?;
+        let mut incoming = FastGraph::new();
+        let result = match &on_progress {
+            Some(callback) => parser
+                .parse(
+                    ProgressReader::new(ttl.as_bytes(), js_progress(callback))
+                        .with_abort_flag(&self.abort),
+                )
+                .map_triples(|t| self.aliases.triple(t))
+                .add_to_graph(&mut incoming),
+            None => parser
+                .parse(self.abortable(ttl))
+                .map_triples(|t| self.aliases.triple(t))
+                .add_to_graph(&mut incoming),
+        };
+        if let Err(e) = result {
+            self.check_abort()?;
+            return Err(ProcessorError::parse_at("Turtle", ttl, e).into());
+        }
+
+        self.merge_loaded(incoming, HashMap::new());
+        Ok(())
+    }
+
+    /// Load RDF data from N-Triples format
+    ///
+    /// # Arguments
+    /// * `nt` - N-Triples-formatted RDF string
     ///
     /// # Returns
     /// * `Ok(())` if successful
     /// * `Err(JsValue)` with error message if parsing fails
-    pub fn load_turtle(&mut self, ttl: &str) -> Result<(), JsValue> {
-        let parser = TurtleParser::new(ttl.as_bytes());
+    pub fn load_ntriples(&mut self, nt: &str) -> Result<(), JsValue> {
+        Ok(self.read_ntriples(nt)?)
+    }
 
-        parser
-            .parse_all(&mut self.graph)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse Turtle: {}", e)))?;
+    /// Load RDF data from N-Quads format
+    ///
+    /// Quads with a graph name are also stored in that named graph.
+    ///
+    /// # Arguments
+    /// * `nq` - N-Quads-formatted RDF string
+    ///
+    /// # Returns
+    /// * `Ok(())` if successful
+    /// * `Err(JsValue)` with error message if parsing fails
+    pub fn load_nquads(&mut self, nq: &str) -> Result<(), JsValue> {
+        let parser = NQuadsParser {};
+
+        self.check_abort()?;
+        let (incoming, named_graphs) = match self.parse_quads(parser.parse(self.abortable(nq))) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.check_abort()?;
+                return Err(ProcessorError::parse_at("N-Quads", nq, e).into());
+            }
+        };
 
+        self.merge_loaded(incoming, named_graphs);
         Ok(())
     }
 
-    /// Query all constructs from the graph
+    /// Load RDF data from TriG format
+    ///
+    /// Triples in the default graph go to the union graph only; triples in a
+    /// named graph are also stored in that named graph.
+    ///
+    /// # Arguments
+    /// * `trig` - TriG-formatted RDF string
     ///
     /// # Returns
-    /// JsValue containing array of Construct objects
-    pub fn query_constructs(&self) -> Result<JsValue, JsValue> {
-        let mut constructs = Vec::new();
-        let construct_type = self.make_term("sn:Construct");
-        let rdf_type = self.make_term("rdf:type");
+    /// * `Ok(())` if successful
+    /// * `Err(JsValue)` with error message if parsing fails
+    pub fn load_trig(&mut self, trig: &str) -> Result<(), JsValue> {
+        let parser = self.trig_parser();
+
+        self.check_abort()?;
+        let (incoming, named_graphs) = match self.parse_quads(parser.parse(self.abortable(trig))) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.check_abort()?;
+                return Err(ProcessorError::parse_at("TriG", trig, e).into());
+            }
+        };
 
-        // Find all instances of sn:Construct
-        for triple in self.graph.triples() {
-            let triple = triple.map_err(|e| JsValue::from_str(&format!("Graph error: {}", e)))?;
+        self.merge_loaded(incoming, named_graphs);
+        Ok(())
+    }
 
-            if self.term_equals(triple.p(), &rdf_type) && self.term_equals(triple.o(), &construct_type) {
-                let subject_iri = self.term_to_string(triple.s());
+    /// List the names of all named graphs
+    ///
+    /// # Returns
+    /// JsValue containing array of graph name IRIs
+    #[wasm_bindgen(unchecked_return_type = "string[]")]
+    pub fn graph_names(&self) -> Result<JsValue, JsValue> {
+        let mut names: Vec<&String> = self.named_graphs.keys().collect();
+        names.sort();
 
-                // Get properties
-                let label = self.get_object_value(&subject_iri, "rdfs:label").unwrap_or_default();
-                let description = self.get_object_value(&subject_iri, "rdfs:comment");
-                let glosses = self.get_glosses(&subject_iri);
-                let relationships = self.get_relationships(&subject_iri);
+        self.output(&names)
+    }
 
-                constructs.push(Construct {
-                    id: subject_iri.clone(),
-                    label,
-                    description,
-                    glosses,
-                    relationships,
-                });
-            }
+    /// Load RDF data from JSON-LD format
+    ///
+    /// The document is expanded against its `@context` before conversion to RDF.
+    /// Only inline contexts are supported; remote contexts are not fetched.
+    /// Triples from named graphs are merged into the default graph.
+    ///
+    /// # Arguments
+    /// * `json` - JSON-LD document string
+    ///
+    /// # Returns
+    /// * `Ok(())` if successful
+    /// * `Err(JsValue)` with error message if parsing or expansion fails
+    pub fn load_jsonld(&mut self, json: &str) -> Result<(), JsValue> {
+        let parser = self.jsonld_parser();
+
+        self.check_abort()?;
+        let mut incoming = FastGraph::new();
+        let result = parser
+            .parse(self.abortable(json))
+            .to_triples()
+            .map_triples(|t| self.aliases.triple(t))
+            .add_to_graph(&mut incoming);
+        if let Err(e) = result {
+            self.check_abort()?;
+            return Err(ProcessorError::parse("JSON-LD", e).into());
         }
 
-        serde_wasm_bindgen::to_value(&constructs)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        self.merge_loaded(incoming, HashMap::new());
+        Ok(())
     }
 
-    /// Query all entanglements from the graph
+    /// Load RDF data from RDF/XML format
+    ///
+    /// # Arguments
+    /// * `xml` - RDF/XML-formatted RDF string
     ///
     /// # Returns
-    /// JsValue containing array of Entanglement objects
-    pub fn query_entanglements(&self) -> Result<JsValue, JsValue> {
-        let mut entanglements = Vec::new();
-        let entanglement_type = self.make_term("sn:Entanglement");
-        let rdf_type = self.make_term("rdf:type");
+    /// * `Ok(())` if successful
+    /// * `Err(JsValue)` with error message if parsing fails
+    pub fn load_rdfxml(&mut self, xml: &str) -> Result<(), JsValue> {
+        let parser = self.rdfxml_parser();
+
+        self.check_abort()?;
+        let mut incoming = FastGraph::new();
+        let result = parser
+            .parse(self.abortable(xml))
+            .map_triples(|t| self.aliases.triple(t))
+            .add_to_graph(&mut incoming);
+        if let Err(e) = result {
+            self.check_abort()?;
+            return Err(ProcessorError::parse("RDF/XML", e).into());
+        }
 
-        for triple in self.graph.triples() {
-            let triple = triple.map_err(|e| JsValue::from_str(&format!("Graph error: {}", e)))?;
+        self.merge_loaded(incoming, HashMap::new());
+        Ok(())
+    }
 
-            if self.term_equals(triple.p(), &rdf_type) && self.term_equals(triple.o(), &entanglement_type) {
-                let subject_iri = self.term_to_string(triple.s());
+    /// Load a Turtle document and remember which triples it contributed
+    ///
+    /// Loading again under the same `source_id` adds to that source. Triples
+    /// that were already present before, without a source, are left out of it.
+    ///
+    /// # Arguments
+    /// * `ttl` - Turtle format string
+    /// * `source_id` - Identifier to unload the document with later, e.g. a post ID
+    pub fn load_turtle_named(&mut self, ttl: &str, source_id: &str) -> Result<(), JsValue> {
+        let incoming = self.parse_turtle(ttl)?;
 
-                let label = self.get_object_value(&subject_iri, "rdfs:label").unwrap_or_default();
-                let description = self.get_object_value(&subject_iri, "rdfs:comment");
-                let source = self.get_object_value(&subject_iri, "sn:hasSource").unwrap_or_default();
-                let target = self.get_object_value(&subject_iri, "sn:hasTarget").unwrap_or_default();
-                let rel_type = self.get_object_value(&subject_iri, "sn:relationshipType").unwrap_or_else(|| "related".to_string());
+        self.load_source(&incoming, source_id);
+        self.finish_edit();
+        Ok(())
+    }
 
-                entanglements.push(Entanglement {
-                    id: subject_iri,
-                    label,
-                    source,
-                    target,
-                    relationship_type: rel_type,
-                    description,
-                });
-            }
+    /// Remove exactly the triples contributed by a source
+    ///
+    /// Triples that another source also contributed are kept.
+    ///
+    /// # Returns
+    /// The number of triples removed from the graph
+    pub fn unload_source(&mut self, source_id: &str) -> usize {
+        let Some(triples) = self.sources.get(source_id).cloned() else {
+            return 0;
+        };
+        for triple in &triples {
+            self.release(source_id, triple);
+        }
+
+        let orphaned: Vec<_> = triples
+            .into_iter()
+            .filter(|triple| !self.sources.values().any(|other| other.contains(triple)))
+            .collect();
+
+        let removed = orphaned
+            .iter()
+            .filter(|triple| self.remove_terms(triple))
+            .count();
+        self.finish_edit();
+        removed
+    }
+
+    /// Compare the graph with a Turtle document, without changing the graph
+    ///
+    /// Blank node labels are not comparable between documents, so blank nodes
+    /// only have to appear in the same positions for two triples to match.
+    ///
+    /// # Returns
+    /// JsValue containing {added, removed}: the triples loading `other_ttl`
+    /// in place of the current graph would add and remove
+    #[wasm_bindgen(unchecked_return_type = "GraphDiff")]
+    pub fn diff(&self, other_ttl: &str) -> Result<JsValue, JsValue> {
+        let other = self.parse_turtle(other_ttl)?;
+
+        let diff = self.diff_graph(&other);
+
+        self.output_json(&diff)
+    }
+
+    /// Merge a Turtle document into the graph and report what it added
+    ///
+    /// The document is parsed into a temporary graph first, so nothing is
+    /// merged if it fails to parse. Blank nodes whose labels are already in use
+    /// are renamed, so they are never conflated with existing ones.
+    ///
+    /// # Returns
+    /// JsValue containing {added, duplicates}
+    #[wasm_bindgen(unchecked_return_type = "MergeReport")]
+    pub fn merge_turtle(&mut self, ttl: &str) -> Result<JsValue, JsValue> {
+        let incoming = self.parse_turtle(ttl)?;
+
+        let report = self.merge_graph(&incoming);
+        self.finish_edit();
+
+        self.output(&report)
+    }
+
+    /// Query all constructs from the graph
+    ///
+    /// # Arguments
+    /// * `graph` - Optional named graph to query; `None` queries the union of all graphs
+    /// * `options` - Optional `QueryOptions` object (limit, offset, sort, order, filter)
+    ///
+    /// # Returns
+    /// JsValue containing array of Construct objects
+    #[wasm_bindgen(unchecked_return_type = "ConstructData[]")]
+    pub fn query_constructs(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let constructs = self.cached(
+            |cache| &mut cache.constructs,
+            graph.clone(),
+            || self.collect_constructs(graph.as_deref()),
+        )?;
+
+        if options == QueryOptions::default() {
+            return self.output(&*constructs);
         }
+        self.output(&options.apply(&constructs))
+    }
 
-        serde_wasm_bindgen::to_value(&entanglements)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    /// Query all entanglements from the graph
+    ///
+    /// # Arguments
+    /// * `graph` - Optional named graph to query; `None` queries the union of all graphs
+    /// * `options` - Optional `QueryOptions` object (limit, offset, sort, order, filter)
+    ///
+    /// # Returns
+    /// JsValue containing array of Entanglement objects
+    #[wasm_bindgen(unchecked_return_type = "EntanglementData[]")]
+    pub fn query_entanglements(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let entanglements = self.cached(
+            |cache| &mut cache.entanglements,
+            graph.clone(),
+            || self.collect_entanglements(graph.as_deref()),
+        )?;
+
+        if options == QueryOptions::default() {
+            return self.output(&*entanglements);
+        }
+        self.output(&options.apply(&entanglements))
     }
 
     /// Find relationships for a specific construct
@@ -219,113 +846,235 @@ pub fn query_entanglements(&self) -> Result<JsValue, JsValue> {
     /// # Arguments
     /// * `construct_id` - IRI of the construct
     ///
+    /// Entanglements attached to the construct count, as do resources linked
+    /// to it either way through `sn:relatesTo` or one of its sub-properties,
+    /// and those connected to it through chains of a transitive property.
+    ///
     /// # Returns
-    /// JsValue containing array of related construct IRIs
+    /// JsValue containing array of related IRIs, sorted
+    #[wasm_bindgen(unchecked_return_type = "string[]")]
     pub fn find_relationships(&self, construct_id: &str) -> Result<JsValue, JsValue> {
-        let relationships = self.get_relationships(construct_id);
+        let graph = self.query_graph(None)?;
+        let construct = self.canonical(self.make_term(construct_id));
+        let relationships = self.get_relationships(&graph, &self.term_to_string(&construct));
 
-        serde_wasm_bindgen::to_value(&relationships)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        self.output(&relationships)
     }
 
-    /// Query all characters from the graph
+    /// Find everything that links to a resource
+    ///
+    /// Uses the graph's object index, so only the triples pointing at the
+    /// resource are visited.
+    ///
+    /// # Arguments
+    /// * `iri` - Resource as `prefix:local` or full IRI
+    /// * `predicate` - Optional predicate to restrict the links to
     ///
     /// # Returns
-    /// JsValue containing array of Character objects
-    pub fn query_characters(&self) -> Result<JsValue, JsValue> {
-        let mut characters = Vec::new();
-        let character_type = self.make_term("sn:Character");
-        let rdf_type = self.make_term("rdf:type");
+    /// JsValue containing array of {source, predicate}
+    #[wasm_bindgen(unchecked_return_type = "IncomingLink[]")]
+    pub fn incoming_links(&self, iri: &str, predicate: Option<String>) -> Result<JsValue, JsValue> {
+        let links = self.get_incoming_links(iri, predicate.as_deref());
 
-        for triple in self.graph.triples() {
-            let triple = triple.map_err(|e| JsValue::from_str(&format!("Graph error: {}", e)))?;
+        self.output(&links)
+    }
 
-            if self.term_equals(triple.p(), &rdf_type) && self.term_equals(triple.o(), &character_type) {
-                let subject_iri = self.term_to_string(triple.s());
+    /// Match triples against a pattern
+    ///
+    /// Each position is optional; `None` matches anything. Subjects and
+    /// predicates may be given as `prefix:local` or full IRIs. Objects use the
+    /// same forms, or SPARQL literal syntax: `"text"`, `"text"@en`,
+    /// `"3"^^xsd:integer`, `42`. Blank nodes are written `_:label`.
+    ///
+    /// # Returns
+    /// JsValue containing an array of {subject, predicate, object}, each a
+    /// {kind, value, datatype?, language?} term
+    #[wasm_bindgen(unchecked_return_type = "TripleMatch[]")]
+    pub fn match_triples(
+        &self,
+        subject: Option<String>,
+        predicate: Option<String>,
+        object: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let matches =
+            self.match_pattern(subject.as_deref(), predicate.as_deref(), object.as_deref())?;
+
+        self.output_json(&matches)
+    }
 
-                let name = self.get_object_value(&subject_iri, "rdfs:label").unwrap_or_default();
-                let description = self.get_object_value(&subject_iri, "rdfs:comment");
-                let constructs = self.get_character_constructs(&subject_iri);
+    /// Describe a resource: every outgoing predicate/value pair
+    ///
+    /// # Arguments
+    /// * `iri` - Resource as `prefix:local` or full IRI
+    /// * `include_incoming` - Also list the resources linking to it
+    ///
+    /// # Returns
+    /// JsValue containing {id, properties, incoming?}, with properties and
+    /// incoming links keyed by predicate IRI, each a list of
+    /// {kind, value, datatype?, language?} terms
+    #[wasm_bindgen(unchecked_return_type = "ResourceDescription")]
+    pub fn describe(&self, iri: &str, include_incoming: Option<bool>) -> Result<JsValue, JsValue> {
+        let description = self.describe_resource(iri, include_incoming.unwrap_or(false));
+
+        self.output_json(&description)
+    }
 
-                characters.push(Character {
-                    id: subject_iri,
-                    name,
-                    description,
-                    constructs,
-                });
-            }
+    /// Query all characters from the graph
+    ///
+    /// # Arguments
+    /// * `graph` - Optional named graph to query; `None` queries the union of all graphs
+    /// * `options` - Optional `QueryOptions` object (limit, offset, sort, order, filter)
+    ///
+    /// # Returns
+    /// JsValue containing array of Character objects
+    #[wasm_bindgen(unchecked_return_type = "CharacterData[]")]
+    pub fn query_characters(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let characters = self.cached(
+            |cache| &mut cache.characters,
+            graph.clone(),
+            || self.collect_characters(graph.as_deref()),
+        )?;
+
+        if options == QueryOptions::default() {
+            return self.output(&*characters);
         }
-
-        serde_wasm_bindgen::to_value(&characters)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+        self.output(&options.apply(&characters))
     }
 
     /// Generate a network graph for visualization
     ///
+    /// Edges come from entanglements and from `sn:relatesTo` and its
+    /// sub-properties. Those of symmetric properties are marked `symmetric`
+    /// and drawn once, whichever way they were stated; so are entanglements
+    /// whose relationship type is a symmetric property. Edges that are not
+    /// symmetric are `directed`, unless `set_undirected_edges` is on.
+    ///
     /// # Returns
     /// JsValue containing NetworkGraph with nodes and edges
+    #[wasm_bindgen(unchecked_return_type = "NetworkGraphData")]
     pub fn generate_network_graph(&self) -> Result<JsValue, JsValue> {
-        let mut nodes = Vec::new();
-        let mut edges = Vec::new();
-        let rdf_type = self.make_term("rdf:type");
+        self.check_abort()?;
+        let graph = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+
+        self.output(&*graph)
+    }
 
-        // Collect all nodes (constructs and characters)
-        for triple in self.graph.triples() {
-            let triple = triple.map_err(|e| JsValue::from_str(&format!("Graph error: {}", e)))?;
-
-            if self.term_equals(triple.p(), &rdf_type) {
-                let subject_iri = self.term_to_string(triple.s());
-                let object_iri = self.term_to_string(triple.o());
-                let label = self.get_object_value(&subject_iri, "rdfs:label")
-                    .unwrap_or_else(|| self.extract_local_name(&subject_iri));
-
-                let node_type = if object_iri.contains("Construct") {
-                    "construct"
-                } else if object_iri.contains("Character") {
-                    "character"
-                } else if object_iri.contains("Entanglement") {
-                    "entanglement"
-                } else {
-                    "other"
-                };
+    /// Serialize the graph to Turtle
+    ///
+    /// Every registered namespace is declared as a prefix, so IRIs in those
+    /// namespaces are written in prefixed form.
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the Turtle document
+    /// * `Err(JsValue)` with error message if serialization fails
+    pub fn to_turtle(&self) -> Result<String, JsValue> {
+        self.serialize_turtle(&self.graph).map_err(JsValue::from)
+    }
 
-                nodes.push(GraphNode {
-                    id: subject_iri.clone(),
-                    label,
-                    node_type: node_type.to_string(),
-                });
-            }
+    /// Export the Concise Bounded Description of a resource
+    ///
+    /// The description holds every triple with the resource as subject,
+    /// plus, recursively, the descriptions of blank nodes it points to.
+    ///
+    /// # Arguments
+    /// * `iri` - Resource as `prefix:local` or full IRI
+    /// * `format` - `"turtle"` (default) or `"jsonld"`
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the serialized description
+    /// * `Err(JsValue)` with error message if the format is unknown or serialization fails
+    pub fn cbd(&self, iri: &str, format: Option<String>) -> Result<String, JsValue> {
+        let description = self.concise_bounded_description(iri);
+
+        match format.as_deref().unwrap_or("turtle") {
+            "turtle" | "ttl" => self.serialize_turtle(&description),
+            "jsonld" | "json-ld" => self.serialize_jsonld(&description),
+            other => Err(ProcessorError::invalid_argument(format!(
+                "Unsupported format: {}",
+                other
+            ))
+            .with_context("format", other)),
         }
+        .map_err(JsValue::from)
+    }
 
-        // Collect all edges (relationships)
-        let entanglement_type = self.make_term("sn:Entanglement");
-
-        for triple in self.graph.triples() {
-            let triple = triple.map_err(|e| JsValue::from_str(&format!("Graph error: {}", e)))?;
-
-            if self.term_equals(triple.p(), &rdf_type) && self.term_equals(triple.o(), &entanglement_type) {
-                let entanglement_iri = self.term_to_string(triple.s());
-
-                if let (Some(source), Some(target)) = (
-                    self.get_object_value(&entanglement_iri, "sn:hasSource"),
-                    self.get_object_value(&entanglement_iri, "sn:hasTarget")
-                ) {
-                    let label = self.get_object_value(&entanglement_iri, "sn:relationshipType")
-                        .unwrap_or_else(|| "related".to_string());
-
-                    edges.push(GraphEdge {
-                        source,
-                        target,
-                        label,
-                    });
-                }
-            }
-        }
+    /// Add a single triple to the graph
+    ///
+    /// # Arguments
+    /// * `subject` - Subject as `prefix:local`, full IRI or `_:label`
+    /// * `predicate` - Predicate as `prefix:local` or full IRI
+    /// * `object` - Object as `prefix:local`, full IRI, `_:label` or SPARQL literal
+    ///   syntax (`"text"@en`, `"3"^^xsd:integer`)
+    /// * `literal` - If true, `object` is taken verbatim as a plain string literal
+    ///
+    /// # Returns
+    /// * `Ok(true)` if the triple was added, `Ok(false)` if it was already present
+    /// * `Err(JsValue)` with error message if a term is invalid
+    pub fn add_triple(
+        &mut self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        literal: Option<bool>,
+    ) -> Result<bool, JsValue> {
+        let triple = self.triple_terms(subject, predicate, object, literal.unwrap_or(false))?;
+        let added = self.add_terms(&triple);
+        self.finish_edit();
+        Ok(added)
+    }
+
+    /// Remove a single triple from the graph, including from named graphs
+    ///
+    /// Takes the same arguments as `add_triple`.
+    ///
+    /// # Returns
+    /// * `Ok(true)` if the triple was removed, `Ok(false)` if it was not present
+    /// * `Err(JsValue)` with error message if a term is invalid
+    pub fn remove_triple(
+        &mut self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        literal: Option<bool>,
+    ) -> Result<bool, JsValue> {
+        let triple = self.triple_terms(subject, predicate, object, literal.unwrap_or(false))?;
+        let removed = self.remove_terms(&triple);
+        self.finish_edit();
+        Ok(removed)
+    }
+
+    /// Start a transaction
+    ///
+    /// Every change made until `commit` or `rollback` can be discarded at once
+    /// with `rollback`. Transactions do not nest.
+    pub fn begin(&mut self) -> Result<(), JsValue> {
+        self.begin_transaction().map_err(JsValue::from)
+    }
 
-        let graph = NetworkGraph { nodes, edges };
+    /// Keep the changes made since `begin`
+    pub fn commit(&mut self) -> Result<(), JsValue> {
+        self.commit_transaction().map_err(JsValue::from)
+    }
+
+    /// Discard the changes made since `begin`
+    ///
+    /// A committed transaction is a single step for `undo`.
+    pub fn rollback(&mut self) -> Result<(), JsValue> {
+        self.rollback_transaction().map_err(JsValue::from)
+    }
 
-        serde_wasm_bindgen::to_value(&graph)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+    /// Whether a transaction is open
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
     }
 
     /// Get the number of triples in the graph
@@ -336,97 +1085,898 @@ pub fn triple_count(&self) -> usize {
     /// Clear all data from the graph
     pub fn clear(&mut self) {
         self.graph = FastGraph::new();
+        self.named_graphs.clear();
+        self.invalidate_cache();
+        self.sources.clear();
+        self.inferred.clear();
+        self.reset_history();
+    }
+}
+
+/// Term matcher that matches a single term, or any term when empty
+///
+/// Unlike `Option<T>`, which matches nothing when `None`, this lets
+/// optional query arguments use the graph indexes when they are set.
+pub(crate) struct TermOrAny(pub(crate) Option<SimpleTerm<'static>>);
+
+impl TermMatcher for TermOrAny {
+    type Term = SimpleTerm<'static>;
+
+    fn matches<T2: Term + ?Sized>(&self, term: &T2) -> bool {
+        match &self.0 {
+            Some(expected) => Term::eq(expected, term.borrow_term()),
+            None => true,
+        }
+    }
+
+    fn constant(&self) -> Option<&Self::Term> {
+        self.0.as_ref()
     }
 }
 
 // Private helper methods
 impl SemanticProcessor {
-    /// Get object value for a subject-predicate pair
-    fn get_object_value(&self, subject: &str, predicate: &str) -> Option<String> {
-        let subject_term = SimpleTerm::Iri(subject.parse().ok()?);
-        let predicate_term = self.make_term(predicate);
+    /// Collect the triples matching an optional subject/predicate/object pattern
+    fn match_pattern(
+        &self,
+        subject: Option<&str>,
+        predicate: Option<&str>,
+        object: Option<&str>,
+    ) -> Result<Vec<TripleMatch>, ProcessorError> {
+        let s = TermOrAny(subject.map(|s| self.parse_term_arg(s)).transpose()?);
+        let p = TermOrAny(predicate.map(|p| self.parse_term_arg(p)).transpose()?);
+        let o = TermOrAny(object.map(|o| self.parse_term_arg(o)).transpose()?);
+
+        Ok(self
+            .graph
+            .triples_matching(s, p, o)
+            .flatten()
+            .map(|t| TripleMatch::from_triple(&t))
+            .collect())
+    }
 
-        for triple in self.graph.triples() {
-            if let Ok(triple) = triple {
-                if self.term_equals(triple.s(), &subject_term) &&
-                   self.term_equals(triple.p(), &predicate_term) {
-                    return Some(self.term_to_string(triple.o()));
-                }
+    /// Build the description returned by `describe`
+    fn describe_resource(&self, iri: &str, include_incoming: bool) -> ResourceDescription {
+        let resource = self.make_term(iri);
+
+        let mut properties: BTreeMap<String, Vec<TermValue>> = BTreeMap::new();
+        for t in self.graph.triples_matching([&resource], Any, Any).flatten() {
+            properties
+                .entry(self.term_to_string(t.p()))
+                .or_default()
+                .push(TermValue::from_term(t.o()));
+        }
+
+        let incoming = include_incoming.then(|| {
+            let mut incoming: BTreeMap<String, Vec<TermValue>> = BTreeMap::new();
+            for t in self.graph.triples_matching(Any, Any, [&resource]).flatten() {
+                incoming
+                    .entry(self.term_to_string(t.p()))
+                    .or_default()
+                    .push(TermValue::from_term(t.s()));
             }
+            incoming
+        });
+
+        ResourceDescription {
+            id: self.term_to_string(&resource),
+            properties,
+            incoming,
         }
-        None
     }
 
-    /// Get all glosses for a construct
-    fn get_glosses(&self, construct_id: &str) -> Vec<Gloss> {
-        let mut glosses = Vec::new();
-        let subject_term = SimpleTerm::Iri(construct_id.parse().unwrap_or_else(|_| "".parse().unwrap()));
-        let has_gloss = self.make_term("sn:hasGloss");
+    /// Collect the Concise Bounded Description of a resource into a new graph
+    fn concise_bounded_description(&self, iri: &str) -> FastGraph {
+        let mut description = FastGraph::new();
+        let mut pending = vec![self.make_term(iri)];
+        let mut visited = HashSet::new();
 
-        for triple in self.graph.triples() {
-            if let Ok(triple) = triple {
-                if self.term_equals(triple.s(), &subject_term) &&
-                   self.term_equals(triple.p(), &has_gloss) {
-                    glosses.push(Gloss {
-                        id: format!("{}#gloss", construct_id),
-                        text: self.term_to_string(triple.o()),
-                        language: "en".to_string(),
-                        position: None,
-                    });
+        while let Some(node) = pending.pop() {
+            if !visited.insert(node.clone()) {
+                continue;
+            }
+            for t in self.graph.triples_matching([&node], Any, Any).flatten() {
+                if t.o().is_blank_node() {
+                    pending.push(t.o().into_term());
                 }
+                let _ = description.insert(t.s(), t.p(), t.o());
             }
         }
-        glosses
+
+        description
     }
 
-    /// Get all relationships for a construct
-    fn get_relationships(&self, construct_id: &str) -> Vec<String> {
-        let mut relationships = Vec::new();
-        let has_source = self.make_term("sn:hasSource");
-        let has_target = self.make_term("sn:hasTarget");
-
-        for triple in self.graph.triples() {
-            if let Ok(triple) = triple {
-                let object_str = self.term_to_string(triple.o());
-
-                if object_str == construct_id {
-                    if self.term_equals(triple.p(), &has_source) ||
-                       self.term_equals(triple.p(), &has_target) {
-                        relationships.push(self.term_to_string(triple.s()));
-                    }
-                }
+    /// Serialize a graph to Turtle, declaring every registered namespace
+    fn serialize_turtle(&self, graph: &FastGraph) -> Result<String, ProcessorError> {
+        let config = TurtleConfig::new()
+            .with_pretty(true)
+            .with_own_prefix_map(self.prefix_map());
+        let mut serializer = TurtleSerializer::new_stringifier_with_config(config);
+
+        serializer
+            .serialize_graph(graph)
+            .map_err(|e| ProcessorError::serialization(e).with_context("format", "Turtle"))?;
+
+        Ok(serializer.to_string())
+    }
+
+    /// Serialize a graph to (expanded) JSON-LD
+    fn serialize_jsonld(&self, graph: &FastGraph) -> Result<String, ProcessorError> {
+        let mut serializer = JsonLdSerializer::new_stringifier();
+
+        serializer
+            .serialize_dataset(&graph.as_dataset())
+            .map_err(|e| ProcessorError::serialization(e).with_context("format", "JSON-LD"))?;
+
+        Ok(serializer.to_string())
+    }
+
+    /// Merge every triple of `other` into the graph, renaming clashing blank nodes
+    fn merge_graph(&mut self, other: &FastGraph) -> MergeReport {
+        let mut report = MergeReport::default();
+
+        for triple in self.renamed_triples(other) {
+            if self.add_terms(&triple) {
+                report.added += 1;
+            } else {
+                report.duplicates += 1;
             }
         }
-        relationships
+
+        report
     }
 
-    /// Get constructs associated with a character
-    fn get_character_constructs(&self, character_id: &str) -> Vec<String> {
-        let mut constructs = Vec::new();
-        let subject_term = SimpleTerm::Iri(character_id.parse().unwrap_or_else(|_| "".parse().unwrap()));
-        let has_construct = self.make_term("sn:hasConstruct");
+    /// The triples of `other`, with blank nodes renamed where their label is
+    /// already used in the graph
+    fn renamed_triples(&self, other: &FastGraph) -> Vec<[SimpleTerm<'static>; 3]> {
+        let in_use = self.blank_node_labels(&self.graph);
+        let taken: HashSet<String> = in_use
+            .union(&self.blank_node_labels(other))
+            .cloned()
+            .collect();
+        let mut renamed: HashMap<String, SimpleTerm<'static>> = HashMap::new();
+        let mut next_label = 0;
+
+        let mut rename = |term: SimpleTerm<'static>| {
+            let label = term.bnode_id().map(|id| id.as_str().to_string());
+            match label {
+                Some(label) if in_use.contains(&label) => renamed
+                    .entry(label)
+                    .or_insert_with(|| loop {
+                        let fresh = format!("m{}", next_label);
+                        next_label += 1;
+                        if !taken.contains(&fresh) {
+                            break SimpleTerm::BlankNode(BnodeId::new_unchecked(MownStr::from(
+                                fresh,
+                            )));
+                        }
+                    })
+                    .clone(),
+                _ => term,
+            }
+        };
+
+        other
+            .triples()
+            .flatten()
+            .map(|t| {
+                [
+                    rename(t.s().into_term()),
+                    rename(t.p().into_term()),
+                    rename(t.o().into_term()),
+                ]
+            })
+            .collect()
+    }
 
-        for triple in self.graph.triples() {
-            if let Ok(triple) = triple {
-                if self.term_equals(triple.s(), &subject_term) &&
-                   self.term_equals(triple.p(), &has_construct) {
-                    constructs.push(self.term_to_string(triple.o()));
-                }
+    /// Insert a parsed document and record its triples under `source_id`
+    fn load_source(&mut self, incoming: &FastGraph, source_id: &str) {
+        for triple in self.renamed_triples(incoming) {
+            let unowned = !self.add_terms(&triple)
+                && !self.sources.values().any(|other| other.contains(&triple));
+            if !unowned {
+                self.claim(source_id, &triple);
             }
         }
-        constructs
+    }
+
+    /// Triples only in `other` (added) and only in the graph (removed)
+    fn diff_graph(&self, other: &FastGraph) -> GraphDiff {
+        type Shape = [Option<SimpleTerm<'static>>; 3];
+
+        fn shape<T: Triple>(t: &T) -> Shape {
+            let term = |t: SimpleTerm<'static>| (!t.is_blank_node()).then_some(t);
+            [
+                term(t.s().into_term()),
+                term(t.p().into_term()),
+                term(t.o().into_term()),
+            ]
+        }
+
+        fn unmatched(from: &FastGraph, against: &FastGraph) -> Vec<TripleMatch> {
+            let mut available: HashMap<Shape, usize> = HashMap::new();
+            for t in against.triples().flatten() {
+                *available.entry(shape(&t)).or_default() += 1;
+            }
+
+            let mut unmatched = Vec::new();
+            for t in from.triples().flatten() {
+                match available.get_mut(&shape(&t)) {
+                    Some(count) if *count > 0 => *count -= 1,
+                    _ => unmatched.push(TripleMatch::from_triple(&t)),
+                }
+            }
+            unmatched
+        }
+
+        GraphDiff {
+            added: unmatched(other, &self.graph),
+            removed: unmatched(&self.graph, other),
+        }
+    }
+
+    /// Labels of all blank nodes used in a graph
+    fn blank_node_labels(&self, graph: &FastGraph) -> HashSet<String> {
+        let mut labels = HashSet::new();
+        for t in graph.triples().flatten() {
+            for term in [t.s(), t.o()] {
+                if let Some(id) = term.bnode_id() {
+                    labels.insert(id.as_str().to_string());
+                }
+            }
+        }
+        labels
+    }
+
+    /// Copy the current state
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            graph: self.graph.clone(),
+            named_graphs: self.named_graphs.clone(),
+            sources: self.sources.clone(),
+            inferred: self.inferred.clone(),
+        }
+    }
+
+    /// Replace the current state with a snapshot
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.graph = snapshot.graph;
+        self.named_graphs = snapshot.named_graphs;
+        self.invalidate_cache();
+        self.sources = snapshot.sources;
+        self.inferred = snapshot.inferred;
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), ProcessorError> {
+        if self.transaction.is_some() {
+            return Err(ProcessorError::transaction(
+                "A transaction is already in progress",
+            ));
+        }
+        self.transaction = Some(self.snapshot());
+        Ok(())
+    }
+
+    fn commit_transaction(&mut self) -> Result<(), ProcessorError> {
+        self.transaction
+            .take()
+            .ok_or_else(|| ProcessorError::transaction("No transaction in progress"))?;
+        self.finish_edit();
+        Ok(())
+    }
+
+    fn rollback_transaction(&mut self) -> Result<(), ProcessorError> {
+        let snapshot = self
+            .transaction
+            .take()
+            .ok_or_else(|| ProcessorError::transaction("No transaction in progress"))?;
+        self.restore(snapshot);
+        self.discard_edit();
+        Ok(())
+    }
+
+    /// Build the terms of a triple passed in by the caller
+    fn triple_terms(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+        literal: bool,
+    ) -> Result<[SimpleTerm<'static>; 3], ProcessorError> {
+        let object = if literal {
+            sparql::typed_literal(object, "http://www.w3.org/2001/XMLSchema#string")
+        } else {
+            self.parse_term_arg(object)?
+        };
+        Ok([
+            self.parse_term_arg(subject)?,
+            self.parse_term_arg(predicate)?,
+            object,
+        ])
+    }
+
+    /// Insert a triple into the default graph; returns true if it was new
+    ///
+    /// All single-triple edits go through here and `remove_terms`.
+    fn add_terms(&mut self, triple: &[SimpleTerm<'static>; 3]) -> bool {
+        self.insert_in(None, triple)
+    }
+
+    /// Remove a triple from the default graph and every named graph;
+    /// returns true if it was present
+    fn remove_terms(&mut self, triple: &[SimpleTerm<'static>; 3]) -> bool {
+        let [s, p, o] = triple;
+        let graphs: Vec<String> = self
+            .named_graphs
+            .iter()
+            .filter(|(_, named)| named.contains(s, p, o).unwrap_or(false))
+            .map(|(name, _)| name.clone())
+            .collect();
+        let sources: Vec<String> = self
+            .sources
+            .iter()
+            .filter(|(_, contributed)| HashSet::contains(contributed, triple))
+            .map(|(source, _)| source.clone())
+            .collect();
+
+        for name in graphs {
+            self.remove_in(Some(&name), triple);
+        }
+        for source in sources {
+            self.release(&source, triple);
+        }
+        self.remove_in(None, triple)
+    }
+
+    /// Parse a term given by the caller: SPARQL term syntax when it starts
+    /// like one, otherwise a `prefix:local` name or full IRI
+    fn parse_term_arg(&self, arg: &str) -> Result<SimpleTerm<'static>, ProcessorError> {
+        let arg = arg.trim();
+        let sparql_syntax = arg.starts_with(['"', '\'', '<', '+', '-'])
+            || arg.starts_with("_:")
+            || arg.starts_with(|c: char| c.is_ascii_digit())
+            || arg == "true"
+            || arg == "false";
+
+        if sparql_syntax {
+            sparql::parse_term_str(arg, &self.namespaces, &self.aliases).map_err(|e| {
+                ProcessorError::invalid_argument(format!("Invalid term {}: {}", arg, e))
+                    .with_context("term", arg)
+            })
+        } else {
+            Ok(self.make_term(arg))
+        }
+    }
+
+    /// Resolve an optional graph name to the graph it designates
+    ///
+    /// `None` designates the union graph.
+    fn select_graph(&self, name: Option<&str>) -> Result<&FastGraph, ProcessorError> {
+        match name {
+            None => Ok(&self.graph),
+            Some(name) => self.named_graphs.get(name).ok_or_else(|| {
+                ProcessorError::query(format!("Unknown graph: {}", name))
+                    .with_context("graph", name)
+            }),
+        }
+    }
+
+    pub(crate) fn read_ntriples(&mut self, nt: &str) -> Result<(), ProcessorError> {
+        let parser = NTriplesParser {};
+
+        self.check_abort()?;
+        let mut incoming = FastGraph::new();
+        let result = parser
+            .parse(self.abortable(nt))
+            .map_triples(|t| self.aliases.triple(t))
+            .add_to_graph(&mut incoming);
+        if let Err(e) = result {
+            self.check_abort()?;
+            return Err(ProcessorError::parse_at("N-Triples", nt, e));
+        }
+
+        self.merge_loaded(incoming, HashMap::new());
+        Ok(())
+    }
+
+    /// Collect quads into a union graph and, when named, their named graph
+    fn parse_quads<QS: QuadSource>(
+        &self,
+        mut quads: QS,
+    ) -> StreamResult<ParsedQuads, QS::Error, <FastGraph as MutableGraph>::MutationError> {
+        let mut graph = FastGraph::new();
+        let mut named_graphs: HashMap<String, FastGraph> = HashMap::new();
+
+        quads.try_for_each_quad(|quad| {
+            let ([s, p, o], name) = self.aliases.quad(quad);
+            graph.insert(s.borrow_term(), p.borrow_term(), o.borrow_term())?;
+
+            if let Some(name) = name {
+                let name = match name.as_simple() {
+                    SimpleTerm::BlankNode(bn) => format!("_:{}", bn.as_str()),
+                    other => other
+                        .iri()
+                        .map(|iri| iri.as_str().to_string())
+                        .unwrap_or_default(),
+                };
+                named_graphs.entry(name).or_default().insert(s, p, o)?;
+            }
+            Ok(())
+        })?;
+        Ok((graph, named_graphs))
+    }
+
+    /// Add a parsed document to the graph and its named graphs
+    ///
+    /// Loads parse into standalone graphs and only merge them here, so a
+    /// document that fails to parse or is aborted leaves everything as it was.
+    fn merge_loaded(&mut self, incoming: FastGraph, named_graphs: HashMap<String, FastGraph>) {
+        self.retract_inferred();
+        self.invalidate_cache();
+        // Inserting into an in-memory graph only fails when it is full
+        for t in incoming.triples().flatten() {
+            let _ = self.graph.insert(t.s(), t.p(), t.o());
+        }
+        for (name, named) in named_graphs {
+            let target = self.named_graphs.entry(name).or_default();
+            for t in named.triples().flatten() {
+                let _ = target.insert(t.s(), t.p(), t.o());
+            }
+        }
+        self.skolemize_loaded();
+        self.materialize();
+        self.reset_history();
+    }
+
+    /// Build the result of `query_constructs`
+    fn collect_constructs(&self, graph: Option<&str>) -> Result<Vec<Construct>, ProcessorError> {
+        let graph = self.query_graph(graph)?;
+        let graph: &FastGraph = &graph;
+        let mut constructs = Vec::new();
+
+        // Find all instances of sn:Construct
+        for subject_iri in self.instances_of(graph, "sn:Construct") {
+            // Get properties
+            let label = self
+                .get_object_value(graph, &subject_iri, "rdfs:label")
+                .unwrap_or_default();
+            let description = self.get_object_value(graph, &subject_iri, "rdfs:comment");
+            let glosses = self.get_glosses(graph, &subject_iri);
+            let relationships = self.get_relationships(graph, &subject_iri);
+
+            constructs.push(Construct {
+                id: subject_iri.to_string(),
+                label,
+                description,
+                glosses,
+                relationships,
+            });
+        }
+
+        Ok(constructs)
+    }
+
+    /// Build the result of `query_entanglements`
+    fn collect_entanglements(
+        &self,
+        graph: Option<&str>,
+    ) -> Result<Vec<Entanglement>, ProcessorError> {
+        let graph = self.query_graph(graph)?;
+        let graph: &FastGraph = &graph;
+        let mut entanglements = Vec::new();
+
+        for subject_iri in self.instances_of(graph, "sn:Entanglement") {
+            let label = self
+                .get_object_value(graph, &subject_iri, "rdfs:label")
+                .unwrap_or_default();
+            let description = self.get_object_value(graph, &subject_iri, "rdfs:comment");
+            let source = self
+                .inferred_value(graph, &subject_iri, "sn:hasSource")
+                .unwrap_or_default();
+            let target = self
+                .inferred_value(graph, &subject_iri, "sn:hasTarget")
+                .unwrap_or_default();
+            let rel_type = self
+                .get_object_value(graph, &subject_iri, "sn:relationshipType")
+                .unwrap_or_else(|| "related".to_string());
+
+            let annotations = self.entanglement_annotations(graph, &subject_iri, &source, &target);
+
+            entanglements.push(Entanglement {
+                id: subject_iri.to_string(),
+                label,
+                source,
+                target,
+                relationship_type: rel_type,
+                description,
+                annotations,
+            });
+        }
+
+        Ok(entanglements)
+    }
+
+    /// Build the result of `query_characters`
+    fn collect_characters(&self, graph: Option<&str>) -> Result<Vec<Character>, ProcessorError> {
+        let graph = self.query_graph(graph)?;
+        let graph: &FastGraph = &graph;
+        let mut characters = Vec::new();
+
+        for subject_iri in self.instances_of(graph, "sn:Character") {
+            let name = self
+                .get_object_value(graph, &subject_iri, "rdfs:label")
+                .unwrap_or_default();
+            let description = self.get_object_value(graph, &subject_iri, "rdfs:comment");
+            let constructs = self.get_character_constructs(graph, &subject_iri);
+
+            characters.push(Character {
+                id: subject_iri.to_string(),
+                name,
+                description,
+                constructs,
+            });
+        }
+
+        Ok(characters)
+    }
+
+    /// Build the result of `generate_network_graph`
+    fn collect_network_graph(&self) -> Result<NetworkGraph, ProcessorError> {
+        let mut typed = Vec::new();
+        let mut edges = Vec::new();
+        let rdf_type = self.make_term("rdf:type");
+        let graph = self.query_graph(None)?;
+        let graph: &FastGraph = &graph;
+
+        // Collect all nodes (constructs and characters)
+        for triple in graph.triples_matching(Any, [&rdf_type], Any) {
+            let triple =
+                triple.map_err(|e| ProcessorError::query(format!("Graph error: {}", e)))?;
+            typed.push((self.term_to_rc(triple.s()), self.term_to_rc(triple.o())));
+        }
+        let nodes = self.network_nodes(&typed, |iri| {
+            self.get_object_value(graph, iri, "rdfs:label")
+                .unwrap_or_else(|| self.extract_local_name(iri))
+        });
+
+        // Collect all edges (relationships)
+        self.check_abort()?;
+
+        // Entanglements whose relationship type is a symmetric property
+        // have no direction either
+        let symmetric_properties = self.symmetric_properties();
+        for entanglement_iri in self.instances_of(graph, "sn:Entanglement") {
+            if let (Some(source), Some(target)) = (
+                self.inferred_value(graph, &entanglement_iri, "sn:hasSource"),
+                self.inferred_value(graph, &entanglement_iri, "sn:hasTarget"),
+            ) {
+                let label = self
+                    .get_object_value(graph, &entanglement_iri, "sn:relationshipType")
+                    .unwrap_or_else(|| "related".to_string());
+                let weight = self.get_object_value(graph, &entanglement_iri, "sn:weight");
+                let symmetric = symmetric_properties.contains(&label);
+
+                edges.push(GraphEdge {
+                    source,
+                    target,
+                    label,
+                    symmetric,
+                    directed: self.edge_directed(symmetric),
+                    weight: parse_weight(weight.as_deref()),
+                    count: 1,
+                    parallel_index: 0,
+                    parallel_count: 1,
+                });
+            }
+        }
+
+        let inverses = self.inverses_of("sn:relatesTo");
+        let mut relates_to = self.subproperties_of("sn:relatesTo");
+        relates_to.extend(inverses.iter().map(|(inverse, _)| inverse.clone()));
+        edges.extend(self.relation_edges(
+            graph.triples_matching(Any, &relates_to[..], Any).flatten(),
+            &inverses,
+        ));
+
+        Ok(NetworkGraph::new(nodes, self.merge_edges(edges)))
+    }
+
+    /// Network graph nodes, one for each subject of `typed`, in the order
+    /// they first appear
+    ///
+    /// `typed` holds (subject, type) pairs. A resource of several types gets
+    /// the first node type, of construct, character and entanglement, that
+    /// one of its types is or is a subclass of.
+    fn network_nodes(
+        &self,
+        typed: &[(Rc<str>, Rc<str>)],
+        label: impl Fn(&str) -> String,
+    ) -> Vec<GraphNode> {
+        let kinds: Vec<(&str, &str, HashSet<String>)> = [
+            ("construct", "Construct"),
+            ("character", "Character"),
+            ("entanglement", "Entanglement"),
+        ]
+        .into_iter()
+        .map(|(kind, class)| {
+            let classes = self.subclasses_of(&format!("sn:{}", class));
+            (
+                kind,
+                class,
+                classes.iter().map(|c| self.term_to_string(c)).collect(),
+            )
+        })
+        .collect();
+        // Classes outside the Sinople ontology are recognized by name
+        let rank = |class: &str| {
+            kinds
+                .iter()
+                .position(|(_, name, classes)| classes.contains(class) || class.contains(name))
+                .unwrap_or(kinds.len())
+        };
+
+        let mut subjects: Vec<&Rc<str>> = Vec::new();
+        let mut types: HashMap<&Rc<str>, Vec<String>> = HashMap::new();
+        for (subject, class) in typed {
+            let classes = types.entry(subject).or_insert_with(|| {
+                subjects.push(subject);
+                Vec::new()
+            });
+            classes.push(class.to_string());
+        }
+
+        subjects
+            .into_iter()
+            .map(|subject| {
+                let mut classes = types.remove(subject).unwrap_or_default();
+                classes.sort();
+                classes.dedup();
+                let best = classes
+                    .iter()
+                    .map(|class| rank(class))
+                    .min()
+                    .unwrap_or(kinds.len());
+                GraphNode {
+                    id: subject.to_string(),
+                    label: label(subject),
+                    node_type: kinds
+                        .get(best)
+                        .map_or("other", |(kind, _, _)| kind)
+                        .to_string(),
+                    types: classes,
+                    in_degree: 0,
+                    out_degree: 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Get the IRIs of all instances of a type
+    ///
+    /// Includes instances of its subclasses unless subclass inference is off.
+    /// Uses the graph's predicate-object index, so only the `rdf:type`
+    /// triples of those types are visited. The IRIs are sorted and unique.
+    fn instances_of(&self, graph: &FastGraph, type_name: &str) -> Vec<Rc<str>> {
+        let rdf_type = self.make_term("rdf:type");
+        let classes = self.subclasses_of(type_name);
+
+        let mut instances: Vec<Rc<str>> = graph
+            .triples_matching(Any, [&rdf_type], &classes[..])
+            .flatten()
+            .map(|t| self.term_to_rc(t.s()))
+            .collect();
+        instances.sort();
+        instances.dedup();
+        instances
+    }
+
+    /// Get object value for a subject-predicate pair
+    fn get_object_value(
+        &self,
+        graph: &FastGraph,
+        subject: &str,
+        predicate: &str,
+    ) -> Option<String> {
+        let subject_term = self.iri_term(subject);
+        let predicate_term = self.make_term(predicate);
+
+        let value = graph
+            .triples_matching([&subject_term], [&predicate_term], Any)
+            .flatten()
+            .next()
+            .map(|triple| self.term_to_string(triple.o()));
+        value
+    }
+
+    /// Get all glosses for a construct
+    ///
+    /// Glosses from `sn:hasGlossList` come first, in list order, followed by
+    /// those attached with `sn:hasGloss`.
+    fn get_glosses(&self, graph: &FastGraph, construct_id: &str) -> Vec<Gloss> {
+        let mut glosses = Vec::new();
+        let subject_term = self.iri_term(construct_id);
+        let has_gloss = self.make_term("sn:hasGloss");
+        let has_gloss_list = self.make_term("sn:hasGlossList");
+
+        for triple in graph
+            .triples_matching([&subject_term], [&has_gloss_list], Any)
+            .flatten()
+        {
+            glosses.extend(self.gloss_list(graph, construct_id, triple.o()));
+        }
+        for triple in graph
+            .triples_matching([&subject_term], [&has_gloss], Any)
+            .flatten()
+        {
+            glosses.push(self.gloss(construct_id, triple.o()));
+        }
+        glosses
+    }
+
+    /// Build the gloss of a construct from the object of `sn:hasGloss`
+    ///
+    /// Each language version of a gloss is a separate entry, with the
+    /// language tag appended to its id.
+    fn gloss<T: Term>(&self, construct_id: &str, text: T) -> Gloss {
+        let language = text
+            .language_tag()
+            .map(|tag| tag.as_str().to_string())
+            .unwrap_or_default();
+        Gloss {
+            id: if language.is_empty() {
+                format!("{}#gloss", construct_id)
+            } else {
+                format!("{}#gloss-{}", construct_id, language)
+            },
+            text: self.term_to_string(&text),
+            language,
+            position: None,
+        }
+    }
+
+    /// Build the glosses in an RDF list, with their position in it
+    fn gloss_list<T: Term>(&self, graph: &FastGraph, construct_id: &str, head: T) -> Vec<Gloss> {
+        self.list_items(graph, head.into_term())
+            .into_iter()
+            .enumerate()
+            .map(|(position, text)| Gloss {
+                position: Some(position),
+                ..self.gloss(construct_id, text)
+            })
+            .collect()
+    }
+
+    /// Get all relationships for a construct
+    ///
+    /// These are the entanglements whose source or target it is, and the
+    /// resources it relates to, or that relate to it, through `sn:relatesTo`.
+    /// Sub-properties and inverses of all three count too, as do resources
+    /// connected to it through chains of a transitive property. The IRIs are
+    /// sorted and unique.
+    fn get_relationships(&self, graph: &FastGraph, construct_id: &str) -> Vec<String> {
+        let mut relationships = Vec::new();
+        let construct = self.iri_term(construct_id);
+        let ends = [
+            self.subproperties_of("sn:hasSource"),
+            self.subproperties_of("sn:hasTarget"),
+        ]
+        .concat();
+        let inverse_ends: Vec<SimpleTerm> = [
+            self.inverses_of("sn:hasSource"),
+            self.inverses_of("sn:hasTarget"),
+        ]
+        .concat()
+        .into_iter()
+        .map(|(inverse, _)| inverse)
+        .collect();
+        // Direction does not matter here, so inverses join the properties
+        let mut relates_to = self.subproperties_of("sn:relatesTo");
+        relates_to.extend(
+            self.inverses_of("sn:relatesTo")
+                .into_iter()
+                .map(|(inverse, _)| inverse),
+        );
+
+        for triple in graph
+            .triples_matching(Any, &ends[..], [&construct])
+            .flatten()
+        {
+            relationships.push(self.term_to_string(triple.s()));
+        }
+        for triple in graph
+            .triples_matching([&construct], &inverse_ends[..], Any)
+            .flatten()
+        {
+            relationships.push(self.term_to_string(triple.o()));
+        }
+        for triple in graph
+            .triples_matching(Any, &relates_to[..], [&construct])
+            .flatten()
+        {
+            relationships.push(self.term_to_string(triple.s()));
+        }
+        for triple in graph
+            .triples_matching([&construct], &relates_to[..], Any)
+            .flatten()
+        {
+            relationships.push(self.term_to_string(triple.o()));
+        }
+        relationships.extend(self.transitive_relations(graph, construct));
+        relationships.sort();
+        relationships.dedup();
+        relationships
+    }
+
+    /// Get the links pointing at a resource, optionally through one predicate
+    fn get_incoming_links(&self, iri: &str, predicate: Option<&str>) -> Vec<IncomingLink> {
+        let resource = self.make_term(iri);
+        let predicate = TermOrAny(predicate.map(|p| self.make_term(p)));
+
+        let links = self
+            .graph
+            .triples_matching(Any, predicate, [&resource])
+            .flatten()
+            .map(|t| IncomingLink {
+                source: self.term_to_string(t.s()),
+                predicate: self.term_to_string(t.p()),
+            })
+            .collect();
+        links
+    }
+
+    /// Get constructs associated with a character
+    fn get_character_constructs(&self, graph: &FastGraph, character_id: &str) -> Vec<String> {
+        let mut constructs = Vec::new();
+        let subject_term = self.iri_term(character_id);
+        let has_construct = self.make_term("sn:hasConstruct");
+
+        for triple in graph
+            .triples_matching([&subject_term], [&has_construct], Any)
+            .flatten()
+        {
+            constructs.push(self.term_to_string(triple.o()));
+        }
+        constructs
+    }
+
+    /// Build a serializer prefix map from the registered namespaces
+    ///
+    /// Entries that are not a valid prefix/IRI pair are skipped.
+    fn prefix_map(&self) -> Vec<PrefixMapPair> {
+        let mut prefixes: Vec<PrefixMapPair> = self
+            .namespaces
+            .iter()
+            .filter_map(|(prefix, iri)| {
+                let prefix = Prefix::new(Box::from(prefix.as_str())).ok()?;
+                let iri = Iri::new(Box::from(iri.as_str())).ok()?;
+                Some((prefix, iri))
+            })
+            .collect();
+        prefixes.sort_by(|a, b| Ord::cmp(a.0.as_str(), b.0.as_str()));
+        prefixes
     }
 
     /// Create a SimpleTerm from a namespaced string (e.g., "sn:Construct")
     fn make_term(&self, namespaced: &str) -> SimpleTerm<'static> {
         if let Some((prefix, local)) = namespaced.split_once(':') {
             if let Some(namespace) = self.namespaces.get(prefix) {
-                let iri = format!("{}{}", namespace, local);
-                return SimpleTerm::Iri(iri.parse().unwrap_or_else(|_| "".parse().unwrap()));
+                return SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(format!(
+                    "{}{}",
+                    namespace, local
+                ))));
             }
         }
         // Fallback: treat as full IRI
-        SimpleTerm::Iri(namespaced.parse().unwrap_or_else(|_| "".parse().unwrap()))
+        self.iri_term(namespaced).into_term()
+    }
+
+    /// Create an IRI SimpleTerm from a full IRI string, borrowing the string
+    /// unless it is in a namespace alias
+    fn iri_term<'a>(&self, iri: &'a str) -> SimpleTerm<'a> {
+        match self.aliases.iri(iri) {
+            Some(canonical) => SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(canonical))),
+            None => SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(iri))),
+        }
     }
 
     /// Convert a Term to String
@@ -435,29 +1985,20 @@ fn term_to_string<T>(&self, term: &T) -> String
     where
         T: Term,
     {
-        match SimpleTerm::from_term(term) {
+        match term.as_simple() {
             SimpleTerm::Iri(iri) => iri.to_string(),
             SimpleTerm::LiteralDatatype(lit, _) => lit.to_string(),
             SimpleTerm::LiteralLanguage(lit, _) => lit.to_string(),
-            SimpleTerm::BlankNode(bn) => format!("_:{}", bn),
+            SimpleTerm::BlankNode(bn) => format!("_:{}", bn.as_str()),
             _ => String::new(),
         }
     }
 
-    /// Check if two terms are equal
-    fn term_equals<T1, T2>(&self, term1: &T1, term2: &T2) -> bool
-    where
-        T1: Term,
-        T2: Term,
-    {
-        SimpleTerm::from_term(term1) == SimpleTerm::from_term(term2)
-    }
-
     /// Extract local name from IRI
     fn extract_local_name(&self, iri: &str) -> String {
         iri.split('#')
-            .last()
-            .or_else(|| iri.split('/').last())
+            .next_back()
+            .or_else(|| iri.split('/').next_back())
             .unwrap_or(iri)
             .to_string()
     }
@@ -469,9 +2010,23 @@ fn default() -> Self {
     }
 }
 
+impl SemanticProcessor {
+    /// Parse a Turtle document into a standalone graph
+    fn parse_turtle(&self, ttl: &str) -> Result<FastGraph, ProcessorError> {
+        let mut graph = FastGraph::new();
+        self.turtle_parser()
+            .parse_str(ttl)
+            .map_triples(|t| self.aliases.triple(t))
+            .add_to_graph(&mut graph)
+            .map_err(|e| ProcessorError::parse_at("Turtle", ttl, e))?;
+        Ok(self.skolemize_graph(graph))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use sophia_turtle::parser::turtle::TurtleParser;
 
     #[test]
     fn test_processor_creation() {
@@ -490,7 +2045,548 @@ fn test_load_simple_turtle() {
                 rdfs:label "Test Construct" .
         "#;
 
-        assert!(processor.load_turtle(ttl).is_ok());
+        assert!(processor.load_turtle(ttl, None).is_ok());
         assert!(processor.triple_count() > 0);
     }
+
+    #[test]
+    fn test_load_ntriples_and_nquads() {
+        let mut processor = SemanticProcessor::new();
+        let nt = "<https://example.org/a> <https://www.w3.org/2000/01/rdf-schema#label> \"A\" .\n";
+        let nq = "<https://example.org/b> <https://www.w3.org/2000/01/rdf-schema#label> \"B\" <https://example.org/g> .\n";
+
+        assert!(processor.load_ntriples(nt).is_ok());
+        assert!(processor.load_nquads(nq).is_ok());
+        assert_eq!(processor.triple_count(), 2);
+    }
+
+    #[test]
+    fn test_load_trig_keeps_named_graphs() {
+        let mut processor = SemanticProcessor::new();
+        let trig = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+
+            <https://example.org/a> a sn:Construct .
+
+            <https://example.org/graphs/glosses> {
+                <https://example.org/a> sn:hasGloss "A gloss" .
+            }
+        "#;
+
+        assert!(processor.load_trig(trig).is_ok());
+        assert_eq!(processor.triple_count(), 2);
+
+        let glosses = processor
+            .select_graph(Some("https://example.org/graphs/glosses"))
+            .unwrap();
+        assert_eq!(glosses.triples().count(), 1);
+    }
+
+    #[test]
+    fn test_to_turtle_round_trip() {
+        let mut processor = SemanticProcessor::new();
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+
+            <https://example.org/test> sn:hasGloss "Un commentaire"@fr ;
+                rdfs:label "Test Construct" .
+        "#;
+        processor.load_turtle(ttl, None).unwrap();
+
+        let output = processor.to_turtle().unwrap();
+        assert!(output.contains("PREFIX sn: <https://sinople.org/ontology#>"));
+        assert!(output.contains("sn:hasGloss"));
+
+        let mut reloaded = SemanticProcessor::new();
+        reloaded.load_turtle(&output, None).unwrap();
+        assert_eq!(reloaded.triple_count(), 2);
+    }
+
+    #[test]
+    fn test_load_jsonld_with_context() {
+        let mut processor = SemanticProcessor::new();
+        let json = r#"{
+            "@context": {
+                "sn": "https://sinople.org/ontology#",
+                "label": "https://www.w3.org/2000/01/rdf-schema#label"
+            },
+            "@id": "https://example.org/test",
+            "@type": "sn:Construct",
+            "label": "Test Construct"
+        }"#;
+
+        assert!(processor.load_jsonld(json).is_ok());
+        assert_eq!(processor.triple_count(), 2);
+    }
+
+    #[test]
+    fn test_load_rdfxml() {
+        let mut processor = SemanticProcessor::new();
+        let xml = r#"<?xml version="1.0"?>
+            <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                     xmlns:rdfs="https://www.w3.org/2000/01/rdf-schema#"
+                     xmlns:sn="https://sinople.org/ontology#">
+                <sn:Construct rdf:about="https://example.org/test">
+                    <rdfs:label>Test Construct</rdfs:label>
+                </sn:Construct>
+            </rdf:RDF>"#;
+
+        assert!(processor.load_rdfxml(xml).is_ok());
+        assert_eq!(processor.triple_count(), 2);
+    }
+
+    #[test]
+    fn test_match_pattern() {
+        let mut processor = SemanticProcessor::new();
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+
+            sn:a rdfs:label "A"@en ; sn:order 1 .
+            sn:b rdfs:label "B" ; sn:order 2 .
+        "#;
+        processor.load_turtle(ttl, None).unwrap();
+
+        let labels = processor
+            .match_pattern(None, Some("rdfs:label"), None)
+            .unwrap();
+        assert_eq!(labels.len(), 2);
+
+        let a = processor
+            .match_pattern(None, None, Some("\"A\"@en"))
+            .unwrap();
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].subject.value, "https://sinople.org/ontology#a");
+
+        let two = processor
+            .match_pattern(Some("sn:b"), None, Some("2"))
+            .unwrap();
+        assert_eq!(two.len(), 1);
+        assert_eq!(two[0].predicate.value, "https://sinople.org/ontology#order");
+        assert_eq!(
+            two[0].object,
+            TermValue {
+                kind: "literal".to_string(),
+                value: "2".to_string(),
+                datatype: Some("http://www.w3.org/2001/XMLSchema#integer".to_string()),
+                language: None,
+                triple: None,
+            }
+        );
+        assert_eq!(a[0].object.language.as_deref(), Some("en"));
+        assert_eq!(a[0].subject.kind, "iri");
+
+        assert!(processor
+            .match_pattern(None, None, Some("\"unterminated"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_describe_resource() {
+        let mut processor = SemanticProcessor::new();
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+
+            sn:a rdfs:label "A" ; sn:mood "calm", "wistful" .
+            sn:b sn:entangledWith sn:a .
+        "#;
+        processor.load_turtle(ttl, None).unwrap();
+
+        let description = processor.describe_resource("sn:a", false);
+        assert_eq!(description.id, "https://sinople.org/ontology#a");
+        assert_eq!(description.properties.len(), 2);
+        assert_eq!(
+            description.properties["https://sinople.org/ontology#mood"].len(),
+            2
+        );
+        assert!(description.incoming.is_none());
+
+        let description = processor.describe_resource("sn:a", true);
+        let incoming = description.incoming.unwrap();
+        assert_eq!(
+            incoming["https://sinople.org/ontology#entangledWith"][0].value,
+            "https://sinople.org/ontology#b"
+        );
+    }
+
+    #[test]
+    fn test_concise_bounded_description_follows_blank_nodes() {
+        let mut processor = SemanticProcessor::new();
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+
+            sn:a rdfs:label "A" ;
+                sn:hasGloss [ sn:text "A gloss" ; sn:source [ rdfs:label "Notes" ] ] ;
+                sn:entangledWith sn:b .
+            sn:b rdfs:label "B" .
+        "#;
+        processor.load_turtle(ttl, None).unwrap();
+
+        let description = processor.concise_bounded_description("sn:a");
+        assert_eq!(description.triples().count(), 6);
+
+        let turtle = processor.serialize_turtle(&description).unwrap();
+        assert!(turtle.contains("A gloss"));
+        assert!(!turtle.contains("\"B\""));
+
+        let jsonld = processor.serialize_jsonld(&description).unwrap();
+        assert!(jsonld.contains("https://sinople.org/ontology#a"));
+    }
+
+    #[test]
+    fn test_add_and_remove_triple() {
+        let mut processor = SemanticProcessor::new();
+        let triple = processor
+            .triple_terms("sn:a", "rdfs:label", "Entropy", true)
+            .unwrap();
+        assert!(processor.add_terms(&triple));
+        assert!(!processor.add_terms(&triple));
+
+        let link = processor
+            .triple_terms("sn:a", "sn:entangledWith", "sn:b", false)
+            .unwrap();
+        assert!(processor.add_terms(&link));
+        assert!(matches!(link[2], SimpleTerm::Iri(_)));
+        assert_eq!(processor.triple_count(), 2);
+
+        // Taken verbatim, the object is a literal rather than an IRI
+        let literal = processor
+            .triple_terms("sn:a", "sn:entangledWith", "sn:b", true)
+            .unwrap();
+        assert!(!processor.remove_terms(&literal));
+        assert!(processor.remove_terms(&link));
+        assert_eq!(processor.triple_count(), 1);
+    }
+
+    #[test]
+    fn test_merge_graph_reports_duplicates() {
+        let mut processor = SemanticProcessor::new();
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            sn:a sn:hasGloss [ sn:text "First" ] ; sn:entangledWith sn:b .
+        "#;
+        processor.load_turtle(ttl, None).unwrap();
+
+        let mut incoming = FastGraph::new();
+        let other = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            sn:a sn:hasGloss [ sn:text "Second" ] ; sn:entangledWith sn:b .
+        "#;
+        TurtleParser::default()
+            .parse_str(other)
+            .add_to_graph(&mut incoming)
+            .unwrap();
+
+        let report = processor.merge_graph(&incoming);
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 2,
+                duplicates: 1
+            }
+        );
+
+        // The two glosses stay distinct blank nodes
+        assert_eq!(processor.blank_node_labels(&processor.graph).len(), 2);
+        assert_eq!(processor.triple_count(), 5);
+    }
+
+    #[test]
+    fn test_unload_source_keeps_shared_triples() {
+        let mut processor = SemanticProcessor::new();
+        let shared = "<https://example.org/a> <https://example.org/p> <https://example.org/b> .";
+        let first = format!(
+            "{}\n<https://example.org/a> <https://example.org/p> \"first\" .",
+            shared
+        );
+        let second = format!(
+            "{}\n<https://example.org/a> <https://example.org/p> \"second\" .",
+            shared
+        );
+
+        processor
+            .load_turtle(
+                "<https://example.org/x> <https://example.org/p> \"x\" .",
+                None,
+            )
+            .unwrap();
+        processor.load_source(&parse_turtle_graph(&first), "post-1");
+        processor.load_source(&parse_turtle_graph(&second), "post-2");
+        assert_eq!(processor.triple_count(), 4);
+
+        assert_eq!(processor.unload_source("post-1"), 1);
+        assert_eq!(processor.triple_count(), 3);
+        assert_eq!(processor.unload_source("post-2"), 2);
+        assert_eq!(processor.triple_count(), 1);
+        assert_eq!(processor.unload_source("post-2"), 0);
+    }
+
+    #[test]
+    fn test_diff_graph() {
+        let mut processor = SemanticProcessor::new();
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            sn:a sn:label "A" ; sn:hasGloss [ sn:text "Gloss" ] .
+            sn:b sn:label "B" .
+        "#;
+        processor.load_turtle(ttl, None).unwrap();
+
+        let other = parse_turtle_graph(
+            r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            sn:a sn:label "A" ; sn:hasGloss [ sn:text "Gloss" ] .
+            sn:c sn:label "C" .
+        "#,
+        );
+
+        let diff = processor.diff_graph(&other);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].object.value, "C");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].object.value, "B");
+    }
+
+    #[test]
+    fn test_transaction_rollback_and_commit() {
+        let mut processor = SemanticProcessor::new();
+        let a = processor
+            .triple_terms("sn:a", "rdfs:label", "A", true)
+            .unwrap();
+        let b = processor
+            .triple_terms("sn:b", "rdfs:label", "B", true)
+            .unwrap();
+        processor.add_terms(&a);
+
+        assert!(processor.commit_transaction().is_err());
+        processor.begin_transaction().unwrap();
+        assert!(processor.begin_transaction().is_err());
+        processor.remove_terms(&a);
+        processor.add_terms(&b);
+        processor.rollback_transaction().unwrap();
+        assert_eq!(processor.triple_count(), 1);
+        assert!(processor.graph.contains(&a[0], &a[1], &a[2]).unwrap());
+
+        processor.begin_transaction().unwrap();
+        processor.add_terms(&b);
+        processor.commit_transaction().unwrap();
+        assert_eq!(processor.triple_count(), 2);
+        assert!(!processor.in_transaction());
+    }
+
+    #[test]
+    fn test_errors_are_structured() {
+        let processor = SemanticProcessor::new();
+
+        let error = processor
+            .select_graph(Some("https://example.org/missing"))
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::QueryError);
+        assert_eq!(error.context["graph"], "https://example.org/missing");
+
+        let error = processor.parse_term_arg("\"unterminated").unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidArgument);
+        assert_eq!(error.context["term"], "\"unterminated");
+    }
+
+    #[test]
+    fn test_parse_error_reports_line_and_column() {
+        let ttl = "@prefix sn: <https://sinople.org/ontology#> .\n\
+                   sn:a sn:label \"Fine\" .\n\
+                   sn:b sn:label sn:c sn:d .\n";
+        let error = SemanticProcessor::new().parse_turtle(ttl).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::ParseError);
+        assert_eq!(error.context["line"], "3");
+        assert_eq!(error.context["column"], "20");
+        assert_eq!(error.context["token"], "sn:d");
+        assert!(error
+            .message
+            .starts_with("Failed to parse Turtle at line 3, column 20"));
+    }
+
+    #[test]
+    fn test_instances_of_uses_type_triples_only() {
+        let mut processor = SemanticProcessor::new();
+        for (subject, class) in [
+            ("sn:a", "sn:Construct"),
+            ("sn:b", "sn:Character"),
+            ("sn:c", "sn:Construct"),
+        ] {
+            let triple = processor
+                .triple_terms(subject, "rdf:type", class, false)
+                .unwrap();
+            processor.add_terms(&triple);
+        }
+        let mention = processor
+            .triple_terms("sn:d", "sn:mentions", "sn:Construct", false)
+            .unwrap();
+        processor.add_terms(&mention);
+
+        let mut constructs = processor.instances_of(&processor.graph, "sn:Construct");
+        constructs.sort();
+        assert_eq!(
+            constructs,
+            vec![
+                Rc::from("https://sinople.org/ontology#a"),
+                Rc::from("https://sinople.org/ontology#c")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_network_graph_has_one_node_per_resource() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+            sn:Hero rdfs:subClassOf sn:Character .
+            sn:mira a sn:Hero, sn:Person .
+            sn:time a sn:Character, sn:Construct .
+        "#,
+                None,
+            )
+            .unwrap();
+
+        let graph = processor.collect_network_graph().unwrap();
+        let nodes: Vec<(&str, &str, usize)> = graph
+            .nodes
+            .iter()
+            .map(|n| {
+                (
+                    n.id.trim_start_matches("https://sinople.org/ontology#"),
+                    n.node_type.as_str(),
+                    n.types.len(),
+                )
+            })
+            .collect();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes.contains(&("mira", "character", 2)));
+        assert!(nodes.contains(&("time", "construct", 2)));
+        assert_eq!(processor.collect_export().unwrap().graph.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_incoming_links() {
+        let mut processor = SemanticProcessor::new();
+        for (subject, predicate) in [
+            ("sn:e1", "sn:hasSource"),
+            ("sn:e2", "sn:hasTarget"),
+            ("sn:c", "sn:mentions"),
+        ] {
+            let triple = processor
+                .triple_terms(subject, predicate, "sn:entropy", false)
+                .unwrap();
+            processor.add_terms(&triple);
+        }
+
+        assert_eq!(processor.get_incoming_links("sn:entropy", None).len(), 3);
+        assert_eq!(
+            processor.get_incoming_links("sn:entropy", Some("sn:mentions")),
+            vec![IncomingLink {
+                source: "https://sinople.org/ontology#c".to_string(),
+                predicate: "https://sinople.org/ontology#mentions".to_string(),
+            }]
+        );
+
+        let mut related =
+            processor.get_relationships(&processor.graph, "https://sinople.org/ontology#entropy");
+        related.sort();
+        assert_eq!(
+            related,
+            vec![
+                "https://sinople.org/ontology#e1",
+                "https://sinople.org/ontology#e2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_glosses_keep_language_tags() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:time sn:hasGloss "Duration", "Durée"@fr, "Ùine"@gd .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let mut glosses: Vec<(String, String, String)> = processor
+            .get_glosses(&processor.graph, "https://sinople.org/ontology#time")
+            .into_iter()
+            .map(|g| (g.language, g.text, g.id))
+            .collect();
+        glosses.sort();
+        assert_eq!(
+            glosses,
+            vec![
+                (
+                    "".to_string(),
+                    "Duration".to_string(),
+                    "https://sinople.org/ontology#time#gloss".to_string()
+                ),
+                (
+                    "fr".to_string(),
+                    "Durée".to_string(),
+                    "https://sinople.org/ontology#time#gloss-fr".to_string()
+                ),
+                (
+                    "gd".to_string(),
+                    "Ùine".to_string(),
+                    "https://sinople.org/ontology#time#gloss-gd".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gloss_list_positions_round_trip() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:time sn:hasGlossList ( "First" "Second"@fr "Third" ) ;
+                    sn:hasGloss "Loose" .
+                "#,
+                None,
+            )
+            .unwrap();
+        let glosses = |processor: &SemanticProcessor| -> Vec<(String, Option<usize>)> {
+            processor
+                .get_glosses(&processor.graph, "https://sinople.org/ontology#time")
+                .into_iter()
+                .map(|g| (g.text, g.position))
+                .collect()
+        };
+        let expected = vec![
+            ("First".to_string(), Some(0)),
+            ("Second".to_string(), Some(1)),
+            ("Third".to_string(), Some(2)),
+            ("Loose".to_string(), None),
+        ];
+        assert_eq!(glosses(&processor), expected);
+
+        let mut reloaded = SemanticProcessor::new();
+        reloaded
+            .load_turtle(&processor.to_turtle().unwrap(), None)
+            .unwrap();
+        assert_eq!(glosses(&reloaded), expected);
+    }
+
+    fn parse_turtle_graph(ttl: &str) -> FastGraph {
+        let mut graph = FastGraph::new();
+        TurtleParser::default()
+            .parse_str(ttl)
+            .add_to_graph(&mut graph)
+            .unwrap();
+        graph
+    }
 }
@@ -0,0 +1,309 @@
+//! Integrity checks before publishing
+//!
+//! `lint` looks for the mistakes that break pages without breaking the RDF:
+//!
+//! - `dangling-reference`: an entanglement without a source or target, or
+//!   whose source or target is not described anywhere
+//! - `missing-label`: a construct, character, entanglement, event or scene
+//!   without `rdfs:label`
+//! - `untyped-subject`: a described resource without `rdf:type`; properties
+//!   and classes are exempt
+//! - `unknown-predicate`: a predicate in the Sinople namespace that neither
+//!   the processor nor the ontology knows, which is usually a typo
+//! - `duplicate-id`: typed resources whose local names differ only in case,
+//!   such as `sn:Time` and `sn:time`, which collide where local names are
+//!   used as slugs
+//!
+//! ```javascript
+//! const report = processor.lint();
+//! if (!report.clean) report.issues.forEach(i => console.warn(i.kind, i.message));
+//! ```
+
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use std::collections::{BTreeMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// Predicates of the Sinople namespace that queries read
+const SINOPLE_PREDICATES: [&str; 19] = [
+    "sn:hasSource",
+    "sn:hasTarget",
+    "sn:relationshipType",
+    "sn:weight",
+    "sn:relatesTo",
+    "sn:hasGloss",
+    "sn:hasGlossList",
+    "sn:hasConstruct",
+    "sn:occursOn",
+    "sn:endsOn",
+    "sn:precedes",
+    "sn:follows",
+    "sn:partOf",
+    "sn:hasPart",
+    "sn:excerpt",
+    "sn:isbn",
+    "sn:doi",
+    "sn:citedBy",
+    "sn:locator",
+];
+
+/// Classes whose instances are shown with their label
+const LABELLED_CLASSES: [&str; 5] = [
+    "sn:Construct",
+    "sn:Character",
+    "sn:Entanglement",
+    "sn:Event",
+    "sn:Scene",
+];
+
+/// The result of `lint`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintReport {
+    /// True if there are no issues
+    pub clean: bool,
+    /// Number of issues of each kind
+    pub counts: BTreeMap<String, usize>,
+    pub issues: Vec<LintIssue>,
+}
+
+/// One problem found by `lint`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintIssue {
+    pub kind: String,
+    /// The resource with the problem; the predicate for `unknown-predicate`
+    pub resource: String,
+    /// The missing source or target, or the other IDs of a duplicate
+    pub related: Vec<String>,
+    pub message: String,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Check the graph for dangling references, missing labels, untyped
+    /// subjects, unknown predicates and duplicate IDs
+    ///
+    /// # Returns
+    /// JsValue containing {clean, counts, issues}, where each issue has
+    /// {kind, resource, related, message}
+    #[wasm_bindgen(unchecked_return_type = "LintReport")]
+    pub fn lint(&self) -> Result<JsValue, JsValue> {
+        let report = self.lint_report();
+
+        self.output(&report)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn lint_report(&self) -> LintReport {
+        let mut issues = Vec::new();
+        let mut issue = |kind: &str, resource: &str, related: Vec<String>, message: String| {
+            issues.push(LintIssue {
+                kind: kind.to_string(),
+                resource: resource.to_string(),
+                related,
+                message,
+            });
+        };
+        let rdf_type = self.make_term("rdf:type");
+        let sub_class_of = self.make_term("rdfs:subClassOf");
+        let described = |iri: &str| {
+            self.graph
+                .triples_matching([self.iri_term(iri)], Any, Any)
+                .next()
+                .is_some()
+        };
+
+        for entanglement in self.instances_of(&self.graph, "sn:Entanglement") {
+            for end in ["sn:hasSource", "sn:hasTarget"] {
+                match self.inferred_value(&self.graph, &entanglement, end) {
+                    None => issue(
+                        "dangling-reference",
+                        &entanglement,
+                        Vec::new(),
+                        format!("Entanglement {} has no {}", entanglement, end),
+                    ),
+                    Some(value) if !described(&value) => issue(
+                        "dangling-reference",
+                        &entanglement,
+                        vec![value.clone()],
+                        format!(
+                            "The {} of entanglement {}, {}, is not described",
+                            end, entanglement, value
+                        ),
+                    ),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut labelled = HashSet::new();
+        for class in LABELLED_CLASSES {
+            for resource in self.instances_of(&self.graph, class) {
+                let unlabelled = self
+                    .get_object_value(&self.graph, &resource, "rdfs:label")
+                    .is_none();
+                if unlabelled && labelled.insert(resource.clone()) {
+                    issue(
+                        "missing-label",
+                        &resource,
+                        Vec::new(),
+                        format!("{} has no rdfs:label", resource),
+                    );
+                }
+            }
+        }
+
+        // Properties and classes are typed by how they are used
+        let mut vocabulary: HashSet<SimpleTerm<'static>> = HashSet::new();
+        for t in self.graph.triples().flatten() {
+            vocabulary.insert(t.p().into_term());
+            if Term::eq(t.p(), &rdf_type) || Term::eq(t.p(), &sub_class_of) {
+                vocabulary.insert(t.o().into_term());
+            }
+        }
+        let mut subjects: Vec<SimpleTerm<'static>> = self
+            .graph
+            .subjects()
+            .flatten()
+            .filter(|s| s.is_iri())
+            .map(|s| s.into_term())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        subjects.sort_by_cached_key(|s| self.term_to_string(s));
+        let mut typed = Vec::new();
+        for subject in &subjects {
+            let iri = self.term_to_string(subject);
+            if self
+                .graph
+                .triples_matching([subject], [&rdf_type], Any)
+                .next()
+                .is_some()
+            {
+                typed.push(iri);
+            } else if !vocabulary.contains(subject) {
+                issue(
+                    "untyped-subject",
+                    &iri,
+                    Vec::new(),
+                    format!("{} has no rdf:type", iri),
+                );
+            }
+        }
+
+        let known: Vec<SimpleTerm> = SINOPLE_PREDICATES
+            .iter()
+            .map(|p| self.make_term(p))
+            .collect();
+        let namespace = self.namespaces.get("sn").cloned().unwrap_or_default();
+        let mut predicates: Vec<String> = self
+            .graph
+            .predicates()
+            .flatten()
+            .filter(|p| !known.iter().any(|k| Term::eq(k, *p)))
+            .filter(|p| self.graph.triples_matching([*p], Any, Any).next().is_none())
+            .map(|p| self.term_to_string(&p))
+            .filter(|p| p.starts_with(&namespace))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        predicates.sort();
+        for predicate in predicates {
+            issue(
+                "unknown-predicate",
+                &predicate,
+                Vec::new(),
+                format!(
+                    "{} is not a Sinople property and is not declared",
+                    predicate
+                ),
+            );
+        }
+
+        let mut by_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for iri in typed {
+            by_name
+                .entry(self.extract_local_name(&iri).to_lowercase())
+                .or_default()
+                .push(iri);
+        }
+        for iris in by_name.into_values().filter(|iris| iris.len() > 1) {
+            for iri in &iris {
+                let others: Vec<String> =
+                    iris.iter().filter(|other| *other != iri).cloned().collect();
+                let message = format!("{} has the same ID as {}", iri, others.join(", "));
+                issue("duplicate-id", iri, others, message);
+            }
+        }
+
+        let mut counts = BTreeMap::new();
+        for issue in &issues {
+            *counts.entry(issue.kind.clone()).or_insert(0) += 1;
+        }
+        LintReport {
+            clean: issues.is_empty(),
+            counts,
+            issues,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        sn:weight rdfs:range rdfs:Literal .
+        sn:time a sn:Construct ; rdfs:label "Time" ; sn:weight 3 .
+        sn:Time a sn:Character ; rdfs:label "Father Time" .
+        sn:space a sn:Construct ; sn:hasGlos sn:g1 .
+        sn:g1 rdfs:comment "An untyped gloss" .
+        sn:e1 a sn:Entanglement ; rdfs:label "E1" ;
+            sn:hasSource sn:time ; sn:hasTarget sn:nowhere .
+        sn:e2 a sn:Entanglement ; rdfs:label "E2" ; sn:hasSource sn:space .
+    "#;
+
+    #[test]
+    fn test_lint_report() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        let report = processor.lint_report();
+
+        let local = |iri: &str| {
+            iri.trim_start_matches("https://sinople.org/ontology#")
+                .to_string()
+        };
+        let summary: Vec<(&str, String)> = report
+            .issues
+            .iter()
+            .map(|i| (i.kind.as_str(), local(&i.resource)))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("dangling-reference", "e1".to_string()),
+                ("dangling-reference", "e2".to_string()),
+                ("missing-label", "space".to_string()),
+                ("untyped-subject", "g1".to_string()),
+                ("unknown-predicate", "hasGlos".to_string()),
+                ("duplicate-id", "Time".to_string()),
+                ("duplicate-id", "time".to_string()),
+            ]
+        );
+        assert_eq!(
+            report.issues[0].related,
+            ["https://sinople.org/ontology#nowhere"]
+        );
+        assert_eq!(report.counts["duplicate-id"], 2);
+        assert!(!report.clean);
+
+        processor.clear();
+        assert!(processor.lint_report().clean);
+    }
+}
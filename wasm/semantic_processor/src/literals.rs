@@ -0,0 +1,412 @@
+//! Typed accessors for literal values
+//!
+//! Ordering indices, dates and flags are stored as XSD-typed literals. These
+//! accessors return them as JavaScript numbers, booleans and `Date`s:
+//!
+//! ```javascript
+//! const order = processor.get_int('sn:time', 'sn:order');          // 3
+//! const published = processor.get_date('sn:time', 'sn:published'); // Date
+//! ```
+//!
+//! Each returns `undefined` if the resource has no value for the predicate,
+//! and fails with a `TypeMismatch` error if the value is not a literal of a
+//! matching datatype, or not a valid value of it. Only the first value is
+//! read when there are several.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use wasm_bindgen::prelude::*;
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+const INTEGER_TYPES: &[&str] = &[
+    "integer",
+    "int",
+    "long",
+    "short",
+    "byte",
+    "nonNegativeInteger",
+    "positiveInteger",
+    "nonPositiveInteger",
+    "negativeInteger",
+    "unsignedLong",
+    "unsignedInt",
+    "unsignedShort",
+    "unsignedByte",
+];
+const FLOAT_TYPES: &[&str] = &["decimal", "double", "float"];
+const BOOLEAN_TYPES: &[&str] = &["boolean"];
+const DATE_TYPES: &[&str] = &["date", "dateTime", "dateTimeStamp"];
+
+/// Largest integer a JavaScript number holds exactly
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Read an integer-typed literal (`xsd:integer`, `xsd:int`, ...)
+    ///
+    /// # Arguments
+    /// * `subject` - Resource as `prefix:local` or full IRI
+    /// * `predicate` - Property as `prefix:local` or full IRI
+    ///
+    /// # Returns
+    /// * `Err(JsValue)` with code `TypeMismatch` if the value is not an
+    ///   integer, or too large for a JavaScript number
+    pub fn get_int(&self, subject: &str, predicate: &str) -> Result<Option<f64>, JsValue> {
+        Ok(self
+            .int_value(subject, predicate)?
+            .map(|value| value as f64))
+    }
+
+    /// Read a decimal, double, float or integer literal
+    pub fn get_float(&self, subject: &str, predicate: &str) -> Result<Option<f64>, JsValue> {
+        Ok(self.float_value(subject, predicate)?)
+    }
+
+    /// Read an `xsd:boolean` literal
+    pub fn get_bool(&self, subject: &str, predicate: &str) -> Result<Option<bool>, JsValue> {
+        Ok(self.bool_value(subject, predicate)?)
+    }
+
+    /// Read an `xsd:date` or `xsd:dateTime` literal
+    ///
+    /// Values without a timezone are taken to be in UTC; an `xsd:date` is
+    /// midnight at the start of the day.
+    pub fn get_date(
+        &self,
+        subject: &str,
+        predicate: &str,
+    ) -> Result<Option<js_sys::Date>, JsValue> {
+        Ok(self
+            .date_value(subject, predicate)?
+            .map(|millis| js_sys::Date::new(&JsValue::from_f64(millis))))
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn int_value(
+        &self,
+        subject: &str,
+        predicate: &str,
+    ) -> Result<Option<i64>, ProcessorError> {
+        self.typed_value(subject, predicate, INTEGER_TYPES, |lex| {
+            lex.parse::<i64>()
+                .ok()
+                .filter(|value| value.abs() <= MAX_SAFE_INTEGER)
+        })
+    }
+
+    pub(crate) fn float_value(
+        &self,
+        subject: &str,
+        predicate: &str,
+    ) -> Result<Option<f64>, ProcessorError> {
+        let types: Vec<&str> = FLOAT_TYPES.iter().chain(INTEGER_TYPES).copied().collect();
+        self.typed_value(subject, predicate, &types, parse_float)
+    }
+
+    pub(crate) fn bool_value(
+        &self,
+        subject: &str,
+        predicate: &str,
+    ) -> Result<Option<bool>, ProcessorError> {
+        self.typed_value(subject, predicate, BOOLEAN_TYPES, |lex| match lex {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        })
+    }
+
+    /// Milliseconds since the Unix epoch
+    pub(crate) fn date_value(
+        &self,
+        subject: &str,
+        predicate: &str,
+    ) -> Result<Option<f64>, ProcessorError> {
+        self.typed_value(subject, predicate, DATE_TYPES, parse_date)
+    }
+
+    /// Find the first value of a property and parse it
+    ///
+    /// `types` are the local names of the XSD datatypes accepted.
+    fn typed_value<T>(
+        &self,
+        subject: &str,
+        predicate: &str,
+        types: &[&str],
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Result<Option<T>, ProcessorError> {
+        let subject_term = self.make_term(subject);
+        let predicate_term = self.make_term(predicate);
+        let Some(triple) = self
+            .graph
+            .triples_matching([&subject_term], [&predicate_term], Any)
+            .flatten()
+            .next()
+        else {
+            return Ok(None);
+        };
+
+        let object = triple.o();
+        let mismatch = |found: &str| {
+            ProcessorError::type_mismatch(format!(
+                "Value of {} on {} is {}, not an xsd:{}",
+                predicate, subject, found, types[0]
+            ))
+            .with_context("subject", subject)
+            .with_context("predicate", predicate)
+            .with_context("expected", format!("{}{}", XSD, types[0]))
+        };
+
+        let SimpleTerm::LiteralDatatype(lex, datatype) = object.as_simple() else {
+            return Err(match object.lexical_form() {
+                Some(lex) => mismatch(&format!("\"{}\"", lex)).with_context("value", lex),
+                None => mismatch(&format!("the resource {}", self.term_to_string(&object))),
+            });
+        };
+        let accepted = datatype
+            .as_str()
+            .strip_prefix(XSD)
+            .is_some_and(|local| types.contains(&local));
+        let value = if accepted { parse(lex.trim()) } else { None };
+        match value {
+            Some(value) => Ok(Some(value)),
+            None => Err(mismatch(&format!("\"{}\"^^<{}>", &*lex, datatype.as_str()))
+                .with_context("value", &*lex)
+                .with_context("datatype", datatype.as_str())),
+        }
+    }
+}
+
+/// The weight of an edge from the value of `sn:weight`
+///
+/// Only positive, finite numbers are weights; anything else counts as the
+/// default of 1.0, since path lengths and layouts divide by it.
+pub(crate) fn parse_weight(value: Option<&str>) -> f64 {
+    value
+        .and_then(|lex| parse_float(lex.trim()))
+        .filter(|weight| weight.is_finite() && *weight > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// Parse an XSD decimal, double or float
+fn parse_float(lex: &str) -> Option<f64> {
+    match lex {
+        "INF" | "+INF" => Some(f64::INFINITY),
+        "-INF" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        // Rust also accepts spellings such as "inf" that XSD does not
+        _ if lex
+            .chars()
+            .any(|c| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E')) =>
+        {
+            None
+        }
+        _ => lex.parse().ok(),
+    }
+}
+
+/// Parse an `xsd:date` or `xsd:dateTime` into milliseconds since the epoch
+///
+/// Years may have a leading `-` and more than four digits, e.g.
+/// `-0500-01-01` for 501 BCE, up to the range of a JavaScript `Date`.
+pub(crate) fn parse_date(lex: &str) -> Option<f64> {
+    let (negative, unsigned) = match lex.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, lex),
+    };
+    // The date ends with the two digits of the day, after `-mm-`
+    let date_end = unsigned.find('-')? + "-mm-dd".len();
+    let (date, rest) = (unsigned.get(..date_end)?, unsigned.get(date_end..)?);
+    let (year, month, day) = parse_ymd(date, negative)?;
+
+    let (time, zone) = match rest.strip_prefix('T') {
+        Some(rest) => {
+            let end = rest.find(['Z', '+', '-']).unwrap_or(rest.len());
+            (parse_time(&rest[..end])?, &rest[end..])
+        }
+        None => (0.0, rest),
+    };
+    let offset = parse_zone(zone)?;
+
+    let days = days_from_civil(year, month, day) as f64;
+    Some(days * 86_400_000.0 + time - offset)
+}
+
+/// Largest year a JavaScript `Date` can hold
+const MAX_YEAR: i64 = 275_760;
+
+fn parse_ymd(date: &str, negative: bool) -> Option<(i64, u32, u32)> {
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() < 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let year: i64 = digits(year).filter(|&year| year <= MAX_YEAR)?;
+    let year = if negative { -year } else { year };
+    let month: u32 = digits(month)?;
+    let day: u32 = digits(day)?;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    (1..=days_in_month)
+        .contains(&day)
+        .then_some((year, month, day))
+}
+
+/// Parse `hh:mm:ss[.fff]` into milliseconds since midnight
+fn parse_time(time: &str) -> Option<f64> {
+    let mut parts = time.splitn(3, ':');
+    let (hours, minutes, seconds) = (parts.next()?, parts.next()?, parts.next()?);
+    if hours.len() != 2 || minutes.len() != 2 || seconds.len() < 2 {
+        return None;
+    }
+    let hours: u32 = digits(hours)?;
+    let minutes: u32 = digits(minutes)?;
+    let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let whole: u32 = digits(whole)?;
+    if !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let seconds: f64 = format!("{}.{}0", whole, fraction).parse().ok()?;
+
+    let midnight = hours == 24 && minutes == 0 && seconds == 0.0;
+    if (hours > 23 && !midnight) || minutes > 59 || whole > 59 {
+        return None;
+    }
+    Some(((hours * 60 + minutes) as f64 * 60.0 + seconds) * 1000.0)
+}
+
+/// Parse a timezone (`Z`, `+hh:mm`, `-hh:mm` or nothing) into milliseconds
+/// east of UTC
+fn parse_zone(zone: &str) -> Option<f64> {
+    if zone.is_empty() || zone == "Z" {
+        return Some(0.0);
+    }
+    let sign = match zone.as_bytes()[0] {
+        b'+' => 1.0,
+        b'-' => -1.0,
+        _ => return None,
+    };
+    let (hours, minutes) = zone[1..].split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: u32 = digits(hours)?;
+    let minutes: u32 = digits(minutes)?;
+    (hours <= 14 && minutes <= 59).then(|| sign * f64::from(hours * 60 + minutes) * 60_000.0)
+}
+
+/// Parse a run of ASCII digits, rejecting signs and whitespace
+fn digits<T: std::str::FromStr>(text: &str) -> Option<T> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Days from 1970-01-01 to a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+
+    fn processor() -> SemanticProcessor {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+                sn:time sn:order 3 ;
+                    sn:weight 0.75 ;
+                    sn:visible true ;
+                    sn:published "2024-03-01"^^xsd:date ;
+                    sn:updated "2024-03-01T12:30:00.5+02:00"^^xsd:dateTime ;
+                    sn:label "Time" ;
+                    sn:related sn:space ;
+                    sn:rank "second"^^xsd:integer .
+                "#,
+                None,
+            )
+            .unwrap();
+        processor
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let processor = processor();
+        assert_eq!(processor.int_value("sn:time", "sn:order").unwrap(), Some(3));
+        assert_eq!(
+            processor.float_value("sn:time", "sn:weight").unwrap(),
+            Some(0.75)
+        );
+        assert_eq!(
+            processor.float_value("sn:time", "sn:order").unwrap(),
+            Some(3.0)
+        );
+        assert_eq!(
+            processor.bool_value("sn:time", "sn:visible").unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            processor.date_value("sn:time", "sn:published").unwrap(),
+            Some(1_709_251_200_000.0)
+        );
+        assert_eq!(
+            processor.date_value("sn:time", "sn:updated").unwrap(),
+            Some(1_709_289_000_500.0)
+        );
+        assert_eq!(processor.int_value("sn:time", "sn:missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let processor = processor();
+        for (predicate, value) in [
+            ("sn:weight", "0.75"),
+            ("sn:label", "Time"),
+            ("sn:rank", "second"),
+        ] {
+            let error = processor.int_value("sn:time", predicate).unwrap_err();
+            assert_eq!(error.code, ErrorCode::TypeMismatch);
+            assert_eq!(error.context["value"], value);
+        }
+        let error = processor.bool_value("sn:time", "sn:related").unwrap_err();
+        assert_eq!(error.code, ErrorCode::TypeMismatch);
+        assert!(processor.date_value("sn:time", "sn:order").is_err());
+
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(
+            parse_date("2024-03-01Z"),
+            parse_date("2024-03-01T00:00:00Z")
+        );
+        let bce = parse_date("-0500-01-01").unwrap();
+        assert_eq!(parse_date("-0500-01-01T00:00:00Z"), Some(bce));
+        assert!(bce < parse_date("0001-01-01").unwrap());
+        assert!(parse_date("12345-06-01").unwrap() > parse_date("9999-12-31").unwrap());
+        assert_eq!(parse_date("999999999999999-01-01"), None);
+        assert_eq!(parse_date("--0500-01-01"), None);
+        assert_eq!(parse_float("inf"), None);
+    }
+}
@@ -0,0 +1,174 @@
+//! Network graph metrics
+//!
+//! Summary figures for the "ontology health" panel, measured on the network
+//! graph with edges taken in either direction:
+//!
+//! ```javascript
+//! const { density, average_degree, clustering_coefficient, diameter_estimate } =
+//!   processor.graph_metrics();
+//! ```
+
+use crate::centrality::{node_index, undirected_links};
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+/// How densely the network graph is connected
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GraphMetrics {
+    pub nodes: usize,
+    /// Pairs of nodes joined by at least one edge
+    pub edges: usize,
+    /// Share of all possible pairs that are joined, between 0 and 1
+    pub density: f64,
+    pub average_degree: f64,
+    /// Average share of each node's neighbours that are joined to each other
+    pub clustering_coefficient: f64,
+    /// Longest shortest path found within a connected part of the graph; a
+    /// lower bound of the diameter, exact for trees
+    pub diameter_estimate: usize,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Measure the density, average degree, clustering and diameter of the
+    /// network graph
+    ///
+    /// # Returns
+    /// JsValue containing {nodes, edges, density, average_degree,
+    /// clustering_coefficient, diameter_estimate}
+    #[wasm_bindgen(unchecked_return_type = "GraphMetrics")]
+    pub fn graph_metrics(&self) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let metrics = self.collect_metrics()?;
+
+        self.output(&metrics)
+    }
+}
+
+impl SemanticProcessor {
+    /// Build the result of `graph_metrics`
+    pub(crate) fn collect_metrics(&self) -> Result<GraphMetrics, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let (ids, index) = node_index(&network);
+        let links = undirected_links(&network, &index);
+        if ids.is_empty() {
+            return Ok(GraphMetrics::default());
+        }
+
+        let n = ids.len() as f64;
+        let edges = links.iter().map(Vec::len).sum::<usize>() / 2;
+        let density = if ids.len() > 1 {
+            2.0 * edges as f64 / (n * (n - 1.0))
+        } else {
+            0.0
+        };
+
+        // Nodes with fewer than two neighbours count as 0
+        let mut clustering = 0.0;
+        for neighbours in &links {
+            let k = neighbours.len();
+            if k < 2 {
+                continue;
+            }
+            let joined = neighbours
+                .iter()
+                .enumerate()
+                .flat_map(|(i, a)| neighbours[i + 1..].iter().map(move |b| (*a, *b)))
+                .filter(|(a, b)| links[*a].contains(b))
+                .count();
+            clustering += 2.0 * joined as f64 / (k * (k - 1)) as f64;
+        }
+
+        // Two sweeps in each connected part: to the farthest node from any
+        // node, then to the farthest node from that one
+        let mut visited = vec![false; ids.len()];
+        let mut diameter = 0;
+        for start in 0..ids.len() {
+            if visited[start] {
+                continue;
+            }
+            self.check_abort()?;
+            let (far, _, members) = farthest(&links, start);
+            for member in members {
+                visited[member] = true;
+            }
+            let (_, distance, _) = farthest(&links, far);
+            diameter = diameter.max(distance);
+        }
+
+        Ok(GraphMetrics {
+            nodes: ids.len(),
+            edges,
+            density,
+            average_degree: 2.0 * edges as f64 / n,
+            clustering_coefficient: clustering / n,
+            diameter_estimate: diameter,
+        })
+    }
+}
+
+/// The node farthest from a start, its distance, and every node reached
+fn farthest(links: &[Vec<usize>], start: usize) -> (usize, usize, Vec<usize>) {
+    let mut distance = vec![usize::MAX; links.len()];
+    distance[start] = 0;
+    let mut reached = vec![start];
+    let mut queue = VecDeque::from([start]);
+    let mut far = start;
+    while let Some(node) = queue.pop_front() {
+        if distance[node] > distance[far] {
+            far = node;
+        }
+        for &next in &links[node] {
+            if distance[next] == usize::MAX {
+                distance[next] = distance[node] + 1;
+                reached.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    (far, distance[far], reached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_of_a_triangle_with_a_tail() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:a sn:relatesTo sn:b . sn:b sn:relatesTo sn:c . sn:c sn:relatesTo sn:a .
+                sn:b sn:relatesTo sn:a .
+                sn:c sn:relatesTo sn:d .
+                sn:x sn:relatesTo sn:y .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let metrics = processor.collect_metrics().unwrap();
+        assert_eq!(metrics.nodes, 6);
+        assert_eq!(metrics.edges, 5);
+        assert!((metrics.density - 10.0 / 30.0).abs() < 1e-9);
+        assert!((metrics.average_degree - 10.0 / 6.0).abs() < 1e-9);
+        // a and b are fully clustered, c has one of three pairs joined
+        assert!((metrics.clustering_coefficient - (2.0 + 1.0 / 3.0) / 6.0).abs() < 1e-9);
+        assert_eq!(metrics.diameter_estimate, 2);
+
+        processor.clear();
+        assert_eq!(
+            processor.collect_metrics().unwrap(),
+            GraphMetrics::default()
+        );
+    }
+}
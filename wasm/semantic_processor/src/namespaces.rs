@@ -0,0 +1,106 @@
+//! Namespace prefixes
+//!
+//! Prefixed names such as `sn:Construct` are resolved against the registered
+//! namespaces wherever a method accepts one: in queries, triple patterns,
+//! SPARQL and RDF Patch. `sn`, `rdf`, `rdfs`, `owl` and `xsd` are registered
+//! from the start; sites using other vocabularies add their own:
+//!
+//! ```javascript
+//! processor.register_namespace('skos', 'http://www.w3.org/2004/02/skos/core#');
+//! processor.match_triples(null, 'skos:prefLabel', null);
+//! ```
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_api::prefix::Prefix;
+use sophia_iri::Iri;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Register a namespace prefix, replacing any namespace it stood for
+    ///
+    /// # Arguments
+    /// * `prefix` - Prefix without the colon, e.g. `"skos"`
+    /// * `iri` - Absolute namespace IRI, usually ending in `#` or `/`
+    pub fn register_namespace(&mut self, prefix: &str, iri: &str) -> Result<(), JsValue> {
+        Ok(self.add_namespace(prefix, iri)?)
+    }
+
+    /// Remove a namespace prefix
+    ///
+    /// # Returns
+    /// `true` if the prefix was registered
+    pub fn remove_namespace(&mut self, prefix: &str) -> bool {
+        let removed = self.namespaces.remove(prefix).is_some();
+        if removed {
+            self.rebuild_aliases();
+        }
+        removed
+    }
+
+    /// List the registered namespaces
+    ///
+    /// # Returns
+    /// JsValue containing an object mapping each prefix to its namespace IRI
+    #[wasm_bindgen(unchecked_return_type = "Record<string, string>")]
+    pub fn list_namespaces(&self) -> Result<JsValue, JsValue> {
+        let namespaces: BTreeMap<&String, &String> = self.namespaces.iter().collect();
+
+        self.output_json(&namespaces)
+    }
+}
+
+impl SemanticProcessor {
+    /// Validate and register a namespace prefix
+    pub(crate) fn add_namespace(&mut self, prefix: &str, iri: &str) -> Result<(), ProcessorError> {
+        if Prefix::new(prefix).is_err() {
+            return Err(ProcessorError::invalid_argument(format!(
+                "Invalid namespace prefix '{}'",
+                prefix
+            ))
+            .with_context("prefix", prefix));
+        }
+        if Iri::new(iri).is_err() {
+            return Err(ProcessorError::invalid_argument(format!(
+                "Invalid namespace IRI '{}'; expected an absolute IRI",
+                iri
+            ))
+            .with_context("iri", iri));
+        }
+
+        self.namespaces.insert(prefix.to_string(), iri.to_string());
+        self.rebuild_aliases();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_remove_namespace() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .add_namespace("skos", "http://www.w3.org/2004/02/skos/core#")
+            .unwrap();
+        assert_eq!(
+            processor.term_to_string(&processor.make_term("skos:prefLabel")),
+            "http://www.w3.org/2004/02/skos/core#prefLabel"
+        );
+
+        assert!(processor
+            .add_namespace("not a prefix", "http://example.org/")
+            .is_err());
+        assert!(processor.add_namespace("ex", "relative/path#").is_err());
+
+        assert!(processor.remove_namespace("skos"));
+        assert!(!processor.remove_namespace("skos"));
+        assert_eq!(
+            processor.term_to_string(&processor.make_term("skos:prefLabel")),
+            "skos:prefLabel"
+        );
+    }
+}
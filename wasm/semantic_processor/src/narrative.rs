@@ -0,0 +1,241 @@
+//! Reading order
+//!
+//! The order scenes and chapters are read in is recorded with `sn:precedes`
+//! and `sn:follows` rather than by hand. `narrative_order` sorts the scenes
+//! and chapters that are `sn:partOf` a work, or every one without a scope,
+//! so that each comes after those preceding it:
+//!
+//! ```javascript
+//! const { order, contradictions } = processor.narrative_order('sn:book1');
+//! for (const cycle of contradictions) {
+//!   console.warn('Contradictory order:', cycle.path.join(' → '));
+//! }
+//! ```
+//!
+//! Relationships are followed with their sub-properties and inverses, so
+//! `sn:hasPart` and `sn:follows` count when declared inverses, and are also
+//! read as such when they are not. Scenes that precede each other in a loop
+//! are a contradiction: they are reported, and listed together in IRI order
+//! where the loop falls. Resources whose order is not fixed by the
+//! relationships are listed in IRI order too.
+
+use crate::cycles::{shortest_loop, strongly_connected, Cycle};
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::SimpleTerm;
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use wasm_bindgen::prelude::*;
+
+/// Scenes and chapters in reading order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NarrativeOrder {
+    /// IRIs, each after every resource preceding it
+    pub order: Vec<String>,
+    /// Resources said to precede each other in a loop
+    pub contradictions: Vec<Cycle>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Sort scenes and chapters by `sn:precedes` and `sn:follows`
+    ///
+    /// # Arguments
+    /// * `scope_iri` - Only sort the scenes and chapters `sn:partOf` this
+    ///   resource; `None` sorts all of them
+    ///
+    /// # Returns
+    /// JsValue containing {order, contradictions}, with one {nodes, path}
+    /// for each loop of precedence
+    #[wasm_bindgen(unchecked_return_type = "NarrativeOrder")]
+    pub fn narrative_order(&self, scope_iri: Option<String>) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let order = self.sort_narrative(scope_iri.as_deref())?;
+
+        self.output(&order)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn sort_narrative(
+        &self,
+        scope: Option<&str>,
+    ) -> Result<NarrativeOrder, ProcessorError> {
+        let graph = self.query_graph(None)?;
+        let graph: &FastGraph = &graph;
+        let mut members: BTreeSet<String> = ["sn:Scene", "sn:Chapter"]
+            .iter()
+            .flat_map(|class| self.instances_of(graph, class))
+            .map(|iri| iri.to_string())
+            .collect();
+        if let Some(scope) = scope {
+            let scope = self.canonical_iri(scope);
+            let parts: BTreeSet<String> = self
+                .pairs(graph, "sn:partOf", "sn:hasPart")
+                .into_iter()
+                .filter(|(_, whole)| *whole == scope)
+                .map(|(part, _)| part)
+                .collect();
+            members.retain(|member| parts.contains(member));
+        }
+
+        let mut successors: BTreeMap<String, BTreeSet<String>> = members
+            .iter()
+            .map(|member| (member.clone(), BTreeSet::new()))
+            .collect();
+        for (before, after) in self.pairs(graph, "sn:precedes", "sn:follows") {
+            if members.contains(&after) {
+                if let Some(next) = successors.get_mut(&before) {
+                    next.insert(after);
+                }
+            }
+        }
+
+        let mut components = strongly_connected(&successors);
+        for component in &mut components {
+            component.sort();
+        }
+        let mut contradictions: Vec<Cycle> = components
+            .iter()
+            .filter(|group| group.len() > 1 || successors[&group[0]].contains(&group[0]))
+            .map(|nodes| Cycle {
+                nodes: nodes.clone(),
+                path: shortest_loop(&successors, nodes),
+            })
+            .collect();
+        contradictions.sort_by(|a, b| a.nodes.cmp(&b.nodes));
+
+        // Kahn's algorithm over the loops collapsed into single steps, taking
+        // the step with the first IRI whenever several are free
+        let component_of: HashMap<&str, usize> = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, group)| group.iter().map(move |member| (member.as_str(), i)))
+            .collect();
+        let mut after: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); components.len()];
+        let mut waiting = vec![0; components.len()];
+        for (from, next) in &successors {
+            for to in next {
+                let (from, to) = (component_of[from.as_str()], component_of[to.as_str()]);
+                if from != to && after[from].insert(to) {
+                    waiting[to] += 1;
+                }
+            }
+        }
+        let mut free: BTreeSet<(&str, usize)> = (0..components.len())
+            .filter(|&i| waiting[i] == 0)
+            .map(|i| (components[i][0].as_str(), i))
+            .collect();
+        let mut order = Vec::with_capacity(members.len());
+        while let Some((_, i)) = free.pop_first() {
+            order.extend(components[i].iter().cloned());
+            for &next in &after[i] {
+                waiting[next] -= 1;
+                if waiting[next] == 0 {
+                    free.insert((components[next][0].as_str(), next));
+                }
+            }
+        }
+
+        Ok(NarrativeOrder {
+            order,
+            contradictions,
+        })
+    }
+
+    /// The (subject, object) pairs of a property, with its sub-properties,
+    /// its declared inverses and the given inverse read the other way round
+    fn pairs(
+        &self,
+        graph: &FastGraph,
+        property: &str,
+        inverse: &str,
+    ) -> BTreeSet<(String, String)> {
+        let mut forward = self.subproperties_of(property);
+        let mut backward = self.subproperties_of(inverse);
+        backward.extend(self.inverses_of(property).into_iter().map(|(term, _)| term));
+        forward.extend(self.inverses_of(inverse).into_iter().map(|(term, _)| term));
+
+        let pair = |predicates: &[SimpleTerm], reverse: bool| {
+            graph
+                .triples_matching(Any, predicates, Any)
+                .flatten()
+                .map(|t| (self.term_to_string(t.s()), self.term_to_string(t.o())))
+                .map(|(s, o)| if reverse { (o, s) } else { (s, o) })
+                .collect::<Vec<_>>()
+        };
+        let mut pairs: BTreeSet<(String, String)> = pair(&forward, false).into_iter().collect();
+        pairs.extend(pair(&backward, true));
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(iris: &[String]) -> Vec<&str> {
+        iris.iter()
+            .map(|iri| iri.trim_start_matches("https://sinople.org/ontology#"))
+            .collect()
+    }
+
+    #[test]
+    fn test_narrative_order_within_a_scope() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:book sn:hasPart sn:dawn, sn:noon .
+                sn:dawn a sn:Scene ; sn:precedes sn:noon .
+                sn:noon a sn:Scene .
+                sn:dusk a sn:Scene ; sn:partOf sn:book ; sn:follows sn:noon .
+                sn:coda a sn:Chapter ; sn:partOf sn:book ; sn:follows sn:dusk .
+                sn:aside a sn:Scene ; sn:partOf sn:book .
+                sn:elsewhere a sn:Scene ; sn:precedes sn:dawn .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let narrative = processor.sort_narrative(Some("sn:book")).unwrap();
+        assert_eq!(
+            local(&narrative.order),
+            ["aside", "dawn", "noon", "dusk", "coda"]
+        );
+        assert!(narrative.contradictions.is_empty());
+
+        let narrative = processor.sort_narrative(None).unwrap();
+        assert_eq!(local(&narrative.order)[..3], ["aside", "elsewhere", "dawn"]);
+    }
+
+    #[test]
+    fn test_narrative_order_flags_loops() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:one a sn:Scene ; sn:precedes sn:two .
+                sn:two a sn:Scene ; sn:precedes sn:three .
+                sn:three a sn:Scene ; sn:precedes sn:four .
+                sn:four a sn:Scene ; sn:precedes sn:two .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let narrative = processor.sort_narrative(None).unwrap();
+        assert_eq!(local(&narrative.order), ["one", "four", "three", "two"]);
+        assert_eq!(narrative.contradictions.len(), 1);
+        assert_eq!(
+            local(&narrative.contradictions[0].nodes),
+            ["four", "three", "two"]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Limiting, sorting and filtering query results
+//!
+//! `query_constructs`, `query_entanglements`, `query_characters` and
+//! `query_events` accept an optional plain object, so list UIs can fetch only
+//! what they show:
+//!
+//! ```javascript
+//! const first = processor.query_constructs(null, {
+//!   filter: 'time', sort: 'label', order: 'desc', offset: 0, limit: 20,
+//! });
+//! ```
+//!
+//! All fields are optional. Without options, results are sorted by IRI.
+
+use crate::error::ProcessorError;
+use crate::{Character, Construct, Entanglement};
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+/// Options for the `query_*` methods
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryOptions {
+    /// Maximum number of results
+    pub limit: Option<usize>,
+    /// Number of results to skip, after filtering and sorting
+    pub offset: usize,
+    pub sort: SortKey,
+    pub order: SortOrder,
+    /// Only keep results whose label contains this text, ignoring case
+    pub filter: Option<String>,
+}
+
+/// Field to sort results by; labels are compared ignoring case
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Id,
+    Label,
+    /// Chronologically, for results that have a date; undated ones go last
+    Date,
+}
+
+/// Direction to sort results in
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A query result with a human-readable label
+pub(crate) trait Labelled {
+    fn label(&self) -> &str;
+
+    /// When the result occurs, in milliseconds since the Unix epoch
+    fn date(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl Labelled for Construct {
+    fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+impl Labelled for Entanglement {
+    fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+impl Labelled for Character {
+    fn label(&self) -> &str {
+        &self.name
+    }
+}
+
+impl QueryOptions {
+    /// Read options passed from JavaScript; `undefined` and `null` mean none
+    pub(crate) fn from_js(value: JsValue) -> Result<Self, ProcessorError> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(Self::default());
+        }
+        serde_wasm_bindgen::from_value(value)
+            .map_err(|e| ProcessorError::invalid_argument(format!("Invalid query options: {}", e)))
+    }
+
+    /// Filter, sort and slice results sorted by IRI
+    pub(crate) fn apply<T: Labelled + Clone>(&self, items: &[T]) -> Vec<T> {
+        let filter = self.filter.as_deref().map(str::to_lowercase);
+        let mut selected: Vec<&T> = items
+            .iter()
+            .filter(|item| {
+                filter
+                    .as_deref()
+                    .is_none_or(|text| item.label().to_lowercase().contains(text))
+            })
+            .collect();
+
+        // The sort is stable, so equal labels stay in IRI order, reversed
+        // along with everything else when descending
+        match self.sort {
+            SortKey::Id => {}
+            SortKey::Label => selected.sort_by_cached_key(|item| item.label().to_lowercase()),
+            SortKey::Date => selected.sort_by(|a, b| match (a.date(), b.date()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (a, b) => a.is_none().cmp(&b.is_none()),
+            }),
+        }
+        if self.order == SortOrder::Desc {
+            selected.reverse();
+        }
+
+        selected
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character(id: &str, name: &str) -> Character {
+        Character {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            constructs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_apply_filters_sorts_and_slices() {
+        let characters = [
+            character("ex:a", "Mira"),
+            character("ex:b", "Anselm"),
+            character("ex:c", "mirabel"),
+            character("ex:d", "Ilse"),
+        ];
+        let ids = |options: &QueryOptions| -> Vec<String> {
+            options
+                .apply(&characters)
+                .into_iter()
+                .map(|c| c.id)
+                .collect()
+        };
+
+        assert_eq!(ids(&QueryOptions::default()).len(), 4);
+
+        let options = QueryOptions {
+            filter: Some("MIRA".to_string()),
+            ..QueryOptions::default()
+        };
+        assert_eq!(ids(&options), ["ex:a", "ex:c"]);
+
+        let options = QueryOptions {
+            sort: SortKey::Label,
+            order: SortOrder::Desc,
+            offset: 1,
+            limit: Some(2),
+            ..QueryOptions::default()
+        };
+        assert_eq!(ids(&options), ["ex:a", "ex:d"]);
+    }
+}
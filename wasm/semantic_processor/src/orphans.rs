@@ -0,0 +1,108 @@
+//! Orphaned constructs and characters
+//!
+//! A construct or character that no entanglement, relationship or character
+//! link touches is drawn as an isolated dot in the network visualization.
+//! `find_orphans` lists them so editors can connect or remove them:
+//!
+//! ```javascript
+//! for (const node of processor.find_orphans()) console.warn(`${node.label} is not connected`);
+//! ```
+
+use crate::error::ProcessorError;
+use crate::{GraphNode, SemanticProcessor};
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::triple::Triple;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// List the constructs and characters without any connection
+    ///
+    /// A node is connected if an edge of the network graph starts or ends at
+    /// it, or if it is either end of an `sn:hasConstruct` link.
+    ///
+    /// # Returns
+    /// JsValue containing array of GraphNode objects {id, label, node_type},
+    /// sorted by IRI
+    #[wasm_bindgen(unchecked_return_type = "GraphNode[]")]
+    pub fn find_orphans(&self) -> Result<JsValue, JsValue> {
+        let orphans = self.orphans()?;
+
+        self.output(&orphans)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn orphans(&self) -> Result<Vec<GraphNode>, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let graph = self.query_graph(None)?;
+
+        let mut connected: HashSet<String> = network
+            .edges
+            .iter()
+            .flat_map(|edge| [edge.source.clone(), edge.target.clone()])
+            .collect();
+        let links = self.subproperties_of("sn:hasConstruct");
+        for t in graph.triples_matching(Any, &links[..], Any).flatten() {
+            connected.insert(self.term_to_string(t.s()));
+            connected.insert(self.term_to_string(t.o()));
+        }
+
+        let mut seen = HashSet::new();
+        let mut orphans: Vec<GraphNode> = network
+            .nodes
+            .iter()
+            .filter(|node| matches!(node.node_type.as_str(), "construct" | "character"))
+            .filter(|node| !connected.contains(&node.id) && seen.insert(node.id.clone()))
+            .cloned()
+            .collect();
+        orphans.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(orphans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orphans_have_no_connections() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:time a sn:Construct .
+                sn:space a sn:Construct .
+                sn:void a sn:Construct .
+                sn:echo a sn:Construct ; sn:relatesTo sn:void .
+                sn:lonely a sn:Construct .
+                sn:mira a sn:Character ; sn:hasConstruct sn:space .
+                sn:ilse a sn:Character .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:time ; sn:hasTarget sn:space .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let orphans: Vec<String> = processor
+            .orphans()
+            .unwrap()
+            .into_iter()
+            .map(|node| format!("{} {}", node.node_type, node.id))
+            .collect();
+        assert_eq!(
+            orphans,
+            [
+                "character https://sinople.org/ontology#ilse",
+                "construct https://sinople.org/ontology#lonely",
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Encoding results as bytes
+//!
+//! By default, query methods return JavaScript objects. A processor running
+//! in a Web Worker would then have its results structured-cloned when posted
+//! to the main thread. With a byte encoding selected, query methods return a
+//! `Uint8Array` holding JSON or CBOR instead, whose buffer can be transferred
+//! without copying:
+//!
+//! ```javascript
+//! processor.set_output_encoding('cbor');
+//! const bytes = processor.query_constructs();
+//! postMessage(bytes, [bytes.buffer]);
+//! ```
+
+use crate::casing::Shaped;
+use crate::curie::Curies;
+use crate::error::{to_js, to_js_json, ProcessorError};
+use crate::SemanticProcessor;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// How query methods return their results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OutputEncoding {
+    /// JavaScript objects
+    #[default]
+    Object,
+    /// UTF-8 JSON in a `Uint8Array`
+    Json,
+    /// CBOR in a `Uint8Array`
+    Cbor,
+}
+
+impl OutputEncoding {
+    fn parse(name: &str) -> Result<Self, ProcessorError> {
+        match name.to_ascii_lowercase().as_str() {
+            "object" => Ok(Self::Object),
+            "json" => Ok(Self::Json),
+            "cbor" => Ok(Self::Cbor),
+            _ => Err(ProcessorError::invalid_argument(format!(
+                "Unknown output encoding '{}'; expected 'object', 'json' or 'cbor'",
+                name
+            ))
+            .with_context("encoding", name)),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose how query methods return their results
+    ///
+    /// Applies to every method returning structured results, e.g. the
+    /// `query_*` methods and their pages, `describe`, `generate_network_graph`,
+    /// `export_all`, `sparql_select` and `stats`.
+    ///
+    /// # Arguments
+    /// * `encoding` - `"object"` (the default), `"json"` or `"cbor"`
+    pub fn set_output_encoding(&mut self, encoding: &str) -> Result<(), JsValue> {
+        self.output = OutputEncoding::parse(encoding)?;
+        Ok(())
+    }
+}
+
+impl SemanticProcessor {
+    /// Return a result in the selected encoding, key case and IRI form
+    pub(crate) fn output<T: Serialize + ?Sized>(&self, value: &T) -> Result<JsValue, JsValue> {
+        let curies = self.curies();
+        let value = self.shaped(value, curies.as_ref());
+        match self.output {
+            OutputEncoding::Object => to_js(&value),
+            _ => Ok(self.encode_bytes(&value)?.into()),
+        }
+    }
+
+    pub(crate) fn shaped<'a, T: ?Sized>(
+        &self,
+        value: &'a T,
+        curies: Option<&'a Curies>,
+    ) -> Shaped<'a, T> {
+        Shaped {
+            value,
+            case: self.key_case,
+            curies,
+        }
+    }
+
+    /// Return a result in the selected encoding, key case and IRI form, with maps as
+    /// plain objects when returning JavaScript objects
+    pub(crate) fn output_json<T: Serialize + ?Sized>(&self, value: &T) -> Result<JsValue, JsValue> {
+        let curies = self.curies();
+        let value = self.shaped(value, curies.as_ref());
+        match self.output {
+            OutputEncoding::Object => to_js_json(&value),
+            _ => Ok(self.encode_bytes(&value)?.into()),
+        }
+    }
+
+    fn encode_bytes<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+    ) -> Result<js_sys::Uint8Array, ProcessorError> {
+        let bytes = encode(self.output, value)?;
+        Ok(js_sys::Uint8Array::from(bytes.as_slice()))
+    }
+}
+
+/// Serialize a value as JSON or CBOR
+pub(crate) fn encode<T: Serialize + ?Sized>(
+    encoding: OutputEncoding,
+    value: &T,
+) -> Result<Vec<u8>, ProcessorError> {
+    match encoding {
+        OutputEncoding::Cbor => {
+            let mut bytes = Vec::new();
+            ciborium::into_writer(value, &mut bytes).map_err(ProcessorError::serialization)?;
+            Ok(bytes)
+        }
+        _ => serde_json::to_vec(value).map_err(ProcessorError::serialization),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_encode_json_and_cbor() {
+        let value = json!({"nodes": [{"id": "sn:a", "label": "A"}], "edges": []});
+
+        let bytes = encode(OutputEncoding::Json, &value).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&bytes).unwrap(), value);
+
+        let bytes = encode(OutputEncoding::Cbor, &value).unwrap();
+        assert_eq!(
+            ciborium::from_reader::<Value, _>(bytes.as_slice()).unwrap(),
+            value
+        );
+
+        assert_eq!(OutputEncoding::parse("CBOR").unwrap(), OutputEncoding::Cbor);
+        assert!(OutputEncoding::parse("xml").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Cursor-based pagination of query results
+//!
+//! Pages are ordered by IRI, and a cursor is the IRI of the last item of the
+//! previous page. A cursor therefore stays valid when the graph changes
+//! between pages: no item is returned twice, and items added before the
+//! cursor are simply not seen.
+//!
+//! ```javascript
+//! let cursor = null;
+//! do {
+//!   const page = processor.query_constructs_page(cursor, 100);
+//!   render(page.items);
+//!   cursor = page.next_cursor;
+//! } while (cursor);
+//! ```
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// One page of query results
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Query one page of constructs
+    ///
+    /// # Arguments
+    /// * `cursor` - `next_cursor` of the previous page; `None` for the first page
+    /// * `page_size` - Maximum number of constructs on the page
+    /// * `graph` - Optional named graph to query; `None` queries the union of all graphs
+    ///
+    /// # Returns
+    /// JsValue containing {items, next_cursor}
+    #[wasm_bindgen(unchecked_return_type = "Page<ConstructData>")]
+    pub fn query_constructs_page(
+        &self,
+        cursor: Option<String>,
+        page_size: usize,
+        graph: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let constructs = self.cached(
+            |cache| &mut cache.constructs,
+            graph.clone(),
+            || self.collect_constructs(graph.as_deref()),
+        )?;
+        let page = paginate(&constructs, |c| &c.id, cursor.as_deref(), page_size)?;
+
+        self.output(&page)
+    }
+
+    /// Query one page of entanglements
+    ///
+    /// Arguments and result are as for `query_constructs_page`.
+    #[wasm_bindgen(unchecked_return_type = "Page<EntanglementData>")]
+    pub fn query_entanglements_page(
+        &self,
+        cursor: Option<String>,
+        page_size: usize,
+        graph: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let entanglements = self.cached(
+            |cache| &mut cache.entanglements,
+            graph.clone(),
+            || self.collect_entanglements(graph.as_deref()),
+        )?;
+        let page = paginate(&entanglements, |e| &e.id, cursor.as_deref(), page_size)?;
+
+        self.output(&page)
+    }
+
+    /// Query one page of characters
+    ///
+    /// Arguments and result are as for `query_constructs_page`.
+    #[wasm_bindgen(unchecked_return_type = "Page<CharacterData>")]
+    pub fn query_characters_page(
+        &self,
+        cursor: Option<String>,
+        page_size: usize,
+        graph: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let characters = self.cached(
+            |cache| &mut cache.characters,
+            graph.clone(),
+            || self.collect_characters(graph.as_deref()),
+        )?;
+        let page = paginate(&characters, |c| &c.id, cursor.as_deref(), page_size)?;
+
+        self.output(&page)
+    }
+}
+
+/// Take the page of `items`, sorted by `id`, that follows `cursor`
+pub(crate) fn paginate<T: Clone>(
+    items: &[T],
+    id: fn(&T) -> &String,
+    cursor: Option<&str>,
+    page_size: usize,
+) -> Result<Page<T>, ProcessorError> {
+    if page_size == 0 {
+        return Err(ProcessorError::invalid_argument(
+            "page_size must be at least 1",
+        ));
+    }
+
+    let start = cursor.map_or(0, |cursor| {
+        items.partition_point(|item| id(item).as_str() <= cursor)
+    });
+    let end = start.saturating_add(page_size).min(items.len());
+    let page = &items[start..end];
+
+    Ok(Page {
+        items: page.to_vec(),
+        next_cursor: (end < items.len())
+            .then(|| page.last().map(|item| id(item).clone()))
+            .flatten(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_with_cursors() {
+        let ids: Vec<String> = ["a", "b", "c", "d", "e"].map(String::from).to_vec();
+
+        let first = paginate(&ids, |id| id, None, 2).unwrap();
+        assert_eq!(first.items, ["a", "b"]);
+        assert_eq!(first.next_cursor.as_deref(), Some("b"));
+
+        // The cursor survives removal of the item it points at
+        let shrunk: Vec<String> = ["a", "c", "d", "e"].map(String::from).to_vec();
+        let second = paginate(&shrunk, |id| id, Some("b"), 2).unwrap();
+        assert_eq!(second.items, ["c", "d"]);
+
+        let last = paginate(&ids, |id| id, Some("d"), 2).unwrap();
+        assert_eq!(last.items, ["e"]);
+        assert_eq!(last.next_cursor, None);
+
+        assert!(paginate(&ids, |id| id, None, 0).is_err());
+
+        let rest = paginate(&ids, |id| id, Some("a"), usize::MAX).unwrap();
+        assert_eq!(rest.items, ["b", "c", "d", "e"]);
+        assert_eq!(rest.next_cursor, None);
+    }
+}
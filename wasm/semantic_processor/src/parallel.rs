@@ -0,0 +1,178 @@
+//! Parallel network graph edges
+//!
+//! Two entanglements between the same pair of resources are drawn on top of
+//! each other. Every edge carries its position among the edges joining its
+//! two nodes, so a renderer can bend them apart:
+//!
+//! ```javascript
+//! const bend = (edge) => (edge.parallel_index - (edge.parallel_count - 1) / 2) * 30;
+//! ```
+//!
+//! Alternatively, `set_merge_parallel_edges(true)` draws them as one edge,
+//! labelled with every relationship, whose `count` says how many it stands
+//! for and whose weight is their sum. Only edges with the same direction are
+//! merged; undirected edges are merged whichever way they were stated.
+//! `set_undirected_edges(true)` makes every edge undirected, not only those
+//! of symmetric relationships.
+
+use crate::{GraphEdge, SemanticProcessor};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose whether parallel network graph edges are merged into one
+    ///
+    /// Off by default.
+    pub fn set_merge_parallel_edges(&mut self, enabled: bool) {
+        if self.merge_parallel_edges != enabled {
+            self.merge_parallel_edges = enabled;
+            self.invalidate_cache();
+        }
+    }
+
+    /// Whether parallel network graph edges are merged into one
+    pub fn merge_parallel_edges(&self) -> bool {
+        self.merge_parallel_edges
+    }
+
+    /// Choose whether every network graph edge is marked undirected
+    ///
+    /// Off by default, when only edges of symmetric relationships are.
+    pub fn set_undirected_edges(&mut self, enabled: bool) {
+        if self.undirected_edges != enabled {
+            self.undirected_edges = enabled;
+            self.invalidate_cache();
+        }
+    }
+
+    /// Whether every network graph edge is marked undirected
+    pub fn undirected_edges(&self) -> bool {
+        self.undirected_edges
+    }
+}
+
+impl SemanticProcessor {
+    /// Whether a network graph edge is drawn with an arrow
+    pub(crate) fn edge_directed(&self, symmetric: bool) -> bool {
+        !symmetric && !self.undirected_edges
+    }
+
+    /// Merge parallel edges if the option is on, keeping the order of the
+    /// first edge of each group
+    pub(crate) fn merge_edges(&self, edges: Vec<GraphEdge>) -> Vec<GraphEdge> {
+        if !self.merge_parallel_edges {
+            return edges;
+        }
+        let mut merged: Vec<GraphEdge> = Vec::new();
+        let mut groups: HashMap<(bool, String, String), usize> = HashMap::new();
+        for edge in edges {
+            let (a, b) = ends(&edge, edge.directed);
+            let key = (edge.directed, a.to_string(), b.to_string());
+            match groups.get(&key) {
+                Some(&i) => {
+                    let group = &mut merged[i];
+                    if !group.label.split(", ").any(|label| label == edge.label) {
+                        group.label = format!("{}, {}", group.label, edge.label);
+                    }
+                    group.symmetric &= edge.symmetric;
+                    group.weight += edge.weight;
+                    group.count += edge.count;
+                }
+                None => {
+                    groups.insert(key, merged.len());
+                    merged.push(edge);
+                }
+            }
+        }
+        merged
+    }
+}
+
+/// Number the edges joining each pair of nodes, in the order they come
+pub(crate) fn index_parallel_edges(edges: &mut [GraphEdge]) {
+    let mut counts: HashMap<(String, String), u32> = HashMap::new();
+    for edge in edges.iter_mut() {
+        let (a, b) = ends(edge, false);
+        let count = counts.entry((a.to_string(), b.to_string())).or_default();
+        edge.parallel_index = *count;
+        *count += 1;
+    }
+    for edge in edges.iter_mut() {
+        let (a, b) = ends(edge, false);
+        edge.parallel_count = counts[&(a.to_string(), b.to_string())];
+    }
+}
+
+/// The ends of an edge, in order if it is directed and sorted if not
+fn ends(edge: &GraphEdge, directed: bool) -> (&str, &str) {
+    if directed || edge.source <= edge.target {
+        (&edge.source, &edge.target)
+    } else {
+        (&edge.target, &edge.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        sn:e1 a sn:Entanglement ; sn:hasSource sn:a ; sn:hasTarget sn:b ;
+            sn:relationshipType "feeds" ; sn:weight 2 .
+        sn:e2 a sn:Entanglement ; sn:hasSource sn:a ; sn:hasTarget sn:b ;
+            sn:relationshipType "opposes" .
+        sn:e3 a sn:Entanglement ; sn:hasSource sn:b ; sn:hasTarget sn:a ;
+            sn:relationshipType "feeds" .
+    "#;
+
+    fn summary(edges: &[GraphEdge]) -> Vec<(String, u32, u32, u32)> {
+        edges
+            .iter()
+            .map(|e| (e.label.clone(), e.count, e.parallel_index, e.parallel_count))
+            .collect()
+    }
+
+    #[test]
+    fn test_parallel_edges_are_indexed() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        let graph = processor.collect_network_graph().unwrap();
+        assert_eq!(
+            summary(&graph.edges),
+            [
+                ("feeds".to_string(), 1, 0, 3),
+                ("opposes".to_string(), 1, 1, 3),
+                ("feeds".to_string(), 1, 2, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parallel_edges_are_merged_by_direction() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        processor.set_merge_parallel_edges(true);
+
+        let graph = processor.collect_network_graph().unwrap();
+        assert_eq!(
+            summary(&graph.edges),
+            [
+                ("feeds, opposes".to_string(), 2, 0, 2),
+                ("feeds".to_string(), 1, 1, 2),
+            ]
+        );
+        assert_eq!(graph.edges[0].weight, 3.0);
+        let export = processor.collect_export().unwrap();
+        assert_eq!(summary(&export.graph.edges), summary(&graph.edges));
+
+        processor.set_undirected_edges(true);
+        let graph = processor.collect_network_graph().unwrap();
+        assert_eq!(
+            summary(&graph.edges),
+            [("feeds, opposes".to_string(), 3, 0, 1)]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! RDF Patch support for the Semantic Processor
+//!
+//! Applies deltas in the RDF Patch format (<https://afs.github.io/rdf-patch/>):
+//!
+//! ```text
+//! H id <uuid:0686c69d-8f89-4496-acb5-744f0157a8db> .
+//! TX .
+//! PA sn: <https://sinople.org/ontology#> .
+//! A sn:entropy sn:entangledWith sn:memory .
+//! D sn:entropy rdfs:label "Old label" .
+//! TC .
+//! ```
+//!
+//! Changes between `TX` and `TA` are discarded. The whole patch is parsed
+//! before anything is applied, so a malformed patch leaves the graph untouched.
+
+use crate::alias::Aliases;
+use crate::error::ProcessorError;
+use crate::sparql::{self, UpdateSummary};
+use crate::SemanticProcessor;
+use sophia_api::graph::Graph;
+use sophia_api::term::{SimpleTerm, Term};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+type Triple = [SimpleTerm<'static>; 3];
+
+/// One change of an RDF Patch
+#[derive(Debug, Clone)]
+pub(crate) enum PatchChange {
+    Add(Triple, Option<String>),
+    Delete(Triple, Option<String>),
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Apply a patch in RDF Patch format
+    ///
+    /// Supports `A` (add) and `D` (delete) rows, optionally with a graph name,
+    /// `PA`/`PD` prefix rows, `H` headers and `TX`/`TC`/`TA` transactions.
+    ///
+    /// # Arguments
+    /// * `patch` - RDF Patch document
+    ///
+    /// # Returns
+    /// JsValue containing the number of triples inserted and deleted
+    #[wasm_bindgen(unchecked_return_type = "UpdateSummary")]
+    pub fn apply_patch(&mut self, patch: &str) -> Result<JsValue, JsValue> {
+        let changes = parse_patch(patch, &self.namespaces, &self.aliases)
+            .map_err(|e| ProcessorError::parse("RDF Patch", e))?;
+        let summary = self.apply_patch_changes(&changes);
+        self.finish_edit();
+
+        self.output(&summary)
+    }
+}
+
+impl SemanticProcessor {
+    /// Apply parsed patch changes in order
+    pub(crate) fn apply_patch_changes(&mut self, changes: &[PatchChange]) -> UpdateSummary {
+        let mut summary = UpdateSummary::default();
+
+        for change in changes {
+            match change {
+                PatchChange::Add(triple, graph) => {
+                    if let Some(name) = graph {
+                        self.insert_in(Some(name), triple);
+                    }
+                    if self.add_terms(triple) {
+                        summary.inserted += 1;
+                    }
+                }
+                PatchChange::Delete(triple, None) => {
+                    if self.remove_terms(triple) {
+                        summary.deleted += 1;
+                    }
+                }
+                PatchChange::Delete(triple, Some(name)) => {
+                    let [s, p, o] = triple;
+                    self.remove_in(Some(name), triple);
+                    // Keep the triple in the union while another named graph holds it
+                    let elsewhere = self
+                        .named_graphs
+                        .values()
+                        .any(|named| named.contains(s, p, o).unwrap_or(false));
+                    if !elsewhere && self.remove_terms(triple) {
+                        summary.deleted += 1;
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+}
+
+/// Parse an RDF Patch document into the changes it commits
+pub(crate) fn parse_patch(
+    input: &str,
+    namespaces: &HashMap<String, String>,
+    aliases: &Aliases,
+) -> Result<Vec<PatchChange>, String> {
+    let mut prefixes = namespaces.clone();
+    let mut committed = Vec::new();
+    let mut transaction: Option<Vec<PatchChange>> = None;
+
+    for (number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let at_line = |e: String| format!("line {}: {}", number + 1, e);
+
+        let body = line
+            .strip_suffix('.')
+            .ok_or_else(|| at_line("row must end with '.'".to_string()))?
+            .trim();
+        let (code, rest) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+        let rest = rest.trim();
+
+        match code {
+            "H" => {}
+            "TX" => {
+                if transaction.is_some() {
+                    return Err(at_line("nested TX".to_string()));
+                }
+                transaction = Some(Vec::new());
+            }
+            "TC" => {
+                let changes = transaction
+                    .take()
+                    .ok_or_else(|| at_line("TC without TX".to_string()))?;
+                committed.extend(changes);
+            }
+            "TA" => {
+                transaction
+                    .take()
+                    .ok_or_else(|| at_line("TA without TX".to_string()))?;
+            }
+            "PA" => {
+                let (prefix, iri) = rest
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| at_line("PA needs a prefix and an IRI".to_string()))?;
+                let prefix = prefix.trim_end_matches(':');
+                let iri = iri
+                    .trim()
+                    .strip_prefix('<')
+                    .and_then(|iri| iri.strip_suffix('>'))
+                    .ok_or_else(|| at_line(format!("invalid namespace IRI {}", iri.trim())))?;
+                prefixes.insert(prefix.to_string(), iri.to_string());
+            }
+            "PD" => {
+                prefixes.remove(rest.trim_end_matches(':'));
+            }
+            "A" | "D" => {
+                let mut terms =
+                    sparql::parse_term_list(rest, &prefixes, aliases).map_err(at_line)?;
+
+                let graph = match terms.len() {
+                    3 => None,
+                    4 => terms.pop().map(|g| graph_name(&g)),
+                    n => return Err(at_line(format!("expected 3 or 4 terms, found {}", n))),
+                };
+                let [s, p, o]: Triple = terms
+                    .try_into()
+                    .map_err(|_| at_line("expected 3 terms".to_string()))?;
+
+                let change = if code == "A" {
+                    PatchChange::Add([s, p, o], graph)
+                } else {
+                    PatchChange::Delete([s, p, o], graph)
+                };
+                match transaction.as_mut() {
+                    Some(changes) => changes.push(change),
+                    None => committed.push(change),
+                }
+            }
+            other => return Err(at_line(format!("unknown row code {}", other))),
+        }
+    }
+
+    if transaction.is_some() {
+        return Err("unterminated transaction: missing TC or TA".to_string());
+    }
+    Ok(committed)
+}
+
+/// Key of a named graph, as used by `named_graphs`
+fn graph_name(term: &SimpleTerm) -> String {
+    match term {
+        SimpleTerm::BlankNode(bn) => format!("_:{}", bn.as_str()),
+        other => other
+            .iri()
+            .map(|iri| iri.as_str().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_patch_with_transactions() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                "<https://sinople.org/ontology#a> <https://sinople.org/ontology#label> \"Old\" .",
+                None,
+            )
+            .unwrap();
+
+        let patch = r#"
+            H id <uuid:0686c69d-8f89-4496-acb5-744f0157a8db> .
+            TX .
+            PA ex: <https://example.org/> .
+            A sn:a sn:label "New" .
+            D sn:a sn:label "Old" .
+            A ex:a ex:p <_:b1> <https://example.org/g> .
+            TC .
+            TX .
+            A sn:a sn:label "Discarded" .
+            TA .
+        "#;
+        let changes = parse_patch(patch, &processor.namespaces, &processor.aliases).unwrap();
+        let summary = processor.apply_patch_changes(&changes);
+
+        assert_eq!(
+            summary,
+            UpdateSummary {
+                inserted: 2,
+                deleted: 1
+            }
+        );
+        assert_eq!(processor.triple_count(), 2);
+        assert!(processor.named_graphs.contains_key("https://example.org/g"));
+    }
+
+    #[test]
+    fn test_malformed_patch_is_rejected() {
+        let namespaces = HashMap::new();
+        let aliases = Aliases::new(&namespaces);
+        let parse = |patch| parse_patch(patch, &namespaces, &aliases);
+        assert!(parse("A <a> <b> .\nTX .").is_err());
+        assert!(parse("A <a> <b> <c>").is_err());
+        assert!(parse("X <a> <b> <c> .").is_err());
+    }
+}
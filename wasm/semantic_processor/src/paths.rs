@@ -0,0 +1,360 @@
+//! Paths between resources
+//!
+//! "How are X and Y related?" is answered by the shortest chain of network
+//! edges from one to the other, where an edge of weight `w` is `1 / w` long,
+//! so strong relationships make short paths. Edges are followed in either
+//! direction and keep their own, so the widget can draw the arrows as stored:
+//!
+//! ```javascript
+//! const path = processor.shortest_path('sn:time', 'sn:memory');
+//! if (path) path.edges.forEach(e => console.log(e.source, e.label, e.target));
+//! ```
+//!
+//! `all_paths` lists every other line of connection too, up to a length:
+//!
+//! ```javascript
+//! for (const path of processor.all_paths('sn:mira', 'sn:ilse', 4)) drawPath(path);
+//! ```
+
+use crate::error::ProcessorError;
+use crate::{GraphEdge, SemanticProcessor};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use wasm_bindgen::prelude::*;
+
+/// A chain of edges between two resources
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphPath {
+    /// The resources along the path, from first to last
+    pub nodes: Vec<String>,
+    /// The edge between each resource and the next
+    pub edges: Vec<GraphEdge>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Find the shortest chain of entanglements and relationships between
+    /// two resources
+    ///
+    /// Each edge is as long as the inverse of its weight; without weights
+    /// this is the chain with the fewest edges.
+    ///
+    /// # Arguments
+    /// * `from_iri` - First resource as `prefix:local` or full IRI
+    /// * `to_iri` - Last resource as `prefix:local` or full IRI
+    ///
+    /// # Returns
+    /// JsValue containing {nodes, edges}, or `null` if they are not connected
+    #[wasm_bindgen(unchecked_return_type = "GraphPath | null")]
+    pub fn shortest_path(&self, from_iri: &str, to_iri: &str) -> Result<JsValue, JsValue> {
+        let path = self.path_between(from_iri, to_iri)?;
+
+        self.output(&path)
+    }
+
+    /// Find every path between two resources that visits no resource twice
+    ///
+    /// # Arguments
+    /// * `from` - First resource as `prefix:local` or full IRI
+    /// * `to` - Last resource as `prefix:local` or full IRI
+    /// * `max_depth` - Most edges a path may have
+    ///
+    /// # Returns
+    /// JsValue containing array of {nodes, edges}, shortest first
+    #[wasm_bindgen(unchecked_return_type = "GraphPath[]")]
+    pub fn all_paths(&self, from: &str, to: &str, max_depth: u32) -> Result<JsValue, JsValue> {
+        let paths = self.paths_between(from, to, max_depth as usize)?;
+
+        self.output(&paths)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn path_between(
+        &self,
+        from: &str,
+        to: &str,
+    ) -> Result<Option<GraphPath>, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let from = self.canonical_iri(from);
+        let to = self.canonical_iri(to);
+
+        let neighbours = neighbours(&network.edges);
+        if from == to {
+            return Ok(neighbours.contains_key(from.as_str()).then(|| GraphPath {
+                nodes: vec![from.clone()],
+                edges: Vec::new(),
+            }));
+        }
+
+        // Dijkstra's algorithm, settling the nearest unsettled resource first
+        let mut distance: HashMap<&str, f64> = HashMap::from([(from.as_str(), 0.0)]);
+        let mut previous: HashMap<&str, (&str, usize)> = HashMap::new();
+        let mut queue = BinaryHeap::from([Visit {
+            distance: 0.0,
+            node: from.as_str(),
+        }]);
+        while let Some(Visit {
+            distance: reached,
+            node,
+        }) = queue.pop()
+        {
+            if reached > distance[node] {
+                continue;
+            }
+            if node == to {
+                let mut nodes = vec![to.clone()];
+                let mut edges = Vec::new();
+                let mut current = node;
+                while let Some(&(before, edge)) = previous.get(current) {
+                    nodes.push(before.to_string());
+                    edges.push(network.edges[edge].clone());
+                    current = before;
+                }
+                nodes.reverse();
+                edges.reverse();
+                return Ok(Some(GraphPath { nodes, edges }));
+            }
+            for &(next, edge) in neighbours.get(node).into_iter().flatten() {
+                let length = reached + 1.0 / network.edges[edge].weight;
+                if distance.get(next).is_none_or(|known| length < *known) {
+                    distance.insert(next, length);
+                    previous.insert(next, (node, edge));
+                    queue.push(Visit {
+                        distance: length,
+                        node: next,
+                    });
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn paths_between(
+        &self,
+        from: &str,
+        to: &str,
+        max_depth: usize,
+    ) -> Result<Vec<GraphPath>, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let from = self.canonical_iri(from);
+        let to = self.canonical_iri(to);
+        let neighbours = neighbours(&network.edges);
+
+        let mut paths = Vec::new();
+        if max_depth == 0 || from == to || !neighbours.contains_key(from.as_str()) {
+            return Ok(paths);
+        }
+        // Depth-first, each frame a node and the position of its next neighbour
+        let mut nodes = vec![from.as_str()];
+        let mut edges: Vec<usize> = Vec::new();
+        let mut frames = vec![0];
+        while let Some(position) = frames.last_mut() {
+            let node = nodes[nodes.len() - 1];
+            let Some(&(next, edge)) = neighbours[node].get(*position) else {
+                frames.pop();
+                nodes.pop();
+                edges.pop();
+                continue;
+            };
+            *position += 1;
+            if nodes.contains(&next) {
+                continue;
+            }
+            if next == to {
+                self.check_abort()?;
+                paths.push(GraphPath {
+                    nodes: nodes.iter().chain([&next]).map(|n| n.to_string()).collect(),
+                    edges: edges
+                        .iter()
+                        .chain([&edge])
+                        .map(|&e| network.edges[e].clone())
+                        .collect(),
+                });
+            } else if edges.len() + 1 < max_depth {
+                nodes.push(next);
+                edges.push(edge);
+                frames.push(0);
+            }
+        }
+        paths.sort_by(|a, b| (a.edges.len(), &a.nodes).cmp(&(b.edges.len(), &b.nodes)));
+        Ok(paths)
+    }
+}
+
+/// A resource waiting in Dijkstra's queue, nearest first, then by IRI
+struct Visit<'a> {
+    distance: f64,
+    node: &'a str,
+}
+
+impl Ord for Visit<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.node.cmp(self.node))
+    }
+}
+
+impl PartialOrd for Visit<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Visit<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Visit<'_> {}
+
+/// The edges at each resource, with the resource at their other end
+///
+/// Neighbours are sorted so that ties resolve the same way every time.
+pub(crate) fn neighbours(edges: &[GraphEdge]) -> BTreeMap<&str, Vec<(&str, usize)>> {
+    let mut neighbours: BTreeMap<&str, Vec<(&str, usize)>> = BTreeMap::new();
+    for (i, edge) in edges.iter().enumerate() {
+        neighbours
+            .entry(&edge.source)
+            .or_default()
+            .push((&edge.target, i));
+        neighbours
+            .entry(&edge.target)
+            .or_default()
+            .push((&edge.source, i));
+    }
+    for adjacent in neighbours.values_mut() {
+        adjacent.sort();
+    }
+    neighbours
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortest_path_follows_edges_both_ways() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:time a sn:Construct .
+                sn:space a sn:Construct .
+                sn:memory a sn:Construct .
+                sn:void a sn:Construct .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:time ; sn:hasTarget sn:space ;
+                    sn:relationshipType "bends" .
+                sn:e2 a sn:Entanglement ; sn:hasSource sn:memory ; sn:hasTarget sn:space .
+                sn:e3 a sn:Entanglement ; sn:hasSource sn:memory ; sn:hasTarget sn:void .
+                sn:time sn:relatesTo sn:void .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let local = |iri: &str| {
+            iri.trim_start_matches("https://sinople.org/ontology#")
+                .to_string()
+        };
+        let path = processor
+            .path_between("sn:space", "sn:void")
+            .unwrap()
+            .unwrap();
+        let nodes: Vec<String> = path.nodes.iter().map(|n| local(n)).collect();
+        assert_eq!(nodes, ["space", "memory", "void"]);
+        assert_eq!(local(&path.edges[0].source), "memory");
+        assert_eq!(path.edges[1].label, "related");
+
+        let path = processor
+            .path_between("sn:time", "sn:time")
+            .unwrap()
+            .unwrap();
+        assert!(path.edges.is_empty());
+        assert!(processor
+            .path_between("sn:time", "sn:nowhere")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_shortest_path_prefers_strong_edges() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:a ; sn:hasTarget sn:d ;
+                    sn:weight 0.4 .
+                sn:e2 a sn:Entanglement ; sn:hasSource sn:a ; sn:hasTarget sn:b ;
+                    sn:weight 2 .
+                sn:e3 a sn:Entanglement ; sn:hasSource sn:b ; sn:hasTarget sn:d ;
+                    sn:weight "strong" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        // Through sn:b is 0.5 + 1 long, the direct edge 2.5
+        let path = processor.path_between("sn:a", "sn:d").unwrap().unwrap();
+        let weights: Vec<f64> = path.edges.iter().map(|edge| edge.weight).collect();
+        assert_eq!(weights, [2.0, 1.0]);
+
+        let export = processor.collect_export().unwrap();
+        let mut weights: Vec<f64> = export.graph.edges.iter().map(|e| e.weight).collect();
+        weights.sort_by(f64::total_cmp);
+        assert_eq!(weights, [0.4, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_all_paths_up_to_a_depth() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:mira sn:relatesTo sn:ilse, sn:otto, sn:kai .
+                sn:otto sn:relatesTo sn:ilse .
+                sn:kai sn:relatesTo sn:otto .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:ilse ; sn:hasTarget sn:mira .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let summary = |depth| -> Vec<String> {
+            processor
+                .paths_between("sn:mira", "sn:ilse", depth)
+                .unwrap()
+                .iter()
+                .map(|path| {
+                    let nodes: Vec<&str> = path
+                        .nodes
+                        .iter()
+                        .map(|n| n.trim_start_matches("https://sinople.org/ontology#"))
+                        .collect();
+                    nodes.join(" ")
+                })
+                .collect()
+        };
+        // The entanglement and the relationship are two paths of one edge
+        assert_eq!(summary(1), ["mira ilse", "mira ilse"]);
+        assert_eq!(summary(2), ["mira ilse", "mira ilse", "mira otto ilse"]);
+        assert_eq!(summary(3).len(), 4);
+        assert_eq!(summary(3)[3], "mira kai otto ilse");
+        assert!(summary(0).is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+//! Places on a map
+//!
+//! Locations are instances of `sn:Place`, positioned with the W3C Basic
+//! Geo vocabulary's `geo:lat` and `geo:long`. `query_places` lists them like
+//! the other `query_*` methods, with the constructs, events and characters
+//! linked to each in either direction:
+//!
+//! ```javascript
+//! for (const place of processor.query_places(null, { sort: 'label' })) {
+//!   console.log(place.label, place.latitude, place.longitude, place.events);
+//! }
+//! ```
+//!
+//! `to_geojson` returns the places with coordinates as a GeoJSON
+//! FeatureCollection of points, ready for Leaflet:
+//!
+//! ```javascript
+//! L.geoJSON(processor.to_geojson(), {
+//!   onEachFeature: (feature, layer) => layer.bindPopup(feature.properties.label),
+//! }).addTo(map);
+//! ```
+//!
+//! Each feature's properties hold the place's label and linked constructs.
+//! Places without a valid latitude and longitude are left out. As GeoJSON
+//! has a fixed schema, key case and CURIE compaction do not apply to it.
+
+use crate::error::{to_js_json, ProcessorError};
+use crate::options::{Labelled, QueryOptions};
+use crate::SemanticProcessor;
+use serde::{Deserialize, Serialize};
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::Term;
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::{BTreeSet, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// Latitude in the W3C Basic Geo vocabulary
+const GEO_LAT: &str = "http://www.w3.org/2003/01/geo/wgs84_pos#lat";
+
+/// Longitude in the W3C Basic Geo vocabulary
+const GEO_LONG: &str = "http://www.w3.org/2003/01/geo/wgs84_pos#long";
+
+/// A location
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub id: String,
+    pub label: String,
+    pub description: Option<String>,
+    /// `geo:lat` in degrees; only given along with a valid longitude
+    pub latitude: Option<f64>,
+    /// `geo:long` in degrees; only given along with a valid latitude
+    pub longitude: Option<f64>,
+    /// IRIs of the constructs linked to the place, sorted
+    pub constructs: Vec<String>,
+    /// IRIs of the events and scenes linked to the place, sorted
+    pub events: Vec<String>,
+    /// IRIs of the characters linked to the place, sorted
+    pub characters: Vec<String>,
+}
+
+impl Labelled for Place {
+    fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// Places as a GeoJSON FeatureCollection
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeatureCollection {
+    /// Always `FeatureCollection`
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub features: Vec<Feature>,
+}
+
+/// One place as a GeoJSON point feature
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Feature {
+    /// Always `Feature`
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// IRI of the place
+    pub id: String,
+    pub geometry: Point,
+    pub properties: PlaceProperties,
+}
+
+/// A GeoJSON point
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Point {
+    /// Always `Point`
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// Longitude and latitude, in that order
+    pub coordinates: [f64; 2],
+}
+
+/// What a map shows about a place
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlaceProperties {
+    pub label: String,
+    /// IRIs of the constructs linked to the place, sorted
+    pub constructs: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Query all places from the graph
+    ///
+    /// # Arguments
+    /// * `graph` - Optional named graph to query; `None` queries the union of all graphs
+    /// * `options` - Optional `QueryOptions` object (limit, offset, sort, order, filter)
+    ///
+    /// # Returns
+    /// JsValue containing array of Place objects
+    #[wasm_bindgen(unchecked_return_type = "PlaceData[]")]
+    pub fn query_places(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let places = self.cached(
+            |cache| &mut cache.places,
+            graph.clone(),
+            || self.collect_places(graph.as_deref()),
+        )?;
+
+        if options == QueryOptions::default() {
+            return self.output(&*places);
+        }
+        self.output(&options.apply(&places))
+    }
+
+    /// Export the places with coordinates as a GeoJSON FeatureCollection
+    ///
+    /// # Returns
+    /// JsValue containing a FeatureCollection of point features, sorted by
+    /// IRI, with `label` and `constructs` properties
+    #[wasm_bindgen(unchecked_return_type = "FeatureCollection")]
+    pub fn to_geojson(&self) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let collection = self.geojson()?;
+
+        to_js_json(&collection)
+    }
+}
+
+impl SemanticProcessor {
+    /// Build the result of `query_places`
+    pub(crate) fn collect_places(&self, graph: Option<&str>) -> Result<Vec<Place>, ProcessorError> {
+        let graph = self.query_graph(graph)?;
+        let graph: &FastGraph = &graph;
+        let instances = |classes: &[&str]| -> HashSet<String> {
+            classes
+                .iter()
+                .flat_map(|class| self.instances_of(graph, class))
+                .map(|iri| iri.to_string())
+                .collect()
+        };
+        let constructs = instances(&["sn:Construct"]);
+        let events = instances(&["sn:Event", "sn:Scene"]);
+        let characters = instances(&["sn:Character"]);
+
+        let mut places = Vec::new();
+        for place in self.instances_of(graph, "sn:Place") {
+            let coordinates = self.coordinates(graph, &place);
+            let linked = self.linked(graph, &place);
+            let among = |kind: &HashSet<String>| -> Vec<String> {
+                linked
+                    .iter()
+                    .filter(|iri| kind.contains(*iri))
+                    .cloned()
+                    .collect()
+            };
+            places.push(Place {
+                id: place.to_string(),
+                label: self
+                    .get_object_value(graph, &place, "rdfs:label")
+                    .unwrap_or_else(|| self.extract_local_name(&place)),
+                description: self.get_object_value(graph, &place, "rdfs:comment"),
+                latitude: coordinates.map(|(latitude, _)| latitude),
+                longitude: coordinates.map(|(_, longitude)| longitude),
+                constructs: among(&constructs),
+                events: among(&events),
+                characters: among(&characters),
+            });
+        }
+        Ok(places)
+    }
+
+    pub(crate) fn geojson(&self) -> Result<FeatureCollection, ProcessorError> {
+        let places = self.cached(
+            |cache| &mut cache.places,
+            None,
+            || self.collect_places(None),
+        )?;
+        let features = places
+            .iter()
+            .filter_map(|place| {
+                Some(Feature {
+                    kind: "Feature",
+                    id: place.id.clone(),
+                    geometry: Point {
+                        kind: "Point",
+                        coordinates: [place.longitude?, place.latitude?],
+                    },
+                    properties: PlaceProperties {
+                        label: place.label.clone(),
+                        constructs: place.constructs.clone(),
+                    },
+                })
+            })
+            .collect();
+
+        Ok(FeatureCollection {
+            kind: "FeatureCollection",
+            features,
+        })
+    }
+
+    /// The latitude and longitude of a resource, if both are valid
+    pub(crate) fn coordinates(&self, graph: &FastGraph, iri: &str) -> Option<(f64, f64)> {
+        let degrees = |property: &str, limit: f64| {
+            self.get_object_value(graph, iri, property)?
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.abs() <= limit)
+        };
+        Some((degrees(GEO_LAT, 90.0)?, degrees(GEO_LONG, 180.0)?))
+    }
+
+    /// The IRIs a resource points at or is pointed at from, sorted
+    pub(crate) fn linked(&self, graph: &FastGraph, iri: &str) -> BTreeSet<String> {
+        let term = self.iri_term(iri);
+        let outgoing = graph
+            .triples_matching([&term], Any, Any)
+            .flatten()
+            .filter(|t| t.o().is_iri())
+            .map(|t| self.term_to_string(t.o()));
+        let incoming = graph
+            .triples_matching(Any, Any, [&term])
+            .flatten()
+            .filter(|t| t.s().is_iri())
+            .map(|t| self.term_to_string(t.s()));
+        outgoing
+            .chain(incoming)
+            .filter(|other| other != iri)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geojson_points_for_places() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                @prefix geo: <http://www.w3.org/2003/01/geo/wgs84_pos#> .
+                sn:harbour a sn:Place ; rdfs:label "Harbour" ;
+                    geo:lat "51.45" ; geo:long -2.59 ; sn:hasConstruct sn:tide .
+                sn:tide a sn:Construct .
+                sn:longing a sn:Construct ; sn:relatesTo sn:harbour .
+                sn:mira a sn:Character ; sn:relatesTo sn:harbour .
+                sn:atlantis a sn:Place ; geo:lat 95 ; geo:long 0 .
+                sn:nowhere a sn:Place .
+                sn:storm a sn:Scene ; sn:locatedIn sn:harbour .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let collection = processor.geojson().unwrap();
+        let json = serde_json::to_value(&collection).unwrap();
+        assert_eq!(json["type"], "FeatureCollection");
+        assert_eq!(json["features"].as_array().unwrap().len(), 1);
+        let harbour = &json["features"][0];
+        assert_eq!(harbour["type"], "Feature");
+        assert_eq!(harbour["geometry"]["type"], "Point");
+        assert_eq!(
+            harbour["geometry"]["coordinates"],
+            serde_json::json!([-2.59, 51.45])
+        );
+        assert_eq!(harbour["properties"]["label"], "Harbour");
+        assert_eq!(
+            harbour["properties"]["constructs"],
+            serde_json::json!([
+                "https://sinople.org/ontology#longing",
+                "https://sinople.org/ontology#tide"
+            ])
+        );
+
+        let places = processor.collect_places(None).unwrap();
+        assert_eq!(places.len(), 3);
+        let harbour = &places[1];
+        assert_eq!(
+            (harbour.latitude, harbour.longitude),
+            (Some(51.45), Some(-2.59))
+        );
+        assert_eq!(harbour.characters, ["https://sinople.org/ontology#mira"]);
+        assert_eq!(harbour.events, ["https://sinople.org/ontology#storm"]);
+        assert_eq!(places[0].label, "atlantis");
+        assert_eq!(places[0].latitude, None);
+    }
+}
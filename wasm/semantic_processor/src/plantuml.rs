@@ -0,0 +1,197 @@
+//! PlantUML class diagrams of the ontology schema
+//!
+//! Documentation pages show how the classes of the ontology fit together.
+//! `schema_to_plantuml` draws the classes and properties the ontology
+//! declares, leaving out its instances:
+//!
+//! ```javascript
+//! const source = processor.schema_to_plantuml();
+//! img.src = `https://www.plantuml.com/plantuml/svg/${plantumlEncoder.encode(source)}`;
+//! ```
+//!
+//! Classes are those declared `owl:Class` or `rdfs:Class` or named by
+//! `rdfs:subClassOf`, `rdfs:domain` or `rdfs:range`. A property with a
+//! datatype or nothing as range becomes an attribute of its domain; any
+//! other property becomes an association from its domain to its range.
+//! Properties without a domain are left out. Names are written as CURIEs
+//! where a namespace is registered.
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::triple::Triple;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Namespace of the XML Schema datatypes
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Datatypes outside the XML Schema namespace
+const DATATYPES: [&str; 3] = [
+    "http://www.w3.org/2000/01/rdf-schema#Literal",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#HTML",
+];
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Draw the classes and properties of the ontology as a PlantUML class
+    /// diagram
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the diagram, from `@startuml` to `@enduml`
+    /// * `Err(JsValue)` with error message if the graph cannot be read
+    pub fn schema_to_plantuml(&self) -> Result<String, JsValue> {
+        self.check_abort()?;
+        Ok(self.plantuml_schema()?)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn plantuml_schema(&self) -> Result<String, ProcessorError> {
+        let graph = self.query_graph(None)?;
+        let objects = |predicate: &str| {
+            let predicate = self.make_term(predicate);
+            graph
+                .triples_matching(Any, [&predicate], Any)
+                .flatten()
+                .map(|t| (self.term_to_string(t.s()), self.term_to_string(t.o())))
+                .filter(|(s, o)| !s.starts_with("_:") && !o.starts_with("_:"))
+                .collect::<BTreeSet<(String, String)>>()
+        };
+
+        let mut classes: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (class, kind) in objects("rdf:type") {
+            if kind == self.expand("owl:Class") || kind == self.expand("rdfs:Class") {
+                classes.entry(class).or_default();
+            }
+        }
+        let subclasses = objects("rdfs:subClassOf");
+        for (child, parent) in &subclasses {
+            classes.entry(child.clone()).or_default();
+            classes.entry(parent.clone()).or_default();
+        }
+
+        let mut ranges: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (property, range) in objects("rdfs:range") {
+            ranges.entry(property).or_default().push(range);
+        }
+        let mut associations = BTreeSet::new();
+        for (property, domain) in objects("rdfs:domain") {
+            let name = self.compact(&property);
+            let property_ranges = ranges.get(&property).map(Vec::as_slice).unwrap_or(&[]);
+            if property_ranges.is_empty() {
+                classes
+                    .entry(domain.clone())
+                    .or_default()
+                    .insert(name.clone());
+            }
+            for range in property_ranges {
+                if is_datatype(range) {
+                    let attribute = format!("{} : {}", name, self.compact(range));
+                    classes.entry(domain.clone()).or_default().insert(attribute);
+                } else {
+                    classes.entry(range.clone()).or_default();
+                    associations.insert((domain.clone(), range.clone(), name.clone()));
+                }
+            }
+            classes.entry(domain).or_default();
+        }
+        classes.retain(|class, _| !is_datatype(class));
+
+        let mut out = String::from("@startuml\nhide empty members\n\n");
+        for (class, attributes) in &classes {
+            let _ = write!(
+                out,
+                "class \"{}\" as {}",
+                self.compact(class),
+                alias(&self.compact(class))
+            );
+            if attributes.is_empty() {
+                out.push('\n');
+                continue;
+            }
+            out.push_str(" {\n");
+            for attribute in attributes {
+                let _ = writeln!(out, "  {}", attribute);
+            }
+            out.push_str("}\n");
+        }
+        if !subclasses.is_empty() || !associations.is_empty() {
+            out.push('\n');
+        }
+        for (child, parent) in &subclasses {
+            let _ = writeln!(
+                out,
+                "{} <|-- {}",
+                alias(&self.compact(parent)),
+                alias(&self.compact(child))
+            );
+        }
+        for (domain, range, name) in &associations {
+            let _ = writeln!(
+                out,
+                "{} --> {} : {}",
+                alias(&self.compact(domain)),
+                alias(&self.compact(range)),
+                name
+            );
+        }
+        out.push_str("@enduml\n");
+        Ok(out)
+    }
+}
+
+/// Whether an IRI names a datatype rather than a class
+fn is_datatype(iri: &str) -> bool {
+    iri.starts_with(XSD) || DATATYPES.contains(&iri)
+}
+
+/// A PlantUML identifier for a class, with every character that may not
+/// appear in one replaced by `_`
+fn alias(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_diagram() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                @prefix owl: <http://www.w3.org/2002/07/owl#> .
+                @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+                sn:Construct a owl:Class .
+                sn:Character rdfs:subClassOf sn:Construct .
+                sn:weight rdfs:domain sn:Entanglement ; rdfs:range xsd:decimal .
+                sn:hasSource rdfs:domain sn:Entanglement ; rdfs:range sn:Construct .
+                sn:entropy a sn:Construct ; sn:relatesTo sn:time .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let diagram = processor.plantuml_schema().unwrap();
+        assert_eq!(
+            diagram,
+            "@startuml\nhide empty members\n\n\
+             class \"sn:Character\" as sn_Character\n\
+             class \"sn:Construct\" as sn_Construct\n\
+             class \"sn:Entanglement\" as sn_Entanglement {\n  sn:weight : xsd:decimal\n}\n\
+             \n\
+             sn_Construct <|-- sn_Character\n\
+             sn_Entanglement --> sn_Construct : sn:hasSource\n\
+             @enduml\n"
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Load progress reporting
+//!
+//! Parsers read their input through a [`ProgressReader`], which reports how
+//! much of it has been consumed so the theme can render a progress bar:
+//!
+//! ```javascript
+//! processor.load_turtle(ttl, (percent, bytes) => bar.value = percent);
+//! ```
+
+use std::cell::Cell;
+use std::io::{self, BufRead, Read};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Size of the slices handed to the parser
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Reader over an in-memory document that reports progress as it is consumed
+///
+/// The callback receives the percentage and number of bytes consumed. It is
+/// only called when the percentage changes, so at most 101 times. Reading
+/// fails once the abort flag, if any, is set.
+pub(crate) struct ProgressReader<'a, F: FnMut(u32, usize)> {
+    data: &'a [u8],
+    position: usize,
+    reported: Option<u32>,
+    on_progress: F,
+    abort: Option<Rc<Cell<bool>>>,
+}
+
+impl<'a, F: FnMut(u32, usize)> ProgressReader<'a, F> {
+    pub(crate) fn new(data: &'a [u8], on_progress: F) -> Self {
+        ProgressReader {
+            data,
+            position: 0,
+            reported: None,
+            on_progress,
+            abort: None,
+        }
+    }
+
+    /// Stop reading once `flag` is set
+    pub(crate) fn with_abort_flag(mut self, flag: &Rc<Cell<bool>>) -> Self {
+        self.abort = Some(Rc::clone(flag));
+        self
+    }
+
+    fn report(&mut self) {
+        let percent = if self.data.is_empty() {
+            100
+        } else {
+            (self.position * 100 / self.data.len()) as u32
+        };
+        if self.reported != Some(percent) {
+            self.reported = Some(percent);
+            (self.on_progress)(percent, self.position);
+        }
+    }
+}
+
+impl<F: FnMut(u32, usize)> Read for ProgressReader<'_, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+        Ok(count)
+    }
+}
+
+impl<F: FnMut(u32, usize)> BufRead for ProgressReader<'_, F> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.abort.as_ref().is_some_and(|flag| flag.get()) {
+            return Err(io::Error::other("aborted"));
+        }
+        if self.reported.is_none() {
+            self.report();
+        }
+        let end = (self.position + CHUNK_SIZE).min(self.data.len());
+        Ok(&self.data[self.position..end])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position = (self.position + amount).min(self.data.len());
+        self.report();
+    }
+}
+
+/// Wrap a JS callback `(percent, bytes) => void` for a [`ProgressReader`]
+///
+/// Exceptions thrown by the callback are ignored.
+pub(crate) fn js_progress(callback: &js_sys::Function) -> impl FnMut(u32, usize) + '_ {
+    move |percent, bytes| {
+        let _ = callback.call2(
+            &JsValue::NULL,
+            &JsValue::from(percent),
+            &JsValue::from(bytes as f64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_is_reported_once_per_percent() {
+        let data = vec![b'x'; 3 * CHUNK_SIZE + 17];
+        let mut reports = Vec::new();
+        let mut reader =
+            ProgressReader::new(&data, |percent, bytes| reports.push((percent, bytes)));
+
+        let mut sink = Vec::new();
+        reader.read_to_end(&mut sink).unwrap();
+
+        assert_eq!(sink.len(), data.len());
+        assert_eq!(reports.first(), Some(&(0, 0)));
+        assert_eq!(reports.last(), Some(&(100, data.len())));
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn test_turtle_parses_through_progress_reader() {
+        use sophia_api::graph::Graph;
+        use sophia_api::parser::TripleParser;
+        use sophia_api::source::TripleSource;
+        use sophia_inmem::graph::FastGraph;
+        use sophia_turtle::parser::turtle::TurtleParser;
+
+        let ttl = "<https://example.org/a> <https://example.org/p> \"x\", \"y\" .";
+        let mut last = 0;
+        let mut graph = FastGraph::new();
+        TurtleParser::default()
+            .parse(ProgressReader::new(ttl.as_bytes(), |percent, _| {
+                last = percent
+            }))
+            .add_to_graph(&mut graph)
+            .unwrap();
+
+        assert_eq!(graph.triples().count(), 2);
+        assert_eq!(last, 100);
+    }
+}
@@ -0,0 +1,466 @@
+//! Materialized inference
+//!
+//! With reasoning switched on, the triples that follow from the ontology are
+//! added to the graph, so every query, SPARQL included, sees them:
+//!
+//! ```javascript
+//! processor.set_reasoning('rdfs');
+//! processor.load_turtle(ontology);
+//! processor.explain('sn:time', 'rdf:type', 'sn:Construct');
+//! // { asserted: false, rule: 'rdfs9', premises: [...] }
+//! ```
+//!
+//! The levels are:
+//!
+//! - `none`, the default: nothing is inferred
+//! - `rdfs`: `rdfs:domain` and `rdfs:range` (rules rdfs2 and rdfs3), and
+//!   `rdfs:subPropertyOf` and `rdfs:subClassOf` (rdfs5, rdfs7, rdfs9 and rdfs11)
+//! - `owl-lite`: `rdfs` plus `owl:equivalentClass` and
+//!   `owl:equivalentProperty` (scm-eqc1, scm-eqp1), `owl:inverseOf`
+//!   (prp-inv1, prp-inv2), symmetric and transitive properties (prp-symp,
+//!   prp-trp), and the symmetry and transitivity of `owl:sameAs` (eq-sym,
+//!   eq-trans)
+//!
+//! Inferred triples are recomputed from the asserted ones at the end of every
+//! load, edit, undo and redo, and when the level changes. They live in the
+//! union graph only, not in named graphs, and are not part of undo history.
+//! Each remembers the rule and premises it was first derived from, which
+//! `explain` reports. Asserting an inferred triple makes it asserted.
+
+use crate::error::ProcessorError;
+use crate::{SemanticProcessor, TripleMatch};
+use serde::Serialize;
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+type Spo = [SimpleTerm<'static>; 3];
+
+/// How much is inferred
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Reasoning {
+    #[default]
+    None,
+    Rdfs,
+    OwlLite,
+}
+
+impl Reasoning {
+    fn name(self) -> &'static str {
+        match self {
+            Reasoning::None => "none",
+            Reasoning::Rdfs => "rdfs",
+            Reasoning::OwlLite => "owl-lite",
+        }
+    }
+}
+
+/// Why an inferred triple is in the graph
+#[derive(Debug, Clone)]
+pub(crate) struct Derivation {
+    rule: &'static str,
+    premises: Vec<Spo>,
+}
+
+/// The result of `explain`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    /// True if the triple was loaded or added, rather than inferred
+    pub asserted: bool,
+    /// Name of the rule that inferred the triple, e.g. `rdfs9`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// The triples the rule inferred it from; these may be inferred too
+    pub premises: Vec<TripleMatch>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose how much is inferred: `none`, `rdfs` or `owl-lite`
+    ///
+    /// The inferred triples are recomputed straight away.
+    ///
+    /// # Returns
+    /// * `Err(JsValue)` with code `InvalidArgument` for an unknown level
+    pub fn set_reasoning(&mut self, level: &str) -> Result<(), JsValue> {
+        Ok(self.set_reasoning_level(level)?)
+    }
+
+    /// The current reasoning level
+    pub fn reasoning(&self) -> String {
+        self.reasoning.name().to_string()
+    }
+
+    /// Tell whether a triple was asserted or inferred, and from what
+    ///
+    /// Terms are written as for `match_triples`.
+    ///
+    /// # Returns
+    /// JsValue containing {asserted, rule?, premises}, or `null` if the
+    /// triple is not in the graph
+    #[wasm_bindgen(unchecked_return_type = "Explanation | null")]
+    pub fn explain(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+    ) -> Result<JsValue, JsValue> {
+        let explanation = self.explanation(subject, predicate, object)?;
+
+        self.output(&explanation)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn set_reasoning_level(&mut self, level: &str) -> Result<(), ProcessorError> {
+        self.reasoning = match level {
+            "none" => Reasoning::None,
+            "rdfs" => Reasoning::Rdfs,
+            "owl-lite" => Reasoning::OwlLite,
+            _ => {
+                return Err(ProcessorError::invalid_argument(format!(
+                    "Unknown reasoning level '{}'; expected none, rdfs or owl-lite",
+                    level
+                ))
+                .with_context("level", level))
+            }
+        };
+        self.materialize();
+        Ok(())
+    }
+
+    pub(crate) fn explanation(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+    ) -> Result<Option<Explanation>, ProcessorError> {
+        let triple: Spo = [
+            self.parse_term_arg(subject)?,
+            self.parse_term_arg(predicate)?,
+            self.parse_term_arg(object)?,
+        ];
+        let [s, p, o] = &triple;
+        if !self.graph.contains(s, p, o).unwrap_or(false) {
+            return Ok(None);
+        }
+
+        Ok(Some(match self.inferred.get(&triple) {
+            Some(derivation) => Explanation {
+                asserted: false,
+                rule: Some(derivation.rule.to_string()),
+                premises: derivation
+                    .premises
+                    .iter()
+                    .map(TripleMatch::from_triple)
+                    .collect(),
+            },
+            None => Explanation {
+                asserted: true,
+                rule: None,
+                premises: Vec::new(),
+            },
+        }))
+    }
+
+    /// Remove the inferred triples from the graph
+    ///
+    /// Loads call this before adding to the graph, so that a loaded triple
+    /// that was inferred before counts as asserted.
+    pub(crate) fn retract_inferred(&mut self) {
+        if self.inferred.is_empty() {
+            return;
+        }
+        for [s, p, o] in self.inferred.keys() {
+            let _ = self.graph.remove(s, p, o);
+        }
+        self.inferred.clear();
+        self.invalidate_cache();
+    }
+
+    /// Recompute the inferred triples from the asserted ones
+    pub(crate) fn materialize(&mut self) {
+        self.retract_inferred();
+        if self.reasoning == Reasoning::None {
+            return;
+        }
+
+        let rules = Rules::new(self);
+        loop {
+            let derived = rules.apply(&self.graph);
+            if derived.is_empty() {
+                break;
+            }
+            for (triple, derivation) in derived {
+                let [s, p, o] = &triple;
+                // Inserting into an in-memory graph only fails when it is full
+                let _ = self.graph.insert(s, p, o);
+                self.inferred.insert(triple, derivation);
+            }
+        }
+        self.invalidate_cache();
+    }
+}
+
+/// The vocabulary the rules match, and the level they run at
+struct Rules {
+    level: Reasoning,
+    rdf_type: SimpleTerm<'static>,
+    domain: SimpleTerm<'static>,
+    range: SimpleTerm<'static>,
+    sub_class_of: SimpleTerm<'static>,
+    sub_property_of: SimpleTerm<'static>,
+    equivalent_class: SimpleTerm<'static>,
+    equivalent_property: SimpleTerm<'static>,
+    inverse_of: SimpleTerm<'static>,
+    symmetric: SimpleTerm<'static>,
+    transitive: SimpleTerm<'static>,
+    same_as: SimpleTerm<'static>,
+}
+
+/// Triples inferred in one round, each once, in the order found
+#[derive(Default)]
+struct Derived {
+    triples: Vec<(Spo, Derivation)>,
+    seen: HashSet<Spo>,
+}
+
+impl Derived {
+    fn add(&mut self, graph: &FastGraph, rule: &'static str, triple: Spo, premises: Vec<Spo>) {
+        let [s, p, o] = &triple;
+        if s.is_literal() || graph.contains(s, p, o).unwrap_or(true) {
+            return;
+        }
+        if self.seen.insert(triple.clone()) {
+            self.triples.push((triple, Derivation { rule, premises }));
+        }
+    }
+}
+
+impl Rules {
+    fn new(processor: &SemanticProcessor) -> Self {
+        let term = |name| processor.make_term(name);
+        Rules {
+            level: processor.reasoning,
+            rdf_type: term("rdf:type"),
+            domain: term("rdfs:domain"),
+            range: term("rdfs:range"),
+            sub_class_of: term("rdfs:subClassOf"),
+            sub_property_of: term("rdfs:subPropertyOf"),
+            equivalent_class: term("owl:equivalentClass"),
+            equivalent_property: term("owl:equivalentProperty"),
+            inverse_of: term("owl:inverseOf"),
+            symmetric: term("owl:SymmetricProperty"),
+            transitive: term("owl:TransitiveProperty"),
+            same_as: term("owl:sameAs"),
+        }
+    }
+
+    /// Apply every rule once, returning the triples not yet in the graph
+    fn apply(&self, graph: &FastGraph) -> Vec<(Spo, Derivation)> {
+        let matching = |p: &SimpleTerm, o: Option<&SimpleTerm>| -> Vec<Spo> {
+            let triples: Vec<_> = match o {
+                Some(o) => graph.triples_matching(Any, [p], [o]).flatten().collect(),
+                None => graph.triples_matching(Any, [p], Any).flatten().collect(),
+            };
+            triples
+                .into_iter()
+                .map(|t| t.to_spo().map(|term| term.into_term()))
+                .collect()
+        };
+        let with = |p: &SimpleTerm| matching(p, None);
+        let typed = |class: &SimpleTerm| matching(&self.rdf_type, Some(class));
+        let mut derived = Derived::default();
+        let mut add = |rule, triple, premises| derived.add(graph, rule, triple, premises);
+
+        for declaration @ [p, _, c] in &with(&self.domain) {
+            for premise @ [x, _, _] in &with(p) {
+                let triple = [x.clone(), self.rdf_type.clone(), c.clone()];
+                add("rdfs2", triple, vec![declaration.clone(), premise.clone()]);
+            }
+        }
+        for declaration @ [p, _, c] in &with(&self.range) {
+            for premise @ [_, _, y] in &with(p) {
+                let triple = [y.clone(), self.rdf_type.clone(), c.clone()];
+                add("rdfs3", triple, vec![declaration.clone(), premise.clone()]);
+            }
+        }
+        let sub_properties = with(&self.sub_property_of);
+        for declaration @ [p, _, q] in &sub_properties {
+            for premise @ [x, _, y] in &with(p) {
+                let triple = [x.clone(), q.clone(), y.clone()];
+                add("rdfs7", triple, vec![declaration.clone(), premise.clone()]);
+            }
+            for next @ [_, _, r] in sub_properties.iter().filter(|[q2, _, _]| Term::eq(q2, q)) {
+                let triple = [p.clone(), self.sub_property_of.clone(), r.clone()];
+                add("rdfs5", triple, vec![declaration.clone(), next.clone()]);
+            }
+        }
+        let sub_classes = with(&self.sub_class_of);
+        for declaration @ [c, _, d] in &sub_classes {
+            for premise @ [x, _, _] in &typed(c) {
+                let triple = [x.clone(), self.rdf_type.clone(), d.clone()];
+                add("rdfs9", triple, vec![declaration.clone(), premise.clone()]);
+            }
+            for next @ [_, _, e] in sub_classes.iter().filter(|[d2, _, _]| Term::eq(d2, d)) {
+                let triple = [c.clone(), self.sub_class_of.clone(), e.clone()];
+                add("rdfs11", triple, vec![declaration.clone(), next.clone()]);
+            }
+        }
+
+        if self.level != Reasoning::OwlLite {
+            return derived.triples;
+        }
+
+        for declaration @ [c, _, d] in &with(&self.equivalent_class) {
+            for (a, b) in [(c, d), (d, c)] {
+                let triple = [a.clone(), self.sub_class_of.clone(), b.clone()];
+                add("scm-eqc1", triple, vec![declaration.clone()]);
+            }
+        }
+        for declaration @ [p, _, q] in &with(&self.equivalent_property) {
+            for (a, b) in [(p, q), (q, p)] {
+                let triple = [a.clone(), self.sub_property_of.clone(), b.clone()];
+                add("scm-eqp1", triple, vec![declaration.clone()]);
+            }
+        }
+        for declaration @ [p, _, q] in &with(&self.inverse_of) {
+            for (rule, from, to) in [("prp-inv1", p, q), ("prp-inv2", q, p)] {
+                for premise @ [x, _, y] in &with(from) {
+                    let triple = [y.clone(), to.clone(), x.clone()];
+                    add(rule, triple, vec![declaration.clone(), premise.clone()]);
+                }
+            }
+        }
+        for declaration @ [p, _, _] in &typed(&self.symmetric) {
+            for premise @ [x, _, y] in &with(p) {
+                let triple = [y.clone(), p.clone(), x.clone()];
+                add(
+                    "prp-symp",
+                    triple,
+                    vec![declaration.clone(), premise.clone()],
+                );
+            }
+        }
+        for declaration @ [p, _, _] in &typed(&self.transitive) {
+            let links = with(p);
+            for first @ [x, _, y] in &links {
+                for second @ [_, _, z] in links.iter().filter(|[y2, _, _]| Term::eq(y2, y)) {
+                    let triple = [x.clone(), p.clone(), z.clone()];
+                    let premises = vec![declaration.clone(), first.clone(), second.clone()];
+                    add("prp-trp", triple, premises);
+                }
+            }
+        }
+        let same = with(&self.same_as);
+        for premise @ [x, _, y] in &same {
+            let triple = [y.clone(), self.same_as.clone(), x.clone()];
+            add("eq-sym", triple, vec![premise.clone()]);
+            for next @ [_, _, z] in same.iter().filter(|[y2, _, _]| Term::eq(y2, y)) {
+                if !Term::eq(x, z) {
+                    let triple = [x.clone(), self.same_as.clone(), z.clone()];
+                    add("eq-trans", triple, vec![premise.clone(), next.clone()]);
+                }
+            }
+        }
+
+        derived.triples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        @prefix owl: <http://www.w3.org/2002/07/owl#> .
+        sn:CoreConstruct rdfs:subClassOf sn:Construct .
+        sn:Construct rdfs:subClassOf sn:Concept .
+        sn:hasSource rdfs:domain sn:Entanglement ; rdfs:range sn:Construct .
+        sn:hasTarget owl:inverseOf sn:isTargetOf .
+        sn:time a sn:CoreConstruct .
+        sn:e1 sn:hasSource sn:entropy ; sn:hasTarget sn:time .
+    "#;
+
+    fn has(processor: &SemanticProcessor, s: &str, p: &str, o: &str) -> bool {
+        processor.explanation(s, p, o).unwrap().is_some()
+    }
+
+    #[test]
+    fn test_rdfs_materialization_and_explanations() {
+        let mut processor = SemanticProcessor::new();
+        processor.set_reasoning_level("rdfs").unwrap();
+        processor.load_turtle(TTL, None).unwrap();
+
+        assert!(has(&processor, "sn:time", "rdf:type", "sn:Concept"));
+        assert!(has(&processor, "sn:e1", "rdf:type", "sn:Entanglement"));
+        assert!(has(&processor, "sn:entropy", "rdf:type", "sn:Concept"));
+        assert!(!has(&processor, "sn:time", "sn:isTargetOf", "sn:e1"));
+
+        let explanation = processor
+            .explanation("sn:time", "rdf:type", "sn:Construct")
+            .unwrap()
+            .unwrap();
+        assert!(!explanation.asserted);
+        assert_eq!(explanation.rule.as_deref(), Some("rdfs9"));
+        assert_eq!(explanation.premises.len(), 2);
+        assert_eq!(
+            processor
+                .explanation("sn:time", "rdf:type", "sn:CoreConstruct")
+                .unwrap()
+                .unwrap()
+                .rule,
+            None
+        );
+        assert_eq!(processor.collect_entanglements(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_inferences_follow_edits_and_levels() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        assert!(!has(&processor, "sn:time", "rdf:type", "sn:Construct"));
+        assert!(processor.set_reasoning_level("full").is_err());
+
+        processor.set_reasoning_level("owl-lite").unwrap();
+        assert!(has(&processor, "sn:time", "sn:isTargetOf", "sn:e1"));
+        let explanation = processor
+            .explanation("sn:time", "sn:isTargetOf", "sn:e1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(explanation.rule.as_deref(), Some("prp-inv1"));
+
+        // Asserting an inferred triple keeps it when its premises go
+        let asserted = processor
+            .triple_terms("sn:time", "rdf:type", "sn:Construct", false)
+            .unwrap();
+        processor.add_terms(&asserted);
+        processor.finish_edit();
+        let premise = processor
+            .triple_terms("sn:time", "rdf:type", "sn:CoreConstruct", false)
+            .unwrap();
+        processor.remove_terms(&premise);
+        processor.finish_edit();
+        assert!(
+            processor
+                .explanation("sn:time", "rdf:type", "sn:Construct")
+                .unwrap()
+                .unwrap()
+                .asserted
+        );
+        assert!(has(&processor, "sn:time", "rdf:type", "sn:Concept"));
+
+        processor.undo_edit().unwrap();
+        assert!(has(&processor, "sn:time", "rdf:type", "sn:CoreConstruct"));
+
+        processor.set_reasoning_level("none").unwrap();
+        assert!(!has(&processor, "sn:time", "rdf:type", "sn:Concept"));
+        assert!(has(&processor, "sn:time", "rdf:type", "sn:Construct"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Reified statements
+//!
+//! Metadata about a single statement, such as the confidence in an
+//! entanglement or the work it is cited from, is attached to an
+//! `rdf:Statement` node naming the statement's subject, predicate and object:
+//!
+//! ```turtle
+//! [] a rdf:Statement ;
+//!     rdf:subject sn:time ; rdf:predicate sn:entangledWith ; rdf:object sn:space ;
+//!     sn:confidence 0.8 ; sn:citation <https://example.org/papers/12> .
+//! ```
+//!
+//! `statements_about` finds these nodes. Entanglements also carry, as
+//! `annotations`, the metadata of statements about the entanglement itself
+//! and of statements linking its source to its target.
+
+use crate::error::ProcessorError;
+use crate::{SemanticProcessor, TermOrAny, TermValue};
+use serde::{Deserialize, Serialize};
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// One property of a reified statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    /// IRI of the property, e.g. `sn:confidence`
+    pub predicate: String,
+    pub value: TermValue,
+}
+
+/// A statement described by an `rdf:Statement` node, with its metadata
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReifiedStatement {
+    /// The `rdf:Statement` node
+    pub id: String,
+    pub subject: TermValue,
+    pub predicate: TermValue,
+    pub object: TermValue,
+    /// Every other property of the node
+    pub annotations: Vec<Annotation>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Find the reified statements about triples matching a pattern
+    ///
+    /// Arguments are written as for `match_triples`; `None` matches anything.
+    ///
+    /// # Returns
+    /// JsValue containing array of {id, subject, predicate, object,
+    /// annotations}, sorted by statement node
+    #[wasm_bindgen(unchecked_return_type = "ReifiedStatement[]")]
+    pub fn statements_about(
+        &self,
+        subject: Option<String>,
+        predicate: Option<String>,
+        object: Option<String>,
+    ) -> Result<JsValue, JsValue> {
+        let statements =
+            self.find_statements(subject.as_deref(), predicate.as_deref(), object.as_deref())?;
+
+        self.output(&statements)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn find_statements(
+        &self,
+        subject: Option<&str>,
+        predicate: Option<&str>,
+        object: Option<&str>,
+    ) -> Result<Vec<ReifiedStatement>, ProcessorError> {
+        let [s, p, o] = [subject, predicate, object]
+            .map(|arg| arg.map(|arg| self.parse_term_arg(arg)).transpose());
+        Ok(self.reified_statements(&self.graph, s?, p?, o?))
+    }
+
+    /// The reified statements in a graph whose terms match those given
+    pub(crate) fn reified_statements(
+        &self,
+        graph: &FastGraph,
+        subject: Option<SimpleTerm<'static>>,
+        predicate: Option<SimpleTerm<'static>>,
+        object: Option<SimpleTerm<'static>>,
+    ) -> Vec<ReifiedStatement> {
+        let rdf_subject = self.make_term("rdf:subject");
+        let rdf_predicate = self.make_term("rdf:predicate");
+        let rdf_object = self.make_term("rdf:object");
+        let rdf_type = self.make_term("rdf:type");
+        let rdf_statement = self.make_term("rdf:Statement");
+
+        let value = |node: &SimpleTerm, property: &SimpleTerm| {
+            graph
+                .triples_matching([node], [property], Any)
+                .flatten()
+                .next()
+                .map(|t| t.o().into_term::<SimpleTerm<'static>>())
+        };
+        let matches = |wanted: &Option<SimpleTerm>, term: &SimpleTerm| {
+            wanted.as_ref().is_none_or(|wanted| Term::eq(wanted, term))
+        };
+
+        let mut seen = HashSet::new();
+        let mut statements = Vec::new();
+        for t in graph
+            .triples_matching(Any, [&rdf_subject], TermOrAny(subject.clone()))
+            .flatten()
+        {
+            let node: SimpleTerm<'static> = t.s().into_term();
+            if !seen.insert(node.clone()) {
+                continue;
+            }
+            let (Some(s), Some(p), Some(o)) = (
+                value(&node, &rdf_subject),
+                value(&node, &rdf_predicate),
+                value(&node, &rdf_object),
+            ) else {
+                continue;
+            };
+            if !matches(&subject, &s) || !matches(&predicate, &p) || !matches(&object, &o) {
+                continue;
+            }
+
+            let annotations = graph
+                .triples_matching([&node], Any, Any)
+                .flatten()
+                .filter(|a| {
+                    let structural = [&rdf_subject, &rdf_predicate, &rdf_object]
+                        .iter()
+                        .any(|property| Term::eq(a.p(), *property));
+                    let typing = Term::eq(a.p(), &rdf_type) && Term::eq(a.o(), &rdf_statement);
+                    !structural && !typing
+                })
+                .map(|a| Annotation {
+                    predicate: self.term_to_string(a.p()),
+                    value: TermValue::from_term(a.o()),
+                })
+                .collect();
+            statements.push(ReifiedStatement {
+                id: self.term_to_string(&node),
+                subject: TermValue::from_term(&s),
+                predicate: TermValue::from_term(&p),
+                object: TermValue::from_term(&o),
+                annotations,
+            });
+        }
+
+        statements.sort_by(|a, b| a.id.cmp(&b.id));
+        statements
+    }
+
+    /// The annotations of the statements about an entanglement, and of those
+    /// linking its source to its target
+    pub(crate) fn entanglement_annotations(
+        &self,
+        graph: &FastGraph,
+        id: &str,
+        source: &str,
+        target: &str,
+    ) -> Vec<Annotation> {
+        let mut statements = self.reified_statements(graph, Some(self.make_term(id)), None, None);
+        if !source.is_empty() && !target.is_empty() {
+            statements.extend(self.reified_statements(
+                graph,
+                Some(self.make_term(source)),
+                None,
+                Some(self.make_term(target)),
+            ));
+        }
+        statements
+            .into_iter()
+            .flat_map(|statement| statement.annotations)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+        sn:e1 a sn:Entanglement ; sn:hasSource sn:time ; sn:hasTarget sn:space .
+        sn:s1 a rdf:Statement ;
+            rdf:subject sn:time ; rdf:predicate sn:entangledWith ; rdf:object sn:space ;
+            sn:confidence 0.8 .
+        sn:s2 rdf:subject sn:e1 ; rdf:predicate sn:hasTarget ; rdf:object sn:space ;
+            sn:citation <https://example.org/papers/12> .
+        sn:s3 rdf:subject sn:time ; rdf:predicate sn:precedes ; rdf:object sn:entropy ;
+            sn:confidence 0.2 .
+    "#;
+
+    #[test]
+    fn test_statements_about() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        let about_time = processor
+            .find_statements(Some("sn:time"), None, None)
+            .unwrap();
+        assert_eq!(about_time.len(), 2);
+        assert_eq!(about_time[0].id, "https://sinople.org/ontology#s1");
+        assert_eq!(
+            about_time[0].annotations,
+            vec![Annotation {
+                predicate: "https://sinople.org/ontology#confidence".to_string(),
+                value: TermValue {
+                    kind: "literal".to_string(),
+                    value: "0.8".to_string(),
+                    datatype: Some("http://www.w3.org/2001/XMLSchema#decimal".to_string()),
+                    language: None,
+                    triple: None,
+                },
+            }]
+        );
+
+        let precedes = processor
+            .find_statements(None, Some("sn:precedes"), Some("sn:entropy"))
+            .unwrap();
+        assert_eq!(precedes.len(), 1);
+        assert_eq!(
+            precedes[0].subject.value,
+            "https://sinople.org/ontology#time"
+        );
+    }
+
+    #[test]
+    fn test_entanglements_carry_annotations() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        let entanglements = processor.collect_entanglements(None).unwrap();
+        let mut predicates: Vec<&str> = entanglements[0]
+            .annotations
+            .iter()
+            .map(|a| a.predicate.as_str())
+            .collect();
+        predicates.sort();
+        assert_eq!(
+            predicates,
+            [
+                "https://sinople.org/ontology#citation",
+                "https://sinople.org/ontology#confidence"
+            ]
+        );
+    }
+}
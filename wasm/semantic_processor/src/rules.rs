@@ -0,0 +1,278 @@
+//! Custom inference rules
+//!
+//! Rules are given as JSON, each with triple patterns to match and triples
+//! to conclude from every match. Terms are written as for `match_triples`,
+//! and names starting with `?` are variables:
+//!
+//! ```javascript
+//! processor.set_rules(JSON.stringify([{
+//!   name: 'indirect',
+//!   if: [['?a', 'sn:entangles', '?b'], ['?b', 'sn:entangles', '?c']],
+//!   then: [['?a', 'sn:indirectlyRelates', '?c']],
+//! }]));
+//! processor.apply_rules(); // { inserted: 3, iterations: 2, complete: true }
+//! ```
+//!
+//! `apply_rules` runs the rules forward until they conclude nothing new, or
+//! until the iteration limit. In each iteration, every rule is matched
+//! against the graph as it was at the start of the iteration. The concluded
+//! triples are added to the default graph like any other edit, so one
+//! `undo` removes everything a run added.
+
+use crate::error::ProcessorError;
+use crate::sparql::{
+    evaluate_group, ground_triple, GroupElement, GroupPattern, PatternTerm, Solution, TriplePattern,
+};
+use crate::SemanticProcessor;
+use serde::{Deserialize, Serialize};
+use sophia_api::term::Term;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// Iterations `apply_rules` runs at most, unless told otherwise
+const MAX_ITERATIONS: usize = 100;
+
+/// A rule as written in JSON
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDefinition {
+    name: Option<String>,
+    #[serde(rename = "if")]
+    conditions: Vec<[String; 3]>,
+    #[serde(rename = "then")]
+    conclusions: Vec<[String; 3]>,
+}
+
+/// A parsed rule
+#[derive(Debug, Clone)]
+pub(crate) struct Rule {
+    pattern: GroupPattern,
+    conclusions: Vec<TriplePattern>,
+}
+
+/// The outcome of `apply_rules`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RuleReport {
+    /// Number of triples added to the graph
+    pub inserted: usize,
+    /// Number of iterations run, including the last one that found nothing new
+    pub iterations: usize,
+    /// False if the iteration limit stopped the run before it was done
+    pub complete: bool,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Replace the rules that `apply_rules` runs
+    ///
+    /// # Arguments
+    /// * `rules_json` - JSON array of rules, each with an optional `name`,
+    ///   and `if` and `then` arrays of `[subject, predicate, object]`
+    ///
+    /// # Returns
+    /// * `Err(JsValue)` with code `InvalidArgument` if a rule is malformed,
+    ///   or concludes with a variable that its conditions do not bind
+    pub fn set_rules(&mut self, rules_json: &str) -> Result<(), JsValue> {
+        self.rules = self.parse_rules(rules_json)?;
+        Ok(())
+    }
+
+    /// Run the rules until they conclude nothing new
+    ///
+    /// # Arguments
+    /// * `max_iterations` - Iterations to run at most (default 100)
+    ///
+    /// # Returns
+    /// JsValue containing {inserted, iterations, complete}
+    #[wasm_bindgen(unchecked_return_type = "RuleReport")]
+    pub fn apply_rules(&mut self, max_iterations: Option<u32>) -> Result<JsValue, JsValue> {
+        let report = self.run_rules(max_iterations.map_or(MAX_ITERATIONS, |max| max as usize))?;
+
+        self.output(&report)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn parse_rules(&self, rules_json: &str) -> Result<Vec<Rule>, ProcessorError> {
+        let definitions: Vec<RuleDefinition> = serde_json::from_str(rules_json)
+            .map_err(|e| ProcessorError::invalid_argument(format!("Invalid rules: {}", e)))?;
+
+        definitions
+            .into_iter()
+            .enumerate()
+            .map(|(index, definition)| {
+                let name = definition
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("rule {}", index + 1));
+                self.parse_rule(&name, definition)
+                    .map_err(|e| e.with_context("rule", &name))
+            })
+            .collect()
+    }
+
+    fn parse_rule(&self, name: &str, definition: RuleDefinition) -> Result<Rule, ProcessorError> {
+        if definition.conditions.is_empty() || definition.conclusions.is_empty() {
+            return Err(ProcessorError::invalid_argument(format!(
+                "Rule {} needs at least one condition and one conclusion",
+                name
+            )));
+        }
+        let patterns = |triples: &[[String; 3]]| -> Result<Vec<TriplePattern>, ProcessorError> {
+            triples
+                .iter()
+                .map(|[s, p, o]| {
+                    Ok(TriplePattern {
+                        s: self.rule_term(s)?,
+                        p: self.rule_term(p)?,
+                        o: self.rule_term(o)?,
+                    })
+                })
+                .collect()
+        };
+        let conditions = patterns(&definition.conditions)?;
+        let conclusions = patterns(&definition.conclusions)?;
+
+        let bound: HashSet<&str> = conditions
+            .iter()
+            .flat_map(|pattern| [&pattern.s, &pattern.p, &pattern.o])
+            .filter_map(|term| match term {
+                PatternTerm::Var(name) => Some(name.as_str()),
+                PatternTerm::Term(_) => None,
+            })
+            .collect();
+        for pattern in &conclusions {
+            for term in [&pattern.s, &pattern.p, &pattern.o] {
+                if let PatternTerm::Var(variable) = term {
+                    if !bound.contains(variable.as_str()) {
+                        return Err(ProcessorError::invalid_argument(format!(
+                            "Rule {} concludes with ?{}, which no condition binds",
+                            name, variable
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(Rule {
+            pattern: GroupPattern {
+                elements: conditions.into_iter().map(GroupElement::Triple).collect(),
+            },
+            conclusions,
+        })
+    }
+
+    /// A variable, or a term written as for `match_triples`
+    fn rule_term(&self, term: &str) -> Result<PatternTerm, ProcessorError> {
+        match term.trim().strip_prefix('?') {
+            Some(variable) if !variable.is_empty() => Ok(PatternTerm::Var(variable.to_string())),
+            _ => Ok(PatternTerm::Term(self.parse_term_arg(term)?)),
+        }
+    }
+
+    pub(crate) fn run_rules(
+        &mut self,
+        max_iterations: usize,
+    ) -> Result<RuleReport, ProcessorError> {
+        let mut report = RuleReport::default();
+        let rules = self.rules.clone();
+
+        while report.iterations < max_iterations {
+            self.check_abort().inspect_err(|_| self.finish_edit())?;
+            report.iterations += 1;
+
+            let mut concluded = Vec::new();
+            for rule in &rules {
+                let solutions = evaluate_group(
+                    &self.graph,
+                    &rule.pattern,
+                    vec![Solution::new()],
+                    &self.abort,
+                )
+                .inspect_err(|_| self.finish_edit())?;
+                for solution in &solutions {
+                    concluded.extend(
+                        rule.conclusions
+                            .iter()
+                            .filter_map(|template| ground_triple(template, solution))
+                            .filter(|[s, p, _]| !s.is_literal() && p.is_iri()),
+                    );
+                }
+            }
+
+            let before = report.inserted;
+            for triple in &concluded {
+                if self.add_terms(triple) {
+                    report.inserted += 1;
+                }
+            }
+            if report.inserted == before {
+                report.complete = true;
+                break;
+            }
+        }
+
+        self.finish_edit();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        sn:a sn:entangles sn:b .
+        sn:b sn:entangles sn:c .
+        sn:c sn:entangles sn:d .
+    "#;
+
+    const RULES: &str = r#"[{
+        "name": "indirect",
+        "if": [["?a", "sn:entangles", "?b"], ["?b", "sn:entangles", "?c"]],
+        "then": [["?a", "sn:entangles", "?c"], ["?a", "sn:indirectlyRelates", "?c"]]
+    }]"#;
+
+    #[test]
+    fn test_rules_run_to_a_fixpoint() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        processor.rules = processor.parse_rules(RULES).unwrap();
+
+        let report = processor.run_rules(MAX_ITERATIONS).unwrap();
+        // a-c, b-d and then a-d, each with its indirectlyRelates triple
+        assert_eq!(
+            report,
+            RuleReport {
+                inserted: 6,
+                iterations: 3,
+                complete: true
+            }
+        );
+        assert_eq!(processor.triple_count(), 9);
+
+        processor.undo_edit().unwrap();
+        assert_eq!(processor.triple_count(), 3);
+    }
+
+    #[test]
+    fn test_iteration_limit_and_malformed_rules() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        processor.rules = processor.parse_rules(RULES).unwrap();
+
+        let report = processor.run_rules(1).unwrap();
+        assert_eq!(report.inserted, 4);
+        assert!(!report.complete);
+
+        let unbound =
+            r#"[{"if": [["?a", "sn:entangles", "?b"]], "then": [["?a", "sn:near", "?z"]]}]"#;
+        let error = processor.parse_rules(unbound).unwrap_err();
+        assert_eq!(error.context["rule"], "rule 1");
+        assert!(processor
+            .parse_rules(r#"[{"if": [], "then": []}]"#)
+            .is_err());
+        assert!(processor.parse_rules(r#"[{"when": []}]"#).is_err());
+    }
+}
@@ -0,0 +1,493 @@
+//! SHACL validation
+//!
+//! Shapes describe what the data must look like, for example that every
+//! construct has exactly one label and at least one gloss:
+//!
+//! ```turtle
+//! sn:ConstructShape a sh:NodeShape ;
+//!     sh:targetClass sn:Construct ;
+//!     sh:property [ sh:path rdfs:label ; sh:minCount 1 ; sh:maxCount 1 ;
+//!                   sh:datatype xsd:string ] ;
+//!     sh:property [ sh:path sn:hasGloss ; sh:minCount 1 ; sh:class sn:Gloss ] .
+//! ```
+//!
+//! ```javascript
+//! processor.load_shapes(shapesTtl);
+//! const report = processor.validate();
+//! if (!report.conforms) report.results.forEach(r => console.warn(r.message));
+//! ```
+//!
+//! Shapes are kept apart from the data, and stay loaded until
+//! `clear_shapes`. The subset of SHACL Core supported is:
+//!
+//! - targets: `sh:targetClass` (including instances of subclasses),
+//!   `sh:targetNode`, `sh:targetSubjectsOf` and `sh:targetObjectsOf`
+//! - property shapes linked with `sh:property`, whose `sh:path` is a single
+//!   property
+//! - constraints: `sh:minCount`, `sh:maxCount`, `sh:datatype`, `sh:class`,
+//!   and `sh:pattern` with `sh:flags`
+//! - `sh:severity`, `sh:message` and `sh:deactivated`
+//!
+//! Other constraints are ignored. A shape with any other kind of path is
+//! rejected when validating.
+
+use crate::error::ProcessorError;
+use crate::inference::Hierarchy;
+use crate::{SemanticProcessor, TermValue};
+use regex::Regex;
+use serde::Serialize;
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{IriRef, SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_api::MownStr;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+const SH: &str = "http://www.w3.org/ns/shacl#";
+
+/// The outcome of `validate`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationReport {
+    /// True if there are no results, whatever their severity
+    pub conforms: bool,
+    pub results: Vec<ValidationResult>,
+}
+
+/// One constraint that a focus node does not satisfy
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationResult {
+    /// The resource that was validated
+    pub focus_node: String,
+    /// The property of a property shape
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// The value that failed the constraint; absent for counts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<TermValue>,
+    /// The shape the constraint belongs to
+    pub shape: String,
+    /// Local name of the constraint component, e.g. `MinCountConstraintComponent`
+    pub constraint: String,
+    /// `Violation`, `Warning` or `Info`
+    pub severity: String,
+    pub message: String,
+}
+
+/// A node or property shape, read from the shapes graph
+struct Shape {
+    id: SimpleTerm<'static>,
+    path: Option<SimpleTerm<'static>>,
+    min_count: Option<usize>,
+    max_count: Option<usize>,
+    datatype: Option<SimpleTerm<'static>>,
+    class: Vec<SimpleTerm<'static>>,
+    pattern: Option<Regex>,
+    severity: String,
+    message: Option<String>,
+    properties: Vec<Shape>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Load SHACL shapes from Turtle, adding to those already loaded
+    ///
+    /// # Returns
+    /// * `Err(JsValue)` if the Turtle does not parse
+    pub fn load_shapes(&mut self, ttl: &str) -> Result<(), JsValue> {
+        let shapes = self.parse_turtle(ttl)?;
+        for t in shapes.triples().flatten() {
+            // Inserting into an in-memory graph only fails when it is full
+            let _ = self.shapes.insert(t.s(), t.p(), t.o());
+        }
+        Ok(())
+    }
+
+    /// Remove all loaded shapes
+    pub fn clear_shapes(&mut self) {
+        self.shapes = FastGraph::new();
+    }
+
+    /// Validate the graph against the loaded shapes
+    ///
+    /// # Returns
+    /// JsValue containing {conforms, results}, where each result has
+    /// {focus_node, path?, value?, shape, constraint, severity, message},
+    /// sorted by focus node
+    /// * `Err(JsValue)` with code `InvalidArgument` if a shape is malformed
+    #[wasm_bindgen(unchecked_return_type = "ValidationReport")]
+    pub fn validate(&self) -> Result<JsValue, JsValue> {
+        let report = self.validation_report()?;
+
+        self.output(&report)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn validation_report(&self) -> Result<ValidationReport, ProcessorError> {
+        let rdf_type = self.make_term("rdf:type");
+        let targets: Vec<SimpleTerm> = [
+            "targetClass",
+            "targetNode",
+            "targetSubjectsOf",
+            "targetObjectsOf",
+        ]
+        .iter()
+        .map(|name| sh(name))
+        .collect();
+        let mut roots: Vec<SimpleTerm<'static>> = self
+            .shapes
+            .triples_matching(Any, &targets[..], Any)
+            .flatten()
+            .map(|t| t.s().into_term())
+            .collect();
+        roots.sort_by_cached_key(|term| self.term_to_string(term));
+        roots.dedup();
+
+        let hierarchy = self.class_hierarchy();
+        let mut results = Vec::new();
+        for root in roots {
+            let Some(shape) = self.read_shape(&root)? else {
+                continue;
+            };
+            let mut focus_nodes: Vec<SimpleTerm<'static>> = Vec::new();
+            for [_, target, value] in self.shape_triples(&root, &targets[..]) {
+                let iri = target.iri().map(|iri| iri.as_str().to_string());
+                match iri.as_deref().and_then(|iri| iri.strip_prefix(SH)) {
+                    Some("targetClass") => {
+                        let classes = hierarchy.with_descendants(value);
+                        focus_nodes.extend(
+                            self.graph
+                                .triples_matching(Any, [&rdf_type], &classes[..])
+                                .flatten()
+                                .map(|t| t.s().into_term()),
+                        );
+                    }
+                    Some("targetNode") => focus_nodes.push(value),
+                    Some("targetSubjectsOf") => focus_nodes.extend(
+                        self.graph
+                            .triples_matching(Any, [&value], Any)
+                            .flatten()
+                            .map(|t| t.s().into_term()),
+                    ),
+                    _ => focus_nodes.extend(
+                        self.graph
+                            .triples_matching(Any, [&value], Any)
+                            .flatten()
+                            .map(|t| t.o().into_term()),
+                    ),
+                }
+            }
+            let mut seen = HashSet::new();
+            focus_nodes.retain(|node| seen.insert(node.clone()));
+
+            for focus in &focus_nodes {
+                self.check_shape(&shape, focus, &hierarchy, &mut results);
+            }
+        }
+
+        results.sort_by(|a, b| {
+            (&a.focus_node, &a.path, &a.constraint).cmp(&(&b.focus_node, &b.path, &b.constraint))
+        });
+        Ok(ValidationReport {
+            conforms: results.is_empty(),
+            results,
+        })
+    }
+
+    /// Read a shape and its property shapes; `None` if it is deactivated
+    fn read_shape(&self, id: &SimpleTerm<'static>) -> Result<Option<Shape>, ProcessorError> {
+        let value = |name: &str| -> Option<SimpleTerm<'static>> {
+            self.shape_triples(id, &[sh(name)])
+                .into_iter()
+                .next()
+                .map(|[_, _, o]| o)
+        };
+        let lexical = |name: &str| value(name).map(|term| self.term_to_string(&term));
+        let count = |name: &str| -> Result<Option<usize>, ProcessorError> {
+            lexical(name)
+                .map(|count| {
+                    count.parse().map_err(|_| {
+                        ProcessorError::invalid_argument(format!(
+                            "sh:{} of shape {} is not a count: {}",
+                            name,
+                            self.term_to_string(id),
+                            count
+                        ))
+                        .with_context("shape", self.term_to_string(id))
+                    })
+                })
+                .transpose()
+        };
+
+        if lexical("deactivated").as_deref() == Some("true") {
+            return Ok(None);
+        }
+        let path = value("path");
+        if path.as_ref().is_some_and(|path| !path.is_iri()) {
+            return Err(ProcessorError::invalid_argument(format!(
+                "Shape {} has a path that is not a single property",
+                self.term_to_string(id)
+            ))
+            .with_context("shape", self.term_to_string(id)));
+        }
+        let pattern = match lexical("pattern") {
+            Some(pattern) => {
+                let flags = lexical("flags").unwrap_or_default();
+                let pattern = if flags.contains('i') {
+                    format!("(?i){}", pattern)
+                } else {
+                    pattern
+                };
+                Some(Regex::new(&pattern).map_err(|e| {
+                    ProcessorError::invalid_argument(format!(
+                        "Invalid sh:pattern in shape {}: {}",
+                        self.term_to_string(id),
+                        e
+                    ))
+                    .with_context("shape", self.term_to_string(id))
+                })?)
+            }
+            None => None,
+        };
+        let severity = value("severity")
+            .map(|severity| self.extract_local_name(&self.term_to_string(&severity)))
+            .unwrap_or_else(|| "Violation".to_string());
+
+        let mut properties = Vec::new();
+        for [_, _, property] in self.shape_triples(id, &[sh("property")]) {
+            properties.extend(self.read_shape(&property)?);
+        }
+
+        Ok(Some(Shape {
+            id: id.clone(),
+            path,
+            min_count: count("minCount")?,
+            max_count: count("maxCount")?,
+            datatype: value("datatype"),
+            class: self
+                .shape_triples(id, &[sh("class")])
+                .into_iter()
+                .map(|[_, _, class]| class)
+                .collect(),
+            pattern,
+            severity,
+            message: lexical("message"),
+            properties,
+        }))
+    }
+
+    /// The triples of the shapes graph about a shape, with given predicates
+    fn shape_triples(
+        &self,
+        shape: &SimpleTerm<'static>,
+        predicates: &[SimpleTerm<'static>],
+    ) -> Vec<[SimpleTerm<'static>; 3]> {
+        let mut triples: Vec<[SimpleTerm<'static>; 3]> = self
+            .shapes
+            .triples_matching([shape], predicates, Any)
+            .flatten()
+            .map(|t| t.to_spo().map(|term| term.into_term()))
+            .collect();
+        triples.sort_by_cached_key(|[_, p, o]| (self.term_to_string(p), self.term_to_string(o)));
+        triples
+    }
+
+    /// Check a focus node against a shape and its property shapes
+    fn check_shape(
+        &self,
+        shape: &Shape,
+        focus: &SimpleTerm<'static>,
+        hierarchy: &Hierarchy,
+        results: &mut Vec<ValidationResult>,
+    ) {
+        let rdf_type = self.make_term("rdf:type");
+        let focus_name = self.term_to_string(focus);
+        let path_name = shape.path.as_ref().map(|path| self.term_to_string(path));
+        let subject = match &path_name {
+            Some(path) => format!("{} of {}", path, focus_name),
+            None => focus_name.clone(),
+        };
+        let mut report = |constraint: &str, value: Option<&SimpleTerm>, default: String| {
+            results.push(ValidationResult {
+                focus_node: focus_name.clone(),
+                path: path_name.clone(),
+                value: value.map(TermValue::from_term),
+                shape: self.term_to_string(&shape.id),
+                constraint: format!("{}ConstraintComponent", constraint),
+                severity: shape.severity.clone(),
+                message: shape.message.clone().unwrap_or(default),
+            });
+        };
+
+        let values: Vec<SimpleTerm<'static>> = match &shape.path {
+            Some(path) => self
+                .graph
+                .triples_matching([focus], [path], Any)
+                .flatten()
+                .map(|t| t.o().into_term())
+                .collect(),
+            None => vec![focus.clone()],
+        };
+
+        if let Some(min) = shape.min_count.filter(|min| values.len() < *min) {
+            let message = format!(
+                "{} has {} values, fewer than {}",
+                subject,
+                values.len(),
+                min
+            );
+            report("MinCount", None, message);
+        }
+        if let Some(max) = shape.max_count.filter(|max| values.len() > *max) {
+            let message = format!("{} has {} values, more than {}", subject, values.len(), max);
+            report("MaxCount", None, message);
+        }
+        for value in &values {
+            let value_name = self.term_to_string(value);
+            if let Some(datatype) = &shape.datatype {
+                if !value.datatype().is_some_and(|dt| Term::eq(&dt, datatype)) {
+                    let message = format!(
+                        "{} is {}, not a literal of type {}",
+                        subject,
+                        value_name,
+                        self.term_to_string(datatype)
+                    );
+                    report("Datatype", Some(value), message);
+                }
+            }
+            for class in &shape.class {
+                let classes = hierarchy.with_descendants(class.clone());
+                let is_instance = !value.is_literal()
+                    && classes.iter().any(|class| {
+                        self.graph
+                            .contains(value, &rdf_type, class)
+                            .unwrap_or(false)
+                    });
+                if !is_instance {
+                    let message = format!(
+                        "{} is {}, not an instance of {}",
+                        subject,
+                        value_name,
+                        self.term_to_string(class)
+                    );
+                    report("Class", Some(value), message);
+                }
+            }
+            if let Some(pattern) = &shape.pattern {
+                if value.is_blank_node() || !pattern.is_match(&value_name) {
+                    let message = format!(
+                        "{} is {}, which does not match {}",
+                        subject,
+                        value_name,
+                        pattern.as_str()
+                    );
+                    report("Pattern", Some(value), message);
+                }
+            }
+        }
+
+        for property in &shape.properties {
+            self.check_shape(property, focus, hierarchy, results);
+        }
+    }
+}
+
+/// A term in the SHACL namespace
+fn sh(name: &str) -> SimpleTerm<'static> {
+    SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(format!(
+        "{}{}",
+        SH, name
+    ))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        sn:CoreConstruct rdfs:subClassOf sn:Construct .
+        sn:time a sn:CoreConstruct ; rdfs:label "Time" ; sn:hasGloss sn:g1 .
+        sn:g1 a sn:Gloss .
+        sn:space a sn:Construct ; rdfs:label "Space", "Room" ; sn:hasGloss sn:time .
+        sn:void a sn:Construct ; rdfs:label 7 .
+    "#;
+
+    const SHAPES: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        @prefix sh: <http://www.w3.org/ns/shacl#> .
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+        sn:ConstructShape a sh:NodeShape ;
+            sh:targetClass sn:Construct ;
+            sh:pattern "^https://sinople\\.org/" ;
+            sh:property [ sh:path rdfs:label ; sh:minCount 1 ; sh:maxCount 1 ;
+                          sh:datatype xsd:string ] ;
+            sh:property [ sh:path sn:hasGloss ; sh:minCount 1 ; sh:class sn:Gloss ;
+                          sh:severity sh:Warning ; sh:message "Add a gloss" ] .
+        sn:OffShape sh:targetNode sn:time ; sh:deactivated true ;
+            sh:property [ sh:path rdfs:label ; sh:maxCount 0 ] .
+    "#;
+
+    #[test]
+    fn test_validation_report() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(DATA, None).unwrap();
+        assert!(processor.validation_report().unwrap().conforms);
+
+        processor.load_shapes(SHAPES).unwrap();
+        let report = processor.validation_report().unwrap();
+        assert!(!report.conforms);
+        let summary: Vec<(&str, &str, &str)> = report
+            .results
+            .iter()
+            .map(|r| {
+                (
+                    r.focus_node
+                        .trim_start_matches("https://sinople.org/ontology#"),
+                    r.constraint.as_str(),
+                    r.severity.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("space", "MaxCountConstraintComponent", "Violation"),
+                ("space", "ClassConstraintComponent", "Warning"),
+                ("void", "DatatypeConstraintComponent", "Violation"),
+                ("void", "MinCountConstraintComponent", "Warning"),
+            ]
+        );
+        assert_eq!(report.results[1].message, "Add a gloss");
+        assert_eq!(
+            report.results[1].value.as_ref().unwrap().value,
+            "https://sinople.org/ontology#time"
+        );
+        assert_eq!(
+            report.results[0].message,
+            "http://www.w3.org/2000/01/rdf-schema#label of https://sinople.org/ontology#space has 2 values, more than 1"
+        );
+
+        processor.clear_shapes();
+        assert!(processor.validation_report().unwrap().conforms);
+    }
+
+    #[test]
+    fn test_malformed_shapes_are_rejected() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(DATA, None).unwrap();
+        processor
+            .load_shapes(
+                r#"@prefix sh: <http://www.w3.org/ns/shacl#> .
+                   <http://example.org/s> sh:targetNode <http://example.org/x> ;
+                       sh:property [ sh:path ( <http://example.org/a> <http://example.org/b> ) ] ."#,
+            )
+            .unwrap();
+        let error = processor.validation_report().unwrap_err();
+        assert!(error.message.contains("not a single property"));
+    }
+}
@@ -0,0 +1,380 @@
+//! Blank node skolemization
+//!
+//! Blank node labels are made up by the parser, so the same node comes back
+//! as `_:riog00000003` from one load and `_:riog00000007` from the next, and
+//! visualizations saved against it lose track of it. With skolemization
+//! enabled, blank nodes are replaced by IRIs derived from what the graph says
+//! about them:
+//!
+//! ```javascript
+//! processor.set_skolemize(true);
+//! processor.load_turtle('sn:time sn:related [ rdfs:label "entropy" ] .');
+//! // object: https://sinople.org/.well-known/genid/3c5e...
+//! ```
+//!
+//! The IRI is a hash of the node's own triples and of those that point at
+//! it, refined with the hashes of the blank nodes it is linked to in either
+//! direction, so nested nodes under different subjects stay apart. Loading
+//! the same document again gives the same IRIs; only blank nodes that
+//! nothing in the graph tells apart get the same IRI, and so become one node.
+//!
+//! Skolemization covers loads and the blank nodes that SPARQL `INSERT`
+//! creates, each update operation hashed like a document of its own.
+//! Blank nodes passed to `add_triple` and RDF Patch keep their labels: the
+//! caller chose them, and may use them again in a later call.
+
+use crate::SemanticProcessor;
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::term::{IriRef, SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_api::MownStr;
+use sophia_inmem::graph::FastGraph;
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// Namespace of the IRIs that stand in for blank nodes
+pub(crate) const GENID: &str = "https://sinople.org/.well-known/genid/";
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Choose whether blank nodes are replaced by stable IRIs
+    ///
+    /// Off by default. Turning it on also skolemizes the blank nodes already
+    /// in the graph, and clears undo history like a load does.
+    pub fn set_skolemize(&mut self, enabled: bool) {
+        self.skolemize = enabled;
+        if enabled {
+            self.retract_inferred();
+            self.skolemize_loaded();
+            self.materialize();
+            self.reset_history();
+        }
+    }
+
+    /// Whether blank nodes are replaced by stable IRIs
+    pub fn skolemize(&self) -> bool {
+        self.skolemize
+    }
+}
+
+impl SemanticProcessor {
+    /// Replace the blank nodes in the graph, if skolemization is enabled
+    ///
+    /// Called at the end of every load, once the whole document is in.
+    pub(crate) fn skolemize_loaded(&mut self) {
+        if !self.skolemize {
+            return;
+        }
+        let iris = skolem_iris(&self.graph);
+        if iris.is_empty() {
+            return;
+        }
+
+        self.invalidate_cache();
+        self.graph = rewrite(&self.graph, &iris);
+        self.named_graphs = std::mem::take(&mut self.named_graphs)
+            .into_iter()
+            .map(|(name, graph)| {
+                let name = name
+                    .strip_prefix("_:")
+                    .and_then(|label| iris.get(label))
+                    .cloned()
+                    .unwrap_or(name);
+                (name, rewrite(&graph, &iris))
+            })
+            .collect();
+        for triples in self.sources.values_mut() {
+            *triples = triples
+                .drain()
+                .map(|triple| triple.map(|term| replace(term, &iris)))
+                .collect();
+        }
+    }
+
+    /// Skolemize the triples an update inserts, if skolemization is enabled
+    pub(crate) fn skolemize_triples(
+        &self,
+        triples: Vec<[SimpleTerm<'static>; 3]>,
+    ) -> Vec<[SimpleTerm<'static>; 3]> {
+        if !self.skolemize || !triples.iter().flatten().any(Term::is_blank_node) {
+            return triples;
+        }
+        let mut graph = FastGraph::new();
+        for [s, p, o] in &triples {
+            // Inserting into a fresh in-memory graph only fails when it is full
+            let _ = graph.insert(s, p, o);
+        }
+        let iris = skolem_iris(&graph);
+        triples
+            .into_iter()
+            .map(|triple| triple.map(|term| replace(term, &iris)))
+            .collect()
+    }
+
+    /// Skolemize a standalone graph, if skolemization is enabled
+    pub(crate) fn skolemize_graph(&self, graph: FastGraph) -> FastGraph {
+        if !self.skolemize {
+            return graph;
+        }
+        let iris = skolem_iris(&graph);
+        if iris.is_empty() {
+            graph
+        } else {
+            rewrite(&graph, &iris)
+        }
+    }
+}
+
+/// The IRI for each blank node label in a graph
+fn skolem_iris(graph: &FastGraph) -> HashMap<String, String> {
+    let mut labels: Vec<String> = graph
+        .triples()
+        .flatten()
+        .flat_map(|t| [t.s().bnode_id(), t.o().bnode_id()])
+        .flatten()
+        .map(|id| id.as_str().to_string())
+        .collect();
+    labels.sort();
+    labels.dedup();
+    let index: HashMap<&str, usize> = labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| (label.as_str(), i))
+        .collect();
+
+    // What each node says about IRIs and literals, and its links to other
+    // blank nodes, with `^` marking the triples that point at it
+    let mut lines: Vec<Vec<String>> = vec![Vec::new(); labels.len()];
+    let mut links: Vec<Vec<(String, usize)>> = vec![Vec::new(); labels.len()];
+    let position = |term: &SimpleTerm| term.bnode_id().map(|id| index[id.as_str()]);
+    for t in graph.triples().flatten() {
+        let [s, p, o] = t.to_spo().map(|term| term.into_term::<SimpleTerm>());
+        let predicate = render(&p);
+        match (position(&s), position(&o)) {
+            (Some(s), Some(o)) => {
+                links[s].push((predicate.clone(), o));
+                links[o].push((format!("^{}", predicate), s));
+            }
+            (Some(s), None) => lines[s].push(format!("{} {}", predicate, render(&o))),
+            (None, Some(o)) => lines[o].push(format!("^{} {}", predicate, render(&s))),
+            (None, None) => {}
+        }
+    }
+
+    let mut hashes = vec![0; labels.len()];
+    for component in components(&links) {
+        refine(&component, &lines, &links, &mut hashes);
+    }
+    labels
+        .into_iter()
+        .zip(hashes)
+        .map(|(label, hash)| (label, format!("{}{:032x}", GENID, hash)))
+        .collect()
+}
+
+/// The groups of blank nodes linked to each other, directly or not
+fn components(links: &[Vec<(String, usize)>]) -> Vec<Vec<usize>> {
+    let mut seen = vec![false; links.len()];
+    let mut components = Vec::new();
+    for start in 0..links.len() {
+        if seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        let mut component = vec![start];
+        let mut next = 0;
+        while let Some(&node) = component.get(next) {
+            next += 1;
+            for &(_, other) in &links[node] {
+                if !seen[other] {
+                    seen[other] = true;
+                    component.push(other);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Hash the nodes of one group by colour refinement
+///
+/// Each node starts from a hash of its own lines. Every round hashes it
+/// again together with the hashes of the blank nodes it is linked to, until
+/// a round tells no more nodes apart. Only nodes that no description can
+/// tell apart share a hash, and since the rounds depend on nothing outside
+/// the group, neither do the hashes.
+fn refine(
+    component: &[usize],
+    lines: &[Vec<String>],
+    links: &[Vec<(String, usize)>],
+    hashes: &mut [u128],
+) {
+    let distinct = |hashes: &[u128]| {
+        component
+            .iter()
+            .map(|&node| hashes[node])
+            .collect::<HashSet<_>>()
+            .len()
+    };
+    for &node in component {
+        let mut own = lines[node].clone();
+        own.sort();
+        hashes[node] = fnv1a(own.join("\n").as_bytes());
+    }
+    let mut classes = distinct(hashes);
+    for _ in 0..component.len() {
+        let refined: Vec<u128> = component
+            .iter()
+            .map(|&node| {
+                let mut neighbours: Vec<String> = links[node]
+                    .iter()
+                    .map(|(predicate, other)| format!("{} [{:032x}]", predicate, hashes[*other]))
+                    .collect();
+                neighbours.sort();
+                let line = format!("{:032x}\n{}", hashes[node], neighbours.join("\n"));
+                fnv1a(line.as_bytes())
+            })
+            .collect();
+        for (&node, hash) in component.iter().zip(refined) {
+            hashes[node] = hash;
+        }
+        let refined_classes = distinct(hashes);
+        if refined_classes == classes {
+            break;
+        }
+        classes = refined_classes;
+    }
+}
+
+/// Write a term out unambiguously, for hashing
+fn render<T: Term>(term: T) -> String {
+    match term.as_simple() {
+        SimpleTerm::Iri(iri) => format!("<{}>", iri.as_str()),
+        SimpleTerm::LiteralLanguage(lex, tag) => format!("{:?}@{}", lex.as_ref(), tag.as_str()),
+        SimpleTerm::LiteralDatatype(lex, datatype) => {
+            format!("{:?}^^<{}>", lex.as_ref(), datatype.as_str())
+        }
+        SimpleTerm::BlankNode(_) => "_:".to_string(),
+        SimpleTerm::Triple(triple) => {
+            let [s, p, o] = triple.as_ref();
+            format!("<< {} {} {} >>", render(s), render(p), render(o))
+        }
+        SimpleTerm::Variable(name) => format!("?{}", name.as_str()),
+    }
+}
+
+/// 128-bit FNV-1a, which unlike the standard library's hashers is the same
+/// on every run and platform
+fn fnv1a(bytes: &[u8]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    bytes.iter().fold(OFFSET, |hash, byte| {
+        (hash ^ u128::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+fn replace(term: SimpleTerm<'static>, iris: &HashMap<String, String>) -> SimpleTerm<'static> {
+    let iri = term.bnode_id().and_then(|id| iris.get(id.as_str()));
+    match iri {
+        Some(iri) => SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(iri.clone()))),
+        None => term,
+    }
+}
+
+fn rewrite(graph: &FastGraph, iris: &HashMap<String, String>) -> FastGraph {
+    let mut rewritten = FastGraph::new();
+    for t in graph.triples().flatten() {
+        let [s, p, o] = t.to_spo().map(|term| replace(term.into_term(), iris));
+        // Inserting into a fresh in-memory graph only fails when it is full
+        let _ = rewritten.insert(&s, &p, &o);
+    }
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        sn:b sn:related [ rdfs:label "anonymous" ; sn:part [ rdfs:label "inner" ] ] .
+        sn:c sn:related [ rdfs:label "anonymous" ] .
+    "#;
+
+    fn related(processor: &SemanticProcessor) -> Vec<String> {
+        let mut objects: Vec<String> = processor
+            .match_pattern(None, Some("sn:related"), None)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.object.value)
+            .collect();
+        objects.sort();
+        objects
+    }
+
+    #[test]
+    fn test_skolem_iris_are_stable_across_loads() {
+        let mut first = SemanticProcessor::new();
+        first.set_skolemize(true);
+        first.load_turtle(TTL, None).unwrap();
+
+        let mut second = SemanticProcessor::new();
+        second
+            .load_turtle("_:x <https://example.org/p> \"padding\" .", None)
+            .unwrap();
+        second.load_turtle(TTL, None).unwrap();
+        second.set_skolemize(true);
+
+        let objects = related(&first);
+        assert_eq!(objects.len(), 2);
+        assert_ne!(objects[0], objects[1]);
+        assert!(objects.iter().all(|iri| iri.starts_with(GENID)));
+        assert_eq!(objects, related(&second));
+        assert!(first
+            .graph
+            .triples()
+            .flatten()
+            .all(|t| !t.s().is_blank_node() && !t.o().is_blank_node()));
+    }
+
+    #[test]
+    fn test_nested_blank_nodes_under_different_subjects_stay_apart() {
+        let mut processor = SemanticProcessor::new();
+        processor.set_skolemize(true);
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:a sn:p [ sn:q [ sn:r "x" ] ] .
+                sn:b sn:p [ sn:q [ sn:r "x" ] ] .
+                "#,
+                None,
+            )
+            .unwrap();
+        assert_eq!(processor.triple_count(), 6);
+
+        let update = crate::sparql::parse_update(
+            "INSERT DATA { sn:c sn:p _:n . _:n sn:r \"y\" }",
+            &processor.namespaces,
+            &processor.aliases,
+        )
+        .unwrap();
+        processor.apply_update(&update).unwrap();
+        assert!(processor
+            .graph
+            .triples()
+            .flatten()
+            .all(|t| !t.s().is_blank_node() && !t.o().is_blank_node()));
+    }
+
+    #[test]
+    fn test_skolemize_off_keeps_blank_nodes() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        assert!(related(&processor)
+            .iter()
+            .all(|label| !label.starts_with(GENID)));
+    }
+}
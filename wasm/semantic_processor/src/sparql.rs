@@ -0,0 +1,1978 @@
+//! SPARQL subset for the Semantic Processor
+//!
+//! Supports SELECT queries made of basic graph patterns, FILTER and OPTIONAL,
+//! with DISTINCT, ORDER BY, LIMIT and OFFSET. Results are returned in the
+//! W3C SPARQL 1.1 Query Results JSON format. ASK queries accept the same
+//! graph patterns and return a plain boolean.
+//!
+//! Updates support INSERT DATA, DELETE DATA, DELETE/INSERT ... WHERE and
+//! DELETE WHERE, separated by `;`.
+//!
+//! Prefixes declared in the query take precedence over the processor's
+//! registered namespaces, which are available without a PREFIX declaration.
+//! Relative IRIs, including those of later PREFIX declarations, resolve
+//! against the query's BASE if it declares one.
+
+use crate::abort::check_flag;
+use crate::alias::Aliases;
+use crate::error::ProcessorError;
+use crate::{SemanticProcessor, TermOrAny};
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::ns::rdf;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{BnodeId, FromTerm, IriRef, LanguageTag, SimpleTerm, Term, TermKind};
+use sophia_api::triple::Triple;
+use sophia_api::MownStr;
+use sophia_inmem::graph::FastGraph;
+use sophia_iri::resolve::BaseIri;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use wasm_bindgen::prelude::*;
+
+/// A variable binding: variable name (without `?`) to bound term
+pub(crate) type Solution = HashMap<String, SimpleTerm<'static>>;
+
+// ---------------------------------------------------------------------------
+// Query AST
+// ---------------------------------------------------------------------------
+
+/// A position in a triple pattern
+#[derive(Debug, Clone)]
+pub(crate) enum PatternTerm {
+    Var(String),
+    Term(SimpleTerm<'static>),
+}
+
+/// A triple pattern, e.g. `?s rdfs:label ?label`
+#[derive(Debug, Clone)]
+pub(crate) struct TriplePattern {
+    pub s: PatternTerm,
+    pub p: PatternTerm,
+    pub o: PatternTerm,
+}
+
+/// An element of a `{ ... }` group graph pattern
+#[derive(Debug, Clone)]
+pub(crate) enum GroupElement {
+    Triple(TriplePattern),
+    Filter(Expr),
+    Optional(GroupPattern),
+    Group(GroupPattern),
+}
+
+/// A `{ ... }` group graph pattern
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GroupPattern {
+    pub elements: Vec<GroupElement>,
+}
+
+/// Comparison operators usable in FILTER expressions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// FILTER / ORDER BY expression
+#[derive(Debug, Clone)]
+pub(crate) enum Expr {
+    Var(String),
+    Term(SimpleTerm<'static>),
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    /// REGEX with a constant pattern and flags, compiled once at parse time
+    Regex(Box<Expr>, regex::Regex),
+}
+
+/// One ORDER BY key
+#[derive(Debug, Clone)]
+pub(crate) struct OrderKey {
+    pub expr: Expr,
+    pub descending: bool,
+}
+
+/// A parsed SELECT query
+#[derive(Debug, Clone)]
+pub(crate) struct SelectQuery {
+    pub distinct: bool,
+    /// Projected variables; `None` means `SELECT *`
+    pub projection: Option<Vec<String>>,
+    pub pattern: GroupPattern,
+    pub order_by: Vec<OrderKey>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+/// One operation of a SPARQL update request
+#[derive(Debug, Clone)]
+pub(crate) enum UpdateOperation {
+    InsertData(Vec<TriplePattern>),
+    DeleteData(Vec<TriplePattern>),
+    /// DELETE { .. } INSERT { .. } WHERE { .. }; either template may be empty
+    Modify {
+        delete: Vec<TriplePattern>,
+        insert: Vec<TriplePattern>,
+        pattern: GroupPattern,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// Results (SPARQL 1.1 Query Results JSON Format)
+// ---------------------------------------------------------------------------
+
+/// A single bound value in the results
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BindingValue {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datatype: Option<String>,
+    #[serde(rename = "xml:lang", skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+/// Header listing the projected variables
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultsHead {
+    pub vars: Vec<String>,
+}
+
+/// Solution sequence
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultsBindings {
+    pub bindings: Vec<BTreeMap<String, BindingValue>>,
+}
+
+/// Results of a SELECT query
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectResults {
+    pub head: ResultsHead,
+    pub results: ResultsBindings,
+}
+
+impl BindingValue {
+    pub(crate) fn from_term(term: &SimpleTerm) -> Self {
+        match term {
+            SimpleTerm::Iri(iri) => BindingValue {
+                kind: "uri".to_string(),
+                value: iri.as_str().to_string(),
+                datatype: None,
+                lang: None,
+            },
+            SimpleTerm::BlankNode(bn) => BindingValue {
+                kind: "bnode".to_string(),
+                value: bn.as_str().to_string(),
+                datatype: None,
+                lang: None,
+            },
+            SimpleTerm::LiteralLanguage(lex, tag) => BindingValue {
+                kind: "literal".to_string(),
+                value: lex.to_string(),
+                datatype: None,
+                lang: Some(tag.as_str().to_string()),
+            },
+            SimpleTerm::LiteralDatatype(lex, dt) => BindingValue {
+                kind: "literal".to_string(),
+                value: lex.to_string(),
+                datatype: Some(dt.as_str().to_string()).filter(|dt| dt != XSD_STRING),
+                lang: None,
+            },
+            SimpleTerm::Triple(_) => BindingValue {
+                kind: "triple".to_string(),
+                value: String::new(),
+                datatype: None,
+                lang: None,
+            },
+            SimpleTerm::Variable(v) => BindingValue {
+                kind: "variable".to_string(),
+                value: v.as_str().to_string(),
+                datatype: None,
+                lang: None,
+            },
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Run a SPARQL SELECT query against the graph
+    ///
+    /// Supports basic graph patterns, FILTER, OPTIONAL, DISTINCT,
+    /// ORDER BY, LIMIT and OFFSET.
+    ///
+    /// # Arguments
+    /// * `query` - SPARQL SELECT query string
+    ///
+    /// # Returns
+    /// JsValue containing results in the SPARQL 1.1 Query Results JSON format
+    #[wasm_bindgen(unchecked_return_type = "SelectResults")]
+    pub fn sparql_select(&self, query: &str) -> Result<JsValue, JsValue> {
+        let query = parse_select(query, &self.namespaces, &self.aliases)
+            .map_err(|e| ProcessorError::parse("SPARQL query", e))?;
+        let results = evaluate_select(&self.graph, &query, &self.abort)?;
+
+        self.output_json(&results)
+    }
+
+    /// Run a SPARQL ASK query against the graph
+    ///
+    /// Cheaper than `sparql_select` when only the existence of a match
+    /// matters, e.g. `ASK { ?e a sn:Entanglement }`.
+    ///
+    /// # Arguments
+    /// * `query` - SPARQL ASK query string
+    ///
+    /// # Returns
+    /// `true` if the pattern has at least one solution
+    pub fn sparql_ask(&self, query: &str) -> Result<bool, JsValue> {
+        let pattern = parse_ask(query, &self.namespaces, &self.aliases)
+            .map_err(|e| ProcessorError::parse("SPARQL query", e))?;
+        Ok(evaluate_ask(&self.graph, &pattern, &self.abort)?)
+    }
+
+    /// Apply a SPARQL update to the graph
+    ///
+    /// Supports INSERT DATA, DELETE DATA, DELETE/INSERT ... WHERE and
+    /// DELETE WHERE. Several operations may be separated by `;`; they are
+    /// applied in order. Deletions also apply to named graphs, insertions go
+    /// to the default graph only.
+    ///
+    /// # Arguments
+    /// * `update` - SPARQL update string
+    ///
+    /// # Returns
+    /// JsValue containing the number of triples inserted and deleted
+    #[wasm_bindgen(unchecked_return_type = "UpdateSummary")]
+    pub fn sparql_update(&mut self, update: &str) -> Result<JsValue, JsValue> {
+        let operations = parse_update(update, &self.namespaces, &self.aliases)
+            .map_err(|e| ProcessorError::parse("SPARQL update", e))?;
+        let summary = self.apply_update(&operations);
+        self.finish_edit();
+
+        self.output(&summary?)
+    }
+}
+
+/// Number of triples actually changed by a SPARQL update
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct UpdateSummary {
+    pub inserted: usize,
+    pub deleted: usize,
+}
+
+impl SemanticProcessor {
+    /// Apply parsed update operations in order
+    ///
+    /// If the abort flag is set, the operations already applied stay applied.
+    pub(crate) fn apply_update(
+        &mut self,
+        operations: &[UpdateOperation],
+    ) -> Result<UpdateSummary, ProcessorError> {
+        let mut summary = UpdateSummary::default();
+        // Blank nodes are all minted before any is inserted, so the graph
+        // alone cannot tell which labels this update has used
+        let mut next_blank = 0;
+
+        for operation in operations {
+            self.check_abort()?;
+            let (delete, insert) = match operation {
+                UpdateOperation::InsertData(triples) => {
+                    let mut blanks = HashMap::new();
+                    let insert = triples
+                        .iter()
+                        .filter_map(|t| {
+                            self.instantiate(t, &Solution::new(), &mut blanks, &mut next_blank)
+                        })
+                        .collect();
+                    (Vec::new(), insert)
+                }
+                UpdateOperation::DeleteData(triples) => {
+                    let delete = triples
+                        .iter()
+                        .filter_map(|t| ground_triple(t, &Solution::new()))
+                        .collect();
+                    (delete, Vec::new())
+                }
+                UpdateOperation::Modify {
+                    delete,
+                    insert,
+                    pattern,
+                } => {
+                    // Every solution is computed before the graph is touched
+                    let solutions =
+                        evaluate_group(&self.graph, pattern, vec![Solution::new()], &self.abort)?;
+                    let mut deletions = Vec::new();
+                    let mut insertions = Vec::new();
+                    for solution in &solutions {
+                        deletions.extend(delete.iter().filter_map(|t| ground_triple(t, solution)));
+                        let mut blanks = HashMap::new();
+                        for template in insert {
+                            if let Some(triple) =
+                                self.instantiate(template, solution, &mut blanks, &mut next_blank)
+                            {
+                                insertions.push(triple);
+                            }
+                        }
+                    }
+                    (deletions, insertions)
+                }
+            };
+
+            let insert = self.skolemize_triples(insert);
+            for triple in &delete {
+                if self.remove_terms(triple) {
+                    summary.deleted += 1;
+                }
+            }
+            for triple in &insert {
+                if self.add_terms(triple) {
+                    summary.inserted += 1;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Instantiate an INSERT template, minting fresh blank nodes for its
+    /// blank node labels
+    fn instantiate(
+        &mut self,
+        template: &TriplePattern,
+        solution: &Solution,
+        blanks: &mut HashMap<String, SimpleTerm<'static>>,
+        next_blank: &mut usize,
+    ) -> Option<[SimpleTerm<'static>; 3]> {
+        let mut resolve = |term: &PatternTerm| match term {
+            PatternTerm::Var(name) if name.starts_with("_:") => Some(
+                blanks
+                    .entry(name.clone())
+                    .or_insert_with(|| self.fresh_blank_node(next_blank))
+                    .clone(),
+            ),
+            PatternTerm::Var(name) => solution.get(name).cloned(),
+            PatternTerm::Term(term) => Some(term.clone()),
+        };
+        Some([
+            resolve(&template.s)?,
+            resolve(&template.p)?,
+            resolve(&template.o)?,
+        ])
+    }
+
+    /// A blank node whose label is not used in the graph yet, numbered from
+    /// `next` so that it differs from those minted earlier in the update
+    fn fresh_blank_node(&self, next: &mut usize) -> SimpleTerm<'static> {
+        let (n, bnode) = (*next..)
+            .map(|n| {
+                let label = MownStr::from(format!("u{}", n));
+                (n, SimpleTerm::BlankNode(BnodeId::new_unchecked(label)))
+            })
+            .find(|(_, bnode)| {
+                self.graph
+                    .triples_matching([bnode], Any, Any)
+                    .next()
+                    .is_none()
+                    && self
+                        .graph
+                        .triples_matching(Any, Any, [bnode])
+                        .next()
+                        .is_none()
+            })
+            .expect("blank node labels are unbounded");
+        *next = n + 1;
+        bnode
+    }
+}
+
+/// Resolve a DELETE template against a solution; blank nodes never match
+pub(crate) fn ground_triple(
+    template: &TriplePattern,
+    solution: &Solution,
+) -> Option<[SimpleTerm<'static>; 3]> {
+    let resolve = |term: &PatternTerm| match term {
+        PatternTerm::Var(name) => solution.get(name).cloned(),
+        PatternTerm::Term(term) => Some(term.clone()),
+    };
+    Some([
+        resolve(&template.s)?,
+        resolve(&template.p)?,
+        resolve(&template.o)?,
+    ])
+}
+
+// ---------------------------------------------------------------------------
+// Lexer
+// ---------------------------------------------------------------------------
+
+const XSD_NS: &str = "http://www.w3.org/2001/XMLSchema#";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+const XSD_DECIMAL: &str = "http://www.w3.org/2001/XMLSchema#decimal";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Iri(String),
+    PrefixedName(String, String),
+    Var(String),
+    BlankNode(String),
+    Str(String),
+    LangTag(String),
+    Number(String),
+    Word(String),
+    Punct(&'static str),
+}
+
+const PUNCTUATION: [&str; 20] = [
+    "^^", "&&", "||", "!=", "<=", ">=", "{", "}", "(", ")", ".", ";", ",", "=", "<", ">", "!", "*",
+    "[", "]",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '<' && iri_end(&chars, i).is_some() {
+            let end = iri_end(&chars, i).unwrap_or(i);
+            tokens.push(Token::Iri(chars[i + 1..end].iter().collect()));
+            i = end + 1;
+        } else if c == '?' || c == '$' {
+            let start = i + 1;
+            i = start;
+            // Unlike other names, variable names hold no '-' or '.'
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            if i == start {
+                return Err(format!("empty variable name at offset {}", start - 1));
+            }
+            tokens.push(Token::Var(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let (value, next) = read_string(&chars, i)?;
+            tokens.push(Token::Str(value));
+            i = next;
+        } else if c == '@' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '-') {
+                i += 1;
+            }
+            tokens.push(Token::LangTag(chars[start..i].iter().collect()));
+        } else if c == '_' && chars.get(i + 1) == Some(&':') {
+            let start = i + 2;
+            i = start;
+            while i < chars.len() && is_name_char(chars[i]) {
+                i += 1;
+            }
+            // As for prefixed names, a trailing '.' ends the triple
+            while i > start && chars[i - 1] == '.' {
+                i -= 1;
+            }
+            tokens.push(Token::BlankNode(chars[start..i].iter().collect()));
+        } else if c.is_ascii_digit()
+            || ((c == '+' || c == '-') && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            if i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit() {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+                if i < chars.len() && (chars[i] == '+' || chars[i] == '-') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if c.is_alphabetic() || c == ':' {
+            let start = i;
+            while i < chars.len() && (is_name_char(chars[i]) || chars[i] == ':') {
+                i += 1;
+            }
+            let mut word: String = chars[start..i].iter().collect();
+            // A trailing '.' ends the triple, it is not part of the local name
+            while word.ends_with('.') {
+                word.pop();
+                i -= 1;
+            }
+            match word.split_once(':') {
+                Some((prefix, local)) => {
+                    tokens.push(Token::PrefixedName(prefix.to_string(), local.to_string()))
+                }
+                None => tokens.push(Token::Word(word)),
+            }
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            match PUNCTUATION.iter().find(|p| rest.starts_with(**p)) {
+                Some(p) => {
+                    tokens.push(Token::Punct(p));
+                    i += p.len();
+                }
+                None => return Err(format!("unexpected character '{}' at offset {}", c, i)),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+/// Find the closing '>' of an IRI starting at `start`, if this is an IRI
+fn iri_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '>' => return Some(i),
+            c if c.is_whitespace() || c == '<' || c == '"' || c == '{' || c == '}' => return None,
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Read a quoted string starting at `start`; returns the value and the next offset
+fn read_string(chars: &[char], start: usize) -> Result<(String, usize), String> {
+    let quote = chars[start];
+    let long = chars.get(start + 1) == Some(&quote) && chars.get(start + 2) == Some(&quote);
+    let mut i = if long { start + 3 } else { start + 1 };
+    let mut value = String::new();
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' {
+            let escaped = chars
+                .get(i + 1)
+                .ok_or_else(|| format!("unterminated string at offset {}", start))?;
+            value.push(match escaped {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                other => *other,
+            });
+            i += 2;
+        } else if c == quote {
+            if !long {
+                return Ok((value, i + 1));
+            }
+            if chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote) {
+                return Ok((value, i + 3));
+            }
+            value.push(c);
+            i += 1;
+        } else if c == '\n' && !long {
+            break;
+        } else {
+            value.push(c);
+            i += 1;
+        }
+    }
+
+    Err(format!("unterminated string at offset {}", start))
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    prefixes: HashMap<String, String>,
+    base: Option<BaseIri<String>>,
+    namespaces: &'a HashMap<String, String>,
+    aliases: &'a Aliases,
+    blank_counter: usize,
+}
+
+/// Parse a SPARQL SELECT query
+pub(crate) fn parse_select(
+    input: &str,
+    namespaces: &HashMap<String, String>,
+    aliases: &Aliases,
+) -> Result<SelectQuery, String> {
+    let mut parser = Parser::new(input, namespaces, aliases)?;
+    parser.parse_prologue()?;
+    let query = parser.parse_select_query()?;
+    parser.expect_end()?;
+    Ok(query)
+}
+
+/// Parse a SPARQL ASK query into its graph pattern
+pub(crate) fn parse_ask(
+    input: &str,
+    namespaces: &HashMap<String, String>,
+    aliases: &Aliases,
+) -> Result<GroupPattern, String> {
+    let mut parser = Parser::new(input, namespaces, aliases)?;
+    parser.parse_prologue()?;
+    parser.expect_keyword("ASK")?;
+    parser.eat_keyword("WHERE");
+    let pattern = parser.parse_group()?;
+    parser.expect_end()?;
+    Ok(pattern)
+}
+
+/// Parse a single term in SPARQL syntax: `<iri>`, `prefix:local`, `_:label`
+/// or a literal such as `"text"@en`, `"3"^^xsd:integer` or `42`
+pub(crate) fn parse_term_str(
+    input: &str,
+    namespaces: &HashMap<String, String>,
+    aliases: &Aliases,
+) -> Result<SimpleTerm<'static>, String> {
+    let mut terms = parse_term_list(input, namespaces, aliases)?;
+    match (terms.pop(), terms.is_empty()) {
+        (Some(term), true) => Ok(term),
+        (None, _) => Err("expected a term, found end of input".to_string()),
+        (Some(_), false) => Err("expected a single term".to_string()),
+    }
+}
+
+/// Parse a whitespace-separated sequence of terms in SPARQL syntax
+pub(crate) fn parse_term_list(
+    input: &str,
+    namespaces: &HashMap<String, String>,
+    aliases: &Aliases,
+) -> Result<Vec<SimpleTerm<'static>>, String> {
+    let mut parser = Parser::new(input, namespaces, aliases)?;
+    let mut terms = Vec::new();
+
+    while parser.peek().is_some() {
+        let blank_label = match parser.peek() {
+            Some(Token::BlankNode(label)) => Some(label.clone()),
+            // RDF Patch writes blank nodes as `<_:label>`
+            Some(Token::Iri(iri)) => iri.strip_prefix("_:").map(str::to_string),
+            _ => None,
+        };
+        let term = match blank_label {
+            Some(label) => {
+                parser.pos += 1;
+                SimpleTerm::BlankNode(BnodeId::new_unchecked(MownStr::from(label)))
+            }
+            None => parser.parse_term()?,
+        };
+        terms.push(term);
+    }
+
+    Ok(terms)
+}
+
+/// Parse a SPARQL update request into its operations
+pub(crate) fn parse_update(
+    input: &str,
+    namespaces: &HashMap<String, String>,
+    aliases: &Aliases,
+) -> Result<Vec<UpdateOperation>, String> {
+    let mut parser = Parser::new(input, namespaces, aliases)?;
+    let mut operations = Vec::new();
+
+    loop {
+        parser.parse_prologue()?;
+        if parser.peek().is_none() {
+            break;
+        }
+        operations.push(parser.parse_update_operation()?);
+        if !parser.eat_punct(";") {
+            break;
+        }
+    }
+    parser.expect_end()?;
+
+    if operations.is_empty() {
+        return Err("empty update".to_string());
+    }
+    Ok(operations)
+}
+
+impl<'a> Parser<'a> {
+    fn new(
+        input: &str,
+        namespaces: &'a HashMap<String, String>,
+        aliases: &'a Aliases,
+    ) -> Result<Self, String> {
+        Ok(Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+            prefixes: HashMap::new(),
+            base: None,
+            namespaces,
+            aliases,
+            blank_counter: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("{:?}", token),
+            None => "end of query".to_string(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {}, found {}",
+                keyword,
+                self.describe_next()
+            ))
+        }
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        if self.is_punct(punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected '{}', found {}",
+                punct,
+                self.describe_next()
+            ))
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(format!("unexpected trailing {}", self.describe_next())),
+        }
+    }
+
+    /// PREFIX and BASE declarations
+    fn parse_prologue(&mut self) -> Result<(), String> {
+        loop {
+            if self.eat_keyword("PREFIX") {
+                let prefix = match self.next() {
+                    Some(Token::PrefixedName(prefix, local)) if local.is_empty() => prefix,
+                    other => return Err(format!("expected prefix name, found {:?}", other)),
+                };
+                let iri = match self.next() {
+                    Some(Token::Iri(iri)) => self.resolve(iri)?,
+                    other => return Err(format!("expected namespace IRI, found {:?}", other)),
+                };
+                self.prefixes.insert(prefix, iri);
+            } else if self.eat_keyword("BASE") {
+                let iri = match self.next() {
+                    Some(Token::Iri(iri)) => self.resolve(iri)?,
+                    other => return Err(format!("expected base IRI, found {:?}", other)),
+                };
+                let base = BaseIri::new(iri)
+                    .map_err(|e| format!("invalid base IRI, expected an absolute IRI: {}", e))?;
+                self.base = Some(base);
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_update_operation(&mut self) -> Result<UpdateOperation, String> {
+        if self.eat_keyword("INSERT") {
+            if self.eat_keyword("DATA") {
+                return Ok(UpdateOperation::InsertData(self.parse_data_block()?));
+            }
+            let insert = self.parse_template()?;
+            self.expect_keyword("WHERE")?;
+            let pattern = self.parse_group()?;
+            return Ok(UpdateOperation::Modify {
+                delete: Vec::new(),
+                insert,
+                pattern,
+            });
+        }
+
+        self.expect_keyword("DELETE")?;
+        if self.eat_keyword("DATA") {
+            let triples = self.parse_data_block()?;
+            if triples.iter().any(|t| {
+                [&t.s, &t.p, &t.o]
+                    .iter()
+                    .any(|term| matches!(term, PatternTerm::Var(name) if name.starts_with("_:")))
+            }) {
+                return Err("blank nodes are not allowed in DELETE DATA".to_string());
+            }
+            return Ok(UpdateOperation::DeleteData(triples));
+        }
+        if self.eat_keyword("WHERE") {
+            let delete = self.parse_template()?;
+            let pattern = GroupPattern {
+                elements: delete.iter().cloned().map(GroupElement::Triple).collect(),
+            };
+            return Ok(UpdateOperation::Modify {
+                delete,
+                insert: Vec::new(),
+                pattern,
+            });
+        }
+
+        let delete = self.parse_template()?;
+        let insert = if self.eat_keyword("INSERT") {
+            self.parse_template()?
+        } else {
+            Vec::new()
+        };
+        self.expect_keyword("WHERE")?;
+        let pattern = self.parse_group()?;
+        Ok(UpdateOperation::Modify {
+            delete,
+            insert,
+            pattern,
+        })
+    }
+
+    /// `{ triples }` without variables, as used by INSERT DATA / DELETE DATA
+    fn parse_data_block(&mut self) -> Result<Vec<TriplePattern>, String> {
+        let triples = self.parse_template()?;
+        let has_variable = triples.iter().any(|t| {
+            [&t.s, &t.p, &t.o]
+                .iter()
+                .any(|term| matches!(term, PatternTerm::Var(name) if !name.starts_with("_:")))
+        });
+        if has_variable {
+            return Err("variables are not allowed in a DATA block".to_string());
+        }
+        Ok(triples)
+    }
+
+    /// `{ triples }` quad template
+    fn parse_template(&mut self) -> Result<Vec<TriplePattern>, String> {
+        self.expect_punct("{")?;
+        let mut triples = Vec::new();
+        loop {
+            if self.eat_punct("}") {
+                return Ok(triples);
+            } else if self.eat_punct(".") {
+                continue;
+            } else if self.peek().is_none() {
+                return Err("unterminated template: expected '}'".to_string());
+            } else {
+                triples.extend(self.parse_triples_same_subject()?);
+            }
+        }
+    }
+
+    fn parse_select_query(&mut self) -> Result<SelectQuery, String> {
+        self.expect_keyword("SELECT")?;
+        let distinct = self.eat_keyword("DISTINCT") || self.eat_keyword("REDUCED");
+
+        let projection = if self.eat_punct("*") {
+            None
+        } else {
+            let mut vars = Vec::new();
+            while let Some(Token::Var(name)) = self.peek() {
+                vars.push(name.clone());
+                self.pos += 1;
+            }
+            if vars.is_empty() {
+                return Err(format!(
+                    "expected variables or '*', found {}",
+                    self.describe_next()
+                ));
+            }
+            Some(vars)
+        };
+
+        self.eat_keyword("WHERE");
+        let pattern = self.parse_group()?;
+        let (order_by, limit, offset) = self.parse_solution_modifiers()?;
+
+        Ok(SelectQuery {
+            distinct,
+            projection,
+            pattern,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    fn parse_solution_modifiers(
+        &mut self,
+    ) -> Result<(Vec<OrderKey>, Option<usize>, usize), String> {
+        let mut order_by = Vec::new();
+        let mut limit = None;
+        let mut offset = 0;
+
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                if self.eat_keyword("ASC") || self.is_keyword("DESC") {
+                    let descending = self.eat_keyword("DESC");
+                    self.expect_punct("(")?;
+                    let expr = self.parse_expression()?;
+                    self.expect_punct(")")?;
+                    order_by.push(OrderKey { expr, descending });
+                } else if let Some(Token::Var(name)) = self.peek() {
+                    order_by.push(OrderKey {
+                        expr: Expr::Var(name.clone()),
+                        descending: false,
+                    });
+                    self.pos += 1;
+                } else if self.is_punct("(") {
+                    self.pos += 1;
+                    let expr = self.parse_expression()?;
+                    self.expect_punct(")")?;
+                    order_by.push(OrderKey {
+                        expr,
+                        descending: false,
+                    });
+                } else {
+                    break;
+                }
+            }
+            if order_by.is_empty() {
+                return Err(format!(
+                    "expected ORDER BY condition, found {}",
+                    self.describe_next()
+                ));
+            }
+        }
+
+        loop {
+            if self.eat_keyword("LIMIT") {
+                limit = Some(self.parse_count()?);
+            } else if self.eat_keyword("OFFSET") {
+                offset = self.parse_count()?;
+            } else {
+                break;
+            }
+        }
+
+        Ok((order_by, limit, offset))
+    }
+
+    fn parse_count(&mut self) -> Result<usize, String> {
+        match self.next() {
+            Some(Token::Number(n)) => n
+                .parse()
+                .map_err(|_| format!("expected a non-negative integer, found {}", n)),
+            other => Err(format!("expected a number, found {:?}", other)),
+        }
+    }
+
+    /// `{ ... }` group graph pattern
+    fn parse_group(&mut self) -> Result<GroupPattern, String> {
+        self.expect_punct("{")?;
+        let mut group = GroupPattern::default();
+
+        loop {
+            if self.eat_punct("}") {
+                return Ok(group);
+            } else if self.eat_keyword("FILTER") {
+                let expr = if self.is_punct("(") {
+                    self.pos += 1;
+                    let expr = self.parse_expression()?;
+                    self.expect_punct(")")?;
+                    expr
+                } else {
+                    self.parse_primary_expression()?
+                };
+                group.elements.push(GroupElement::Filter(expr));
+            } else if self.eat_keyword("OPTIONAL") {
+                let optional = self.parse_group()?;
+                group.elements.push(GroupElement::Optional(optional));
+            } else if self.is_punct("{") {
+                let nested = self.parse_group()?;
+                group.elements.push(GroupElement::Group(nested));
+            } else if self.eat_punct(".") {
+                continue;
+            } else if self.peek().is_none() {
+                return Err("unterminated group pattern: expected '}'".to_string());
+            } else {
+                for triple in self.parse_triples_same_subject()? {
+                    group.elements.push(GroupElement::Triple(triple));
+                }
+            }
+        }
+    }
+
+    /// `subject verb object (, object)* (; verb object (, object)*)*`
+    fn parse_triples_same_subject(&mut self) -> Result<Vec<TriplePattern>, String> {
+        let subject = self.parse_pattern_term()?;
+        let mut triples = Vec::new();
+
+        loop {
+            let predicate = if matches!(self.peek(), Some(Token::Word(w)) if w == "a") {
+                self.pos += 1;
+                PatternTerm::Term(rdf::type_.into_term())
+            } else {
+                self.parse_pattern_term()?
+            };
+
+            loop {
+                let object = self.parse_pattern_term()?;
+                triples.push(TriplePattern {
+                    s: subject.clone(),
+                    p: predicate.clone(),
+                    o: object,
+                });
+                if !self.eat_punct(",") {
+                    break;
+                }
+            }
+
+            if !self.eat_punct(";") {
+                break;
+            }
+            // Allow a trailing ';' before '.' or '}'
+            if self.is_punct(".") || self.is_punct("}") {
+                break;
+            }
+        }
+
+        Ok(triples)
+    }
+
+    fn parse_pattern_term(&mut self) -> Result<PatternTerm, String> {
+        match self.peek() {
+            Some(Token::Var(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(PatternTerm::Var(name))
+            }
+            Some(Token::BlankNode(label)) => {
+                // Blank nodes in patterns behave as non-projectable variables
+                let name = format!("_:{}", label);
+                self.pos += 1;
+                Ok(PatternTerm::Var(name))
+            }
+            Some(Token::Punct("[")) => {
+                self.pos += 1;
+                self.expect_punct("]")?;
+                self.blank_counter += 1;
+                Ok(PatternTerm::Var(format!("_:anon{}", self.blank_counter)))
+            }
+            _ => self.parse_term().map(PatternTerm::Term),
+        }
+    }
+
+    /// IRI, prefixed name or literal
+    fn parse_term(&mut self) -> Result<SimpleTerm<'static>, String> {
+        match self.next() {
+            Some(Token::Iri(iri)) => self.iri(iri),
+            Some(Token::PrefixedName(prefix, local)) => {
+                let namespace = self
+                    .prefixes
+                    .get(&prefix)
+                    .or_else(|| self.namespaces.get(&prefix))
+                    .ok_or_else(|| format!("unknown prefix '{}:'", prefix))?;
+                self.iri(format!("{}{}", namespace, local))
+            }
+            Some(Token::Str(value)) => {
+                if let Some(Token::LangTag(tag)) = self.peek() {
+                    let tag = tag.clone();
+                    self.pos += 1;
+                    Ok(SimpleTerm::LiteralLanguage(
+                        MownStr::from(value),
+                        LanguageTag::new_unchecked(MownStr::from(tag)),
+                    ))
+                } else if self.eat_punct("^^") {
+                    match self.parse_term()? {
+                        SimpleTerm::Iri(datatype) => Ok(typed_literal(&value, datatype.as_str())),
+                        other => Err(format!("expected datatype IRI, found {:?}", other)),
+                    }
+                } else {
+                    Ok(typed_literal(&value, XSD_STRING))
+                }
+            }
+            Some(Token::Number(n)) => {
+                let datatype = if n.contains(['e', 'E']) {
+                    XSD_DOUBLE
+                } else if n.contains('.') {
+                    XSD_DECIMAL
+                } else {
+                    XSD_INTEGER
+                };
+                Ok(typed_literal(&n, datatype))
+            }
+            Some(Token::Word(w)) if w == "true" || w == "false" => {
+                Ok(typed_literal(&w, XSD_BOOLEAN))
+            }
+            other => Err(format!("expected a term, found {:?}", other)),
+        }
+    }
+
+    /// IRI term from query text, rewritten out of any namespace alias and
+    /// rejected if it is not a valid IRI reference
+    fn iri(&self, iri: String) -> Result<SimpleTerm<'static>, String> {
+        let iri = self.resolve(iri)?;
+        let iri = self.aliases.iri(&iri).unwrap_or(iri);
+        IriRef::new(MownStr::from(iri))
+            .map(SimpleTerm::Iri)
+            .map_err(|e| format!("invalid IRI: {}", e))
+    }
+
+    /// IRI reference resolved against the query's BASE, if it has one
+    fn resolve(&self, iri: String) -> Result<String, String> {
+        match &self.base {
+            Some(base) => base
+                .resolve(iri.as_str())
+                .map(|iri| iri.as_str().to_string())
+                .map_err(|e| format!("invalid IRI: {}", e)),
+            None => Ok(iri),
+        }
+    }
+
+    // Expressions, by increasing precedence: || then && then comparison then unary
+
+    fn parse_expression(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.eat_punct("||") {
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_comparison()?;
+        while self.eat_punct("&&") {
+            let right = self.parse_comparison()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let left = self.parse_unary()?;
+        let op = match self.peek() {
+            Some(Token::Punct("=")) => CompareOp::Eq,
+            Some(Token::Punct("!=")) => CompareOp::Ne,
+            Some(Token::Punct("<")) => CompareOp::Lt,
+            Some(Token::Punct("<=")) => CompareOp::Le,
+            Some(Token::Punct(">")) => CompareOp::Gt,
+            Some(Token::Punct(">=")) => CompareOp::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.parse_unary()?;
+        Ok(Expr::Compare(op, Box::new(left), Box::new(right)))
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat_punct("!") {
+            Ok(Expr::Not(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_primary_expression()
+        }
+    }
+
+    fn parse_primary_expression(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Punct("(")) => {
+                self.pos += 1;
+                let expr = self.parse_expression()?;
+                self.expect_punct(")")?;
+                Ok(expr)
+            }
+            Some(Token::Var(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(Expr::Var(name))
+            }
+            Some(Token::Word(w)) if w != "true" && w != "false" => {
+                let name = w.to_ascii_uppercase();
+                self.pos += 1;
+                self.expect_punct("(")?;
+                let mut args = Vec::new();
+                if !self.eat_punct(")") {
+                    loop {
+                        args.push(self.parse_expression()?);
+                        if self.eat_punct(")") {
+                            break;
+                        }
+                        self.expect_punct(",")?;
+                    }
+                }
+                if name == "REGEX" {
+                    if let Some(regex) = constant_regex(&args) {
+                        return Ok(Expr::Regex(Box::new(args.swap_remove(0)), regex));
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            _ => self.parse_term().map(Expr::Term),
+        }
+    }
+}
+
+pub(crate) fn iri_term(iri: &str) -> SimpleTerm<'static> {
+    SimpleTerm::Iri(IriRef::new_unchecked(MownStr::from(iri.to_string())))
+}
+
+pub(crate) fn typed_literal(value: &str, datatype: &str) -> SimpleTerm<'static> {
+    SimpleTerm::LiteralDatatype(
+        MownStr::from(value.to_string()),
+        IriRef::new_unchecked(MownStr::from(datatype.to_string())),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation
+// ---------------------------------------------------------------------------
+
+/// Evaluate a SELECT query against a graph, until `abort` is set
+pub(crate) fn evaluate_select(
+    graph: &FastGraph,
+    query: &SelectQuery,
+    abort: &Cell<bool>,
+) -> Result<SelectResults, ProcessorError> {
+    let mut solutions = evaluate_group(graph, &query.pattern, vec![Solution::new()], abort)?;
+
+    if !query.order_by.is_empty() {
+        solutions.sort_by(|a, b| {
+            for key in &query.order_by {
+                let ordering = compare_order(
+                    eval_expr(&key.expr, a).as_ref(),
+                    eval_expr(&key.expr, b).as_ref(),
+                );
+                let ordering = if key.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        });
+    }
+
+    let vars = match &query.projection {
+        Some(vars) => vars.clone(),
+        None => {
+            let mut vars = Vec::new();
+            collect_group_vars(&query.pattern, &mut vars);
+            vars
+        }
+    };
+
+    let mut bindings: Vec<BTreeMap<String, BindingValue>> = solutions
+        .iter()
+        .map(|solution| {
+            vars.iter()
+                .filter_map(|var| {
+                    solution
+                        .get(var)
+                        .map(|term| (var.clone(), BindingValue::from_term(term)))
+                })
+                .collect()
+        })
+        .collect();
+
+    if query.distinct {
+        let mut seen = Vec::new();
+        bindings.retain(|binding| {
+            if seen.iter().any(|b| b == binding) {
+                false
+            } else {
+                seen.push(binding.clone());
+                true
+            }
+        });
+    }
+
+    let bindings = bindings
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    Ok(SelectResults {
+        head: ResultsHead { vars },
+        results: ResultsBindings { bindings },
+    })
+}
+
+/// Evaluate an ASK query's pattern: does it have at least one solution?
+pub(crate) fn evaluate_ask(
+    graph: &FastGraph,
+    pattern: &GroupPattern,
+    abort: &Cell<bool>,
+) -> Result<bool, ProcessorError> {
+    Ok(!evaluate_group(graph, pattern, vec![Solution::new()], abort)?.is_empty())
+}
+
+/// Evaluate a group graph pattern, extending each input solution
+///
+/// Fails with an `Aborted` error once `abort` is set, checked before each
+/// solution is joined with the next pattern.
+pub(crate) fn evaluate_group(
+    graph: &FastGraph,
+    group: &GroupPattern,
+    input: Vec<Solution>,
+    abort: &Cell<bool>,
+) -> Result<Vec<Solution>, ProcessorError> {
+    let mut solutions = input;
+    let mut filters = Vec::new();
+
+    for element in &group.elements {
+        match element {
+            GroupElement::Triple(pattern) => {
+                let mut joined = Vec::new();
+                for solution in &solutions {
+                    check_flag(abort)?;
+                    joined.extend(match_pattern(graph, pattern, solution));
+                }
+                solutions = joined;
+            }
+            GroupElement::Optional(optional) => {
+                let mut joined = Vec::new();
+                for solution in solutions {
+                    let extended = evaluate_group(graph, optional, vec![solution.clone()], abort)?;
+                    if extended.is_empty() {
+                        joined.push(solution);
+                    } else {
+                        joined.extend(extended);
+                    }
+                }
+                solutions = joined;
+            }
+            GroupElement::Group(nested) => {
+                solutions = evaluate_group(graph, nested, solutions, abort)?;
+            }
+            // Filters apply to the whole group, whatever their position
+            GroupElement::Filter(expr) => filters.push(expr),
+        }
+    }
+
+    solutions.retain(|solution| {
+        filters
+            .iter()
+            .all(|f| effective_boolean(eval_expr(f, solution).as_ref()))
+    });
+    Ok(solutions)
+}
+
+/// Match a single triple pattern against the graph under an existing solution
+fn match_pattern(graph: &FastGraph, pattern: &TriplePattern, solution: &Solution) -> Vec<Solution> {
+    let resolve = |term: &PatternTerm| match term {
+        PatternTerm::Term(t) => Some(t.clone()),
+        PatternTerm::Var(name) => solution.get(name).cloned(),
+    };
+
+    let mut results = Vec::new();
+    let matches = graph.triples_matching(
+        TermOrAny(resolve(&pattern.s)),
+        TermOrAny(resolve(&pattern.p)),
+        TermOrAny(resolve(&pattern.o)),
+    );
+
+    for triple in matches.flatten() {
+        let mut extended = solution.clone();
+        if bind(&mut extended, &pattern.s, triple.s())
+            && bind(&mut extended, &pattern.p, triple.p())
+            && bind(&mut extended, &pattern.o, triple.o())
+        {
+            results.push(extended);
+        }
+    }
+    results
+}
+
+/// Bind a variable to a term; fails if already bound to a different term
+fn bind<T: Term>(solution: &mut Solution, pattern: &PatternTerm, term: T) -> bool {
+    match pattern {
+        PatternTerm::Term(_) => true,
+        PatternTerm::Var(name) => {
+            let term = SimpleTerm::from_term(term);
+            match solution.get(name) {
+                Some(existing) => *existing == term,
+                None => {
+                    solution.insert(name.clone(), term);
+                    true
+                }
+            }
+        }
+    }
+}
+
+fn collect_group_vars(group: &GroupPattern, vars: &mut Vec<String>) {
+    for element in &group.elements {
+        match element {
+            GroupElement::Triple(pattern) => {
+                for term in [&pattern.s, &pattern.p, &pattern.o] {
+                    if let PatternTerm::Var(name) = term {
+                        if !name.starts_with("_:") && !vars.contains(name) {
+                            vars.push(name.clone());
+                        }
+                    }
+                }
+            }
+            GroupElement::Optional(nested) | GroupElement::Group(nested) => {
+                collect_group_vars(nested, vars)
+            }
+            GroupElement::Filter(_) => {}
+        }
+    }
+}
+
+/// Evaluate an expression; `None` stands for an evaluation error or unbound value
+pub(crate) fn eval_expr(expr: &Expr, solution: &Solution) -> Option<SimpleTerm<'static>> {
+    match expr {
+        Expr::Var(name) => solution.get(name).cloned(),
+        Expr::Term(term) => Some(term.clone()),
+        Expr::Or(left, right) => {
+            let left = eval_expr(left, solution);
+            let right = eval_expr(right, solution);
+            Some(boolean(
+                effective_boolean(left.as_ref()) || effective_boolean(right.as_ref()),
+            ))
+        }
+        Expr::And(left, right) => {
+            let left = eval_expr(left, solution);
+            let right = eval_expr(right, solution);
+            Some(boolean(
+                effective_boolean(left.as_ref()) && effective_boolean(right.as_ref()),
+            ))
+        }
+        Expr::Not(inner) => {
+            let inner = eval_expr(inner, solution)?;
+            Some(boolean(!effective_boolean(Some(&inner))))
+        }
+        Expr::Compare(op, left, right) => {
+            let left = eval_expr(left, solution)?;
+            let right = eval_expr(right, solution)?;
+            compare_terms(*op, &left, &right).map(boolean)
+        }
+        Expr::Call(name, args) => eval_call(name, args, solution),
+        Expr::Regex(text, regex) => {
+            let text = term_lexical(&eval_expr(text, solution)?)?;
+            Some(boolean(regex.is_match(&text)))
+        }
+    }
+}
+
+/// The compiled pattern of a REGEX call whose pattern and flags are
+/// constants, or `None` if they depend on the solution or do not compile
+fn constant_regex(args: &[Expr]) -> Option<regex::Regex> {
+    if args.len() > 3 {
+        return None;
+    }
+    let constant = |arg: &Expr| match arg {
+        Expr::Term(term) => term_lexical(term),
+        _ => None,
+    };
+    let pattern = constant(args.get(1)?)?;
+    let flags = match args.get(2) {
+        Some(flags) => constant(flags)?,
+        None => String::new(),
+    };
+    regex::Regex::new(&regex_pattern(pattern, &flags)).ok()
+}
+
+/// A REGEX pattern with its flags applied
+fn regex_pattern(pattern: String, flags: &str) -> String {
+    if flags.contains('i') {
+        format!("(?i){}", pattern)
+    } else {
+        pattern
+    }
+}
+
+fn eval_call(name: &str, args: &[Expr], solution: &Solution) -> Option<SimpleTerm<'static>> {
+    if name == "BOUND" {
+        return match args.first() {
+            Some(Expr::Var(var)) => Some(boolean(solution.contains_key(var))),
+            _ => None,
+        };
+    }
+
+    let values: Vec<SimpleTerm<'static>> = args
+        .iter()
+        .map(|arg| eval_expr(arg, solution))
+        .collect::<Option<_>>()?;
+    let first = values.first()?;
+
+    match name {
+        "STR" => Some(typed_literal(&term_lexical(first)?, XSD_STRING)),
+        "LANG" => Some(typed_literal(
+            &first
+                .language_tag()
+                .map(|tag| tag.as_str().to_string())
+                .unwrap_or_default(),
+            XSD_STRING,
+        )),
+        "DATATYPE" => first.datatype().map(|dt| iri_term(dt.as_str())),
+        "LCASE" => Some(typed_literal(
+            &term_lexical(first)?.to_lowercase(),
+            XSD_STRING,
+        )),
+        "UCASE" => Some(typed_literal(
+            &term_lexical(first)?.to_uppercase(),
+            XSD_STRING,
+        )),
+        "STRLEN" => Some(typed_literal(
+            &term_lexical(first)?.chars().count().to_string(),
+            XSD_INTEGER,
+        )),
+        "ISIRI" | "ISURI" => Some(boolean(first.kind() == TermKind::Iri)),
+        "ISBLANK" => Some(boolean(first.kind() == TermKind::BlankNode)),
+        "ISLITERAL" => Some(boolean(first.kind() == TermKind::Literal)),
+        "ISNUMERIC" => Some(boolean(numeric_value(first).is_some())),
+        "CONTAINS" | "STRSTARTS" | "STRENDS" => {
+            let haystack = term_lexical(first)?;
+            let needle = term_lexical(values.get(1)?)?;
+            Some(boolean(match name {
+                "CONTAINS" => haystack.contains(&needle),
+                "STRSTARTS" => haystack.starts_with(&needle),
+                _ => haystack.ends_with(&needle),
+            }))
+        }
+        "LANGMATCHES" => {
+            let tag = term_lexical(first)?.to_lowercase();
+            let range = term_lexical(values.get(1)?)?.to_lowercase();
+            Some(boolean(if range == "*" {
+                !tag.is_empty()
+            } else {
+                tag == range || tag.starts_with(&format!("{}-", range))
+            }))
+        }
+        "REGEX" => {
+            let text = term_lexical(first)?;
+            let pattern = term_lexical(values.get(1)?)?;
+            let flags = values.get(2).and_then(term_lexical).unwrap_or_default();
+            let regex = regex::Regex::new(&regex_pattern(pattern, &flags)).ok()?;
+            Some(boolean(regex.is_match(&text)))
+        }
+        "SAMETERM" => Some(boolean(*first == *values.get(1)?)),
+        _ => None,
+    }
+}
+
+/// Lexical form of a literal, or the IRI string of an IRI
+fn term_lexical(term: &SimpleTerm) -> Option<String> {
+    match term {
+        SimpleTerm::Iri(iri) => Some(iri.as_str().to_string()),
+        SimpleTerm::LiteralDatatype(lex, _) | SimpleTerm::LiteralLanguage(lex, _) => {
+            Some(lex.to_string())
+        }
+        _ => None,
+    }
+}
+
+fn boolean(value: bool) -> SimpleTerm<'static> {
+    typed_literal(if value { "true" } else { "false" }, XSD_BOOLEAN)
+}
+
+/// Numeric value of a literal with a numeric XSD datatype
+fn numeric_value(term: &SimpleTerm) -> Option<f64> {
+    match term {
+        SimpleTerm::LiteralDatatype(lex, dt) => {
+            let dt = dt.as_str();
+            let numeric = dt.strip_prefix(XSD_NS).is_some_and(|local| {
+                matches!(
+                    local,
+                    "integer"
+                        | "decimal"
+                        | "double"
+                        | "float"
+                        | "int"
+                        | "long"
+                        | "short"
+                        | "nonNegativeInteger"
+                        | "positiveInteger"
+                        | "negativeInteger"
+                        | "nonPositiveInteger"
+                        | "unsignedInt"
+                        | "unsignedLong"
+                )
+            });
+            if numeric {
+                lex.trim().parse().ok()
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// SPARQL effective boolean value; errors are false
+fn effective_boolean(term: Option<&SimpleTerm>) -> bool {
+    match term {
+        None => false,
+        Some(term) => {
+            if let Some(n) = numeric_value(term) {
+                return n != 0.0 && !n.is_nan();
+            }
+            match term {
+                SimpleTerm::LiteralDatatype(lex, dt) if dt.as_str() == XSD_BOOLEAN => {
+                    &**lex == "true" || &**lex == "1"
+                }
+                SimpleTerm::LiteralDatatype(lex, _) | SimpleTerm::LiteralLanguage(lex, _) => {
+                    !lex.is_empty()
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Compare two terms for FILTER; `None` means the comparison is an error
+fn compare_terms(op: CompareOp, left: &SimpleTerm, right: &SimpleTerm) -> Option<bool> {
+    let ordering = match (numeric_value(left), numeric_value(right)) {
+        (Some(l), Some(r)) => l.partial_cmp(&r)?,
+        _ => match (left, right) {
+            (SimpleTerm::LiteralDatatype(l, ldt), SimpleTerm::LiteralDatatype(r, rdt))
+                if ldt.as_str() == rdt.as_str() =>
+            {
+                Ord::cmp(&**l, &**r)
+            }
+            _ => {
+                return match op {
+                    CompareOp::Eq => Some(left == right),
+                    CompareOp::Ne => Some(left != right),
+                    _ => match (left, right) {
+                        (SimpleTerm::LiteralLanguage(l, _), SimpleTerm::LiteralLanguage(r, _)) => {
+                            Some(ordering_matches(op, Ord::cmp(&**l, &**r)))
+                        }
+                        _ => None,
+                    },
+                };
+            }
+        },
+    };
+    Some(ordering_matches(op, ordering))
+}
+
+fn ordering_matches(op: CompareOp, ordering: Ordering) -> bool {
+    match op {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Ne => ordering != Ordering::Equal,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+    }
+}
+
+/// ORDER BY ordering: unbound < blank nodes < IRIs < literals
+fn compare_order(left: Option<&SimpleTerm>, right: Option<&SimpleTerm>) -> Ordering {
+    fn rank(term: Option<&SimpleTerm>) -> u8 {
+        match term.map(|t| t.kind()) {
+            None => 0,
+            Some(TermKind::BlankNode) => 1,
+            Some(TermKind::Iri) => 2,
+            Some(TermKind::Literal) => 3,
+            Some(_) => 4,
+        }
+    }
+
+    match (left, right) {
+        (Some(l), Some(r)) if l.kind() == r.kind() => {
+            if let (Some(a), Some(b)) = (numeric_value(l), numeric_value(r)) {
+                return a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+            }
+            Ord::cmp(
+                &term_lexical(l).unwrap_or_default(),
+                &term_lexical(r).unwrap_or_default(),
+            )
+        }
+        _ => Ord::cmp(&rank(left), &rank(right)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor() -> SemanticProcessor {
+        let mut processor = SemanticProcessor::new();
+        let ttl = r#"
+            @prefix sn: <https://sinople.org/ontology#> .
+            @prefix rdfs: <https://www.w3.org/2000/01/rdf-schema#> .
+
+            sn:entropy a sn:Construct ; rdfs:label "Entropy" ; sn:order 2 .
+            sn:memory a sn:Construct ; rdfs:label "Memory" ; sn:order 1 ;
+                sn:hasGloss "Remembrance"@en .
+            sn:alice a sn:Character ; rdfs:label "Alice" .
+        "#;
+        processor.load_turtle(ttl, None).unwrap();
+        processor
+    }
+
+    fn select(processor: &SemanticProcessor, query: &str) -> SelectResults {
+        let query = parse_select(query, &processor.namespaces, &processor.aliases).unwrap();
+        evaluate_select(&processor.graph, &query, &processor.abort).unwrap()
+    }
+
+    #[test]
+    fn test_select_with_optional_filter_and_order() {
+        let processor = processor();
+        let results = select(
+            &processor,
+            r#"SELECT ?c ?label ?gloss WHERE {
+                ?c a sn:Construct ; rdfs:label ?label ; sn:order ?order .
+                OPTIONAL { ?c sn:hasGloss ?gloss }
+                FILTER (?order >= 1 && !CONTAINS(?label, "x"))
+            } ORDER BY ?order"#,
+        );
+
+        assert_eq!(results.head.vars, vec!["c", "label", "gloss"]);
+        let bindings = &results.results.bindings;
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(bindings[0]["label"].value, "Memory");
+        assert_eq!(bindings[0]["gloss"].lang.as_deref(), Some("en"));
+        assert_eq!(bindings[1]["label"].value, "Entropy");
+        assert!(!bindings[1].contains_key("gloss"));
+    }
+
+    #[test]
+    fn test_select_limit_offset_and_descending_order() {
+        let processor = processor();
+        let results = select(
+            &processor,
+            "SELECT ?label WHERE { ?s rdfs:label ?label } ORDER BY DESC(?label) LIMIT 1 OFFSET 1",
+        );
+
+        let bindings = &results.results.bindings;
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0]["label"].value, "Entropy");
+    }
+
+    #[test]
+    fn test_trailing_dot_ends_variables_and_blank_nodes() {
+        let processor = processor();
+        let results = select(&processor, "SELECT ?o WHERE { sn:alice rdfs:label ?o. }");
+        assert_eq!(results.results.bindings.len(), 1);
+        assert_eq!(results.results.bindings[0]["o"].value, "Alice");
+
+        let tokens = tokenize("?s ?p _:b1. ?o.").unwrap();
+        assert!(matches!(&tokens[2], Token::BlankNode(label) if label == "b1"));
+        assert!(matches!(&tokens[4], Token::Var(name) if name == "o"));
+    }
+
+    #[test]
+    fn test_base_and_regex() {
+        let processor = processor();
+        let results = select(
+            &processor,
+            r#"BASE <https://sinople.org/ontology>
+            PREFIX local: <#>
+            SELECT ?label WHERE { <#entropy> rdfs:label ?label . local:entropy a ?type }"#,
+        );
+        assert_eq!(results.results.bindings.len(), 1);
+        assert_eq!(results.results.bindings[0]["label"].value, "Entropy");
+
+        let query = parse_select(
+            r#"SELECT ?s WHERE { ?s rdfs:label ?l FILTER REGEX(?l, "^(m|a)", "i") }"#,
+            &processor.namespaces,
+            &processor.aliases,
+        )
+        .unwrap();
+        let [GroupElement::Filter(Expr::Regex(..)), ..] = &query.pattern.elements[1..] else {
+            panic!("constant REGEX was not compiled: {:?}", query.pattern);
+        };
+        let results = evaluate_select(&processor.graph, &query, &processor.abort).unwrap();
+        assert_eq!(results.results.bindings.len(), 2);
+
+        let relative_base = "BASE <ontology> SELECT ?s WHERE { ?s ?p ?o }";
+        assert!(parse_select(relative_base, &processor.namespaces, &processor.aliases).is_err());
+    }
+
+    #[test]
+    fn test_ask() {
+        let processor = processor();
+        let ask = |query: &str| {
+            evaluate_ask(
+                &processor.graph,
+                &parse_ask(query, &processor.namespaces, &processor.aliases).unwrap(),
+                &processor.abort,
+            )
+            .unwrap()
+        };
+
+        assert!(ask("ASK { ?c a sn:Construct }"));
+        assert!(!ask("ASK WHERE { ?e a sn:Entanglement }"));
+        assert!(!ask(r#"ASK { ?c rdfs:label ?l FILTER (?l = "Nobody") }"#));
+    }
+
+    #[test]
+    fn test_update_data_and_modify() {
+        let mut processor = processor();
+        let mut update = |update: &str| {
+            let operations =
+                parse_update(update, &processor.namespaces, &processor.aliases).unwrap();
+            processor.apply_update(&operations).unwrap()
+        };
+
+        let summary = update(
+            r#"INSERT DATA { sn:time a sn:Construct ; rdfs:label "Time" } ;
+               DELETE DATA { sn:alice rdfs:label "Alice" }"#,
+        );
+        assert_eq!(
+            summary,
+            UpdateSummary {
+                inserted: 2,
+                deleted: 1
+            }
+        );
+
+        let summary = update(
+            r#"DELETE { ?c rdfs:label ?old } INSERT { ?c rdfs:label "Tempus" }
+               WHERE { ?c rdfs:label ?old FILTER (?old = "Time") }"#,
+        );
+        assert_eq!(
+            summary,
+            UpdateSummary {
+                inserted: 1,
+                deleted: 1
+            }
+        );
+
+        let summary = update("DELETE WHERE { ?c sn:order ?order }");
+        assert_eq!(summary.deleted, 2);
+        assert_eq!(processor.triple_count(), 8);
+    }
+
+    #[test]
+    fn test_inserted_blank_nodes_stay_distinct() {
+        let mut processor = processor();
+        let mut update = |update: &str| {
+            let operations =
+                parse_update(update, &processor.namespaces, &processor.aliases).unwrap();
+            processor.apply_update(&operations).unwrap();
+        };
+        update(r#"INSERT DATA { _:a sn:note "1" . _:b sn:note "2" . _:a sn:rank 1 }"#);
+        update(r#"INSERT { _:n sn:names ?c } WHERE { ?c a sn:Construct }"#);
+
+        let subjects = |predicate: &str| {
+            processor
+                .match_pattern(None, Some(predicate), None)
+                .unwrap()
+                .into_iter()
+                .map(|triple| triple.subject.value)
+                .collect::<std::collections::HashSet<_>>()
+        };
+        let notes = subjects("sn:note");
+        assert_eq!(notes.len(), 2);
+        assert!(notes.is_superset(&subjects("sn:rank")));
+        let names = subjects("sn:names");
+        assert_eq!(names.len(), 2);
+        assert!(names.is_disjoint(&notes));
+    }
+
+    #[test]
+    fn test_namespace_aliases_apply_to_queries_and_updates() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                <https://ex.org/a> rdfs:label "A" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let results = select(
+            &processor,
+            "PREFIX rdfs: <https://www.w3.org/2000/01/rdf-schema#>
+             SELECT ?label WHERE { <https://ex.org/a> rdfs:label ?label }",
+        );
+        assert_eq!(results.results.bindings.len(), 1);
+
+        let operations = parse_update(
+            "INSERT DATA { <https://ex.org/b> <https://www.w3.org/2000/01/rdf-schema#label> \"B\" }",
+            &processor.namespaces,
+            &processor.aliases,
+        )
+        .unwrap();
+        processor.apply_update(&operations).unwrap();
+        assert_eq!(
+            processor
+                .match_pattern(
+                    None,
+                    Some("http://www.w3.org/2000/01/rdf-schema#label"),
+                    None
+                )
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_are_reported() {
+        let namespaces = HashMap::new();
+        let aliases = Aliases::new(&namespaces);
+        let select = |query| parse_select(query, &namespaces, &aliases);
+        assert!(select("SELECT ?x WHERE { ?x unknown:p ?y }").is_err());
+        assert!(select("SELECT ?x WHERE { ?x ?p ?y ").is_err());
+        assert!(select("SELECT WHERE { ?x ?p ?y }").is_err());
+        assert!(parse_update("INSERT DATA { ?x ?p ?y }", &namespaces, &aliases).is_err());
+    }
+}
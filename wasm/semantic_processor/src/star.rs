@@ -0,0 +1,134 @@
+//! RDF-star quoted triples
+//!
+//! Turtle-star and TriG-star documents can make statements about other
+//! statements, either by quoting them or with annotation syntax:
+//!
+//! ```turtle
+//! << sn:mira sn:loves sn:anselm >> sn:accordingTo sn:narrator .
+//! sn:ilse sn:fears sn:flood {| sn:accordingTo sn:mira |} .
+//! ```
+//!
+//! Quoted triples come back from `match_triples` and `describe` as terms of
+//! kind `"triple"`. `annotations_on` lists what is said about one of them:
+//!
+//! ```javascript
+//! processor.annotations_on('sn:mira', 'sn:loves', 'sn:anselm');
+//! // [{ predicate: '…#accordingTo', value: { kind: 'iri', value: '…#narrator' } }]
+//! ```
+
+use crate::error::ProcessorError;
+use crate::reification::Annotation;
+use crate::{SemanticProcessor, TermValue};
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::SimpleTerm;
+use sophia_api::triple::Triple;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// List the statements made about a quoted triple
+    ///
+    /// Terms are written as for `match_triples`.
+    ///
+    /// # Returns
+    /// JsValue containing array of {predicate, value}
+    #[wasm_bindgen(unchecked_return_type = "Annotation[]")]
+    pub fn annotations_on(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+    ) -> Result<JsValue, JsValue> {
+        let annotations = self.quoted_annotations(subject, predicate, object)?;
+
+        self.output(&annotations)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn quoted_annotations(
+        &self,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+    ) -> Result<Vec<Annotation>, ProcessorError> {
+        let quoted = SimpleTerm::Triple(Box::new([
+            self.parse_term_arg(subject)?,
+            self.parse_term_arg(predicate)?,
+            self.parse_term_arg(object)?,
+        ]));
+
+        Ok(self
+            .graph
+            .triples_matching([&quoted], Any, Any)
+            .flatten()
+            .map(|t| Annotation {
+                predicate: self.term_to_string(t.p()),
+                value: TermValue::from_term(t.o()),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lenient::split_statements;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        << sn:mira sn:loves sn:anselm >> sn:accordingTo sn:narrator .
+        sn:ilse sn:fears sn:flood {| sn:accordingTo sn:mira ; sn:since 2002 |} .
+        << sn:mira sn:says "a > b. Surely" >> sn:accordingTo sn:ilse .
+    "#;
+
+    #[test]
+    fn test_annotations_on_quoted_triples() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        let narrator = processor
+            .quoted_annotations("sn:mira", "sn:loves", "sn:anselm")
+            .unwrap();
+        assert_eq!(narrator.len(), 1);
+        assert_eq!(
+            narrator[0].value.value,
+            "https://sinople.org/ontology#narrator"
+        );
+        assert_eq!(
+            processor
+                .quoted_annotations("sn:ilse", "sn:fears", "sn:flood")
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let matches = processor
+            .match_pattern(None, Some("sn:accordingTo"), Some("sn:narrator"))
+            .unwrap();
+        let quoted = matches[0].subject.triple.as_ref().unwrap();
+        assert_eq!(quoted.predicate.value, "https://sinople.org/ontology#loves");
+    }
+
+    #[test]
+    fn test_quoted_triples_are_aliased_and_split() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                "<< <http://sinople.org/ontology#a> <https://sinople.org/ontology#p> \"x\" >> \
+                 <https://sinople.org/ontology#q> \"y\" .",
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            processor
+                .quoted_annotations("sn:a", "sn:p", "\"x\"")
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert_eq!(split_statements(TTL).len(), 4);
+    }
+}
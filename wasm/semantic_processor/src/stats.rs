@@ -0,0 +1,166 @@
+//! Graph statistics
+//!
+//! Helps decide when an ontology has grown large enough to be split across
+//! pages:
+//!
+//! ```javascript
+//! const { triples, approximate_bytes, instances } = processor.stats();
+//! ```
+
+use crate::error::ProcessorError;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::Term;
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::{BTreeMap, HashSet};
+use std::mem::size_of;
+use wasm_bindgen::prelude::*;
+
+/// Estimated bytes per triple: one entry in each of the three indexes,
+/// plus the B-tree nodes around it
+const TRIPLE_BYTES: usize = 3 * 2 * size_of::<[u32; 3]>();
+
+/// Estimated bytes per distinct term, besides its text: the term itself and
+/// its entries in the term index
+const TERM_BYTES: usize = 64;
+
+/// Size and shape of the graph
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct GraphStats {
+    /// Triples in the union of all graphs
+    pub triples: usize,
+    pub distinct_subjects: usize,
+    pub distinct_predicates: usize,
+    pub distinct_objects: usize,
+    /// Rough estimate of the memory held by the graph and its named graphs
+    pub approximate_bytes: usize,
+    /// Number of instances of each `rdf:type`, keyed by type IRI
+    pub instances: BTreeMap<String, usize>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Get statistics about the graph
+    ///
+    /// # Returns
+    /// JsValue containing {triples, distinct_subjects, distinct_predicates,
+    /// distinct_objects, approximate_bytes, instances}
+    #[wasm_bindgen(unchecked_return_type = "GraphStats")]
+    pub fn stats(&self) -> Result<JsValue, JsValue> {
+        let stats = self.collect_stats()?;
+
+        self.output(&stats)
+    }
+}
+
+impl SemanticProcessor {
+    /// Build the result of `stats`
+    pub(crate) fn collect_stats(&self) -> Result<GraphStats, ProcessorError> {
+        let rdf_type = self.make_term("rdf:type");
+        let mut subjects = HashSet::new();
+        let mut predicates = HashSet::new();
+        let mut objects = HashSet::new();
+        let mut stats = GraphStats::default();
+
+        for triple in self.graph.triples() {
+            let triple =
+                triple.map_err(|e| ProcessorError::query(format!("Graph error: {}", e)))?;
+            stats.triples += 1;
+            subjects.insert(triple.s().as_simple());
+            predicates.insert(triple.p().as_simple());
+            objects.insert(triple.o().as_simple());
+
+            if Term::eq(triple.p(), &rdf_type) {
+                *stats
+                    .instances
+                    .entry(self.term_to_string(triple.o()))
+                    .or_default() += 1;
+            }
+        }
+
+        stats.distinct_subjects = subjects.len();
+        stats.distinct_predicates = predicates.len();
+        stats.distinct_objects = objects.len();
+        stats.approximate_bytes = footprint(&self.graph)
+            + self
+                .named_graphs
+                .iter()
+                .map(|(name, graph)| name.len() + footprint(graph))
+                .sum::<usize>();
+
+        Ok(stats)
+    }
+}
+
+/// Estimate the memory held by a graph
+fn footprint(graph: &FastGraph) -> usize {
+    let mut triples = 0;
+    let mut terms = HashSet::new();
+    for triple in graph.triples().flatten() {
+        triples += 1;
+        terms.extend([
+            triple.s().as_simple(),
+            triple.p().as_simple(),
+            triple.o().as_simple(),
+        ]);
+    }
+
+    let text: usize = terms.iter().map(term_text_len).sum();
+    triples * TRIPLE_BYTES + terms.len() * TERM_BYTES + text
+}
+
+/// Bytes of text stored for a term
+fn term_text_len(term: &sophia_api::term::SimpleTerm) -> usize {
+    use sophia_api::term::SimpleTerm::*;
+    match term {
+        Iri(iri) => iri.len(),
+        BlankNode(id) => id.len(),
+        LiteralDatatype(lex, datatype) => lex.len() + datatype.len(),
+        LiteralLanguage(lex, tag) => lex.len() + tag.len(),
+        Triple(triple) => triple.iter().map(term_text_len).sum(),
+        Variable(name) => name.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_counts_terms_and_instances() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdf: <https://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+                sn:time rdf:type sn:Construct ; sn:near sn:space .
+                sn:space rdf:type sn:Construct .
+                sn:mira rdf:type sn:Character .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let stats = processor.collect_stats().unwrap();
+        assert_eq!(stats.triples, 4);
+        assert_eq!(stats.distinct_subjects, 3);
+        assert_eq!(stats.distinct_predicates, 2);
+        assert_eq!(stats.distinct_objects, 3);
+        assert_eq!(
+            stats
+                .instances
+                .get("https://sinople.org/ontology#Construct"),
+            Some(&2)
+        );
+        assert_eq!(
+            stats
+                .instances
+                .get("https://sinople.org/ontology#Character"),
+            Some(&1)
+        );
+        assert!(stats.approximate_bytes > 4 * TRIPLE_BYTES);
+    }
+}
@@ -0,0 +1,348 @@
+//! Chunked Turtle loading
+//!
+//! Lets a Turtle document be loaded as it is downloaded, without holding the
+//! whole text in JavaScript first:
+//!
+//! ```javascript
+//! processor.begin_load();
+//! const decoder = new TextDecoder();
+//! for await (const chunk of response.body) {
+//!   processor.load_chunk(decoder.decode(chunk, { stream: true }));
+//! }
+//! processor.finish_load();
+//! ```
+//!
+//! Each chunk is buffered until it completes one or more statements, which are
+//! parsed and added right away; only the unfinished tail stays in memory.
+
+use crate::error::{ProcessorError, SyntaxError};
+use crate::lenient::{is_directive, split_statements};
+use crate::SemanticProcessor;
+use sophia_api::graph::{Graph, MutableGraph};
+use sophia_api::parser::TripleParser;
+use sophia_api::source::{StreamError, TripleSource};
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{BnodeId, SimpleTerm, Term};
+use sophia_api::MownStr;
+use sophia_inmem::graph::FastGraph;
+use std::collections::{HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// State of a chunked load between `begin_load` and `finish_load`
+#[derive(Debug, Clone)]
+pub(crate) struct StreamingLoad {
+    /// Text received but not parsed yet
+    buffer: String,
+    /// Directives seen so far, prepended to every parse
+    directives: String,
+    /// Position of the start of `buffer` in the document
+    line: usize,
+    column: usize,
+    /// Number of parses so far, used to name the blank nodes each generates
+    parses: usize,
+}
+
+impl Default for StreamingLoad {
+    fn default() -> Self {
+        StreamingLoad {
+            buffer: String::new(),
+            directives: String::new(),
+            line: 1,
+            column: 1,
+            parses: 0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Start a chunked Turtle load
+    ///
+    /// Discards any chunked load that was not finished, and clears the abort
+    /// flag. Setting the flag during the load makes the next `load_chunk` or
+    /// `finish_load` abandon it.
+    pub fn begin_load(&mut self) {
+        self.abort.set(false);
+        self.retract_inferred();
+        self.streaming = Some(StreamingLoad::default());
+    }
+
+    /// Add the next piece of the document to a chunked load
+    ///
+    /// Chunks may split statements anywhere. Complete statements are parsed
+    /// and added to the graph immediately.
+    ///
+    /// # Returns
+    /// * `Err(JsValue)` if no load was started, or a statement fails to parse;
+    ///   the load is then abandoned, but statements added before are kept and
+    ///   the inferences are brought up to date with them
+    pub fn load_chunk(&mut self, chunk: &str) -> Result<(), JsValue> {
+        Ok(self.stream_chunk(chunk)?)
+    }
+
+    /// Parse the rest of a chunked load and end it
+    pub fn finish_load(&mut self) -> Result<(), JsValue> {
+        Ok(self.finish_stream()?)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn stream_chunk(&mut self, chunk: &str) -> Result<(), ProcessorError> {
+        let mut load = self.take_stream()?;
+        load.buffer.push_str(chunk);
+
+        // A statement ending right at the end of the buffer may still go on,
+        // e.g. "1." followed by "5"
+        let complete = split_statements(&load.buffer)
+            .into_iter()
+            .rev()
+            .find(|statement| statement.terminated && statement.end < load.buffer.len())
+            .map(|statement| statement.end);
+
+        if let Some(end) = complete {
+            self.parse_streamed(&mut load, end)
+                .inspect_err(|_| self.abandon_stream())?;
+        }
+        self.streaming = Some(load);
+        Ok(())
+    }
+
+    pub(crate) fn finish_stream(&mut self) -> Result<(), ProcessorError> {
+        let mut load = self.take_stream()?;
+        let end = load.buffer.len();
+        let result = self.parse_streamed(&mut load, end);
+        self.abandon_stream();
+        result
+    }
+
+    /// End a chunked load, keeping the statements it added so far
+    fn abandon_stream(&mut self) {
+        self.streaming = None;
+        self.skolemize_loaded();
+        self.materialize();
+        self.reset_history();
+    }
+
+    fn take_stream(&mut self) -> Result<StreamingLoad, ProcessorError> {
+        if self.streaming.is_some() {
+            self.check_abort().inspect_err(|_| self.abandon_stream())?;
+        }
+        self.streaming.take().ok_or_else(|| {
+            ProcessorError::transaction("No load in progress; call begin_load first")
+        })
+    }
+
+    /// Parse the first `end` bytes of the buffer into the graph
+    fn parse_streamed(
+        &mut self,
+        load: &mut StreamingLoad,
+        end: usize,
+    ) -> Result<(), ProcessorError> {
+        let text = &load.buffer[..end];
+        let document = format!("{}{}", load.directives, text);
+
+        let mut parsed = FastGraph::new();
+        let result = self
+            .turtle_parser()
+            .parse_str(&document)
+            .add_to_graph(&mut parsed);
+        if let Err(e) = result {
+            return Err(match &e {
+                StreamError::SourceError(source) => match SyntaxError::locate(&document, source) {
+                    Some(mut syntax) => {
+                        let line = syntax
+                            .line
+                            .saturating_sub(load.directives.lines().count())
+                            .max(1);
+                        if line == 1 {
+                            syntax.column += load.column - 1;
+                        }
+                        syntax.line = load.line + line - 1;
+                        ProcessorError::syntax("Turtle", syntax)
+                    }
+                    None => ProcessorError::parse("Turtle", source),
+                },
+                StreamError::SinkError(_) => ProcessorError::parse("Turtle", e),
+            });
+        }
+
+        load.parses += 1;
+        self.invalidate_cache();
+        // Every parse numbers anonymous blank nodes (`[]`) from the start
+        // again, so the same label would stand for different nodes in two
+        // chunks. Labels written in the document name the same node in every
+        // chunk and are kept; all others get a label unique to this parse.
+        let written = written_labels(&document);
+        let mut generated: HashMap<String, SimpleTerm<'static>> = HashMap::new();
+        for triple in parsed.triples().flatten() {
+            let [s, p, o] = self.aliases.triple(triple).map(|term| {
+                let label = term
+                    .bnode_id()
+                    .map(|id| id.as_str().to_string())
+                    .filter(|label| !written.contains(label.as_str()));
+                match label {
+                    Some(label) => {
+                        let count = generated.len();
+                        generated
+                            .entry(label)
+                            .or_insert_with(|| self.unused_blank_node(load.parses, count, &written))
+                            .clone()
+                    }
+                    None => term,
+                }
+            });
+            self.graph
+                .insert(&s, &p, &o)
+                .map_err(|e| ProcessorError::parse("Turtle", e))?;
+        }
+
+        for statement in split_statements(text) {
+            if is_directive(statement.text) {
+                load.directives.push_str(statement.text);
+                load.directives.push('\n');
+            }
+        }
+        for c in text.chars() {
+            if c == '\n' {
+                load.line += 1;
+                load.column = 1;
+            } else {
+                load.column += 1;
+            }
+        }
+        load.buffer.drain(..end);
+        Ok(())
+    }
+
+    /// The `count`th blank node of a parse, under a label that is neither
+    /// written in the chunk nor used in the graph yet
+    fn unused_blank_node(
+        &self,
+        parse: usize,
+        count: usize,
+        written: &HashSet<&str>,
+    ) -> SimpleTerm<'static> {
+        (0..)
+            .map(|attempt| match attempt {
+                0 => format!("c{}b{}", parse, count),
+                _ => format!("c{}b{}x{}", parse, count, attempt),
+            })
+            .filter(|label| !written.contains(label.as_str()))
+            .map(|label| SimpleTerm::BlankNode(BnodeId::new_unchecked(MownStr::from(label))))
+            .find(|bnode| {
+                self.graph
+                    .triples_matching([bnode], Any, Any)
+                    .next()
+                    .is_none()
+                    && self
+                        .graph
+                        .triples_matching(Any, Any, [bnode])
+                        .next()
+                        .is_none()
+            })
+            .expect("blank node labels are unbounded")
+    }
+}
+
+/// The blank node labels written in a Turtle text, e.g. `x` for `_:x`
+fn written_labels(text: &str) -> HashSet<&str> {
+    text.match_indices("_:")
+        .map(|(start, _)| {
+            let label = &text[start + 2..];
+            let end = label
+                .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+                .unwrap_or(label.len());
+            // A trailing '.' ends the statement
+            label[..end].trim_end_matches('.')
+        })
+        .filter(|label| !label.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sophia_api::triple::Triple;
+
+    #[test]
+    fn test_chunked_load_matches_whole_document() {
+        let ttl = "@prefix sn: <https://sinople.org/ontology#> .\n\
+                   sn:a sn:label \"A. Still A\" ;\n  sn:ratio 1.5 .\n\
+                   sn:b sn:related [ sn:label \"anonymous\" ] .\n\
+                   sn:c sn:related [ sn:label \"another\" ] .\n";
+        let mut processor = SemanticProcessor::new();
+        processor.begin_load();
+        for chunk in ttl.as_bytes().chunks(7) {
+            processor
+                .stream_chunk(std::str::from_utf8(chunk).unwrap())
+                .unwrap();
+        }
+        processor.finish_stream().unwrap();
+
+        assert_eq!(processor.triple_count(), 6);
+        let anonymous: std::collections::HashSet<_> = processor
+            .graph
+            .triples()
+            .flatten()
+            .filter_map(|t| t.o().bnode_id().map(|id| id.as_str().to_string()))
+            .collect();
+        assert_eq!(anonymous.len(), 2);
+        assert!(processor.streaming.is_none());
+    }
+
+    #[test]
+    fn test_written_blank_node_labels_span_chunks() {
+        let mut processor = SemanticProcessor::new();
+        processor.begin_load();
+        for chunk in [
+            "@prefix sn: <https://sinople.org/ontology#> .\nsn:a sn:related _:x, [] .\n",
+            "sn:b sn:related _:x.\n_:x sn:label \"shared\" .\n",
+            "sn:c sn:related [] .\n",
+        ] {
+            processor.stream_chunk(chunk).unwrap();
+        }
+        processor.finish_stream().unwrap();
+
+        let related: std::collections::HashSet<_> = processor
+            .graph
+            .triples()
+            .flatten()
+            .filter_map(|t| t.o().bnode_id().map(|id| id.as_str().to_string()))
+            .collect();
+        // _:x, and the anonymous nodes of the first and third chunks
+        assert_eq!(related.len(), 3);
+        assert!(related.contains("x"));
+        assert_eq!(processor.triple_count(), 5);
+    }
+
+    #[test]
+    fn test_chunked_load_reports_document_position() {
+        let mut processor = SemanticProcessor::new();
+        processor.begin_load();
+        processor
+            .stream_chunk("@prefix sn: <https://sinople.org/ontology#> .\nsn:a sn:label \"A\" .\n")
+            .unwrap();
+        let error = processor
+            .stream_chunk("sn:b sn:label sn:c sn:d .\n")
+            .unwrap_err();
+
+        assert_eq!(error.context["line"], "3");
+        assert_eq!(error.context["column"], "20");
+        assert!(processor.stream_chunk("sn:e sn:label \"E\" .\n").is_err());
+    }
+
+    #[test]
+    fn test_abort_flag_abandons_chunked_load() {
+        let mut processor = SemanticProcessor::new();
+        processor.begin_load();
+        processor
+            .stream_chunk("<https://example.org/a> <https://example.org/p> \"a\" .\n")
+            .unwrap();
+        processor.set_abort_flag();
+
+        let error = processor.finish_stream().unwrap_err();
+        assert_eq!(error.code, crate::ErrorCode::Aborted);
+        assert!(processor.streaming.is_none());
+        assert_eq!(processor.triple_count(), 1);
+    }
+}
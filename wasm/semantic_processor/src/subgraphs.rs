@@ -0,0 +1,506 @@
+//! Parts of the network graph
+//!
+//! A post only shows the resources near its subject, so shipping the whole
+//! network graph to each page is wasteful. `neighborhood_graph` keeps the
+//! nodes within a number of hops of a focus resource, following edges in
+//! either direction, and the edges between them:
+//!
+//! ```javascript
+//! const { nodes, edges } = processor.neighborhood_graph('sn:entropy', 2);
+//! ```
+//!
+//! `generate_network_graph_filtered` keeps the nodes of some types instead:
+//!
+//! ```javascript
+//! const cast = processor.generate_network_graph_filtered(['character']);
+//! ```
+//!
+//! Either way, an edge is only kept if both of its ends are, so no edge
+//! points at a missing node. `generate_network_graph_by_relationship` works
+//! the other way round, keeping the edges with some labels and the nodes
+//! they connect:
+//!
+//! ```javascript
+//! const conflicts = processor.generate_network_graph_by_relationship(['opposes']);
+//! ```
+//!
+//! `character_network` is the relationship map: characters, the constructs
+//! they are tied to, and the edges between them, without entanglement
+//! nodes. Resources linked to the same entanglement node are joined
+//! directly, by an undirected edge labelled with its relationship type.
+
+use crate::error::ProcessorError;
+use crate::literals::parse_weight;
+use crate::paths::neighbours;
+use crate::{GraphEdge, GraphNode, NetworkGraph, SemanticProcessor};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use wasm_bindgen::prelude::*;
+
+/// The `node_type`s of network graph nodes
+const NODE_TYPES: [&str; 4] = ["construct", "character", "entanglement", "other"];
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Generate the part of the network graph around one resource
+    ///
+    /// # Arguments
+    /// * `iri` - Focus resource as `prefix:local` or full IRI
+    /// * `depth` - Most hops from the focus resource; 0 keeps only itself
+    ///
+    /// # Returns
+    /// JsValue containing NetworkGraph with the nodes within `depth` hops and
+    /// the edges between them; node degrees count only those edges
+    #[wasm_bindgen(unchecked_return_type = "NetworkGraphData")]
+    pub fn neighborhood_graph(&self, iri: &str, depth: u32) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let graph = self.neighborhood(iri, depth as usize)?;
+
+        self.output(&graph)
+    }
+
+    /// Generate the network graph with only the nodes of some types
+    ///
+    /// # Arguments
+    /// * `types` - Node types to keep: `construct`, `character`,
+    ///   `entanglement` or `other`
+    ///
+    /// # Returns
+    /// JsValue containing NetworkGraph with the nodes of those types and the
+    /// edges between them; node degrees count only those edges
+    #[wasm_bindgen(unchecked_return_type = "NetworkGraphData")]
+    pub fn generate_network_graph_filtered(&self, types: Vec<String>) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let graph = self.network_graph_of_types(&types)?;
+
+        self.output(&graph)
+    }
+
+    /// Generate the network graph with only the edges of some relationships
+    ///
+    /// Nodes left without edges are dropped.
+    ///
+    /// # Arguments
+    /// * `types` - Edge labels to keep: relationship types of entanglements,
+    ///   such as `opposes` or `sn:mirrors`, `related` for entanglements
+    ///   without one, or local names of relationship properties such as
+    ///   `relatesTo`
+    ///
+    /// # Returns
+    /// JsValue containing NetworkGraph with those edges and the nodes at
+    /// their ends; node degrees count only those edges
+    #[wasm_bindgen(unchecked_return_type = "NetworkGraphData")]
+    pub fn generate_network_graph_by_relationship(
+        &self,
+        types: Vec<String>,
+    ) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let graph = self.network_graph_of_relationships(&types)?;
+
+        self.output(&graph)
+    }
+
+    /// Generate the network of characters and the constructs they are tied to
+    ///
+    /// Edges are those of the network graph between a character and a
+    /// character or construct, `hasConstruct` from a character to its
+    /// constructs, and one for each pair of them linked to the same
+    /// entanglement node. Constructs without an edge are dropped.
+    ///
+    /// # Returns
+    /// JsValue containing NetworkGraph with character and construct nodes;
+    /// node degrees count only its edges
+    #[wasm_bindgen(unchecked_return_type = "NetworkGraphData")]
+    pub fn character_network(&self) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let graph = self.characters_graph()?;
+
+        self.output(&graph)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn neighborhood(
+        &self,
+        iri: &str,
+        depth: usize,
+    ) -> Result<NetworkGraph, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let focus = self.canonical_iri(iri);
+        let neighbours = neighbours(&network.edges);
+
+        let mut reached: HashSet<&str> = HashSet::from([focus.as_str()]);
+        let mut frontier = vec![focus.as_str()];
+        for _ in 0..depth {
+            frontier = frontier
+                .iter()
+                .flat_map(|node| neighbours.get(node).into_iter().flatten())
+                .map(|&(next, _)| next)
+                .filter(|next| reached.insert(next))
+                .collect();
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        let nodes = network
+            .nodes
+            .iter()
+            .filter(|node| reached.contains(node.id.as_str()))
+            .cloned()
+            .collect();
+        Ok(induced(&network, nodes, &reached))
+    }
+
+    pub(crate) fn network_graph_of_types(
+        &self,
+        types: &[String],
+    ) -> Result<NetworkGraph, ProcessorError> {
+        if let Some(unknown) = types.iter().find(|t| !NODE_TYPES.contains(&t.as_str())) {
+            return Err(ProcessorError::invalid_argument(format!(
+                "Unknown node type '{}'; expected construct, character, entanglement or other",
+                unknown
+            ))
+            .with_context("type", unknown));
+        }
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+
+        let kept: HashSet<&str> = network
+            .nodes
+            .iter()
+            .filter(|node| types.contains(&node.node_type))
+            .map(|node| node.id.as_str())
+            .collect();
+        let nodes = network
+            .nodes
+            .iter()
+            .filter(|node| kept.contains(node.id.as_str()))
+            .cloned()
+            .collect();
+        Ok(induced(&network, nodes, &kept))
+    }
+
+    pub(crate) fn network_graph_of_relationships(
+        &self,
+        types: &[String],
+    ) -> Result<NetworkGraph, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        // IRIs are compared in full, whichever way they were written
+        let labels: HashSet<String> = types
+            .iter()
+            .flat_map(|t| [t.clone(), self.term_to_string(&self.make_term(t))])
+            .collect();
+
+        let edges: Vec<GraphEdge> = network
+            .edges
+            .iter()
+            .filter(|edge| labels.contains(&edge.label))
+            .cloned()
+            .collect();
+        let connected: HashSet<&str> = edges
+            .iter()
+            .flat_map(|edge| [edge.source.as_str(), edge.target.as_str()])
+            .collect();
+        let nodes = network
+            .nodes
+            .iter()
+            .filter(|node| connected.contains(node.id.as_str()))
+            .cloned()
+            .collect();
+        Ok(NetworkGraph::new(nodes, edges))
+    }
+
+    pub(crate) fn characters_graph(&self) -> Result<NetworkGraph, ProcessorError> {
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let graph = self.query_graph(None)?;
+        let kind: HashMap<&str, &str> = network
+            .nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.node_type.as_str()))
+            .collect();
+        let is = |id: &str, node_type: &str| kind.get(id) == Some(&node_type);
+        let kept = |id: &str| is(id, "character") || is(id, "construct");
+        let tied =
+            |a: &str, b: &str| kept(a) && kept(b) && (is(a, "character") || is(b, "character"));
+        let edge =
+            |source: &str, target: &str, label: String, directed: bool, weight: f64| GraphEdge {
+                source: source.to_string(),
+                target: target.to_string(),
+                label,
+                symmetric: false,
+                directed,
+                weight,
+                count: 1,
+                parallel_index: 0,
+                parallel_count: 1,
+            };
+
+        let mut edges: Vec<GraphEdge> = Vec::new();
+        let mut entangled: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for e in &network.edges {
+            let (source, target) = (e.source.as_str(), e.target.as_str());
+            if tied(source, target) {
+                edges.push(e.clone());
+            } else if is(source, "entanglement") && kept(target) {
+                entangled.entry(source).or_default().insert(target);
+            } else if is(target, "entanglement") && kept(source) {
+                entangled.entry(target).or_default().insert(source);
+            }
+        }
+        for node in network
+            .nodes
+            .iter()
+            .filter(|node| node.node_type == "character")
+        {
+            for construct in self.get_character_constructs(&graph, &node.id) {
+                if is(&construct, "construct") {
+                    let directed = self.edge_directed(false);
+                    edges.push(edge(
+                        &node.id,
+                        &construct,
+                        "hasConstruct".to_string(),
+                        directed,
+                        1.0,
+                    ));
+                }
+            }
+        }
+        for (entanglement, members) in &entangled {
+            self.check_abort()?;
+            let label = self
+                .get_object_value(&graph, entanglement, "sn:relationshipType")
+                .unwrap_or_else(|| "related".to_string());
+            let weight = parse_weight(
+                self.get_object_value(&graph, entanglement, "sn:weight")
+                    .as_deref(),
+            );
+            for (i, a) in members.iter().enumerate() {
+                for b in members.iter().skip(i + 1).filter(|b| tied(a, b)) {
+                    edges.push(edge(a, b, label.clone(), false, weight));
+                }
+            }
+        }
+
+        let connected: HashSet<&str> = edges
+            .iter()
+            .flat_map(|edge| [edge.source.as_str(), edge.target.as_str()])
+            .collect();
+        let nodes = network
+            .nodes
+            .iter()
+            .filter(|node| {
+                node.node_type == "character"
+                    || (kept(&node.id) && connected.contains(node.id.as_str()))
+            })
+            .cloned()
+            .collect();
+        Ok(NetworkGraph::new(nodes, self.merge_edges(edges)))
+    }
+}
+
+/// A graph of some nodes and the edges of the network graph between the
+/// resources kept
+fn induced(network: &NetworkGraph, nodes: Vec<GraphNode>, kept: &HashSet<&str>) -> NetworkGraph {
+    let edges = network
+        .edges
+        .iter()
+        .filter(|edge| kept.contains(edge.source.as_str()) && kept.contains(edge.target.as_str()))
+        .cloned()
+        .collect();
+    NetworkGraph::new(nodes, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighborhood_is_limited_by_depth() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:a a sn:Construct . sn:b a sn:Construct .
+                sn:c a sn:Construct . sn:d a sn:Construct .
+                sn:a sn:relatesTo sn:b .
+                sn:c sn:relatesTo sn:b .
+                sn:c sn:relatesTo sn:d .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:a ; sn:hasTarget sn:c .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let summary = |depth| {
+            let graph = processor.neighborhood("sn:b", depth).unwrap();
+            let mut ids: Vec<String> = graph
+                .nodes
+                .iter()
+                .map(|n| {
+                    n.id.trim_start_matches("https://sinople.org/ontology#")
+                        .to_string()
+                })
+                .collect();
+            ids.sort();
+            (ids, graph.edges.len())
+        };
+        assert_eq!(summary(0), (vec!["b".to_string()], 0));
+        // The entanglement between the neighbours of sn:b is kept
+        assert_eq!(
+            summary(1),
+            (vec!["a".to_string(), "b".to_string(), "c".to_string()], 3)
+        );
+        assert_eq!(summary(2).1, 4);
+
+        let graph = processor.neighborhood("sn:b", 1).unwrap();
+        let c = graph.node("https://sinople.org/ontology#c").unwrap();
+        assert_eq!((c.in_degree, c.out_degree), (1, 1));
+    }
+
+    #[test]
+    fn test_network_graph_of_types_drops_edges_to_other_nodes() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:mira a sn:Character . sn:ilse a sn:Character .
+                sn:time a sn:Construct .
+                sn:mira sn:relatesTo sn:ilse .
+                sn:mira sn:relatesTo sn:time .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:ilse ; sn:hasTarget sn:time .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let graph = processor
+            .network_graph_of_types(&["character".to_string()])
+            .unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.edges[0].target, "https://sinople.org/ontology#ilse");
+
+        let both = ["character".to_string(), "construct".to_string()];
+        let graph = processor.network_graph_of_types(&both).unwrap();
+        assert_eq!((graph.nodes.len(), graph.edges.len()), (3, 3));
+
+        let error = processor
+            .network_graph_of_types(&["Construct".to_string()])
+            .unwrap_err();
+        assert_eq!(error.context["type"], "Construct");
+    }
+
+    #[test]
+    fn test_network_graph_of_relationships_drops_isolated_nodes() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:a a sn:Construct . sn:b a sn:Construct .
+                sn:c a sn:Construct . sn:d a sn:Construct .
+                sn:e1 a sn:Entanglement ; sn:hasSource sn:a ; sn:hasTarget sn:b ;
+                    sn:relationshipType "opposes" .
+                sn:e2 a sn:Entanglement ; sn:hasSource sn:b ; sn:hasTarget sn:c ;
+                    sn:relationshipType sn:mirrors .
+                sn:c sn:relatesTo sn:d .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let ids = |graph: &NetworkGraph| -> Vec<String> {
+            let mut ids: Vec<String> = graph
+                .nodes
+                .iter()
+                .map(|n| {
+                    n.id.trim_start_matches("https://sinople.org/ontology#")
+                        .to_string()
+                })
+                .collect();
+            ids.sort();
+            ids
+        };
+        let graph = processor
+            .network_graph_of_relationships(&["opposes".to_string()])
+            .unwrap();
+        assert_eq!(ids(&graph), ["a", "b"]);
+        assert_eq!(graph.edges.len(), 1);
+
+        let types = ["sn:mirrors".to_string(), "relatesTo".to_string()];
+        let graph = processor.network_graph_of_relationships(&types).unwrap();
+        assert_eq!(ids(&graph), ["b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_character_network_collapses_entanglement_nodes() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                sn:mira a sn:Character ; sn:hasConstruct sn:time .
+                sn:ilse a sn:Character . sn:oren a sn:Character .
+                sn:time a sn:Construct . sn:order a sn:Construct .
+                sn:lost a sn:Construct ; sn:relatesTo sn:order .
+                sn:rivalry a sn:Entanglement ; sn:hasSource sn:mira ; sn:hasTarget sn:ilse ;
+                    sn:relationshipType "rivals" .
+                sn:pact a sn:Entanglement ; sn:relationshipType "allies" .
+                sn:ilse sn:relatesTo sn:pact . sn:oren sn:relatesTo sn:pact .
+                sn:pact sn:relatesTo sn:order .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let graph = processor.characters_graph().unwrap();
+        let local = |iri: &str| {
+            iri.trim_start_matches("https://sinople.org/ontology#")
+                .to_string()
+        };
+        let mut ids: Vec<String> = graph.nodes.iter().map(|n| local(&n.id)).collect();
+        ids.sort();
+        assert_eq!(ids, ["ilse", "mira", "order", "oren", "time"]);
+
+        let mut edges: Vec<(String, String, &str, bool)> = graph
+            .edges
+            .iter()
+            .map(|e| {
+                (
+                    local(&e.source),
+                    local(&e.target),
+                    e.label.as_str(),
+                    e.directed,
+                )
+            })
+            .collect();
+        edges.sort();
+        assert_eq!(
+            edges,
+            [
+                ("ilse".to_string(), "order".to_string(), "allies", false),
+                ("ilse".to_string(), "oren".to_string(), "allies", false),
+                ("mira".to_string(), "ilse".to_string(), "rivals", true),
+                ("mira".to_string(), "time".to_string(), "hasConstruct", true),
+                ("order".to_string(), "oren".to_string(), "allies", false),
+            ]
+        );
+    }
+}
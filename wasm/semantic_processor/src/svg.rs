@@ -0,0 +1,314 @@
+//! Static SVG rendering of the network graph
+//!
+//! Where the interactive canvas cannot run, such as in feeds, AMP pages or
+//! with scripts disabled, the theme inlines a picture of the network graph
+//! rendered at build time instead:
+//!
+//! ```javascript
+//! const svg = processor.render_svg(800, 600, { layout: 'radial', center: 'sn:entropy' });
+//! ```
+//!
+//! The graph is laid out by force, or in rings around a resource, and scaled
+//! to fit. Every node and edge has a `<title>` naming it for screen readers
+//! and tooltips, and a class to style it by: `node` with the node's
+//! `node_type`, and `edge` with `directed` or `undirected`.
+
+use crate::error::ProcessorError;
+use crate::graphml::escape_xml;
+use crate::SemanticProcessor;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use wasm_bindgen::prelude::*;
+
+/// Rounds of the force-directed simulation run when no number is given
+const DEFAULT_ITERATIONS: u32 = 100;
+
+/// Space left free around the drawing
+const MARGIN: f64 = 24.0;
+
+/// Radius of a node
+const NODE_RADIUS: f64 = 6.0;
+
+/// Default look, which a page's own CSS can override
+const STYLE: &str = concat!(
+    ".edge{stroke:#8d99ae;stroke-width:1}",
+    ".node circle{fill:#8d99ae;stroke:#fff;stroke-width:1}",
+    ".construct circle{fill:#2a9d8f}.character circle{fill:#e9c46a}",
+    ".entanglement circle{fill:#e76f51}",
+    ".node text{font:10px sans-serif;fill:#333}",
+);
+
+/// Options for `render_svg`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SvgOptions {
+    pub layout: SvgLayout,
+    /// Focus resource of a radial layout
+    pub center: Option<String>,
+    /// Rounds of a force-directed layout
+    pub iterations: Option<u32>,
+    /// Whether to write each node's label next to it
+    pub labels: bool,
+    /// Accessible name of the picture; "Network graph" if omitted
+    pub title: Option<String>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            layout: SvgLayout::default(),
+            center: None,
+            iterations: None,
+            labels: true,
+            title: None,
+        }
+    }
+}
+
+/// How `render_svg` places the nodes
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SvgLayout {
+    /// By `layout_force_directed`
+    #[default]
+    Force,
+    /// By `layout_radial` around `center`, leaving out what it cannot reach
+    Radial,
+}
+
+impl SvgOptions {
+    /// Read options passed from JavaScript; `undefined` and `null` mean none
+    pub(crate) fn from_js(value: JsValue) -> Result<Self, ProcessorError> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(Self::default());
+        }
+        serde_wasm_bindgen::from_value(value)
+            .map_err(|e| ProcessorError::invalid_argument(format!("Invalid SVG options: {}", e)))
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Render the network graph as a standalone SVG picture
+    ///
+    /// # Arguments
+    /// * `width` - Width of the picture in pixels
+    /// * `height` - Height of the picture in pixels
+    /// * `options` - Optional `SvgOptions` object (layout, center,
+    ///   iterations, labels, title)
+    ///
+    /// # Returns
+    /// * `Ok(String)` with the `<svg>` element
+    /// * `Err(JsValue)` with error message if the options are invalid
+    pub fn render_svg(
+        &self,
+        width: u32,
+        height: u32,
+        #[wasm_bindgen(unchecked_optional_param_type = "SvgOptions")] options: JsValue,
+    ) -> Result<String, JsValue> {
+        self.check_abort()?;
+        let options = SvgOptions::from_js(options)?;
+        Ok(self.svg(width, height, &options)?)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn svg(
+        &self,
+        width: u32,
+        height: u32,
+        options: &SvgOptions,
+    ) -> Result<String, ProcessorError> {
+        let positions: HashMap<String, (f64, f64)> = match options.layout {
+            SvgLayout::Force => self
+                .force_directed(options.iterations.unwrap_or(DEFAULT_ITERATIONS))?
+                .into_iter()
+                .map(|p| (p.id, (p.x, p.y)))
+                .collect(),
+            SvgLayout::Radial => {
+                let center = options.center.as_deref().ok_or_else(|| {
+                    ProcessorError::invalid_argument("A radial layout needs a center")
+                        .with_context("layout", "radial")
+                })?;
+                self.radial(center, None)?
+                    .into_iter()
+                    .map(|p| (p.id, (p.x, p.y)))
+                    .collect()
+            }
+        };
+        let network = self.cached(
+            |cache| &mut cache.network_graph,
+            (),
+            || self.collect_network_graph(),
+        )?;
+        let nodes: Vec<_> = self
+            .every_node(&network)
+            .into_iter()
+            .filter(|node| positions.contains_key(&node.id))
+            .collect();
+        let labels: HashMap<&str, &str> = nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.label.as_str()))
+            .collect();
+        let edges: Vec<_> = network
+            .edges
+            .iter()
+            .filter(|edge| edge.source != edge.target)
+            .filter(|edge| labels.contains_key(edge.source.as_str()))
+            .filter(|edge| labels.contains_key(edge.target.as_str()))
+            .collect();
+
+        // Scale the layout to fit, keeping its proportions, and centre it
+        let (width, height) = (width as f64, height as f64);
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(x, y) in positions.values() {
+            (min_x, max_x) = (min_x.min(x), max_x.max(x));
+            (min_y, max_y) = (min_y.min(y), max_y.max(y));
+        }
+        let room = |size: f64, span: f64| {
+            if span > 0.0 {
+                (size - 2.0 * MARGIN).max(0.0) / span
+            } else {
+                f64::INFINITY
+            }
+        };
+        let scale = room(width, max_x - min_x).min(room(height, max_y - min_y));
+        let scale = if scale.is_finite() { scale } else { 1.0 };
+        let place = |id: &str| {
+            let (x, y) = positions[id];
+            (
+                width / 2.0 + (x - (min_x + max_x) / 2.0) * scale,
+                height / 2.0 + (y - (min_y + max_y) / 2.0) * scale,
+            )
+        };
+
+        let title = options.title.as_deref().unwrap_or("Network graph");
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" role=\"img\" aria-label=\"{title}\">",
+            w = width,
+            h = height,
+            title = escape_xml(title)
+        );
+        let _ = writeln!(out, "<title>{}</title>", escape_xml(title));
+        let _ = writeln!(
+            out,
+            "<desc>{} resources and {} relationships</desc>",
+            nodes.len(),
+            edges.len()
+        );
+        let _ = writeln!(out, "<style>{}</style>", STYLE);
+        out.push_str(concat!(
+            "<defs><marker id=\"sinople-arrow\" viewBox=\"0 0 10 10\" refX=\"16\" refY=\"5\" ",
+            "markerWidth=\"6\" markerHeight=\"6\" orient=\"auto-start-reverse\">",
+            "<path d=\"M0,0L10,5L0,10z\" fill=\"#8d99ae\"/></marker></defs>\n",
+        ));
+
+        out.push_str("<g class=\"edges\">\n");
+        for edge in &edges {
+            self.check_abort()?;
+            let ((x1, y1), (x2, y2)) = (place(&edge.source), place(&edge.target));
+            let _ =
+                writeln!(
+                out,
+                "<line class=\"edge {}\" x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"{}>\
+                 <title>{} {} {}</title></line>",
+                if edge.directed { "directed" } else { "undirected" },
+                x1,
+                y1,
+                x2,
+                y2,
+                if edge.directed {
+                    " marker-end=\"url(#sinople-arrow)\""
+                } else {
+                    ""
+                },
+                escape_xml(labels[edge.source.as_str()]),
+                escape_xml(&edge.label),
+                escape_xml(labels[edge.target.as_str()])
+            );
+        }
+        out.push_str("</g>\n<g class=\"nodes\">\n");
+        for node in &nodes {
+            let (x, y) = place(&node.id);
+            let label = escape_xml(&node.label);
+            let _ = write!(
+                out,
+                "<g class=\"node {}\"><title>{} ({})</title>\
+                 <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\"/>",
+                escape_xml(&node.node_type),
+                label,
+                escape_xml(&node.node_type),
+                x,
+                y,
+                NODE_RADIUS
+            );
+            if options.labels {
+                let _ = write!(
+                    out,
+                    "<text x=\"{:.1}\" y=\"{:.1}\" aria-hidden=\"true\">{}</text>",
+                    x + NODE_RADIUS + 2.0,
+                    y + 3.0,
+                    label
+                );
+            }
+            out.push_str("</g>\n");
+        }
+        out.push_str("</g>\n</svg>\n");
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_draws_every_node_and_edge() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                sn:entropy a sn:Construct ; rdfs:label "Entropy <decay>" ;
+                    sn:relatesTo sn:time, sn:order .
+                sn:x sn:relatesTo sn:y .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let svg = processor.svg(400, 300, &SvgOptions::default()).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"400\""));
+        assert_eq!(svg.matches("<circle ").count(), 5);
+        assert_eq!(svg.matches("<line ").count(), 3);
+        assert!(svg.contains("<title>Entropy &lt;decay&gt; (construct)</title>"));
+        assert!(svg.contains("<title>Entropy &lt;decay&gt; relatesTo time</title>"));
+        for value in svg.split("cx=\"").skip(1) {
+            let x: f64 = value[..value.find('"').unwrap()].parse().unwrap();
+            assert!((MARGIN - 0.1..=400.0 - MARGIN + 0.1).contains(&x));
+        }
+
+        let options = SvgOptions {
+            layout: SvgLayout::Radial,
+            center: Some("sn:entropy".to_string()),
+            labels: false,
+            ..SvgOptions::default()
+        };
+        let svg = processor.svg(400, 300, &options).unwrap();
+        assert_eq!(svg.matches("<circle ").count(), 3);
+        assert!(!svg.contains("<text"));
+
+        let options = SvgOptions {
+            layout: SvgLayout::Radial,
+            ..SvgOptions::default()
+        };
+        let error = processor.svg(400, 300, &options).unwrap_err();
+        assert_eq!(error.context["layout"], "radial");
+    }
+}
@@ -0,0 +1,219 @@
+//! TimelineJS export
+//!
+//! Chronological views of the narrative use TimelineJS. `to_timeline` builds
+//! its data from every resource placed in time with `sn:occursOn`, ending at
+//! `sn:endsOn` if it has one, so the timeline no longer has to be kept up to
+//! date by hand:
+//!
+//! ```javascript
+//! new TL.Timeline('timeline', processor.to_timeline());
+//! ```
+//!
+//! Each event's headline is the resource's `rdfs:label`, its text the
+//! `rdfs:comment` and its group the local name of its class. Dates are
+//! taken as written, without converting between time zones. As with the
+//! exported classes, key case and CURIE compaction do not apply, since
+//! TimelineJS expects its own field names.
+
+use crate::error::{to_js_json, ProcessorError};
+use crate::literals::parse_date;
+use crate::SemanticProcessor;
+use serde::Serialize;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::BTreeSet;
+use wasm_bindgen::prelude::*;
+
+/// Data for a TimelineJS timeline
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Timeline {
+    pub events: Vec<TimelineEvent>,
+}
+
+/// One slide of a timeline
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineEvent {
+    pub start_date: TimelineDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<TimelineDate>,
+    pub text: TimelineText,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// IRI of the resource
+    pub unique_id: String,
+}
+
+/// A date as TimelineJS reads it; the time is only given for an
+/// `xsd:dateTime`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TimelineDate {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hour: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minute: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub second: Option<u32>,
+}
+
+/// The words of a slide
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimelineText {
+    pub headline: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Export the resources placed in time as TimelineJS data
+    ///
+    /// Resources whose `sn:occursOn` is not a valid date are left out.
+    ///
+    /// # Returns
+    /// JsValue containing {events}, in chronological order
+    #[wasm_bindgen(unchecked_return_type = "Timeline")]
+    pub fn to_timeline(&self) -> Result<JsValue, JsValue> {
+        self.check_abort()?;
+        let timeline = self.timeline()?;
+
+        to_js_json(&timeline)
+    }
+}
+
+impl SemanticProcessor {
+    pub(crate) fn timeline(&self) -> Result<Timeline, ProcessorError> {
+        let graph = self.query_graph(None)?;
+        let graph: &FastGraph = &graph;
+        let occurs_on = self.make_term("sn:occursOn");
+        let rdf_type = self.make_term("rdf:type");
+
+        let subjects: BTreeSet<String> = graph
+            .triples_matching(Any, [&occurs_on], Any)
+            .flatten()
+            .map(|t| self.term_to_string(t.s()))
+            .collect();
+        let mut dated = Vec::new();
+        for id in subjects {
+            let Some(start) = self.get_object_value(graph, &id, "sn:occursOn") else {
+                continue;
+            };
+            let (Some(time), Some(start_date)) = (parse_date(&start), timeline_date(&start)) else {
+                continue;
+            };
+            let group = graph
+                .triples_matching([self.iri_term(&id)], [&rdf_type], Any)
+                .flatten()
+                .map(|t| self.term_to_string(t.o()))
+                .min()
+                .map(|class| self.extract_local_name(&class));
+
+            dated.push((
+                time,
+                TimelineEvent {
+                    start_date,
+                    end_date: self
+                        .get_object_value(graph, &id, "sn:endsOn")
+                        .and_then(|end| timeline_date(&end)),
+                    text: TimelineText {
+                        headline: self
+                            .get_object_value(graph, &id, "rdfs:label")
+                            .unwrap_or_else(|| self.extract_local_name(&id)),
+                        text: self.get_object_value(graph, &id, "rdfs:comment"),
+                    },
+                    group,
+                    unique_id: id,
+                },
+            ));
+        }
+
+        // The sort is stable, so events at the same time stay in IRI order
+        dated.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Timeline {
+            events: dated.into_iter().map(|(_, event)| event).collect(),
+        })
+    }
+}
+
+/// Split a valid `xsd:date` or `xsd:dateTime` into its parts, ignoring any
+/// time zone and fractions of a second
+fn timeline_date(lex: &str) -> Option<TimelineDate> {
+    parse_date(lex)?;
+    let (date, time) = match lex.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (&lex[..lex.len().min(10)], None),
+    };
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    let mut clock = time.map(|time| time.splitn(3, ':').map(|part| part.get(..2)));
+    let mut next = || {
+        clock
+            .as_mut()
+            .and_then(|parts| parts.next()?.and_then(|p| p.parse().ok()))
+    };
+    Some(TimelineDate {
+        year: year.parse().ok()?,
+        month: month.parse().ok()?,
+        day: day.parse().ok()?,
+        hour: next(),
+        minute: next(),
+        second: next(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_orders_dated_resources() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+                sn:flood a sn:Scene ; rdfs:label "Flood" ; rdfs:comment "The river rises." ;
+                    sn:occursOn "2024-05-31T18:05:09Z"^^xsd:dateTime .
+                sn:winter sn:occursOn "2023-12-01"^^xsd:date ;
+                    sn:endsOn "2024-03-20"^^xsd:date .
+                sn:rumour a sn:Event ; sn:occursOn "someday" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let timeline = processor.timeline().unwrap();
+        let headlines: Vec<&str> = timeline
+            .events
+            .iter()
+            .map(|e| e.text.headline.as_str())
+            .collect();
+        assert_eq!(headlines, ["winter", "Flood"]);
+
+        let winter = &timeline.events[0];
+        assert_eq!(winter.group, None);
+        assert_eq!(winter.end_date.unwrap().month, 3);
+        assert_eq!(winter.start_date.hour, None);
+
+        let flood = &timeline.events[1];
+        assert_eq!(flood.group.as_deref(), Some("Scene"));
+        assert_eq!(flood.text.text.as_deref(), Some("The river rises."));
+        assert_eq!(
+            flood.start_date,
+            TimelineDate {
+                year: 2024,
+                month: 5,
+                day: 31,
+                hour: Some(18),
+                minute: Some(5),
+                second: Some(9),
+            }
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Transitive properties
+//!
+//! A property declared `owl:TransitiveProperty`, such as `sn:derivesFrom`,
+//! holds along every chain of its statements:
+//!
+//! ```turtle
+//! sn:derivesFrom a owl:TransitiveProperty .
+//! sn:entropy sn:derivesFrom sn:time .
+//! sn:time sn:derivesFrom sn:origin .
+//! ```
+//!
+//! `transitive_closure` follows such chains in either direction, and
+//! `find_relationships` includes everything a construct is connected to
+//! through chains of a transitive property:
+//!
+//! ```javascript
+//! processor.transitive_closure('sn:entropy', 'sn:derivesFrom'); // [time, origin]
+//! processor.transitive_closure('sn:origin', 'sn:derivesFrom', true); // [time, entropy]
+//! ```
+
+use crate::SemanticProcessor;
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Follow a property from a resource for as long as it goes
+    ///
+    /// Sub-properties and inverses of the property are followed too. Each
+    /// resource is visited once, so cycles end, and the starting resource is
+    /// never part of the result.
+    ///
+    /// # Arguments
+    /// * `iri` - Resource to start from, as `prefix:local` or full IRI
+    /// * `property` - Property to follow, e.g. `sn:derivesFrom`
+    /// * `reverse` - Follow the property backwards, e.g. to find everything
+    ///   that derives from `iri`
+    ///
+    /// # Returns
+    /// JsValue containing array of IRIs, nearest first and otherwise sorted
+    #[wasm_bindgen(unchecked_return_type = "string[]")]
+    pub fn transitive_closure(
+        &self,
+        iri: &str,
+        property: &str,
+        reverse: Option<bool>,
+    ) -> Result<JsValue, JsValue> {
+        let reached = self.closure(
+            &self.graph,
+            self.make_term(iri),
+            property,
+            reverse.unwrap_or(false),
+        );
+
+        self.output(&reached)
+    }
+}
+
+impl SemanticProcessor {
+    /// The resources reachable from `start` through chains of `property`
+    pub(crate) fn closure(
+        &self,
+        graph: &FastGraph,
+        start: SimpleTerm<'static>,
+        property: &str,
+        reverse: bool,
+    ) -> Vec<String> {
+        let properties = self.subproperties_of(property);
+        let inverses: Vec<SimpleTerm> = self
+            .inverses_of(property)
+            .into_iter()
+            .map(|(inverse, _)| inverse)
+            .collect();
+        // Statements are read forwards with one list and backwards with the other
+        let (outgoing, incoming) = if reverse {
+            (&inverses, &properties)
+        } else {
+            (&properties, &inverses)
+        };
+
+        let mut reached = Vec::new();
+        let mut visited = HashSet::from([start.clone()]);
+        let mut frontier = vec![start];
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for node in &frontier {
+                let neighbours = graph
+                    .triples_matching([node], &outgoing[..], Any)
+                    .flatten()
+                    .map(|t| t.o().into_term::<SimpleTerm<'static>>())
+                    .chain(
+                        graph
+                            .triples_matching(Any, &incoming[..], [node])
+                            .flatten()
+                            .map(|t| t.s().into_term()),
+                    );
+                for neighbour in neighbours {
+                    if visited.insert(neighbour.clone()) {
+                        next.push(neighbour);
+                    }
+                }
+            }
+            next.sort_by_cached_key(|term| self.term_to_string(term));
+            reached.extend(next.iter().map(|term| self.term_to_string(term)));
+            frontier = next;
+        }
+        reached
+    }
+
+    /// Everything connected to a resource, either way, through chains of the
+    /// properties declared `owl:TransitiveProperty`
+    pub(crate) fn transitive_relations<T: Term>(&self, graph: &FastGraph, iri: T) -> Vec<String> {
+        let rdf_type = self.make_term("rdf:type");
+        let transitive = self.make_term("owl:TransitiveProperty");
+        let start: SimpleTerm<'static> = iri.into_term();
+
+        let properties: Vec<String> = self
+            .graph
+            .triples_matching(Any, [&rdf_type], [&transitive])
+            .flatten()
+            .map(|t| self.term_to_string(t.s()))
+            .collect();
+        properties
+            .iter()
+            .flat_map(|property| {
+                let mut related = self.closure(graph, start.clone(), property, false);
+                related.extend(self.closure(graph, start.clone(), property, true));
+                related
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: &str = r#"
+        @prefix sn: <https://sinople.org/ontology#> .
+        @prefix owl: <http://www.w3.org/2002/07/owl#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+        sn:derivesFrom a owl:TransitiveProperty .
+        sn:descendsFrom rdfs:subPropertyOf sn:derivesFrom .
+        sn:entropy a sn:Construct ; sn:derivesFrom sn:time .
+        sn:time a sn:Construct ; sn:descendsFrom sn:origin .
+        sn:origin a sn:Construct ; sn:derivesFrom sn:void .
+        sn:void a sn:Construct ; sn:derivesFrom sn:time .
+        sn:echo a sn:Construct ; sn:derivesFrom sn:entropy .
+    "#;
+
+    fn local(iris: Vec<String>) -> Vec<String> {
+        iris.into_iter()
+            .map(|iri| {
+                iri.trim_start_matches("https://sinople.org/ontology#")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_closure_follows_chains_and_stops_at_cycles() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+        let closure = |iri, reverse| {
+            local(processor.closure(
+                &processor.graph,
+                processor.make_term(iri),
+                "sn:derivesFrom",
+                reverse,
+            ))
+        };
+
+        assert_eq!(closure("sn:entropy", false), ["time", "origin", "void"]);
+        assert_eq!(closure("sn:time", false), ["origin", "void"]);
+        assert_eq!(
+            closure("sn:time", true),
+            ["entropy", "void", "echo", "origin"]
+        );
+        assert!(closure("sn:echo", true).is_empty());
+    }
+
+    #[test]
+    fn test_relationships_include_transitive_chains() {
+        let mut processor = SemanticProcessor::new();
+        processor.load_turtle(TTL, None).unwrap();
+
+        assert_eq!(
+            local(
+                processor.get_relationships(&processor.graph, "https://sinople.org/ontology#echo")
+            ),
+            ["entropy", "origin", "time", "void"]
+        );
+
+        let export = processor.collect_export().unwrap();
+        for construct in &export.constructs {
+            assert_eq!(
+                construct.relationships,
+                processor.get_relationships(&processor.graph, &construct.id)
+            );
+        }
+    }
+}
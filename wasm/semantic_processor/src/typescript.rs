@@ -0,0 +1,490 @@
+//! TypeScript definitions for query results
+//!
+//! Query methods return `JsValue`, which `wasm-bindgen` types as `any`. The
+//! interfaces below are appended to `semantic_processor.d.ts`, and each query
+//! method names the one it returns through `unchecked_return_type`:
+//!
+//! ```typescript
+//! const constructs: ConstructData[] = processor.query_constructs();
+//! ```
+//!
+//! They describe the default output: JavaScript objects with snake_case field
+//! names. After `set_output_encoding('json' | 'cbor')` the methods return a
+//! `Uint8Array` instead, and after `set_key_case('camel')` the field names of
+//! these interfaces are in camelCase.
+//!
+//! Plain objects of the types that are also exported as classes, such as
+//! `Construct`, are named with a `Data` suffix: they carry the same fields as
+//! the class, but none of its methods.
+//!
+//! Keep the interfaces in step with the `Serialize` implementations of the
+//! result structs. Optional fields hold `undefined` when absent, except in
+//! results documented as plain JSON objects, where they hold `null`.
+
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(typescript_custom_section)]
+const RESULT_TYPES: &'static str = r#"
+/** A Construct in the Sinople ontology */
+export interface ConstructData {
+    id: string;
+    label: string;
+    description?: string;
+    glosses: GlossData[];
+    relationships: string[];
+}
+
+/** An Entanglement (relationship between constructs) */
+export interface EntanglementData {
+    id: string;
+    label: string;
+    source: string;
+    target: string;
+    relationship_type: string;
+    description?: string;
+    annotations: Annotation[];
+}
+
+/** A Gloss (annotation or explanation) */
+export interface GlossData {
+    id: string;
+    text: string;
+    language: string;
+    position?: number;
+}
+
+/** A Character in the semantic universe */
+export interface CharacterData {
+    id: string;
+    name: string;
+    description?: string;
+    constructs: string[];
+}
+
+/** An event or scene, placed in time by `sn:occursOn` */
+export interface EventData {
+    id: string;
+    label: string;
+    kind: string;
+    occurs_on?: string;
+    ends_on?: string;
+    description?: string;
+    participants: string[];
+    locations: string[];
+}
+
+/** Network graph node for visualization */
+export interface GraphNodeData {
+    id: string;
+    label: string;
+    node_type: "construct" | "character" | "entanglement" | "other";
+    /** IRIs of every `rdf:type` of the resource, sorted */
+    types: string[];
+    /** Number of edges of the graph that end at the node */
+    in_degree: number;
+    /** Number of edges of the graph that start at the node */
+    out_degree: number;
+}
+
+/** Network graph edge for visualization */
+export interface GraphEdgeData {
+    source: string;
+    target: string;
+    label: string;
+    /** True if the relationship holds both ways, so the edge has no direction */
+    symmetric: boolean;
+    /** True if the edge should be drawn with an arrow */
+    directed: boolean;
+    /** Strength of the relationship, from `sn:weight`; 1 if it has none */
+    weight: number;
+    /** Number of relationships the edge stands for, if parallel edges are merged */
+    count: number;
+    /** Position among the edges joining the same two nodes, either way */
+    parallel_index: number;
+    /** Number of edges joining the same two nodes, either way */
+    parallel_count: number;
+}
+
+/** Network graph structure */
+export interface NetworkGraphData {
+    nodes: GraphNodeData[];
+    edges: GraphEdgeData[];
+}
+
+/** Options for the `query_*` methods */
+export interface QueryOptions {
+    limit?: number;
+    offset?: number;
+    sort?: "id" | "label" | "date";
+    order?: "asc" | "desc";
+    filter?: string;
+}
+
+/** One page of query results */
+export interface Page<T> {
+    items: T[];
+    next_cursor?: string;
+}
+
+/** Results of `export_all` */
+export interface SemanticExport {
+    constructs: ConstructData[];
+    entanglements: EntanglementData[];
+    characters: CharacterData[];
+    graph: NetworkGraphData;
+}
+
+/** An RDF term, as in the SPARQL 1.1 Query Results JSON Format */
+export interface BindingValue {
+    type: "uri" | "bnode" | "literal" | "triple";
+    value: string;
+    datatype?: string;
+    "xml:lang"?: string;
+}
+
+/** Results of a SPARQL SELECT query */
+export interface SelectResults {
+    head: { vars: string[] };
+    results: { bindings: Record<string, BindingValue>[] };
+}
+
+/** An RDF term, with the datatype and language of a literal */
+export interface TermValue {
+    kind: "iri" | "blank" | "literal" | "triple";
+    value: string;
+    datatype?: string;
+    language?: string;
+    triple?: TripleMatch;
+}
+
+/** A triple returned by pattern matching */
+export interface TripleMatch {
+    subject: TermValue;
+    predicate: TermValue;
+    object: TermValue;
+}
+
+/** One property of a reified statement */
+export interface Annotation {
+    predicate: string;
+    value: TermValue;
+}
+
+/** A statement described by an `rdf:Statement` node, with its metadata */
+export interface ReifiedStatement {
+    id: string;
+    subject: TermValue;
+    predicate: TermValue;
+    object: TermValue;
+    annotations: Annotation[];
+}
+
+/** Whether a triple was asserted or inferred, and from what */
+export interface Explanation {
+    asserted: boolean;
+    rule?: string;
+    premises: TripleMatch[];
+}
+
+/** A triple that does not respect a domain or range declaration */
+export interface Violation {
+    kind: "domain" | "range";
+    subject: string;
+    predicate: string;
+    object: TermValue;
+    expected: string;
+    found: string[];
+    message: string;
+}
+
+/** One constraint that a focus node does not satisfy */
+export interface ValidationResult {
+    focus_node: string;
+    path?: string;
+    value?: TermValue;
+    shape: string;
+    constraint: string;
+    severity: "Violation" | "Warning" | "Info";
+    message: string;
+}
+
+/** The outcome of SHACL validation */
+export interface ValidationReport {
+    conforms: boolean;
+    results: ValidationResult[];
+}
+
+/** The outcome of running custom rules */
+export interface RuleReport {
+    inserted: number;
+    iterations: number;
+    complete: boolean;
+}
+
+/** One problem found by linting */
+export interface LintIssue {
+    kind: "dangling-reference" | "missing-label" | "untyped-subject" | "unknown-predicate" | "duplicate-id";
+    resource: string;
+    related: string[];
+    message: string;
+}
+
+/** The result of linting the graph */
+export interface LintReport {
+    clean: boolean;
+    counts: Record<string, number>;
+    issues: LintIssue[];
+}
+
+/** A resource and the label it was grouped by */
+export interface LabelledEntity {
+    id: string;
+    label: string;
+}
+
+/** Resources that share a label */
+export interface DuplicateLabel {
+    label: string;
+    entities: LabelledEntity[];
+}
+
+/** One problem of an entanglement */
+export interface EntanglementError {
+    code: "missing-source" | "missing-target" | "untyped-source" | "untyped-target" | "unknown-relationship-type";
+    value?: string;
+    message: string;
+}
+
+/** The problems of one entanglement */
+export interface EntanglementErrors {
+    entanglement: string;
+    errors: EntanglementError[];
+}
+
+/** Resources that can all reach each other through directed relationships */
+export interface Cycle {
+    nodes: string[];
+    /** One shortest loop, from the first node back to just before it */
+    path: string[];
+}
+
+/** Scenes and chapters in reading order */
+export interface NarrativeOrder {
+    order: string[];
+    /** Resources said to precede each other in a loop */
+    contradictions: Cycle[];
+}
+
+/** A chain of edges between two resources */
+export interface GraphPath {
+    nodes: string[];
+    /** The edge between each node and the next, in its stored direction */
+    edges: GraphEdge[];
+}
+
+/** The PageRank centrality of a network graph node */
+export interface NodeScore {
+    id: string;
+    label: string;
+    score: number;
+}
+
+/** Where to draw a network graph node */
+export interface NodePosition {
+    id: string;
+    x: number;
+    y: number;
+}
+
+/** Where to draw a resource of a hierarchy or of rings around a centre */
+export interface LayeredPosition {
+    id: string;
+    x: number;
+    y: number;
+    /** Steps below the root, or hops from the centre, which are layer 0 */
+    layer: number;
+}
+
+/** A network graph node in the Cytoscape.js elements format */
+export interface CytoscapeNode {
+    data: GraphNodeData;
+    /** The node's `node_type` */
+    classes: string;
+    /** Where the latest layout placed the node, if one has been computed */
+    position?: { x: number; y: number };
+}
+
+/** A network graph edge in the Cytoscape.js elements format */
+export interface CytoscapeEdge {
+    data: GraphEdgeData & { id: string };
+    classes: "directed" | "undirected";
+}
+
+/** Network graph in the Cytoscape.js elements format */
+export interface CytoscapeElements {
+    nodes: CytoscapeNode[];
+    edges: CytoscapeEdge[];
+}
+
+/** A resource and the resources deriving from it, for `d3.hierarchy` */
+export interface HierarchyNode {
+    id: string;
+    label: string;
+    children: HierarchyNode[];
+}
+
+/** Options for `render_svg` */
+export interface SvgOptions {
+    /** Lay out by force (default), or in rings around `center` */
+    layout?: "force" | "radial";
+    center?: string;
+    iterations?: number;
+    /** Whether to write each node's label next to it; true by default */
+    labels?: boolean;
+    /** Accessible name of the picture; "Network graph" by default */
+    title?: string;
+}
+
+/** A date as TimelineJS reads it */
+export interface TimelineDate {
+    year: number;
+    month: number;
+    day: number;
+    hour?: number;
+    minute?: number;
+    second?: number;
+}
+
+/** One slide of a TimelineJS timeline */
+export interface TimelineEvent {
+    start_date: TimelineDate;
+    end_date?: TimelineDate;
+    text: { headline: string; text?: string };
+    /** Local name of the resource's class */
+    group?: string;
+    /** IRI of the resource */
+    unique_id: string;
+}
+
+/** Data for a TimelineJS timeline */
+export interface Timeline {
+    events: TimelineEvent[];
+}
+
+/** A location, positioned by `geo:lat` and `geo:long` */
+export interface PlaceData {
+    id: string;
+    label: string;
+    description?: string;
+    latitude?: number;
+    longitude?: number;
+    constructs: string[];
+    events: string[];
+    characters: string[];
+}
+
+/** A place as a GeoJSON point feature */
+export interface Feature {
+    type: "Feature";
+    /** IRI of the place */
+    id: string;
+    geometry: { type: "Point"; coordinates: [longitude: number, latitude: number] };
+    properties: {
+        label: string;
+        /** IRIs of the constructs linked to the place */
+        constructs: string[];
+    };
+}
+
+/** Places as a GeoJSON FeatureCollection */
+export interface FeatureCollection {
+    type: "FeatureCollection";
+    features: Feature[];
+}
+
+/** A book, essay or other text */
+export interface WorkData {
+    id: string;
+    title: string;
+    authors: string[];
+    year?: number;
+    publisher?: string;
+    isbn?: string;
+    /** Without a `https://doi.org/` or `doi:` prefix */
+    doi?: string;
+    excerpts: string[];
+    constructs: string[];
+}
+
+/** A reference to a work */
+export interface Citation {
+    authors: string[];
+    year?: number;
+    title: string;
+    /** Where in the work the passage is, e.g. `p. 23` */
+    locator?: string;
+    /** IRI of the work */
+    work: string;
+}
+
+/** A link pointing at a resource */
+export interface IncomingLink {
+    source: string;
+    predicate: string;
+}
+
+/** Every property of a single resource, keyed by predicate IRI */
+export interface ResourceDescription {
+    id: string;
+    properties: Record<string, TermValue[]>;
+    incoming?: Record<string, TermValue[]>;
+}
+
+/** Triples that differ between the graph and another document */
+export interface GraphDiff {
+    added: TripleMatch[];
+    removed: TripleMatch[];
+}
+
+/** Outcome of merging a document into the graph */
+export interface MergeReport {
+    added: number;
+    duplicates: number;
+}
+
+/** Number of triples changed by a SPARQL update or RDF Patch */
+export interface UpdateSummary {
+    inserted: number;
+    deleted: number;
+}
+
+/** A statement skipped by a lenient load */
+export interface LoadWarning {
+    line: number;
+    column: number;
+    token: string;
+    message: string;
+}
+
+/** Size and shape of the graph */
+export interface GraphStats {
+    triples: number;
+    distinct_subjects: number;
+    distinct_predicates: number;
+    distinct_objects: number;
+    approximate_bytes: number;
+    instances: Map<string, number>;
+}
+
+/** How densely the network graph is connected */
+export interface GraphMetrics {
+    nodes: number;
+    /** Pairs of nodes joined by at least one edge */
+    edges: number;
+    density: number;
+    average_degree: number;
+    clustering_coefficient: number;
+    /** A lower bound of the diameter, exact for trees */
+    diameter_estimate: number;
+}
+"#;
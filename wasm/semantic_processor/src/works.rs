@@ -0,0 +1,281 @@
+//! Works and their excerpts
+//!
+//! Books, essays and other texts are instances of `sn:Work`, described with
+//! Dublin Core terms: `dcterms:title`, `dcterms:creator` and
+//! `dcterms:issued` or `dcterms:date`. `query_works` lists them with their
+//! `sn:isbn` and `sn:doi`, the passages quoted from them as `sn:excerpt`, and
+//! the constructs linked to them in either direction, to drive the
+//! bibliography:
+//!
+//! ```javascript
+//! for (const work of processor.query_works(null, { sort: 'date' })) {
+//!   console.log(work.authors.join(', '), work.year, work.title);
+//! }
+//! ```
+//!
+//! A creator may be a name, or a resource named by its `rdfs:label`.
+//! Creators given as an RDF list keep its order; any others are sorted.
+
+use crate::error::ProcessorError;
+use crate::literals::parse_date;
+use crate::options::{Labelled, QueryOptions};
+use crate::SemanticProcessor;
+use serde::{Deserialize, Serialize};
+use sophia_api::graph::Graph;
+use sophia_api::term::matcher::Any;
+use sophia_api::term::{SimpleTerm, Term};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+/// Namespace of the Dublin Core terms
+pub(crate) const DCTERMS: &str = "http://purl.org/dc/terms/";
+
+/// Prefixes a DOI may be written with
+const DOI_PREFIXES: [&str; 4] = [
+    "https://doi.org/",
+    "http://doi.org/",
+    "https://dx.doi.org/",
+    "doi:",
+];
+
+/// A book, essay or other text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Work {
+    pub id: String,
+    /// `dcterms:title`, else `rdfs:label`
+    pub title: String,
+    pub authors: Vec<String>,
+    /// Year of `dcterms:issued`, else of `dcterms:date`
+    pub year: Option<i32>,
+    pub publisher: Option<String>,
+    pub isbn: Option<String>,
+    /// The DOI itself, without a `https://doi.org/` or `doi:` prefix
+    pub doi: Option<String>,
+    /// Passages quoted from the work, sorted
+    pub excerpts: Vec<String>,
+    /// IRIs of the constructs linked to the work, sorted
+    pub constructs: Vec<String>,
+}
+
+impl Labelled for Work {
+    fn label(&self) -> &str {
+        &self.title
+    }
+
+    fn date(&self) -> Option<f64> {
+        let year = self.year?;
+        let sign = if year < 0 { "-" } else { "" };
+        parse_date(&format!("{}{:04}-01-01", sign, year.unsigned_abs()))
+    }
+}
+
+#[wasm_bindgen]
+impl SemanticProcessor {
+    /// Query all works from the graph
+    ///
+    /// # Arguments
+    /// * `graph` - Optional named graph to query; `None` queries the union of all graphs
+    /// * `options` - Optional `QueryOptions` object; `sort: 'date'` orders
+    ///   them by year, with undated works last
+    ///
+    /// # Returns
+    /// JsValue containing array of Work objects
+    #[wasm_bindgen(unchecked_return_type = "WorkData[]")]
+    pub fn query_works(
+        &self,
+        graph: Option<String>,
+        #[wasm_bindgen(unchecked_optional_param_type = "QueryOptions")] options: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let options = QueryOptions::from_js(options)?;
+        let works = self.cached(
+            |cache| &mut cache.works,
+            graph.clone(),
+            || self.collect_works(graph.as_deref()),
+        )?;
+
+        if options == QueryOptions::default() {
+            return self.output(&*works);
+        }
+        self.output(&options.apply(&works))
+    }
+}
+
+impl SemanticProcessor {
+    /// Build the result of `query_works`
+    pub(crate) fn collect_works(&self, graph: Option<&str>) -> Result<Vec<Work>, ProcessorError> {
+        let graph = self.query_graph(graph)?;
+        let graph: &FastGraph = &graph;
+        let constructs: HashSet<String> = self
+            .instances_of(graph, "sn:Construct")
+            .iter()
+            .map(|iri| iri.to_string())
+            .collect();
+
+        Ok(self
+            .instances_of(graph, "sn:Work")
+            .iter()
+            .map(|work| {
+                let mut work = self.describe_work(graph, work);
+                work.constructs = self
+                    .linked(graph, &work.id)
+                    .into_iter()
+                    .filter(|iri| constructs.contains(iri))
+                    .collect();
+                work
+            })
+            .collect())
+    }
+
+    /// Read the bibliographic description of a work, leaving out the
+    /// constructs linked to it
+    pub(crate) fn describe_work(&self, graph: &FastGraph, id: &str) -> Work {
+        let dcterms = |local: &str| format!("{}{}", DCTERMS, local);
+        let value = |property: &str| {
+            self.get_object_value(graph, id, property)
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let mut excerpts: Vec<String> = graph
+            .triples_matching([self.iri_term(id)], [self.make_term("sn:excerpt")], Any)
+            .flatten()
+            .map(|t| self.term_to_string(t.o()))
+            .collect();
+        excerpts.sort();
+
+        Work {
+            id: id.to_string(),
+            title: value(&dcterms("title"))
+                .or_else(|| value("rdfs:label"))
+                .unwrap_or_else(|| self.extract_local_name(id)),
+            authors: self.creators(graph, id),
+            year: value(&dcterms("issued"))
+                .or_else(|| value(&dcterms("date")))
+                .and_then(|date| year_of(&date)),
+            publisher: value(&dcterms("publisher")),
+            isbn: value("sn:isbn"),
+            doi: value("sn:doi").map(|doi| {
+                let doi = DOI_PREFIXES
+                    .iter()
+                    .find_map(|prefix| doi.strip_prefix(prefix))
+                    .unwrap_or(&doi);
+                doi.to_string()
+            }),
+            excerpts,
+            constructs: Vec::new(),
+        }
+    }
+
+    /// The names of the creators of a work
+    fn creators(&self, graph: &FastGraph, id: &str) -> Vec<String> {
+        let creator = self.make_term(&format!("{}creator", DCTERMS));
+        let name = |term: &SimpleTerm| match term {
+            SimpleTerm::Iri(_) => {
+                let iri = self.term_to_string(term);
+                self.get_object_value(graph, &iri, "rdfs:label")
+                    .unwrap_or_else(|| self.extract_local_name(&iri))
+            }
+            _ => self.term_to_string(term),
+        };
+
+        let mut listed = Vec::new();
+        let mut others = Vec::new();
+        for t in graph
+            .triples_matching([self.iri_term(id)], [&creator], Any)
+            .flatten()
+        {
+            let object = t.o().into_term::<SimpleTerm<'static>>();
+            let items = if object.is_blank_node() {
+                self.list_items(graph, object.clone())
+            } else {
+                Vec::new()
+            };
+            if items.is_empty() {
+                others.push(name(&object));
+            } else {
+                listed.extend(items.iter().map(name));
+            }
+        }
+        others.sort();
+        listed.extend(others);
+        listed.retain(|name| !name.is_empty());
+        listed
+    }
+}
+
+/// The year a date starts with, e.g. 1967 for `1967-05-01` or `1967`
+fn year_of(date: &str) -> Option<i32> {
+    let (sign, digits) = match date.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, date),
+    };
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    if end < 4 {
+        return None;
+    }
+    digits[..end].parse::<i32>().ok().map(|year| sign * year)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_works_with_identifiers_and_excerpts() {
+        let mut processor = SemanticProcessor::new();
+        processor
+            .load_turtle(
+                r#"
+                @prefix sn: <https://sinople.org/ontology#> .
+                @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+                @prefix dcterms: <http://purl.org/dc/terms/> .
+                sn:solaris a sn:Work ; dcterms:title "Solaris" ;
+                    dcterms:creator sn:lem ; dcterms:issued "1961" ;
+                    sn:isbn "978-0-15-602760-1" ;
+                    sn:excerpt "We don't want to conquer the cosmos." , "Man has gone out to explore other worlds." .
+                sn:lem rdfs:label "Stanisław Lem" .
+                sn:entropy a sn:Construct ; sn:relatesTo sn:solaris .
+                sn:paper a sn:Work ; rdfs:label "On Drift" ;
+                    dcterms:creator ( "Ng, A." "Abel, B." ) , "Zed, C." ;
+                    dcterms:date "2019-04-02" ; sn:doi "https://doi.org/10.1000/182" .
+                sn:republic a sn:Work ; dcterms:title "Republic" ; dcterms:issued "-0375" .
+                "#,
+                None,
+            )
+            .unwrap();
+
+        let works = processor.collect_works(None).unwrap();
+        assert_eq!(works.len(), 3);
+        let (paper, republic, solaris) = (&works[0], &works[1], &works[2]);
+        assert_eq!(paper.title, "On Drift");
+        assert_eq!(paper.authors, ["Ng, A.", "Abel, B.", "Zed, C."]);
+        assert_eq!(paper.year, Some(2019));
+        assert_eq!(paper.doi.as_deref(), Some("10.1000/182"));
+        assert_eq!(solaris.authors, ["Stanisław Lem"]);
+        assert_eq!(solaris.year, Some(1961));
+        assert_eq!(solaris.isbn.as_deref(), Some("978-0-15-602760-1"));
+        assert_eq!(solaris.excerpts.len(), 2);
+        assert_eq!(solaris.constructs, ["https://sinople.org/ontology#entropy"]);
+        assert_eq!(republic.year, Some(-375));
+        assert!(republic.date().unwrap() < solaris.date().unwrap());
+
+        let options = QueryOptions {
+            sort: crate::options::SortKey::Date,
+            ..QueryOptions::default()
+        };
+        let sorted = options.apply(&works);
+        let titles: Vec<&str> = sorted.iter().map(|work| work.title.as_str()).collect();
+        assert_eq!(titles, ["Republic", "Solaris", "On Drift"]);
+    }
+
+    #[test]
+    fn test_year_of() {
+        assert_eq!(year_of("1967-05-01"), Some(1967));
+        assert_eq!(year_of("-0450"), Some(-450));
+        assert_eq!(year_of("67"), None);
+        assert_eq!(year_of("c. 1900"), None);
+    }
+}